@@ -0,0 +1,217 @@
+use reqwest::{RequestBuilder, Response};
+use serde::Serialize;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CircuitStatus {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub retry_after_secs: Option<u64>,
+}
+
+pub enum CircuitRequestError {
+    Open,
+    Failed,
+}
+
+struct CircuitInner {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probe_started_at: Option<Instant>,
+}
+
+/// Tracks consecutive failures of an upstream service so callers can skip it
+/// for a cooldown instead of waiting out a request timeout every time.
+pub struct CircuitBreaker {
+    name: &'static str,
+    inner: Mutex<CircuitInner>,
+}
+
+impl CircuitBreaker {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            inner: Mutex::new(CircuitInner {
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_started_at: None,
+            }),
+        }
+    }
+
+    /// Closed lets everything through; open rejects until the cooldown has
+    /// passed, after which a single probe request is allowed (half-open). A
+    /// probe that never reports back is replaced after another cooldown.
+    pub fn allow(&self, cooldown: Duration) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+
+        match inner.opened_at {
+            None => true,
+            Some(opened_at) if opened_at.elapsed() < cooldown => false,
+            Some(_)
+                if inner
+                    .probe_started_at
+                    .is_some_and(|at| at.elapsed() < cooldown) =>
+            {
+                false
+            }
+            Some(_) => {
+                inner.probe_started_at = Some(Instant::now());
+                true
+            }
+        }
+    }
+
+    /// Like `allow`, but without claiming the half-open probe slot.
+    pub fn is_open(&self, cooldown: Duration) -> bool {
+        let inner = self.inner.lock().unwrap();
+
+        match inner.opened_at {
+            None => false,
+            Some(opened_at) if opened_at.elapsed() < cooldown => true,
+            Some(_) => inner
+                .probe_started_at
+                .is_some_and(|at| at.elapsed() < cooldown),
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.probe_started_at = None;
+    }
+
+    pub fn record_failure(&self, threshold: u32) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+
+        if inner.probe_started_at.is_some() || inner.consecutive_failures >= threshold {
+            tracing::warn!(
+                consecutive_failures = inner.consecutive_failures,
+                "{} circuit opened",
+                self.name
+            );
+            inner.opened_at = Some(Instant::now());
+        }
+        inner.probe_started_at = None;
+    }
+
+    pub fn status(&self, cooldown: Duration) -> CircuitStatus {
+        let inner = self.inner.lock().unwrap();
+
+        let (state, retry_after_secs) = match inner.opened_at {
+            None => (CircuitState::Closed, None),
+            Some(opened_at) => match cooldown.checked_sub(opened_at.elapsed()) {
+                Some(remaining) if !remaining.is_zero() => {
+                    (CircuitState::Open, Some(remaining.as_secs().max(1)))
+                }
+                _ => (CircuitState::HalfOpen, None),
+            },
+        };
+
+        CircuitStatus {
+            state,
+            consecutive_failures: inner.consecutive_failures,
+            retry_after_secs,
+        }
+    }
+
+    /// Sends a request through the breaker. Only transport errors and 5xx
+    /// statuses count as failures; a 4xx is the caller's fault, so it still
+    /// proves the upstream is reachable.
+    pub async fn send(
+        &self,
+        request: RequestBuilder,
+        threshold: u32,
+        cooldown: Duration,
+    ) -> Result<Response, CircuitRequestError> {
+        if !self.allow(cooldown) {
+            return Err(CircuitRequestError::Open);
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                self.record_success();
+                Ok(response)
+            }
+            Ok(response) if response.status().is_server_error() => {
+                tracing::warn!(status = %response.status(), "{} request failed", self.name);
+                self.record_failure(threshold);
+                Err(CircuitRequestError::Failed)
+            }
+            Ok(response) => {
+                tracing::warn!(status = %response.status(), "{} request rejected", self.name);
+                self.record_success();
+                Err(CircuitRequestError::Failed)
+            }
+            Err(error) => {
+                tracing::warn!(error = %error, "{} request failed", self.name);
+                self.record_failure(threshold);
+                Err(CircuitRequestError::Failed)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn opens_after_threshold_consecutive_failures() {
+        let breaker = CircuitBreaker::new("test");
+
+        breaker.record_failure(3);
+        breaker.record_failure(3);
+        assert!(breaker.allow(COOLDOWN));
+        assert_eq!(breaker.status(COOLDOWN).state, CircuitState::Closed);
+
+        breaker.record_failure(3);
+        assert!(!breaker.allow(COOLDOWN));
+        assert!(breaker.is_open(COOLDOWN));
+        assert_eq!(breaker.status(COOLDOWN).state, CircuitState::Open);
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let breaker = CircuitBreaker::new("test");
+
+        breaker.record_failure(2);
+        breaker.record_success();
+        breaker.record_failure(2);
+        assert!(breaker.allow(COOLDOWN));
+        assert_eq!(breaker.status(COOLDOWN).consecutive_failures, 1);
+    }
+
+    #[test]
+    fn half_open_allows_a_single_probe() {
+        let breaker = CircuitBreaker::new("test");
+        breaker.record_failure(1);
+
+        assert_eq!(breaker.status(Duration::ZERO).state, CircuitState::HalfOpen);
+        assert!(breaker.allow(Duration::ZERO));
+        assert!(!breaker.allow(COOLDOWN));
+
+        breaker.record_failure(1);
+        assert!(breaker.is_open(COOLDOWN));
+
+        assert!(breaker.allow(Duration::ZERO));
+        breaker.record_success();
+        assert_eq!(breaker.status(COOLDOWN).state, CircuitState::Closed);
+        assert!(breaker.allow(COOLDOWN));
+    }
+}
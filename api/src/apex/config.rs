@@ -0,0 +1,293 @@
+use serde::de::DeserializeOwned;
+use std::{collections::HashMap, env::var, hash::Hash, str::FromStr, sync::OnceLock};
+
+use crate::{
+    apex::utils::DEFAULT_TRUSTED_PROXY_HOPS,
+    auth::schemas::{
+        DEFAULT_ARGON2_ITERATIONS, DEFAULT_ARGON2_MEMORY_KIB, DEFAULT_ARGON2_PARALLELISM,
+        DEFAULT_LOGIN_MAX_ATTEMPTS, DEFAULT_LOGIN_WINDOW_SECS, DEFAULT_RESERVED_USERNAMES,
+    },
+    chat::schemas::{MAX_MESSAGE_LENGTH, MAX_MESSAGE_LIMIT, MessageType},
+    orders::schemas::{MAX_ORDER_LIMIT, MAX_ORDER_STATS_MONTHS},
+    products::schemas::{
+        DEFAULT_EMBEDDING_MAX_IMAGES, DEFAULT_MAX_ACTIVE_LISTINGS, DEFAULT_MAX_PRODUCT_QUANTITY,
+        DEFAULT_PRODUCT_CREATION_RATE_LIMIT, MAX_PAGE_LIMIT, MAX_QUESTIONS_COUNT, MAX_TAGS_COUNT,
+        ProductCategory,
+    },
+    recommendations::schemas::{
+        DEFAULT_NOT_INTERESTED_WINDOW_SECS, DEFAULT_TIME_DECAY_BATCH_SIZE,
+        DEFAULT_TIME_DECAY_INTERVAL_SECS,
+    },
+    search::schemas::{
+        DEFAULT_CLIP_COOLDOWN_SECS, DEFAULT_CLIP_FAILURE_THRESHOLD, DEFAULT_GROQ_COOLDOWN_SECS,
+        DEFAULT_GROQ_FAILURE_THRESHOLD, DEFAULT_MAX_VECTOR_SEARCH_CANDIDATES,
+        DEFAULT_MIN_QUERY_LENGTH, DEFAULT_TRANSCRIPTION_LANGUAGES,
+        DEFAULT_VECTOR_SEARCH_CANDIDATES_MULTIPLIER, MAX_SEARCH_RESULTS, MIN_SEARCH_CANDIDATES,
+        VECTOR_SEARCH_CANDIDATES_HARD_LIMIT,
+    },
+};
+
+static CONFIG: OnceLock<AppConfig> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    pub max_search_results: u32,
+    pub min_query_length: usize,
+    pub vector_search_candidates_multiplier: u32,
+    pub max_vector_search_candidates: u32,
+    pub clip_failure_threshold: u32,
+    pub clip_cooldown_secs: u64,
+    pub groq_failure_threshold: u32,
+    pub groq_cooldown_secs: u64,
+    pub max_page_limit: u32,
+    pub max_message_limit: u32,
+    pub max_message_length: usize,
+    pub message_type_max_length: HashMap<MessageType, usize>,
+    pub max_order_limit: u32,
+    pub max_order_stats_months: u32,
+    pub admin_user_ids: Vec<String>,
+    pub reserved_usernames: Vec<String>,
+    pub login_max_attempts: u32,
+    pub login_window_secs: u64,
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub category_max_tags: HashMap<ProductCategory, usize>,
+    pub category_max_questions: HashMap<ProductCategory, usize>,
+    pub embedding_max_images: usize,
+    pub max_product_quantity: u32,
+    pub product_creation_rate_limit: u64,
+    pub max_active_listings: u64,
+    pub time_decay_interval_secs: u64,
+    pub time_decay_batch_size: i64,
+    pub request_time_decay: bool,
+    pub not_interested_window_secs: u64,
+    pub transcription_languages: Vec<String>,
+    pub trusted_proxy_hops: usize,
+}
+
+impl AppConfig {
+    pub fn from_env() -> Self {
+        Self {
+            max_search_results: env_limit("MAX_SEARCH_RESULTS", MAX_SEARCH_RESULTS),
+            min_query_length: env_or("MIN_QUERY_LENGTH", DEFAULT_MIN_QUERY_LENGTH),
+            vector_search_candidates_multiplier: env_or(
+                "VECTOR_SEARCH_CANDIDATES_MULTIPLIER",
+                DEFAULT_VECTOR_SEARCH_CANDIDATES_MULTIPLIER,
+            )
+            .max(1),
+            max_vector_search_candidates: env_or(
+                "MAX_VECTOR_SEARCH_CANDIDATES",
+                DEFAULT_MAX_VECTOR_SEARCH_CANDIDATES,
+            )
+            .clamp(MIN_SEARCH_CANDIDATES, VECTOR_SEARCH_CANDIDATES_HARD_LIMIT),
+            clip_failure_threshold: env_or(
+                "CLIP_FAILURE_THRESHOLD",
+                DEFAULT_CLIP_FAILURE_THRESHOLD,
+            )
+            .max(1),
+            clip_cooldown_secs: env_or("CLIP_COOLDOWN_SECS", DEFAULT_CLIP_COOLDOWN_SECS),
+            groq_failure_threshold: env_or(
+                "GROQ_FAILURE_THRESHOLD",
+                DEFAULT_GROQ_FAILURE_THRESHOLD,
+            )
+            .max(1),
+            groq_cooldown_secs: env_or("GROQ_COOLDOWN_SECS", DEFAULT_GROQ_COOLDOWN_SECS),
+            max_page_limit: env_limit("MAX_PAGE_LIMIT", MAX_PAGE_LIMIT),
+            max_message_limit: env_limit("MAX_MESSAGE_LIMIT", MAX_MESSAGE_LIMIT),
+            max_message_length: env_or("MAX_MESSAGE_LENGTH", MAX_MESSAGE_LENGTH),
+            message_type_max_length: env_keyed_map("MESSAGE_TYPE_MAX_LENGTH"),
+            max_order_limit: env_limit("MAX_ORDER_LIMIT", MAX_ORDER_LIMIT),
+            max_order_stats_months: env_or("MAX_ORDER_STATS_MONTHS", MAX_ORDER_STATS_MONTHS),
+            admin_user_ids: env_list("ADMIN_USER_IDS"),
+            reserved_usernames: DEFAULT_RESERVED_USERNAMES
+                .iter()
+                .map(|name| name.to_string())
+                .chain(env_list("RESERVED_USERNAMES"))
+                .map(|name| normalize_username(&name))
+                .collect(),
+            login_max_attempts: env_or("LOGIN_MAX_ATTEMPTS", DEFAULT_LOGIN_MAX_ATTEMPTS),
+            login_window_secs: env_or("LOGIN_WINDOW_SECS", DEFAULT_LOGIN_WINDOW_SECS),
+            argon2_memory_kib: env_or("ARGON2_MEMORY_KIB", DEFAULT_ARGON2_MEMORY_KIB),
+            argon2_iterations: env_or("ARGON2_ITERATIONS", DEFAULT_ARGON2_ITERATIONS),
+            argon2_parallelism: env_or("ARGON2_PARALLELISM", DEFAULT_ARGON2_PARALLELISM),
+            category_max_tags: env_keyed_map("CATEGORY_MAX_TAGS"),
+            category_max_questions: env_keyed_map("CATEGORY_MAX_QUESTIONS"),
+            embedding_max_images: env_or("EMBEDDING_MAX_IMAGES", DEFAULT_EMBEDDING_MAX_IMAGES)
+                .max(1),
+            max_product_quantity: env_or("MAX_PRODUCT_QUANTITY", DEFAULT_MAX_PRODUCT_QUANTITY),
+            product_creation_rate_limit: env_or(
+                "PRODUCT_CREATION_RATE_LIMIT",
+                DEFAULT_PRODUCT_CREATION_RATE_LIMIT,
+            )
+            .max(1),
+            max_active_listings: env_or("MAX_ACTIVE_LISTINGS", DEFAULT_MAX_ACTIVE_LISTINGS).max(1),
+            time_decay_interval_secs: env_or(
+                "TIME_DECAY_INTERVAL_SECS",
+                DEFAULT_TIME_DECAY_INTERVAL_SECS,
+            )
+            .max(1),
+            time_decay_batch_size: env_or("TIME_DECAY_BATCH_SIZE", DEFAULT_TIME_DECAY_BATCH_SIZE)
+                .max(1),
+            request_time_decay: env_or("REQUEST_TIME_DECAY", true),
+            not_interested_window_secs: env_or(
+                "NOT_INTERESTED_WINDOW_SECS",
+                DEFAULT_NOT_INTERESTED_WINDOW_SECS,
+            )
+            .max(1),
+            transcription_languages: {
+                let languages = env_list("TRANSCRIPTION_LANGUAGES");
+                if languages.is_empty() {
+                    DEFAULT_TRANSCRIPTION_LANGUAGES
+                        .iter()
+                        .map(|language| language.to_string())
+                        .collect()
+                } else {
+                    languages
+                        .into_iter()
+                        .map(|language| language.to_lowercase())
+                        .collect()
+                }
+            },
+            trusted_proxy_hops: env_or("TRUSTED_PROXY_HOPS", DEFAULT_TRUSTED_PROXY_HOPS),
+        }
+    }
+
+    #[inline]
+    pub fn is_admin(&self, user_id: &str) -> bool {
+        self.admin_user_ids.iter().any(|id| id == user_id)
+    }
+
+    #[inline]
+    pub fn is_reserved_username(&self, username: &str) -> bool {
+        self.reserved_usernames
+            .contains(&normalize_username(username))
+    }
+
+    #[inline]
+    pub fn max_tags_for(&self, category: ProductCategory) -> usize {
+        self.category_max_tags
+            .get(&category)
+            .copied()
+            .unwrap_or(MAX_TAGS_COUNT)
+    }
+
+    #[inline]
+    pub fn max_questions_for(&self, category: ProductCategory) -> usize {
+        self.category_max_questions
+            .get(&category)
+            .copied()
+            .unwrap_or(MAX_QUESTIONS_COUNT)
+    }
+
+    #[inline]
+    pub fn max_message_length_for(&self, message_type: MessageType) -> usize {
+        self.message_type_max_length
+            .get(&message_type)
+            .copied()
+            .unwrap_or(self.max_message_length)
+    }
+}
+
+// Compares usernames on their letters alone so "Admin_", "admin1" and
+// "no.reply" can't slip past the reserved list.
+fn normalize_username(username: &str) -> String {
+    username
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    parse_or(var(name).ok().as_deref(), default)
+}
+
+fn parse_or<T: FromStr>(value: Option<&str>, default: T) -> T {
+    value
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// Result limit ceilings below 1 would make every listing come back empty.
+fn env_limit(name: &str, default: u32) -> u32 {
+    limit_or(var(name).ok().as_deref(), default)
+}
+
+fn limit_or(value: Option<&str>, default: u32) -> u32 {
+    parse_or(value, default).max(1)
+}
+
+fn env_list(name: &str) -> Vec<String> {
+    var(name)
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn env_keyed_map<K: DeserializeOwned + Eq + Hash>(name: &str) -> HashMap<K, usize> {
+    env_list(name)
+        .into_iter()
+        .filter_map(|entry| {
+            let (key, limit) = entry.split_once('=')?;
+            let key =
+                serde_json::from_value(serde_json::Value::String(key.trim().to_string())).ok()?;
+            let limit = limit.trim().parse().ok()?;
+            Some((key, limit))
+        })
+        .collect()
+}
+
+pub fn init_config() {
+    let _ = CONFIG.set(AppConfig::from_env());
+}
+
+#[inline]
+pub fn config() -> &'static AppConfig {
+    CONFIG.get_or_init(AppConfig::from_env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserved_usernames_ignore_case_digits_and_separators() {
+        let config = AppConfig::from_env();
+
+        for username in [
+            "admin",
+            "Admin_",
+            "admin1",
+            "ADMIN-2",
+            "no.reply",
+            "Support__99",
+        ] {
+            assert!(config.is_reserved_username(username), "{username}");
+        }
+        for username in ["adminton", "alice", "helper_1"] {
+            assert!(!config.is_reserved_username(username), "{username}");
+        }
+    }
+
+    #[test]
+    fn configured_limit_ceilings_are_enforced() {
+        let ceiling = limit_or(Some(" 20 "), MAX_ORDER_LIMIT);
+        assert_eq!(ceiling, 20);
+        assert_eq!(50_u32.min(ceiling), 20);
+        assert_eq!(10_u32.min(ceiling), 10);
+    }
+
+    #[test]
+    fn limit_ceilings_fall_back_or_floor_at_one() {
+        assert_eq!(limit_or(None, MAX_PAGE_LIMIT), MAX_PAGE_LIMIT);
+        assert_eq!(limit_or(Some("lots"), MAX_PAGE_LIMIT), MAX_PAGE_LIMIT);
+        assert_eq!(limit_or(Some("0"), MAX_SEARCH_RESULTS), 1);
+    }
+}
@@ -1,8 +1,67 @@
 use axum::Json;
 use serde_json::json;
+use std::env::var;
+
+use crate::{
+    apex::circuit::CircuitState,
+    search::{clip::clip_circuit_status, groq::groq_circuit_status},
+};
+
+// Commit the binary was built from. Heroku exposes SOURCE_VERSION while
+// compiling; other deploys can set GIT_COMMIT_SHA at build time or at runtime.
+// An empty value at any step falls through to the next source.
+fn build_commit() -> Option<String> {
+    let non_empty = |commit: &&str| !commit.trim().is_empty();
+
+    option_env!("GIT_COMMIT_SHA")
+        .filter(non_empty)
+        .or(option_env!("SOURCE_VERSION").filter(non_empty))
+        .map(str::to_string)
+        .or_else(|| {
+            var("GIT_COMMIT_SHA")
+                .ok()
+                .filter(|commit| !commit.trim().is_empty())
+        })
+}
 
 pub async fn root_endpoint() -> Json<serde_json::Value> {
     Json(json!({
-        "message": "ok"
+        "message": "ok",
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "build": {
+            "commit": build_commit(),
+            "built_at": option_env!("BUILD_TIMESTAMP"),
+            "profile": if cfg!(debug_assertions) { "debug" } else { "release" }
+        }
     }))
 }
+
+pub async fn health_endpoint() -> Json<serde_json::Value> {
+    let clip = clip_circuit_status();
+    let groq = groq_circuit_status();
+    let status = if clip.state == CircuitState::Closed && groq.state == CircuitState::Closed {
+        "ok"
+    } else {
+        "degraded"
+    };
+
+    Json(json!({
+        "status": status,
+        "clip": clip,
+        "groq": groq
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn root_endpoint_reports_the_crate_version() {
+        let Json(body) = root_endpoint().await;
+
+        assert_eq!(body["message"], "ok");
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    }
+}
@@ -0,0 +1,135 @@
+use axum::{
+    body::Body,
+    http::{
+        HeaderMap, Request,
+        header::{ACCEPT_LANGUAGE, CONTENT_LENGTH},
+    },
+    middleware::Next,
+    response::Response,
+};
+
+use super::utils::{
+    ERROR_CODE_INVALID_PRICE, ERROR_CODE_PRODUCT_NOT_FOUND, ERROR_CODE_RATE_LIMITED,
+    ERROR_CODE_UNAUTHORIZED, ErrorCode, ErrorMessage,
+};
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+pub const SUPPORTED_LANGUAGES: [&str; 2] = ["en", "hi"];
+
+pub fn localized_error_message(code: &str, language: &str) -> Option<&'static str> {
+    match language {
+        "hi" => match code {
+            ERROR_CODE_PRODUCT_NOT_FOUND => Some("उत्पाद नहीं मिला"),
+            ERROR_CODE_INVALID_PRICE => Some("अमान्य मूल्य प्रारूप"),
+            ERROR_CODE_RATE_LIMITED => Some("बहुत अधिक अनुरोध। कृपया बाद में पुनः प्रयास करें"),
+            ERROR_CODE_UNAUTHORIZED => Some("अनधिकृत"),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub fn preferred_language(headers: &HeaderMap) -> &'static str {
+    let Some(header) = headers
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return DEFAULT_LANGUAGE;
+    };
+
+    let mut best: Option<(&'static str, f32)> = None;
+
+    for entry in header.split(',') {
+        let mut parts = entry.split(';').map(str::trim);
+        let tag = parts.next().unwrap_or_default();
+        let primary = tag
+            .split('-')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        let quality = parts
+            .find_map(|param| param.strip_prefix("q="))
+            .and_then(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        let Some(language) = SUPPORTED_LANGUAGES
+            .iter()
+            .find(|language| **language == primary)
+        else {
+            continue;
+        };
+
+        if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+            best = Some((language, quality));
+        }
+    }
+
+    best.map(|(language, _)| language)
+        .unwrap_or(DEFAULT_LANGUAGE)
+}
+
+pub async fn localize_errors(req: Request<Body>, next: Next) -> Response {
+    let language = preferred_language(req.headers());
+    let response = next.run(req).await;
+
+    if language == DEFAULT_LANGUAGE {
+        return response;
+    }
+
+    let Some(ErrorCode(code)) = response.extensions().get::<ErrorCode>().copied() else {
+        return response;
+    };
+
+    let Some(message) = localized_error_message(code, language) else {
+        return response;
+    };
+
+    let status = response.status();
+    let (mut parts, _) = response.into_parts();
+    let Ok(body) = serde_json::to_vec(&ErrorMessage::with_code(status, code, message.to_string()))
+    else {
+        return Response::from_parts(parts, Body::empty());
+    };
+
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(accept_language: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            ACCEPT_LANGUAGE,
+            HeaderValue::from_str(accept_language).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn hindi_accept_language_selects_hindi_messages() {
+        for accept_language in ["hi", "hi-IN", "hi-IN,en;q=0.8", "en;q=0.5, hi;q=0.9"] {
+            let language = preferred_language(&headers(accept_language));
+            assert_eq!(language, "hi", "{accept_language}");
+            assert_eq!(
+                localized_error_message(ERROR_CODE_PRODUCT_NOT_FOUND, language),
+                Some("उत्पाद नहीं मिला")
+            );
+        }
+    }
+
+    #[test]
+    fn unsupported_or_missing_languages_fall_back_to_english() {
+        assert_eq!(preferred_language(&HeaderMap::new()), DEFAULT_LANGUAGE);
+        assert_eq!(preferred_language(&headers("fr-FR")), DEFAULT_LANGUAGE);
+        assert_eq!(preferred_language(&headers("hi;q=0, fr")), DEFAULT_LANGUAGE);
+        assert_eq!(
+            localized_error_message(ERROR_CODE_PRODUCT_NOT_FOUND, DEFAULT_LANGUAGE),
+            None
+        );
+    }
+}
@@ -1,2 +1,5 @@
+pub mod circuit;
+pub mod config;
 pub mod endpoints;
+pub mod i18n;
 pub mod utils;
@@ -1,12 +1,33 @@
 use axum::{
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
+use image::{DynamicImage, ImageFormat};
+use mongodb::bson::{Document, doc};
 use serde::Serialize;
+use std::{
+    env::var,
+    io::Cursor,
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::config::config;
+use crate::DB;
+
+pub const ERROR_CODE_PRODUCT_NOT_FOUND: &str = "PRODUCT_NOT_FOUND";
+pub const ERROR_CODE_INVALID_PRICE: &str = "INVALID_PRICE";
+pub const ERROR_CODE_RATE_LIMITED: &str = "RATE_LIMITED";
+pub const ERROR_CODE_UNAUTHORIZED: &str = "UNAUTHORIZED";
+pub const ERROR_CODE_CLIP_UNAVAILABLE: &str = "CLIP_UNAVAILABLE";
+pub const DEFAULT_TRUSTED_PROXY_HOPS: usize = 1;
+pub const COLLECTIONS_MIGRATIONS: &str = "migrations";
 
 #[derive(Serialize)]
 pub struct ErrorMessage {
     pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
     pub message: String,
 }
 
@@ -15,14 +36,28 @@ impl ErrorMessage {
     pub fn new(_status: StatusCode, message: String) -> Self {
         Self {
             status: "error",
+            code: None,
+            message,
+        }
+    }
+
+    #[inline]
+    pub fn with_code(_status: StatusCode, code: &'static str, message: String) -> Self {
+        Self {
+            status: "error",
+            code: Some(code),
             message,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorCode(pub &'static str);
+
 #[derive(Debug)]
 pub enum VerboseHTTPError {
     Standard(StatusCode, String),
+    Coded(StatusCode, &'static str, String),
 }
 
 impl IntoResponse for VerboseHTTPError {
@@ -32,6 +67,225 @@ impl IntoResponse for VerboseHTTPError {
                 let error_message = ErrorMessage::new(status, message);
                 (status, axum::Json(error_message)).into_response()
             }
+            Self::Coded(status, code, message) => {
+                let error_message = ErrorMessage::with_code(status, code, message);
+                let mut response = (status, axum::Json(error_message)).into_response();
+                response.extensions_mut().insert(ErrorCode(code));
+                response
+            }
         }
     }
 }
+
+impl VerboseHTTPError {
+    #[inline]
+    pub fn product_not_found() -> Self {
+        Self::Coded(
+            StatusCode::NOT_FOUND,
+            ERROR_CODE_PRODUCT_NOT_FOUND,
+            "Product not found".to_string(),
+        )
+    }
+}
+
+#[inline]
+pub fn env_flag(name: &str) -> bool {
+    var(name)
+        .map(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes"
+            )
+        })
+        .unwrap_or(false)
+}
+
+// Each trusted proxy appends the address it received the request from, so
+// the entry `trusted_hops` from the right is the client. Entries further left
+// are client-supplied and ignored.
+fn forwarded_client_ip(forwarded_for: Option<&str>, trusted_hops: usize) -> Option<String> {
+    if trusted_hops == 0 {
+        return None;
+    }
+
+    let hops: Vec<&str> = forwarded_for?
+        .split(',')
+        .map(str::trim)
+        .filter(|ip| !ip.is_empty())
+        .collect();
+
+    hops.len()
+        .checked_sub(trusted_hops)
+        .map(|index| hops[index].to_string())
+}
+
+/// The client address as seen by the outermost trusted proxy, or the
+/// connection address when the request did not come through them.
+pub fn client_ip(headers: &HeaderMap, peer: SocketAddr) -> String {
+    let forwarded_for = headers
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok());
+
+    forwarded_client_ip(forwarded_for, config().trusted_proxy_hops)
+        .unwrap_or_else(|| peer.ip().to_string())
+}
+
+pub fn is_bot_user_agent(user_agent: &str) -> bool {
+    const BOT_MARKERS: [&str; 11] = [
+        "bot",
+        "crawl",
+        "spider",
+        "slurp",
+        "headless",
+        "lighthouse",
+        "facebookexternalhit",
+        "curl/",
+        "wget/",
+        "python-requests",
+        "go-http-client",
+    ];
+
+    let user_agent = user_agent.trim().to_ascii_lowercase();
+    user_agent.is_empty() || BOT_MARKERS.iter().any(|marker| user_agent.contains(marker))
+}
+
+#[inline]
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+pub fn normalize_embedding(mut embedding: Vec<f32>) -> Vec<f32> {
+    let norm = embedding
+        .iter()
+        .map(|value| value * value)
+        .sum::<f32>()
+        .sqrt();
+    if norm > f32::EPSILON {
+        embedding.iter_mut().for_each(|value| *value /= norm);
+    }
+    embedding
+}
+
+pub async fn resize_image(data: Vec<u8>, max_dimension: u32) -> Option<Vec<u8>> {
+    tokio::task::spawn_blocking(move || {
+        let image = image::load_from_memory(&data).ok()?;
+        let thumbnail =
+            DynamicImage::ImageRgb8(image.thumbnail(max_dimension, max_dimension).to_rgb8());
+
+        let mut buffer = Cursor::new(Vec::new());
+        thumbnail.write_to(&mut buffer, ImageFormat::Jpeg).ok()?;
+        Some(buffer.into_inner())
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Whether the one-time startup migration `name` has already completed.
+pub async fn migration_done(name: &str) -> bool {
+    let Some(database) = DB.get() else {
+        return false;
+    };
+
+    database
+        .collection::<Document>(COLLECTIONS_MIGRATIONS)
+        .find_one(doc! { "_id": name })
+        .await
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+pub async fn mark_migration_done(name: &str) {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    if let Err(error) = database
+        .collection::<Document>(COLLECTIONS_MIGRATIONS)
+        .update_one(
+            doc! { "_id": name },
+            doc! { "$set": { "completed_at": now_millis() as i64 } },
+        )
+        .upsert(true)
+        .await
+    {
+        tracing::warn!("Failed to record migration {}: {}", name, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bot_user_agents_are_recognized() {
+        assert!(is_bot_user_agent(""));
+        assert!(is_bot_user_agent("   "));
+        assert!(is_bot_user_agent("Mozilla/5.0 (compatible; Googlebot/2.1)"));
+        assert!(is_bot_user_agent("curl/8.4.0"));
+        assert!(is_bot_user_agent("Mozilla/5.0 HeadlessChrome/120.0"));
+        assert!(!is_bot_user_agent(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 Chrome/120.0 Safari/537.36"
+        ));
+    }
+
+    #[test]
+    fn forwarded_client_ip_skips_client_supplied_entries() {
+        assert_eq!(
+            forwarded_client_ip(Some("6.6.6.6, 203.0.113.7"), 1),
+            Some("203.0.113.7".to_string())
+        );
+        assert_eq!(
+            forwarded_client_ip(Some("6.6.6.6, 203.0.113.7, 10.0.0.2"), 2),
+            Some("203.0.113.7".to_string())
+        );
+    }
+
+    #[test]
+    fn forwarded_client_ip_needs_every_trusted_hop() {
+        assert_eq!(forwarded_client_ip(Some("203.0.113.7"), 2), None);
+        assert_eq!(forwarded_client_ip(None, 1), None);
+        assert_eq!(forwarded_client_ip(Some("203.0.113.7"), 0), None);
+    }
+
+    #[test]
+    fn normalized_embeddings_have_unit_length() {
+        let embedding = normalize_embedding(vec![3.0, -4.0, 12.0]);
+        let norm = embedding
+            .iter()
+            .map(|value| value * value)
+            .sum::<f32>()
+            .sqrt();
+        assert!((norm - 1.0).abs() < 1e-6);
+        assert!((embedding[0] - 3.0 / 13.0).abs() < 1e-6);
+        assert!((embedding[1] + 4.0 / 13.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn zero_embeddings_are_left_unchanged() {
+        let embedding = normalize_embedding(vec![0.0; 4]);
+        assert_eq!(embedding, vec![0.0; 4]);
+        assert!(embedding.iter().all(|value| value.is_finite()));
+    }
+
+    #[tokio::test]
+    async fn missing_products_return_the_product_not_found_code() {
+        let response = VerboseHTTPError::product_not_found().into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.extensions().get::<ErrorCode>().map(|code| code.0),
+            Some(ERROR_CODE_PRODUCT_NOT_FOUND)
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "PRODUCT_NOT_FOUND");
+        assert_eq!(body["message"], "Product not found");
+    }
+}
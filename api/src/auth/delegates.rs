@@ -1,29 +1,64 @@
 use argon2::{
-    Argon2,
-    password_hash::{PasswordHasher, SaltString, rand_core::OsRng},
+    Argon2, Params, Version,
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
 };
 use axum::http::StatusCode;
+use futures::TryStreamExt;
 use mongodb::{
-    Collection,
-    bson::{doc, to_bson},
+    Collection, IndexModel,
+    bson::{Document, doc, to_bson},
+    options::{IndexOptions, ReturnDocument},
 };
 use std::{
     sync::LazyLock,
     time::{SystemTime, UNIX_EPOCH},
 };
+use totp_rs::{Algorithm, Secret, TOTP};
 use uuid::Uuid;
 
-use super::schemas::{AuthObject, UserOut};
-use crate::{DB, apex::utils::VerboseHTTPError};
+use super::schemas::{
+    Address, AddressOut, AddressRequest, AuthObject, COLLECTIONS_ADDRESSES, EncryptedString,
+    LoginAttempts, MAX_ADDRESS_FIELD_LENGTH, MAX_ADDRESS_LABEL_LENGTH, MAX_ADDRESSES_PER_USER,
+    MAX_BIO_LENGTH, MAX_CITY_LENGTH, MAX_REGION_LENGTH, MAX_SESSIONS_PER_USER, MAX_USERNAME_LENGTH,
+    MIN_USERNAME_LENGTH, ProfileOut, PublicProfile, ShippingAddress, TOTP_CHALLENGE_EXPIRY_SECS,
+    TOTP_ISSUER, TotpChallenge, UpdateProfileRequest, UserOut, normalize_bio, normalize_city,
+    normalize_region, normalize_shipping_address,
+};
+use crate::{
+    DB,
+    apex::{config::config, utils::VerboseHTTPError},
+    products::schemas::Product,
+};
 
 const COLLECTIONS_USERS: &str = "users";
-
-static ARGON2: LazyLock<Argon2> = LazyLock::new(Argon2::default);
+const COOKIE_LIFETIME_SECS: u64 = 15_552_000;
+const COOKIE_REFRESH_WINDOW_SECS: u64 = 2_592_000;
+const COLLECTIONS_LOGIN_ATTEMPTS: &str = "login_attempts";
+
+static ARGON2: LazyLock<Argon2> = LazyLock::new(|| {
+    argon2_with(
+        config().argon2_memory_kib,
+        config().argon2_iterations,
+        config().argon2_parallelism,
+    )
+});
+
+// Only new hashes use these; stored hashes carry their own parameters and keep
+// verifying after they are tuned.
+fn argon2_with(memory_kib: u32, iterations: u32, parallelism: u32) -> Argon2<'static> {
+    match Params::new(memory_kib, iterations, parallelism, None) {
+        Ok(params) => Argon2::new(argon2::Algorithm::Argon2id, Version::V0x13, params),
+        Err(error) => {
+            tracing::warn!(%error, "Invalid Argon2 parameters, using the defaults");
+            Argon2::default()
+        }
+    }
+}
 
 #[inline]
 fn is_valid_password(pwd: &str) -> bool {
     let len = pwd.len();
-    if len < 8 || len > 32 {
+    if !(8..=32).contains(&len) {
         return false;
     }
 
@@ -41,6 +76,80 @@ fn is_valid_password(pwd: &str) -> bool {
     upper && lower && digit && symbol
 }
 
+pub fn validate_username(username: &str) -> Result<String, VerboseHTTPError> {
+    let username = username.trim();
+
+    if username.is_empty() {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Username is required".to_string(),
+        ));
+    }
+
+    if username.len() < MIN_USERNAME_LENGTH || username.len() > MAX_USERNAME_LENGTH {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Username must be between {} and {} characters",
+                MIN_USERNAME_LENGTH, MAX_USERNAME_LENGTH
+            ),
+        ));
+    }
+
+    if !username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+    {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Username may only contain letters, digits, '_', '.' and '-'".to_string(),
+        ));
+    }
+
+    if config().is_reserved_username(username) {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "This username is reserved".to_string(),
+        ));
+    }
+
+    Ok(username.to_string())
+}
+
+pub async fn backfill_username_lower() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    match users
+        .update_many(
+            doc! { "$or": [{ "username_lower": { "$exists": false } }, { "username_lower": "" }] },
+            vec![doc! { "$set": { "username_lower": { "$toLower": "$username" } } }],
+        )
+        .await
+    {
+        Ok(result) if result.modified_count > 0 => {
+            tracing::info!(
+                "Backfilled username_lower for {} users",
+                result.modified_count
+            );
+        }
+        Ok(_) => {}
+        Err(error) => tracing::warn!("Failed to backfill username_lower: {}", error),
+    }
+
+    let index = IndexModel::builder()
+        .keys(doc! { "username_lower": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+
+    if let Err(error) = users.create_index(index).await {
+        tracing::warn!("Failed to create unique username_lower index: {}", error);
+    }
+}
+
 pub async fn hash_password(password: String) -> Option<(String, String)> {
     if !is_valid_password(&password) {
         return None;
@@ -58,19 +167,13 @@ pub async fn hash_password(password: String) -> Option<(String, String)> {
     .flatten()
 }
 
-pub async fn verify_password(
-    plaintext_password: String,
-    salt: String,
-    hashed_password: String,
-) -> bool {
+pub async fn verify_password(plaintext_password: String, hashed_password: String) -> bool {
     tokio::task::spawn_blocking(move || {
-        SaltString::from_b64(&salt)
-            .ok()
-            .and_then(|salt_string| {
+        PasswordHash::new(&hashed_password)
+            .map(|parsed_hash| {
                 ARGON2
-                    .hash_password(plaintext_password.as_bytes(), &salt_string)
-                    .ok()
-                    .map(|hash| hash.to_string() == hashed_password)
+                    .verify_password(plaintext_password.as_bytes(), &parsed_hash)
+                    .is_ok()
             })
             .unwrap_or(false)
     })
@@ -78,21 +181,97 @@ pub async fn verify_password(
     .unwrap_or(false)
 }
 
-pub async fn generate_cookie(username: String) -> Option<AuthObject> {
+pub async fn login_lockout_remaining(username: &str) -> Option<u64> {
+    let database = DB.get()?;
+    let collection: Collection<LoginAttempts> = database.collection(COLLECTIONS_LOGIN_ATTEMPTS);
+
+    let attempts = collection
+        .find_one(doc! { "username": username })
+        .await
+        .ok()
+        .flatten()?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let window_end = attempts.window_started_at + config().login_window_secs;
+
+    (attempts.failed_count >= config().login_max_attempts && now < window_end)
+        .then(|| window_end - now)
+}
+
+pub async fn record_failed_login(username: &str) {
+    let Some(database) = DB.get() else {
+        return;
+    };
+    let collection: Collection<LoginAttempts> = database.collection(COLLECTIONS_LOGIN_ATTEMPTS);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let window_start = now.saturating_sub(config().login_window_secs) as i64;
+
+    let incremented = collection
+        .update_one(
+            doc! {
+                "username": username,
+                "window_started_at": { "$gt": window_start }
+            },
+            doc! { "$inc": { "failed_count": 1 } },
+        )
+        .await
+        .map(|result| result.matched_count > 0)
+        .unwrap_or(false);
+
+    if !incremented {
+        let _ = collection
+            .update_one(
+                doc! { "username": username },
+                doc! {
+                    "$set": {
+                        "failed_count": 1,
+                        "window_started_at": now as i64
+                    }
+                },
+            )
+            .upsert(true)
+            .await;
+    }
+}
+
+pub async fn clear_failed_logins(username: &str) {
+    let Some(database) = DB.get() else {
+        return;
+    };
+    let collection: Collection<LoginAttempts> = database.collection(COLLECTIONS_LOGIN_ATTEMPTS);
+
+    let _ = collection.delete_one(doc! { "username": username }).await;
+}
+
+pub async fn generate_cookie(username: String, device: Option<String>) -> Option<AuthObject> {
     let database = DB.get()?;
     let collection: Collection<UserOut> = database.collection("users");
 
-    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() + 15_552_000;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
 
     let auth_object = AuthObject {
+        session_id: Uuid::new_v4().to_string(),
         cookie: Uuid::new_v4().to_string(),
-        cookie_expire: now.to_string(),
+        cookie_expire: (now + COOKIE_LIFETIME_SECS).to_string(),
+        created_at: now,
+        device,
     };
 
     collection
         .update_one(
             doc! { "username": username },
-            doc! { "$set": { "auth": to_bson(&auth_object).ok()? } },
+            doc! {
+                "$push": {
+                    "sessions": {
+                        "$each": [to_bson(&auth_object).ok()?],
+                        "$slice": -MAX_SESSIONS_PER_USER
+                    }
+                }
+            },
         )
         .await
         .ok()?;
@@ -100,25 +279,168 @@ pub async fn generate_cookie(username: String) -> Option<AuthObject> {
     Some(auth_object)
 }
 
+#[inline]
+fn session_cookie_filter(cookie: &str) -> Document {
+    doc! {
+        "$or": [
+            { "sessions.cookie": cookie },
+            { "auth.cookie": cookie }
+        ]
+    }
+}
+
+pub async fn find_session_by_cookie(cookie: &str) -> Option<(UserOut, AuthObject)> {
+    let database = DB.get()?;
+    let collection: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    let mut user = collection
+        .find_one(session_cookie_filter(cookie))
+        .await
+        .ok()
+        .flatten()?;
+
+    if let Some(session) = user
+        .sessions
+        .iter()
+        .find(|session| session.cookie == cookie)
+    {
+        let session = session.clone();
+        return Some((user, session));
+    }
+
+    let legacy = user.auth.take().filter(|auth| auth.cookie == cookie)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let session = AuthObject {
+        session_id: Uuid::new_v4().to_string(),
+        cookie: legacy.cookie,
+        cookie_expire: legacy.cookie_expire,
+        created_at: now,
+        device: None,
+    };
+
+    collection
+        .update_one(
+            doc! { "uid": &user.uid, "auth.cookie": cookie },
+            doc! {
+                "$push": { "sessions": to_bson(&session).ok()? },
+                "$unset": { "auth": "" }
+            },
+        )
+        .await
+        .ok()?;
+
+    user.sessions.push(session.clone());
+    Some((user, session))
+}
+
+pub async fn refresh_cookie_if_expiring(cookie: &str, current_expire: u64) -> Option<u64> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+
+    if current_expire <= now || current_expire - now > COOKIE_REFRESH_WINDOW_SECS {
+        return None;
+    }
+
+    let database = DB.get()?;
+    let collection: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+    let new_expire = now + COOKIE_LIFETIME_SECS;
+
+    let result = collection
+        .update_one(
+            doc! {
+                "sessions": {
+                    "$elemMatch": {
+                        "cookie": cookie,
+                        "cookie-expire": current_expire.to_string()
+                    }
+                }
+            },
+            doc! { "$set": { "sessions.$.cookie-expire": new_expire.to_string() } },
+        )
+        .await
+        .ok()?;
+
+    (result.modified_count > 0).then_some(new_expire)
+}
+
 pub async fn kill_cookie(cookie: String) -> bool {
     let Some(database) = DB.get() else {
         return false;
     };
     let collection: Collection<UserOut> = database.collection("users");
 
-    let auth_object = AuthObject {
-        cookie: Uuid::new_v4().to_string(),
-        cookie_expire: "0".to_string(),
+    // A legacy `auth` cookie that was never migrated into `sessions` has to
+    // go as well, or it would keep working after logout.
+    collection
+        .update_one(
+            session_cookie_filter(&cookie),
+            vec![doc! {
+                "$set": {
+                    "sessions": {
+                        "$filter": {
+                            "input": { "$ifNull": ["$sessions", []] },
+                            "cond": { "$ne": ["$$this.cookie", &cookie] }
+                        }
+                    },
+                    "auth": {
+                        "$cond": [
+                            { "$eq": ["$auth.cookie", &cookie] },
+                            "$$REMOVE",
+                            "$auth"
+                        ]
+                    }
+                }
+            }],
+        )
+        .await
+        .is_ok()
+}
+
+pub async fn revoke_session(user: &UserOut, session_id: &str) -> Result<(), VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
     };
 
-    let Some(auth_bson) = to_bson(&auth_object).ok() else {
+    let collection: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    let result = collection
+        .update_one(
+            doc! { "uid": &user.uid, "sessions.session_id": session_id },
+            doc! { "$pull": { "sessions": { "session_id": session_id } } },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to revoke session".to_string(),
+            )
+        })?;
+
+    if result.modified_count == 0 {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "Session not found".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn revoke_all_sessions(uid: &str) -> bool {
+    let Some(database) = DB.get() else {
         return false;
     };
+    let collection: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
 
     collection
         .update_one(
-            doc! { "auth.cookie": cookie },
-            doc! { "$set": { "auth": auth_bson } },
+            doc! { "uid": uid },
+            doc! {
+                "$set": { "sessions": [] },
+                "$unset": { "auth": "" }
+            },
         )
         .await
         .is_ok()
@@ -129,7 +451,7 @@ pub async fn check_user_existence(username: &str, email: &str) -> Option<(bool,
     let collection: Collection<UserOut> = database.collection("users");
 
     let username_exists = collection
-        .find_one(doc! { "username": username })
+        .find_one(doc! { "username_lower": username.trim().to_lowercase() })
         .await
         .ok()
         .flatten()
@@ -153,16 +475,15 @@ pub async fn retrieve_user_by_username_or_email(
     let database = DB.get()?;
     let collection: Collection<UserOut> = database.collection("users");
 
-    if let Some(username) = username {
-        if let Some(user) = collection
-            .find_one(doc! { "username": username })
+    if let Some(username) = username
+        && let Some(user) = collection
+            .find_one(doc! { "username_lower": username.trim().to_lowercase() })
             .await
             .ok()
             .flatten()
-        {
-            let _ = user.initialize_encryption();
-            return Some(user);
-        }
+    {
+        let _ = user.initialize_encryption();
+        return Some(user);
     }
 
     if let Some(email) = email {
@@ -186,16 +507,12 @@ pub async fn change_password(
     old_password: String,
     new_password: String,
 ) -> Result<super::schemas::ChangePasswordResponse, VerboseHTTPError> {
-    use argon2::{
-        Argon2, PasswordHash, PasswordHasher, PasswordVerifier, password_hash::SaltString,
-    };
-
-    if !argon2::Argon2::default()
+    if ARGON2
         .verify_password(
             old_password.as_bytes(),
             &PasswordHash::new(&user.password).unwrap(),
         )
-        .is_ok()
+        .is_err()
     {
         return Err(VerboseHTTPError::Standard(
             StatusCode::UNAUTHORIZED,
@@ -204,7 +521,7 @@ pub async fn change_password(
     }
 
     let new_salt = SaltString::generate(&mut OsRng);
-    let new_password_hash = Argon2::default()
+    let new_password_hash = ARGON2
         .hash_password(new_password.as_bytes(), &new_salt)
         .map_err(|_| {
             VerboseHTTPError::Standard(
@@ -223,15 +540,13 @@ pub async fn change_password(
 
     let collection: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
 
+    // The argon2 hash carries its own salt. `user.salt` keys the encrypted
+    // fields (email, WhatsApp number, TOTP secret, addresses) and must stay
+    // fixed, or everything encrypted under it becomes unreadable.
     collection
         .update_one(
             doc! { "uid": &user.uid },
-            doc! {
-                "$set": {
-                    "password": &new_password_hash,
-                    "salt": new_salt.as_str()
-                }
-            },
+            doc! { "$set": { "password": &new_password_hash } },
         )
         .await
         .map_err(|_| {
@@ -247,25 +562,14 @@ pub async fn change_password(
     })
 }
 
-use rand::Rng;
-use sha2::{Digest, Sha256};
-
-const COLLECTIONS_OTP_VERIFICATIONS: &str = "otp_verifications";
-const OTP_EXPIRY_MINUTES: u64 = 10;
-const MAX_OTP_ATTEMPTS: u32 = 5;
-
-fn generate_otp() -> String {
-    let mut rng = rand::thread_rng();
-    (0..6).map(|_| rng.gen_range(0..10).to_string()).collect()
-}
-
-fn hash_otp(otp: &str, salt: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("{}{}", otp, salt));
-    format!("{:x}", hasher.finalize())
-}
+pub async fn delete_account(user: &UserOut, password: String) -> Result<(), VerboseHTTPError> {
+    if !verify_password(password, user.password.clone()).await {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::UNAUTHORIZED,
+            "Password is incorrect".to_string(),
+        ));
+    }
 
-pub async fn send_email_otp(email: &str) -> Result<(), VerboseHTTPError> {
     let Some(database) = DB.get() else {
         return Err(VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -275,50 +579,321 @@ pub async fn send_email_otp(email: &str) -> Result<(), VerboseHTTPError> {
 
     let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
 
-    if let Ok(Some(user)) = users
-        .find_one(doc! { "email_hash": super::schemas::create_email_hash(email) })
+    users
+        .update_one(
+            doc! { "uid": &user.uid },
+            doc! {
+                "$set": {
+                    "enabled": false,
+                    "sessions": []
+                },
+                "$unset": { "auth": "" }
+            },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to delete account".to_string(),
+            )
+        })?;
+
+    let products: Collection<Product> = database.collection("products");
+
+    if let Err(error) = products
+        .update_many(
+            doc! { "user_id": &user.uid },
+            doc! { "$set": { "enabled": false } },
+        )
         .await
     {
-        if user.email_verified {
-            return Err(VerboseHTTPError::Standard(
-                StatusCode::BAD_REQUEST,
-                "Email already verified".to_string(),
-            ));
-        }
-    } else {
+        tracing::warn!(
+            "Failed to disable products for deleted user {}: {}",
+            user.uid,
+            error
+        );
     }
 
-    let otp = generate_otp();
-    let salt = Uuid::new_v4().to_string();
-    let otp_hash = hash_otp(&otp, &salt);
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let expires_at = now + (OTP_EXPIRY_MINUTES * 60);
+    let addresses: Collection<Address> = database.collection(COLLECTIONS_ADDRESSES);
 
-    let verification = super::schemas::OTPVerification {
-        identifier: email.to_string(),
-        otp_hash: format!("{}:{}", otp_hash, salt),
-        created_at: now,
-        expires_at,
-        attempts: 0,
-        verification_type: "email".to_string(),
-    };
+    if let Err(error) = addresses.delete_many(doc! { "user_id": &user.uid }).await {
+        tracing::warn!(
+            "Failed to delete addresses for deleted user {}: {}",
+            user.uid,
+            error
+        );
+    }
 
-    let otps: Collection<super::schemas::OTPVerification> =
-        database.collection(COLLECTIONS_OTP_VERIFICATIONS);
+    Ok(())
+}
 
-    let _ = otps
-        .delete_many(doc! { "identifier": email, "verification_type": "email" })
-        .await;
+const COLLECTIONS_TOTP_CHALLENGES: &str = "totp_challenges";
 
-    otps.insert_one(&verification).await.map_err(|_| {
-        VerboseHTTPError::Standard(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to store OTP".to_string(),
-        )
-    })?;
+fn build_totp(encoded_secret: &str, account_name: &str) -> Option<TOTP> {
+    let secret = Secret::Encoded(encoded_secret.to_string())
+        .to_bytes()
+        .ok()?;
+
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret,
+        Some(TOTP_ISSUER.to_string()),
+        account_name.to_string(),
+    )
+    .ok()
+}
+
+fn check_totp_code(user: &UserOut, code: &str) -> bool {
+    let Some(ref totp_secret) = user.totp_secret else {
+        return false;
+    };
+
+    build_totp(totp_secret, &user.username)
+        .and_then(|totp| totp.check_current(code.trim()).ok())
+        .unwrap_or(false)
+}
+
+pub async fn enable_totp(user: &UserOut) -> Result<String, VerboseHTTPError> {
+    if user.totp_enabled {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    let Secret::Encoded(encoded_secret) = Secret::generate_secret().to_encoded() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to generate TOTP secret".to_string(),
+        ));
+    };
+
+    let Some(totp) = build_totp(&encoded_secret, &user.username) else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to generate TOTP secret".to_string(),
+        ));
+    };
+
+    let encrypted_secret = EncryptedString::new(&encoded_secret, &user.salt)
+        .ok()
+        .and_then(|secret| to_bson(&secret).ok())
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to store TOTP secret".to_string(),
+            )
+        })?;
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    users
+        .update_one(
+            doc! { "uid": &user.uid },
+            doc! {
+                "$set": {
+                    "totp_secret": encrypted_secret,
+                    "totp_enabled": false
+                }
+            },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to store TOTP secret".to_string(),
+            )
+        })?;
+
+    Ok(totp.get_url())
+}
+
+pub async fn confirm_totp(user: &UserOut, code: &str) -> Result<(), VerboseHTTPError> {
+    if user.totp_enabled {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    if user.totp_secret.is_none() {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Two-factor authentication setup has not been started".to_string(),
+        ));
+    }
+
+    if !verify_totp(user, code) {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Invalid authentication code".to_string(),
+        ));
+    }
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    users
+        .update_one(
+            doc! { "uid": &user.uid },
+            doc! { "$set": { "totp_enabled": true } },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to enable two-factor authentication".to_string(),
+            )
+        })?;
+
+    Ok(())
+}
+
+#[inline]
+pub fn verify_totp(user: &UserOut, code: &str) -> bool {
+    check_totp_code(user, code)
+}
+
+pub async fn create_totp_challenge(user: &UserOut) -> Option<String> {
+    let database = DB.get()?;
+    let challenges: Collection<TotpChallenge> = database.collection(COLLECTIONS_TOTP_CHALLENGES);
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let challenge = TotpChallenge {
+        token: Uuid::new_v4().to_string(),
+        uid: user.uid.clone(),
+        expires_at: now + TOTP_CHALLENGE_EXPIRY_SECS,
+    };
+
+    let _ = challenges.delete_many(doc! { "uid": &user.uid }).await;
+    challenges.insert_one(&challenge).await.ok()?;
+
+    Some(challenge.token)
+}
+
+pub async fn resolve_totp_challenge(token: &str) -> Option<UserOut> {
+    let database = DB.get()?;
+    let challenges: Collection<TotpChallenge> = database.collection(COLLECTIONS_TOTP_CHALLENGES);
+
+    let challenge = challenges
+        .find_one(doc! { "token": token })
+        .await
+        .ok()
+        .flatten()?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    if challenge.expires_at <= now {
+        let _ = challenges.delete_one(doc! { "token": token }).await;
+        return None;
+    }
+
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+    let user = users
+        .find_one(doc! { "uid": &challenge.uid, "enabled": true })
+        .await
+        .ok()
+        .flatten()?;
+
+    let _ = user.initialize_encryption();
+    Some(user)
+}
+
+pub async fn consume_totp_challenge(token: &str) {
+    let Some(database) = DB.get() else {
+        return;
+    };
+    let challenges: Collection<TotpChallenge> = database.collection(COLLECTIONS_TOTP_CHALLENGES);
+
+    let _ = challenges.delete_one(doc! { "token": token }).await;
+}
+
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+const COLLECTIONS_OTP_VERIFICATIONS: &str = "otp_verifications";
+const OTP_EXPIRY_MINUTES: u64 = 10;
+const MAX_OTP_ATTEMPTS: u32 = 5;
+const OTP_TYPE_PASSWORD_RESET: &str = "password_reset";
+
+fn generate_otp() -> String {
+    let mut rng = rand::thread_rng();
+    (0..6).map(|_| rng.gen_range(0..10).to_string()).collect()
+}
+
+fn hash_otp(otp: &str, salt: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}{}", otp, salt));
+    format!("{:x}", hasher.finalize())
+}
+
+pub async fn send_email_otp(email: &str) -> Result<(), VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    if let Ok(Some(user)) = users
+        .find_one(doc! { "email_hash": super::schemas::create_email_hash(email) })
+        .await
+        && user.email_verified
+    {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Email already verified".to_string(),
+        ));
+    }
+
+    let otp = generate_otp();
+    let salt = Uuid::new_v4().to_string();
+    let otp_hash = hash_otp(&otp, &salt);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let expires_at = now + (OTP_EXPIRY_MINUTES * 60);
+
+    let verification = super::schemas::OTPVerification {
+        identifier: email.to_string(),
+        otp_hash: format!("{}:{}", otp_hash, salt),
+        created_at: now,
+        expires_at,
+        attempts: 0,
+        verification_type: "email".to_string(),
+    };
+
+    let otps: Collection<super::schemas::OTPVerification> =
+        database.collection(COLLECTIONS_OTP_VERIFICATIONS);
+
+    let _ = otps
+        .delete_many(doc! { "identifier": email, "verification_type": "email" })
+        .await;
+
+    otps.insert_one(&verification).await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to store OTP".to_string(),
+        )
+    })?;
 
     match crate::notifications::delegates::send_email_internal(
         email,
@@ -437,7 +1012,7 @@ pub async fn verify_email_otp(email: &str, otp: &str) -> Result<(), VerboseHTTPE
     Ok(())
 }
 
-pub async fn send_whatsapp_otp(whatsapp_number: &str) -> Result<(), VerboseHTTPError> {
+pub async fn send_password_reset_otp(email: &str) -> Result<(), VerboseHTTPError> {
     let Some(database) = DB.get() else {
         return Err(VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -446,25 +1021,15 @@ pub async fn send_whatsapp_otp(whatsapp_number: &str) -> Result<(), VerboseHTTPE
     };
 
     let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
-    let mut whatsapp_already_verified = false;
 
-    if let Ok(mut cursor) = users.find(doc! {}).await {
-        use futures::TryStreamExt;
-        while let Ok(Some(user)) = cursor.try_next().await {
-            if let Some(ref whatsapp) = user.whatsapp_number {
-                if user.whatsapp_verified && whatsapp.to_string() == whatsapp_number {
-                    whatsapp_already_verified = true;
-                    break;
-                }
-            }
-        }
-    }
+    let user_exists = users
+        .count_documents(doc! { "email_hash": super::schemas::create_email_hash(email) })
+        .await
+        .map(|count| count > 0)
+        .unwrap_or(false);
 
-    if whatsapp_already_verified {
-        return Err(VerboseHTTPError::Standard(
-            StatusCode::BAD_REQUEST,
-            "WhatsApp number already verified".to_string(),
-        ));
+    if !user_exists {
+        return Ok(());
     }
 
     let otp = generate_otp();
@@ -474,22 +1039,21 @@ pub async fn send_whatsapp_otp(whatsapp_number: &str) -> Result<(), VerboseHTTPE
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
-    let expires_at = now + (OTP_EXPIRY_MINUTES * 60);
 
     let verification = super::schemas::OTPVerification {
-        identifier: whatsapp_number.to_string(),
+        identifier: email.to_string(),
         otp_hash: format!("{}:{}", otp_hash, salt),
         created_at: now,
-        expires_at,
+        expires_at: now + (OTP_EXPIRY_MINUTES * 60),
         attempts: 0,
-        verification_type: "whatsapp".to_string(),
+        verification_type: OTP_TYPE_PASSWORD_RESET.to_string(),
     };
 
     let otps: Collection<super::schemas::OTPVerification> =
         database.collection(COLLECTIONS_OTP_VERIFICATIONS);
 
     let _ = otps
-        .delete_many(doc! { "identifier": whatsapp_number, "verification_type": "whatsapp" })
+        .delete_many(doc! { "identifier": email, "verification_type": OTP_TYPE_PASSWORD_RESET })
         .await;
 
     otps.insert_one(&verification).await.map_err(|_| {
@@ -499,20 +1063,36 @@ pub async fn send_whatsapp_otp(whatsapp_number: &str) -> Result<(), VerboseHTTPE
         )
     })?;
 
-    crate::notifications::delegates::send_whatsapp_internal(
-        whatsapp_number,
-        &format!("Your GoodsPoint verification code is: {}", otp),
-    )
-    .await?;
+    if let Err(VerboseHTTPError::Standard(_, message)) =
+        crate::notifications::delegates::send_email_internal(
+            email,
+            None,
+            "Password Reset - GoodsPoint",
+            &format!(
+                "Your password reset code is: {}. If you did not request this, you can ignore this email.",
+                otp
+            ),
+        )
+        .await
+    {
+        tracing::warn!("Failed to send password reset email: {}", message);
+    }
 
     Ok(())
 }
 
-pub async fn verify_whatsapp_otp(
-    user: &UserOut,
-    whatsapp_number: &str,
+pub async fn reset_password(
+    email: &str,
     otp: &str,
+    new_password: String,
 ) -> Result<(), VerboseHTTPError> {
+    if !is_valid_password(&new_password) {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Invalid password".to_string(),
+        ));
+    }
+
     let Some(database) = DB.get() else {
         return Err(VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -522,9 +1102,17 @@ pub async fn verify_whatsapp_otp(
 
     let otps: Collection<super::schemas::OTPVerification> =
         database.collection(COLLECTIONS_OTP_VERIFICATIONS);
+    let otp_filter = doc! { "identifier": email, "verification_type": OTP_TYPE_PASSWORD_RESET };
+
+    let invalid_otp = || {
+        VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Invalid or expired OTP".to_string(),
+        )
+    };
 
     let verification = otps
-        .find_one(doc! { "identifier": whatsapp_number, "verification_type": "whatsapp" })
+        .find_one(otp_filter.clone())
         .await
         .map_err(|_| {
             VerboseHTTPError::Standard(
@@ -532,34 +1120,483 @@ pub async fn verify_whatsapp_otp(
                 "Database error".to_string(),
             )
         })?
-        .ok_or_else(|| {
-            VerboseHTTPError::Standard(
-                StatusCode::NOT_FOUND,
-                "No verification request found".to_string(),
-            )
-        })?;
+        .ok_or_else(invalid_otp)?;
 
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
 
-    if now > verification.expires_at {
-        let _ = otps
-            .delete_one(doc! { "identifier": whatsapp_number, "verification_type": "whatsapp" })
-            .await;
+    if now > verification.expires_at || verification.attempts >= MAX_OTP_ATTEMPTS {
+        let _ = otps.delete_one(otp_filter).await;
+        return Err(invalid_otp());
+    }
+
+    let Some((stored_hash, salt)) = verification.otp_hash.split_once(':') else {
         return Err(VerboseHTTPError::Standard(
-            StatusCode::BAD_REQUEST,
-            "OTP expired".to_string(),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Invalid OTP format".to_string(),
         ));
-    }
+    };
 
-    if verification.attempts >= MAX_OTP_ATTEMPTS {
+    if hash_otp(otp, salt) != stored_hash {
         let _ = otps
-            .delete_one(doc! { "identifier": whatsapp_number, "verification_type": "whatsapp" })
+            .update_one(otp_filter, doc! { "$inc": { "attempts": 1 } })
             .await;
+        return Err(invalid_otp());
+    }
+
+    let Some((password_hash, _)) = hash_password(new_password).await else {
         return Err(VerboseHTTPError::Standard(
-            StatusCode::BAD_REQUEST,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to hash new password".to_string(),
+        ));
+    };
+
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    users
+        .update_one(
+            doc! { "email_hash": super::schemas::create_email_hash(email) },
+            doc! {
+                "$set": {
+                    "password": password_hash,
+                    "sessions": []
+                },
+                "$unset": { "auth": "" }
+            },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to reset password".to_string(),
+            )
+        })?;
+
+    let _ = otps.delete_one(otp_filter).await;
+
+    Ok(())
+}
+
+pub async fn remove_whatsapp(user: &UserOut, password: String) -> Result<(), VerboseHTTPError> {
+    if !verify_password(password, user.password.clone()).await {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::UNAUTHORIZED,
+            "Password is incorrect".to_string(),
+        ));
+    }
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    users
+        .update_one(
+            doc! { "uid": &user.uid },
+            doc! {
+                "$set": {
+                    "whatsapp_number": null,
+                    "whatsapp_verified": false
+                },
+                "$unset": { "whatsapp_hash": "" }
+            },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to remove WhatsApp number".to_string(),
+            )
+        })?;
+
+    Ok(())
+}
+
+pub async fn set_trusted_seller(uid: &str, trusted: bool) -> Result<(), VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    let result = users
+        .update_one(
+            doc! { "uid": uid },
+            doc! { "$set": { "trusted_seller": trusted } },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update user".to_string(),
+            )
+        })?;
+
+    if result.matched_count == 0 {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "User not found".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn update_profile(
+    user: &UserOut,
+    request: UpdateProfileRequest,
+) -> Result<ProfileOut, VerboseHTTPError> {
+    if request.region.is_none()
+        && request.city.is_none()
+        && request.bio.is_none()
+        && request.notification_preferences.is_none()
+    {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "No profile fields to update".to_string(),
+        ));
+    }
+
+    let region = match request.region.as_deref().map(str::trim) {
+        Some("") => None,
+        Some(region) => Some(normalize_region(region).ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Region must be 1-{} letters, digits, hyphens or underscores",
+                    MAX_REGION_LENGTH
+                ),
+            )
+        })?),
+        None => user.region.clone(),
+    };
+
+    let city = match request.city.as_deref().map(str::trim) {
+        Some("") => None,
+        Some(city) => Some(normalize_city(city).ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "City must be 1-{} characters of letters and spaces",
+                    MAX_CITY_LENGTH
+                ),
+            )
+        })?),
+        None => user.city.clone(),
+    };
+
+    let bio = match request.bio.as_deref().map(str::trim) {
+        Some("") => None,
+        Some(bio) => Some(normalize_bio(bio).ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Bio must be at most {} characters of plain text",
+                    MAX_BIO_LENGTH
+                ),
+            )
+        })?),
+        None => user.bio.clone(),
+    };
+
+    let notification_preferences = request
+        .notification_preferences
+        .unwrap_or(user.notification_preferences);
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    users
+        .update_one(
+            doc! { "uid": &user.uid },
+            doc! {
+                "$set": {
+                    "region": &region,
+                    "city": &city,
+                    "bio": &bio,
+                    "notification_preferences": {
+                        "email": notification_preferences.email,
+                        "whatsapp": notification_preferences.whatsapp
+                    }
+                }
+            },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update profile".to_string(),
+            )
+        })?;
+
+    if region != user.region || city != user.city {
+        let products: Collection<Product> = database.collection("products");
+
+        products
+            .update_many(
+                doc! { "user_id": &user.uid },
+                doc! { "$set": { "region": &region, "city": &city } },
+            )
+            .await
+            .map_err(|_| {
+                VerboseHTTPError::Standard(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to update product locations".to_string(),
+                )
+            })?;
+    }
+
+    Ok(ProfileOut {
+        region,
+        city,
+        bio,
+        notification_preferences,
+    })
+}
+
+pub async fn get_public_profile(username: &str) -> Result<PublicProfile, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    let user = users
+        .find_one(doc! { "username_lower": username.trim().to_lowercase(), "enabled": true })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "User not found".to_string())
+        })?;
+
+    let products: Collection<Product> = database.collection("products");
+
+    let product_count = products
+        .count_documents(doc! { "user_id": &user.uid, "enabled": true, "status": "published" })
+        .await
+        .unwrap_or(0);
+
+    Ok(PublicProfile {
+        username: user.username,
+        bio: user.bio,
+        region: user.region,
+        city: user.city,
+        product_count,
+    })
+}
+
+async fn whatsapp_number_taken(
+    users: &Collection<UserOut>,
+    user: &UserOut,
+    whatsapp_number: &str,
+) -> Result<bool, VerboseHTTPError> {
+    users
+        .find_one(doc! {
+            "whatsapp_hash": super::schemas::create_whatsapp_hash(whatsapp_number),
+            "whatsapp_verified": true,
+            "uid": { "$ne": &user.uid }
+        })
+        .await
+        .map(|existing| existing.is_some())
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })
+}
+
+#[inline]
+fn whatsapp_number_taken_error() -> VerboseHTTPError {
+    VerboseHTTPError::Standard(
+        StatusCode::CONFLICT,
+        "WhatsApp number is already verified by another account".to_string(),
+    )
+}
+
+pub async fn backfill_whatsapp_hashes() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    if let Ok(mut cursor) = users
+        .find(doc! {
+            "whatsapp_number": { "$ne": null },
+            "whatsapp_hash": { "$exists": false }
+        })
+        .await
+    {
+        while let Ok(Some(user)) = cursor.try_next().await {
+            let Some(ref whatsapp) = user.whatsapp_number else {
+                continue;
+            };
+            if whatsapp.set_salt(&user.salt).is_err() {
+                continue;
+            }
+
+            let _ = users
+                .update_one(
+                    doc! { "uid": &user.uid },
+                    doc! {
+                        "$set": {
+                            "whatsapp_hash": super::schemas::create_whatsapp_hash(whatsapp)
+                        }
+                    },
+                )
+                .await;
+        }
+    }
+
+    let index = IndexModel::builder()
+        .keys(doc! { "whatsapp_hash": 1 })
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "whatsapp_verified": true })
+                .build(),
+        )
+        .build();
+
+    if let Err(error) = users.create_index(index).await {
+        tracing::warn!("Failed to create unique whatsapp_hash index: {}", error);
+    }
+}
+
+pub async fn send_whatsapp_otp(
+    user: &UserOut,
+    whatsapp_number: &str,
+) -> Result<(), VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    if whatsapp_number_taken(&users, user, whatsapp_number).await? {
+        return Err(whatsapp_number_taken_error());
+    }
+
+    let otp = generate_otp();
+    let salt = Uuid::new_v4().to_string();
+    let otp_hash = hash_otp(&otp, &salt);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let expires_at = now + (OTP_EXPIRY_MINUTES * 60);
+
+    let verification = super::schemas::OTPVerification {
+        identifier: whatsapp_number.to_string(),
+        otp_hash: format!("{}:{}", otp_hash, salt),
+        created_at: now,
+        expires_at,
+        attempts: 0,
+        verification_type: "whatsapp".to_string(),
+    };
+
+    let otps: Collection<super::schemas::OTPVerification> =
+        database.collection(COLLECTIONS_OTP_VERIFICATIONS);
+
+    let _ = otps
+        .delete_many(doc! { "identifier": whatsapp_number, "verification_type": "whatsapp" })
+        .await;
+
+    otps.insert_one(&verification).await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to store OTP".to_string(),
+        )
+    })?;
+
+    crate::notifications::delegates::send_whatsapp_internal(
+        whatsapp_number,
+        &format!("Your GoodsPoint verification code is: {}", otp),
+    )
+    .await?;
+
+    Ok(())
+}
+
+pub async fn verify_whatsapp_otp(
+    user: &UserOut,
+    whatsapp_number: &str,
+    otp: &str,
+) -> Result<(), VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let otps: Collection<super::schemas::OTPVerification> =
+        database.collection(COLLECTIONS_OTP_VERIFICATIONS);
+
+    let verification = otps
+        .find_one(doc! { "identifier": whatsapp_number, "verification_type": "whatsapp" })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::NOT_FOUND,
+                "No verification request found".to_string(),
+            )
+        })?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    if now > verification.expires_at {
+        let _ = otps
+            .delete_one(doc! { "identifier": whatsapp_number, "verification_type": "whatsapp" })
+            .await;
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "OTP expired".to_string(),
+        ));
+    }
+
+    if verification.attempts >= MAX_OTP_ATTEMPTS {
+        let _ = otps
+            .delete_one(doc! { "identifier": whatsapp_number, "verification_type": "whatsapp" })
+            .await;
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
             "Too many attempts".to_string(),
         ));
     }
@@ -590,6 +1627,12 @@ pub async fn verify_whatsapp_otp(
         ));
     }
 
+    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
+
+    if whatsapp_number_taken(&users, user, whatsapp_number).await? {
+        return Err(whatsapp_number_taken_error());
+    }
+
     let encrypted_whatsapp = super::schemas::EncryptedString::new(whatsapp_number, &user.salt)
         .map_err(|_| {
             VerboseHTTPError::Standard(
@@ -598,23 +1641,26 @@ pub async fn verify_whatsapp_otp(
             )
         })?;
 
-    let users: Collection<UserOut> = database.collection(COLLECTIONS_USERS);
     users
         .update_one(
             doc! { "uid": &user.uid },
             doc! {
                 "$set": {
                     "whatsapp_number": to_bson(&encrypted_whatsapp).unwrap(),
+                    "whatsapp_hash": super::schemas::create_whatsapp_hash(whatsapp_number),
                     "whatsapp_verified": true
                 }
             },
         )
         .await
-        .map_err(|_| {
-            VerboseHTTPError::Standard(
+        .map_err(|error| match *error.kind {
+            mongodb::error::ErrorKind::Write(mongodb::error::WriteFailure::WriteError(
+                ref write_error,
+            )) if write_error.code == 11000 => whatsapp_number_taken_error(),
+            _ => VerboseHTTPError::Standard(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Failed to verify WhatsApp number".to_string(),
-            )
+            ),
         })?;
 
     let _ = otps
@@ -623,3 +1669,301 @@ pub async fn verify_whatsapp_otp(
 
     Ok(())
 }
+
+fn addresses_collection() -> Result<Collection<Address>, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    Ok(database.collection(COLLECTIONS_ADDRESSES))
+}
+
+fn validate_address_request(
+    request: &AddressRequest,
+) -> Result<(Option<String>, ShippingAddress), VerboseHTTPError> {
+    let label = match request.label.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(label) if label.chars().count() > MAX_ADDRESS_LABEL_LENGTH => {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Address label too long. Maximum {} characters allowed",
+                    MAX_ADDRESS_LABEL_LENGTH
+                ),
+            ));
+        }
+        Some(label) => Some(label.to_string()),
+    };
+
+    let address = normalize_shipping_address(&request.address).ok_or_else(|| {
+        VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Invalid address. full_name, phone, line1, city, state, postal_code and country \
+                 are required, up to {} characters each",
+                MAX_ADDRESS_FIELD_LENGTH
+            ),
+        )
+    })?;
+
+    Ok((label, address))
+}
+
+fn encrypt_address(
+    user: &UserOut,
+    address: &ShippingAddress,
+) -> Result<EncryptedString, VerboseHTTPError> {
+    serde_json::to_string(address)
+        .ok()
+        .and_then(|json| EncryptedString::new(&json, &user.salt).ok())
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to encrypt address".to_string(),
+            )
+        })
+}
+
+fn address_out(user: &UserOut, address: Address) -> Result<AddressOut, VerboseHTTPError> {
+    let decrypt_error = || {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to decrypt address".to_string(),
+        )
+    };
+
+    address
+        .details
+        .set_salt(&user.salt)
+        .map_err(|_| decrypt_error())?;
+    let details: ShippingAddress =
+        serde_json::from_str(&address.details).map_err(|_| decrypt_error())?;
+
+    Ok(AddressOut {
+        address_id: address.address_id,
+        label: address.label,
+        address: details,
+        created_at: address.created_at,
+        updated_at: address.updated_at,
+    })
+}
+
+pub async fn list_addresses(user: &UserOut) -> Result<Vec<AddressOut>, VerboseHTTPError> {
+    let addresses: Vec<Address> = addresses_collection()?
+        .find(doc! { "user_id": &user.uid })
+        .sort(doc! { "created_at": 1 })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    addresses
+        .into_iter()
+        .map(|address| address_out(user, address))
+        .collect()
+}
+
+pub async fn create_address(
+    user: &UserOut,
+    request: AddressRequest,
+) -> Result<AddressOut, VerboseHTTPError> {
+    let (label, details) = validate_address_request(&request)?;
+    let collection = addresses_collection()?;
+
+    let existing = collection
+        .count_documents(doc! { "user_id": &user.uid })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    if existing >= MAX_ADDRESSES_PER_USER {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!("You can save at most {} addresses", MAX_ADDRESSES_PER_USER),
+        ));
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let address = Address {
+        address_id: Uuid::new_v4().to_string(),
+        user_id: user.uid.clone(),
+        label,
+        details: encrypt_address(user, &details)?,
+        created_at: now,
+        updated_at: now,
+    };
+
+    collection.insert_one(&address).await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to save address".to_string(),
+        )
+    })?;
+
+    Ok(AddressOut {
+        address_id: address.address_id,
+        label: address.label,
+        address: details,
+        created_at: now,
+        updated_at: now,
+    })
+}
+
+pub async fn update_address(
+    user: &UserOut,
+    address_id: &str,
+    request: AddressRequest,
+) -> Result<AddressOut, VerboseHTTPError> {
+    let (label, details) = validate_address_request(&request)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let encrypted = to_bson(&encrypt_address(user, &details)?).map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to encrypt address".to_string(),
+        )
+    })?;
+
+    let updated = addresses_collection()?
+        .find_one_and_update(
+            doc! { "address_id": address_id, "user_id": &user.uid },
+            doc! {
+                "$set": {
+                    "label": label,
+                    "details": encrypted,
+                    "updated_at": now as i64
+                }
+            },
+        )
+        .return_document(ReturnDocument::After)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update address".to_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "Address not found".to_string())
+        })?;
+
+    Ok(AddressOut {
+        address_id: updated.address_id,
+        label: updated.label,
+        address: details,
+        created_at: updated.created_at,
+        updated_at: updated.updated_at,
+    })
+}
+
+pub async fn delete_address(user: &UserOut, address_id: &str) -> Result<(), VerboseHTTPError> {
+    let result = addresses_collection()?
+        .delete_one(doc! { "address_id": address_id, "user_id": &user.uid })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to delete address".to_string(),
+            )
+        })?;
+
+    if result.deleted_count == 0 {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "Address not found".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Orders keep a copy of the address rather than the id, so later edits or
+// deletions never rewrite where a past order was shipped.
+pub async fn shipping_address_snapshot(
+    user: &UserOut,
+    address_id: &str,
+) -> Result<ShippingAddress, VerboseHTTPError> {
+    let address = addresses_collection()?
+        .find_one(doc! { "address_id": address_id, "user_id": &user.uid })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                "Shipping address not found".to_string(),
+            )
+        })?;
+
+    Ok(address_out(user, address)?.address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_with(argon2: &Argon2, password: &str) -> String {
+        argon2
+            .hash_password(password.as_bytes(), &SaltString::generate(&mut OsRng))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn configured_argon2_parameters_are_used_for_new_hashes() {
+        let hash = hash_with(&ARGON2, "correct horse");
+        assert!(hash.contains(&format!(
+            "m={},t={},p={}",
+            config().argon2_memory_kib,
+            config().argon2_iterations,
+            config().argon2_parallelism
+        )));
+
+        let tuned = hash_with(&argon2_with(8192, 3, 2), "correct horse");
+        assert!(tuned.starts_with("$argon2id$v=19$m=8192,t=3,p=2$"));
+
+        let fallback = hash_with(&argon2_with(0, 0, 0), "correct horse");
+        assert!(fallback.contains("m=19456,t=2,p=1"));
+    }
+
+    #[tokio::test]
+    async fn hashes_with_other_parameters_still_verify() {
+        for hash in [
+            hash_with(&argon2_with(8192, 3, 2), "correct horse"),
+            hash_with(&Argon2::default(), "correct horse"),
+        ] {
+            assert!(verify_password("correct horse".to_string(), hash.clone()).await);
+            assert!(!verify_password("wrong horse".to_string(), hash).await);
+        }
+        assert!(!verify_password("correct horse".to_string(), "not a hash".to_string()).await);
+    }
+}
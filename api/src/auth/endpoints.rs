@@ -1,16 +1,17 @@
 use axum::{
     Json,
     body::Body,
+    extract::{Extension, Path},
     http::{
-        Request, StatusCode,
-        header::{COOKIE, SET_COOKIE},
+        HeaderMap, HeaderValue, Request, StatusCode,
+        header::{COOKIE, RETRY_AFTER, SET_COOKIE, USER_AGENT},
     },
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use email_address::EmailAddress;
 use httpdate::fmt_http_date;
-use mongodb::{Collection, bson::doc};
+use mongodb::Collection;
 use serde_json::json;
 use std::{
     env::var,
@@ -19,32 +20,55 @@ use std::{
 
 use super::{
     delegates::{
-        check_user_existence, generate_cookie, hash_password, kill_cookie,
-        retrieve_user_by_username_or_email, verify_password,
+        check_user_existence, clear_failed_logins, consume_totp_challenge, create_totp_challenge,
+        find_session_by_cookie, generate_cookie, hash_password, kill_cookie,
+        login_lockout_remaining, record_failed_login, refresh_cookie_if_expiring,
+        resolve_totp_challenge, retrieve_user_by_username_or_email, revoke_all_sessions,
+        revoke_session, validate_username, verify_password, verify_totp,
     },
-    schemas::{UserIn, UserOut, UserQuery},
+    schemas::{MAX_DEVICE_LABEL_LENGTH, SessionOut, UserIn, UserOut, UserQuery},
 };
-use crate::{DB, apex::utils::VerboseHTTPError};
+use crate::{
+    DB,
+    apex::{
+        config::config,
+        utils::{ERROR_CODE_RATE_LIMITED, ERROR_CODE_UNAUTHORIZED, VerboseHTTPError, env_flag},
+    },
+};
+
+fn cleared_auth_cookie_header() -> String {
+    build_auth_cookie("null", "Thu, 01 Jan 1970 00:00:00 GMT")
+}
+
+fn device_label(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|agent| !agent.is_empty())
+        .map(|agent| agent.chars().take(MAX_DEVICE_LABEL_LENGTH).collect())
+}
 
 pub(crate) async fn logout_user(req: Request<Body>) -> impl IntoResponse {
-    if let Some(user) = req.extensions().get::<UserOut>() {
-        if kill_cookie(user.auth.cookie.clone()).await {
-            let domain = var("DOMAIN").unwrap_or_else(|_| ".goodspoint.com".to_string());
-            let headers = [(
-                SET_COOKIE,
-                format!(
-                    "GOODSPOINT_AUTHENTICATION=null; expires=Thu, 01 Jan 1970 00:00:00 GMT; Path=/; Domain={}; HttpOnly",
-                    domain
-                ),
-            )];
-            return (headers, Json(json!({ "status": "ok" }))).into_response();
-        }
+    if let Some(cookie) = extract_auth_cookie(req.headers())
+        && kill_cookie(cookie).await
+    {
+        let headers = [(SET_COOKIE, cleared_auth_cookie_header())];
+        return (headers, Json(json!({ "status": "ok" }))).into_response();
     }
 
-    VerboseHTTPError::Standard(StatusCode::UNAUTHORIZED, "Unauthorized".to_string()).into_response()
+    VerboseHTTPError::Coded(
+        StatusCode::UNAUTHORIZED,
+        ERROR_CODE_UNAUTHORIZED,
+        "Unauthorized".to_string(),
+    )
+    .into_response()
 }
 
-pub(crate) async fn login_user(Json(payload): Json<UserIn>) -> impl IntoResponse {
+pub(crate) async fn login_user(
+    headers: HeaderMap,
+    Json(payload): Json<UserIn>,
+) -> impl IntoResponse {
     if payload.username.is_none() && payload.email.is_none() {
         return VerboseHTTPError::Standard(
             StatusCode::BAD_REQUEST,
@@ -53,14 +77,14 @@ pub(crate) async fn login_user(Json(payload): Json<UserIn>) -> impl IntoResponse
         .into_response();
     }
 
-    if let Some(ref email) = payload.email {
-        if !EmailAddress::is_valid(email) {
-            return VerboseHTTPError::Standard(
-                StatusCode::BAD_REQUEST,
-                "Invalid email format".to_string(),
-            )
-            .into_response();
-        }
+    if let Some(ref email) = payload.email
+        && !EmailAddress::is_valid(email)
+    {
+        return VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Invalid email format".to_string(),
+        )
+        .into_response();
     }
 
     let Some(user) =
@@ -74,7 +98,21 @@ pub(crate) async fn login_user(Json(payload): Json<UserIn>) -> impl IntoResponse
         .into_response();
     };
 
-    if !verify_password(payload.password, user.salt.clone(), user.password.clone()).await {
+    if let Some(retry_after) = login_lockout_remaining(&user.username).await {
+        let mut response = VerboseHTTPError::Coded(
+            StatusCode::TOO_MANY_REQUESTS,
+            ERROR_CODE_RATE_LIMITED,
+            "Too many failed login attempts. Please try again later".to_string(),
+        )
+        .into_response();
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+        return response;
+    }
+
+    if !verify_password(payload.password, user.password.clone()).await {
+        record_failed_login(&user.username).await;
         return VerboseHTTPError::Standard(
             StatusCode::BAD_REQUEST,
             "Invalid username or password".to_string(),
@@ -82,6 +120,14 @@ pub(crate) async fn login_user(Json(payload): Json<UserIn>) -> impl IntoResponse
         .into_response();
     }
 
+    if !user.enabled {
+        return VerboseHTTPError::Standard(
+            StatusCode::FORBIDDEN,
+            "This account has been deleted".to_string(),
+        )
+        .into_response();
+    }
+
     if !user.email_verified {
         return VerboseHTTPError::Standard(
             StatusCode::FORBIDDEN,
@@ -90,7 +136,32 @@ pub(crate) async fn login_user(Json(payload): Json<UserIn>) -> impl IntoResponse
         .into_response();
     }
 
-    let Some(auth_object) = generate_cookie(user.username.clone()).await else {
+    // With 2FA on, the password alone doesn't count as a successful login;
+    // the counter is cleared only once the TOTP code checks out, so logging
+    // in again can't reset the lockout on code guesses.
+    if user.totp_enabled {
+        let Some(totp_token) = create_totp_challenge(&user).await else {
+            return VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+            .into_response();
+        };
+
+        return Json(json!({
+            "status": "ok",
+            "requires_totp": true,
+            "totp_token": totp_token
+        }))
+        .into_response();
+    }
+
+    clear_failed_logins(&user.username).await;
+    issue_login_cookie(&user.username, device_label(&headers)).await
+}
+
+async fn issue_login_cookie(username: &str, device: Option<String>) -> Response {
+    let Some(auth_object) = generate_cookie(username.to_string(), device).await else {
         return VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Internal server error".to_string(),
@@ -98,16 +169,11 @@ pub(crate) async fn login_user(Json(payload): Json<UserIn>) -> impl IntoResponse
         .into_response();
     };
 
-    let expire_time =
-        UNIX_EPOCH + Duration::from_secs(auth_object.cookie_expire.parse::<u64>().unwrap_or(0));
-    let formatted_expire_time = fmt_http_date(SystemTime::from(expire_time));
-    let domain = var("DOMAIN").unwrap_or_else(|_| ".goodspoint.com".to_string());
-
     let headers = [(
         SET_COOKIE,
-        format!(
-            "GOODSPOINT_AUTHENTICATION={}; HttpOnly; Path=/; Domain={}; expires={}",
-            auth_object.cookie, domain, formatted_expire_time
+        auth_cookie_header(
+            &auth_object.cookie,
+            auth_object.cookie_expire.parse::<u64>().unwrap_or(0),
         ),
     )];
 
@@ -115,21 +181,23 @@ pub(crate) async fn login_user(Json(payload): Json<UserIn>) -> impl IntoResponse
 }
 
 pub(crate) async fn register_user(Json(payload): Json<UserIn>) -> impl IntoResponse {
-    if let Some(ref email) = payload.email {
-        if !EmailAddress::is_valid(email) {
-            return VerboseHTTPError::Standard(
-                StatusCode::BAD_REQUEST,
-                "Invalid email format".to_string(),
-            )
-            .into_response();
-        }
+    if let Some(ref email) = payload.email
+        && !EmailAddress::is_valid(email)
+    {
+        return VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Invalid email format".to_string(),
+        )
+        .into_response();
     }
 
-    let Some((username_exists, email_exists)) = check_user_existence(
-        payload.username.as_deref().unwrap_or(""),
-        payload.email.as_deref().unwrap_or(""),
-    )
-    .await
+    let username = match validate_username(payload.username.as_deref().unwrap_or("")) {
+        Ok(username) => username,
+        Err(error) => return error.into_response(),
+    };
+
+    let Some((username_exists, email_exists)) =
+        check_user_existence(&username, payload.email.as_deref().unwrap_or("")).await
     else {
         return VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -163,21 +231,11 @@ pub(crate) async fn register_user(Json(payload): Json<UserIn>) -> impl IntoRespo
             .into_response();
     };
 
-    let Some(auth_object) = generate_cookie(payload.username.clone().unwrap_or_default()).await
-    else {
-        return VerboseHTTPError::Standard(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Internal server error".to_string(),
-        )
-        .into_response();
-    };
-
     let user = match UserOut::new(
-        payload.username.clone().unwrap_or_default(),
+        username,
         payload.email.clone().unwrap_or_default(),
         hashed_password,
         salt,
-        auth_object,
         uuid::Uuid::new_v4().to_string(),
         true,
     ) {
@@ -220,6 +278,10 @@ pub(crate) async fn register_user(Json(payload): Json<UserIn>) -> impl IntoRespo
             username: Some(user.username.clone()),
             email: Some(user.email.to_string()),
             uid: Some(user.uid.clone()),
+            region: user.region.clone(),
+            city: user.city.clone(),
+            bio: user.bio.clone(),
+            notification_preferences: Some(user.notification_preferences),
         }
     }))
     .into_response()
@@ -231,6 +293,10 @@ pub(crate) async fn get_user(req: Request<Body>) -> impl IntoResponse {
             username: Some(user.username.clone()),
             email: Some(user.email.to_string()),
             uid: Some(user.uid.clone()),
+            region: user.region.clone(),
+            city: user.city.clone(),
+            bio: user.bio.clone(),
+            notification_preferences: Some(user.notification_preferences),
         };
         return Json(json!({
             "user": response,
@@ -238,7 +304,23 @@ pub(crate) async fn get_user(req: Request<Body>) -> impl IntoResponse {
         .into_response();
     }
 
-    VerboseHTTPError::Standard(StatusCode::UNAUTHORIZED, "Unauthorized".to_string()).into_response()
+    VerboseHTTPError::Coded(
+        StatusCode::UNAUTHORIZED,
+        ERROR_CODE_UNAUTHORIZED,
+        "Unauthorized".to_string(),
+    )
+    .into_response()
+}
+
+pub(crate) async fn get_public_profile_endpoint(Path(username): Path<String>) -> impl IntoResponse {
+    match super::delegates::get_public_profile(&username).await {
+        Ok(profile) => Json(json!({
+            "status": "ok",
+            "profile": profile
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
 }
 
 pub(crate) async fn get_whatsapp_status(req: Request<Body>) -> impl IntoResponse {
@@ -250,46 +332,115 @@ pub(crate) async fn get_whatsapp_status(req: Request<Body>) -> impl IntoResponse
         .into_response();
     }
 
-    VerboseHTTPError::Standard(StatusCode::UNAUTHORIZED, "Unauthorized".to_string()).into_response()
+    VerboseHTTPError::Coded(
+        StatusCode::UNAUTHORIZED,
+        ERROR_CODE_UNAUTHORIZED,
+        "Unauthorized".to_string(),
+    )
+    .into_response()
+}
+
+fn auth_cookie_header(cookie: &str, expire: u64) -> String {
+    let expire_time = UNIX_EPOCH + Duration::from_secs(expire);
+    let formatted_expire_time = fmt_http_date(expire_time);
+
+    build_auth_cookie(cookie, &formatted_expire_time)
+}
+
+fn build_auth_cookie(value: &str, expires: &str) -> String {
+    let domain = var("DOMAIN").unwrap_or_else(|_| ".goodspoint.com".to_string());
+    let same_site = var("COOKIE_SAMESITE")
+        .map(|value| value.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let (same_site, secure) = match same_site.as_str() {
+        "none" => ("None", true),
+        "strict" => ("Strict", env_flag("COOKIE_SECURE")),
+        _ => ("Lax", env_flag("COOKIE_SECURE")),
+    };
+
+    let mut header = format!(
+        "GOODSPOINT_AUTHENTICATION={}; HttpOnly; Path=/; Domain={}; expires={}; SameSite={}",
+        value, domain, expires, same_site
+    );
+    if secure {
+        header.push_str("; Secure");
+    }
+
+    header
+}
+
+pub(crate) fn extract_auth_cookie(headers: &HeaderMap) -> Option<String> {
+    let cookie_header = headers.get(COOKIE).and_then(|h| h.to_str().ok())?;
+    cookie_header.split(';').map(str::trim).find_map(|pair| {
+        let mut parts = pair.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some("GOODSPOINT_AUTHENTICATION"), Some(value)) => Some(value.to_string()),
+            _ => None,
+        }
+    })
+}
+
+pub(crate) async fn optional_user(headers: &HeaderMap) -> Option<UserOut> {
+    let cookie = extract_auth_cookie(headers)?;
+    let (user, session) = find_session_by_cookie(&cookie).await?;
+
+    if !user.enabled {
+        return None;
+    }
+
+    let expire = session.cookie_expire.parse::<u64>().ok()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    if expire <= now {
+        return None;
+    }
+
+    let _ = user.initialize_encryption();
+    Some(user)
 }
 
 pub async fn cookie_auth(mut req: Request<Body>, next: Next) -> Result<Response, VerboseHTTPError> {
-    let Some(database) = DB.get() else {
+    if DB.get().is_none() {
         return Err(VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Database unavailable".to_string(),
         ));
-    };
+    }
 
-    let collection: Collection<UserOut> = database.collection("users");
+    if let Some(cookie) = extract_auth_cookie(req.headers())
+        && let Some((user, session)) = find_session_by_cookie(&cookie).await
+    {
+        if !user.enabled {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::FORBIDDEN,
+                "Account disabled".to_string(),
+            ));
+        }
 
-    if let Some(cookie_header) = req.headers().get(COOKIE).and_then(|h| h.to_str().ok()) {
-        if let Some(cookie) = cookie_header.split(';').map(str::trim).find_map(|pair| {
-            let mut parts = pair.splitn(2, '=');
-            match (parts.next(), parts.next()) {
-                (Some("GOODSPOINT_AUTHENTICATION"), Some(value)) => Some(value.to_string()),
-                _ => None,
-            }
-        }) {
-            if let Some(user) = collection
-                .find_one(doc! {"auth.cookie": &cookie})
-                .await
-                .ok()
-                .flatten()
+        let _ = user.initialize_encryption();
+        if let Ok(expire) = session.cookie_expire.parse::<u64>()
+            && SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .is_ok_and(|now| expire > now.as_secs())
+        {
+            let refreshed_expire = if env_flag("SESSION_SLIDING_EXPIRY") {
+                refresh_cookie_if_expiring(&cookie, expire).await
+            } else {
+                None
+            };
+
+            req.extensions_mut().insert(user);
+            let mut response = next.run(req).await;
+
+            if let Some(new_expire) = refreshed_expire
+                && let Ok(header) = auth_cookie_header(&cookie, new_expire).parse()
             {
-                let _ = user.initialize_encryption();
-                if let Ok(expire) = user.auth.cookie_expire.parse::<u64>() {
-                    if SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .map_or(false, |now| expire > now.as_secs())
-                    {
-                        req.extensions_mut().insert(user);
-                        return Ok(next.run(req).await);
-                    }
-                }
-                kill_cookie(cookie).await;
+                response.headers_mut().append(SET_COOKIE, header);
             }
+
+            return Ok(response);
         }
+        kill_cookie(cookie).await;
     }
 
     Err(VerboseHTTPError::Standard(
@@ -298,6 +449,24 @@ pub async fn cookie_auth(mut req: Request<Body>, next: Next) -> Result<Response,
     ))
 }
 
+pub async fn admin_auth(req: Request<Body>, next: Next) -> Result<Response, VerboseHTTPError> {
+    let Some(user) = req.extensions().get::<UserOut>() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::UNAUTHORIZED,
+            "Unauthorized".to_string(),
+        ));
+    };
+
+    if !config().is_admin(&user.uid) {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::FORBIDDEN,
+            "Admin access required".to_string(),
+        ));
+    }
+
+    Ok(next.run(req).await)
+}
+
 pub async fn change_password_endpoint(req: Request<Body>) -> impl IntoResponse {
     let Some(user) = req.extensions().get::<UserOut>().cloned() else {
         return VerboseHTTPError::Standard(StatusCode::UNAUTHORIZED, "Unauthorized".to_string())
@@ -330,16 +499,179 @@ pub async fn change_password_endpoint(req: Request<Body>) -> impl IntoResponse {
     }
 }
 
+pub(crate) async fn delete_account_endpoint(
+    Extension(user): Extension<UserOut>,
+    Json(request): Json<super::schemas::DeleteAccountRequest>,
+) -> impl IntoResponse {
+    match super::delegates::delete_account(&user, request.password).await {
+        Ok(_) => {
+            let headers = [(SET_COOKIE, cleared_auth_cookie_header())];
+            (headers, Json(json!({ "status": "ok" }))).into_response()
+        }
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn remove_whatsapp_endpoint(
+    Extension(user): Extension<UserOut>,
+    Json(request): Json<super::schemas::RemoveWhatsAppRequest>,
+) -> impl IntoResponse {
+    match super::delegates::remove_whatsapp(&user, request.password).await {
+        Ok(_) => Json(json!({ "status": "ok" })).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn set_trusted_seller_endpoint(
+    Path(uid): Path<String>,
+    Json(request): Json<super::schemas::TrustedSellerRequest>,
+) -> impl IntoResponse {
+    match super::delegates::set_trusted_seller(&uid, request.trusted).await {
+        Ok(_) => Json(json!({ "status": "ok", "trusted": request.trusted })).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn update_profile_endpoint(
+    Extension(user): Extension<UserOut>,
+    Json(request): Json<super::schemas::UpdateProfileRequest>,
+) -> impl IntoResponse {
+    match super::delegates::update_profile(&user, request).await {
+        Ok(profile) => Json(json!({
+            "status": "ok",
+            "profile": profile
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn list_sessions_endpoint(
+    Extension(user): Extension<UserOut>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let current_cookie = extract_auth_cookie(&headers);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    let sessions: Vec<SessionOut> = user
+        .sessions
+        .iter()
+        .filter_map(|session| {
+            let expires_at = session.cookie_expire.parse::<u64>().ok()?;
+            (expires_at > now).then(|| SessionOut {
+                session_id: session.session_id.clone(),
+                device: session.device.clone(),
+                created_at: session.created_at,
+                expires_at,
+                current: current_cookie.as_deref() == Some(session.cookie.as_str()),
+            })
+        })
+        .collect();
+
+    Json(json!({
+        "status": "ok",
+        "sessions": sessions
+    }))
+    .into_response()
+}
+
+pub(crate) async fn revoke_session_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(session_id): Path<String>,
+) -> impl IntoResponse {
+    match revoke_session(&user, &session_id).await {
+        Ok(_) => Json(json!({ "status": "ok" })).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn logout_all_endpoint(Extension(user): Extension<UserOut>) -> impl IntoResponse {
+    if !revoke_all_sessions(&user.uid).await {
+        return VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to revoke sessions".to_string(),
+        )
+        .into_response();
+    }
+
+    let headers = [(SET_COOKIE, cleared_auth_cookie_header())];
+    (headers, Json(json!({ "status": "ok" }))).into_response()
+}
+
+pub(crate) async fn verify_totp_endpoint(
+    headers: HeaderMap,
+    Json(request): Json<super::schemas::VerifyTotpRequest>,
+) -> impl IntoResponse {
+    let Some(user) = resolve_totp_challenge(&request.totp_token).await else {
+        return VerboseHTTPError::Standard(
+            StatusCode::UNAUTHORIZED,
+            "Login session expired. Please log in again.".to_string(),
+        )
+        .into_response();
+    };
+
+    if let Some(retry_after) = login_lockout_remaining(&user.username).await {
+        let mut response = VerboseHTTPError::Coded(
+            StatusCode::TOO_MANY_REQUESTS,
+            ERROR_CODE_RATE_LIMITED,
+            "Too many failed login attempts. Please try again later".to_string(),
+        )
+        .into_response();
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+        return response;
+    }
+
+    if !verify_totp(&user, &request.code) {
+        record_failed_login(&user.username).await;
+        return VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Invalid authentication code".to_string(),
+        )
+        .into_response();
+    }
+
+    clear_failed_logins(&user.username).await;
+    consume_totp_challenge(&request.totp_token).await;
+
+    issue_login_cookie(&user.username, device_label(&headers)).await
+}
+
+pub(crate) async fn enable_totp_endpoint(Extension(user): Extension<UserOut>) -> impl IntoResponse {
+    match super::delegates::enable_totp(&user).await {
+        Ok(provisioning_uri) => Json(json!({
+            "status": "ok",
+            "provisioning_uri": provisioning_uri
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn confirm_totp_endpoint(
+    Extension(user): Extension<UserOut>,
+    Json(request): Json<super::schemas::TotpCodeRequest>,
+) -> impl IntoResponse {
+    match super::delegates::confirm_totp(&user, &request.code).await {
+        Ok(_) => Json(json!({
+            "success": true,
+            "message": "Two-factor authentication enabled"
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
 pub(crate) async fn send_email_otp_endpoint(
     Json(request): Json<super::schemas::SendEmailOTPRequest>,
 ) -> impl IntoResponse {
     match super::delegates::send_email_otp(&request.email).await {
-        Ok(_) => {
-            Json(json!({"success": true, "message": "OTP sent to email"})).into_response()
-        }
-        Err(error) => {
-            error.into_response()
-        }
+        Ok(_) => Json(json!({"success": true, "message": "OTP sent to email"})).into_response(),
+        Err(error) => error.into_response(),
     }
 }
 
@@ -350,13 +682,49 @@ pub(crate) async fn verify_email_otp_endpoint(
         Ok(_) => {
             Json(json!({"success": true, "message": "Email verified successfully"})).into_response()
         }
-        Err(error) => {
-            error.into_response()
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn send_password_reset_endpoint(
+    Json(request): Json<super::schemas::SendPasswordResetRequest>,
+) -> impl IntoResponse {
+    if !EmailAddress::is_valid(&request.email) {
+        return VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Invalid email format".to_string(),
+        )
+        .into_response();
+    }
+
+    match super::delegates::send_password_reset_otp(&request.email).await {
+        Ok(_) => Json(json!({
+            "success": true,
+            "message": "If an account exists for this email, a reset code has been sent"
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn reset_password_endpoint(
+    Json(request): Json<super::schemas::ResetPasswordRequest>,
+) -> impl IntoResponse {
+    match super::delegates::reset_password(&request.email, &request.otp, request.new_password).await
+    {
+        Ok(_) => {
+            Json(json!({"success": true, "message": "Password reset successfully"})).into_response()
         }
+        Err(error) => error.into_response(),
     }
 }
 
 pub(crate) async fn send_whatsapp_otp_endpoint(req: Request<Body>) -> impl IntoResponse {
+    let Some(user) = req.extensions().get::<UserOut>().cloned() else {
+        return VerboseHTTPError::Standard(StatusCode::UNAUTHORIZED, "Unauthorized".to_string())
+            .into_response();
+    };
+
     let body_bytes = match axum::body::to_bytes(req.into_body(), usize::MAX).await {
         Ok(bytes) => bytes,
         Err(_) => {
@@ -377,7 +745,7 @@ pub(crate) async fn send_whatsapp_otp_endpoint(req: Request<Body>) -> impl IntoR
         }
     };
 
-    match super::delegates::send_whatsapp_otp(&request.whatsapp_number).await {
+    match super::delegates::send_whatsapp_otp(&user, &request.whatsapp_number).await {
         Ok(_) => Json(json!({"success": true, "message": "OTP sent to WhatsApp"})).into_response(),
         Err(error) => error.into_response(),
     }
@@ -419,3 +787,55 @@ pub(crate) async fn verify_whatsapp_otp_endpoint(req: Request<Body>) -> impl Int
         Err(error) => error.into_response(),
     }
 }
+
+pub(crate) async fn list_addresses_endpoint(
+    Extension(user): Extension<UserOut>,
+) -> impl IntoResponse {
+    match super::delegates::list_addresses(&user).await {
+        Ok(addresses) => Json(json!({
+            "status": "ok",
+            "addresses": addresses
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn create_address_endpoint(
+    Extension(user): Extension<UserOut>,
+    Json(request): Json<super::schemas::AddressRequest>,
+) -> impl IntoResponse {
+    match super::delegates::create_address(&user, request).await {
+        Ok(address) => Json(json!({
+            "status": "ok",
+            "address": address
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn update_address_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(address_id): Path<String>,
+    Json(request): Json<super::schemas::AddressRequest>,
+) -> impl IntoResponse {
+    match super::delegates::update_address(&user, &address_id, request).await {
+        Ok(address) => Json(json!({
+            "status": "ok",
+            "address": address
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn delete_address_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(address_id): Path<String>,
+) -> impl IntoResponse {
+    match super::delegates::delete_address(&user, &address_id).await {
+        Ok(()) => Json(json!({ "status": "ok" })).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
@@ -1,3 +1,7 @@
-pub(self) mod delegates;
+mod delegates;
 pub(crate) mod endpoints;
 pub(crate) mod schemas;
+
+pub(crate) use delegates::{
+    backfill_username_lower, backfill_whatsapp_hashes, shipping_address_snapshot,
+};
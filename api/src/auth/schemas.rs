@@ -5,14 +5,56 @@ use aes_gcm::{
 use base64::{Engine, engine::general_purpose::STANDARD};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::{env::var, error::Error, ops::Deref, sync::OnceLock};
+use std::{env::var, error::Error, fmt, ops::Deref, sync::OnceLock};
+
+pub const DEFAULT_LOGIN_MAX_ATTEMPTS: u32 = 5;
+pub const DEFAULT_LOGIN_WINDOW_SECS: u64 = 900;
+pub const DEFAULT_ARGON2_MEMORY_KIB: u32 = argon2::Params::DEFAULT_M_COST;
+pub const DEFAULT_ARGON2_ITERATIONS: u32 = argon2::Params::DEFAULT_T_COST;
+pub const DEFAULT_ARGON2_PARALLELISM: u32 = argon2::Params::DEFAULT_P_COST;
+pub const MIN_USERNAME_LENGTH: usize = 3;
+pub const MAX_USERNAME_LENGTH: usize = 32;
+// Always reserved; RESERVED_USERNAMES adds to this list rather than replacing it.
+pub const DEFAULT_RESERVED_USERNAMES: [&str; 20] = [
+    "admin",
+    "administrator",
+    "api",
+    "billing",
+    "goodspoint",
+    "help",
+    "helpdesk",
+    "info",
+    "moderator",
+    "no-reply",
+    "noreply",
+    "null",
+    "official",
+    "root",
+    "security",
+    "staff",
+    "support",
+    "system",
+    "undefined",
+    "webmaster",
+];
+pub const MAX_SESSIONS_PER_USER: i32 = 10;
+pub const MAX_DEVICE_LABEL_LENGTH: usize = 120;
+pub const TOTP_ISSUER: &str = "GoodsPoint";
+pub const TOTP_CHALLENGE_EXPIRY_SECS: u64 = 300;
+pub const MAX_REGION_LENGTH: usize = 32;
+pub const MAX_CITY_LENGTH: usize = 64;
+pub const MAX_BIO_LENGTH: usize = 500;
+pub const COLLECTIONS_ADDRESSES: &str = "addresses";
+pub const MAX_ADDRESSES_PER_USER: u64 = 10;
+pub const MAX_ADDRESS_FIELD_LENGTH: usize = 200;
+pub const MAX_ADDRESS_LABEL_LENGTH: usize = 40;
 
 #[derive(Serialize, Deserialize)]
 pub struct EncryptedString {
     data: String,
     nonce: String,
     #[serde(skip)]
-    salt: Option<String>,
+    salt: OnceLock<String>,
     #[serde(skip)]
     decrypted_data: OnceLock<String>,
 }
@@ -23,7 +65,7 @@ impl Clone for EncryptedString {
             data: self.data.clone(),
             nonce: self.nonce.clone(),
             salt: self.salt.clone(),
-            decrypted_data: OnceLock::new(),
+            decrypted_data: self.decrypted_data.clone(),
         }
     }
 }
@@ -46,8 +88,8 @@ impl EncryptedString {
 
         Ok(Self {
             data: STANDARD.encode(&ciphertext),
-            nonce: STANDARD.encode(&nonce_bytes),
-            salt: Some(salt.to_string()),
+            nonce: STANDARD.encode(nonce_bytes),
+            salt: OnceLock::from(salt.to_string()),
             decrypted_data: {
                 let cell = OnceLock::new();
                 let _ = cell.set(text.to_string());
@@ -57,15 +99,20 @@ impl EncryptedString {
     }
 
     pub fn set_salt(&self, salt: &str) -> Result<(), Box<dyn Error>> {
-        unsafe {
-            let ptr = self as *const Self as *mut Self;
-            (*ptr).salt = Some(salt.to_string());
+        match self.salt.get() {
+            Some(existing) if existing != salt => {
+                Err("Salt already set to a different value".into())
+            }
+            Some(_) => Ok(()),
+            None => {
+                let _ = self.salt.set(salt.to_string());
+                Ok(())
+            }
         }
-        Ok(())
     }
 
     fn decrypt(&self) -> Result<String, Box<dyn Error>> {
-        let salt = self.salt.as_ref().ok_or("Salt not set")?;
+        let salt = self.salt.get().ok_or("Salt not set")?;
         let key_material = format!("{}{}", var("ENCRYPTION_KEY")?, salt);
         let mut key_bytes = [0u8; 32];
         let bytes = key_material.as_bytes();
@@ -82,11 +129,11 @@ impl EncryptedString {
 
         Ok(String::from_utf8(plaintext)?)
     }
+}
 
-    pub fn to_string(&self) -> String {
-        self.decrypted_data
-            .get_or_init(|| self.decrypt().unwrap_or_else(|_| "ERROR".to_string()))
-            .clone()
+impl fmt::Display for EncryptedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self)
     }
 }
 
@@ -106,6 +153,98 @@ pub fn create_email_hash(email: &str) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+#[inline]
+pub fn create_whatsapp_hash(whatsapp_number: &str) -> String {
+    let mut hasher = Sha256::default();
+    hasher.update(whatsapp_number.trim().as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+#[inline]
+pub fn normalize_region(region: &str) -> Option<String> {
+    let region = region.trim().to_ascii_lowercase();
+
+    let valid = !region.is_empty()
+        && region.len() <= MAX_REGION_LENGTH
+        && region
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    valid.then_some(region)
+}
+
+#[inline]
+pub fn normalize_city(city: &str) -> Option<String> {
+    let city = city.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let valid = !city.is_empty()
+        && city.chars().count() <= MAX_CITY_LENGTH
+        && city
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '\'' | '.'));
+
+    valid.then_some(city)
+}
+
+fn normalize_address_field(field: &str) -> Option<String> {
+    let field = field.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let valid = !field.is_empty()
+        && field.chars().count() <= MAX_ADDRESS_FIELD_LENGTH
+        && !field
+            .chars()
+            .any(|c| c.is_control() || c == '<' || c == '>');
+
+    valid.then_some(field)
+}
+
+pub fn normalize_shipping_address(address: &ShippingAddress) -> Option<ShippingAddress> {
+    let line2 = match address.line2.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(line2) => Some(normalize_address_field(line2)?),
+    };
+
+    Some(ShippingAddress {
+        full_name: normalize_address_field(&address.full_name)?,
+        phone: normalize_address_field(&address.phone)?,
+        line1: normalize_address_field(&address.line1)?,
+        line2,
+        city: normalize_city(&address.city)?,
+        state: normalize_address_field(&address.state)?,
+        postal_code: normalize_address_field(&address.postal_code)?,
+        country: normalize_address_field(&address.country)?,
+    })
+}
+
+#[inline]
+pub fn normalize_bio(bio: &str) -> Option<String> {
+    let bio = bio.trim();
+
+    let valid = !bio.is_empty()
+        && bio.chars().count() <= MAX_BIO_LENGTH
+        && !bio
+            .chars()
+            .any(|c| (c.is_control() && c != '\n') || c == '<' || c == '>');
+
+    valid.then(|| bio.to_string())
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationPreferences {
+    pub email: bool,
+    pub whatsapp: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            email: true,
+            whatsapp: true,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct UserIn {
     pub username: Option<String>,
@@ -115,24 +254,60 @@ pub struct UserIn {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct AuthObject {
+    #[serde(default)]
+    pub session_id: String,
     pub cookie: String,
     #[serde(rename = "cookie-expire")]
     pub cookie_expire: String,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub device: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionOut {
+    pub session_id: String,
+    pub device: Option<String>,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub current: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct UserOut {
     pub username: String,
+    #[serde(default)]
+    pub username_lower: String,
     pub email: EncryptedString,
     pub email_hash: String,
     pub email_verified: bool,
     pub whatsapp_number: Option<EncryptedString>,
+    #[serde(default)]
+    pub whatsapp_hash: Option<String>,
     pub whatsapp_verified: bool,
     pub password: String,
     pub salt: String,
-    pub auth: AuthObject,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthObject>,
+    #[serde(default)]
+    pub sessions: Vec<AuthObject>,
     pub uid: String,
     pub enabled: bool,
+    #[serde(default)]
+    pub totp_secret: Option<EncryptedString>,
+    #[serde(default)]
+    pub totp_enabled: bool,
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub city: Option<String>,
+    #[serde(default)]
+    pub bio: Option<String>,
+    #[serde(default)]
+    pub notification_preferences: NotificationPreferences,
+    #[serde(default)]
+    pub trusted_seller: bool,
 }
 
 impl UserOut {
@@ -141,7 +316,6 @@ impl UserOut {
         email: String,
         password: String,
         salt: String,
-        auth: AuthObject,
         uid: String,
         enabled: bool,
     ) -> Result<Self, Box<dyn Error>> {
@@ -149,22 +323,38 @@ impl UserOut {
         let email_hash = create_email_hash(&email);
 
         Ok(Self {
+            username_lower: username.to_lowercase(),
             username,
             email: encrypted_email,
             email_hash,
             email_verified: false,
             whatsapp_number: None,
+            whatsapp_hash: None,
             whatsapp_verified: false,
             password,
             salt,
-            auth,
+            auth: None,
+            sessions: Vec::new(),
             uid,
             enabled,
+            totp_secret: None,
+            totp_enabled: false,
+            region: None,
+            city: None,
+            bio: None,
+            notification_preferences: NotificationPreferences::default(),
+            trusted_seller: false,
         })
     }
 
     #[inline]
     pub fn initialize_encryption(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(ref whatsapp_number) = self.whatsapp_number {
+            whatsapp_number.set_salt(&self.salt)?;
+        }
+        if let Some(ref totp_secret) = self.totp_secret {
+            totp_secret.set_salt(&self.salt)?;
+        }
         self.email.set_salt(&self.salt)
     }
 }
@@ -174,6 +364,14 @@ pub struct UserQuery {
     pub username: Option<String>,
     pub email: Option<String>,
     pub uid: Option<String>,
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub city: Option<String>,
+    #[serde(default)]
+    pub bio: Option<String>,
+    #[serde(default)]
+    pub notification_preferences: Option<NotificationPreferences>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -188,6 +386,11 @@ pub struct ChangePasswordResponse {
     pub message: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SendEmailOTPRequest {
     pub email: String,
@@ -200,8 +403,15 @@ pub struct VerifyEmailOTPRequest {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct AddWhatsAppRequest {
-    pub whatsapp_number: String,
+pub struct SendPasswordResetRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub email: String,
+    pub otp: String,
+    pub new_password: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -215,6 +425,82 @@ pub struct VerifyWhatsAppOTPRequest {
     pub otp: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoveWhatsAppRequest {
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrustedSellerRequest {
+    pub trusted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShippingAddress {
+    pub full_name: String,
+    pub phone: String,
+    pub line1: String,
+    #[serde(default)]
+    pub line2: Option<String>,
+    pub city: String,
+    pub state: String,
+    pub postal_code: String,
+    pub country: String,
+}
+
+// The address itself is stored as an encrypted JSON-encoded ShippingAddress
+// keyed with the owner's salt; only the label stays readable.
+#[derive(Serialize, Deserialize)]
+pub struct Address {
+    pub address_id: String,
+    pub user_id: String,
+    pub label: Option<String>,
+    pub details: EncryptedString,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddressRequest {
+    #[serde(default)]
+    pub label: Option<String>,
+    pub address: ShippingAddress,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddressOut {
+    pub address_id: String,
+    pub label: Option<String>,
+    pub address: ShippingAddress,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateProfileRequest {
+    pub region: Option<String>,
+    pub city: Option<String>,
+    pub bio: Option<String>,
+    pub notification_preferences: Option<NotificationPreferences>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicProfile {
+    pub username: String,
+    pub bio: Option<String>,
+    pub region: Option<String>,
+    pub city: Option<String>,
+    pub product_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProfileOut {
+    pub region: Option<String>,
+    pub city: Option<String>,
+    pub bio: Option<String>,
+    pub notification_preferences: NotificationPreferences,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OTPVerification {
     pub identifier: String,
@@ -224,3 +510,81 @@ pub struct OTPVerification {
     pub attempts: u32,
     pub verification_type: String,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoginAttempts {
+    pub username: String,
+    pub failed_count: u32,
+    pub window_started_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TotpChallenge {
+    pub token: String,
+    pub uid: String,
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TotpCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyTotpRequest {
+    pub totp_token: String,
+    pub code: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{from_document, to_document};
+
+    fn test_user() -> UserOut {
+        if var("ENCRYPTION_KEY").is_err() {
+            // SAFETY: set once, before anything in this test reads it.
+            unsafe { std::env::set_var("ENCRYPTION_KEY", "test-encryption-key") };
+        }
+
+        let salt = "0123456789abcdef".to_string();
+        let mut user = UserOut::new(
+            "alice".to_string(),
+            "alice@example.com".to_string(),
+            "hash".to_string(),
+            salt.clone(),
+            "uid-1".to_string(),
+            true,
+        )
+        .unwrap();
+        user.whatsapp_number = Some(EncryptedString::new("+919876543210", &salt).unwrap());
+        user
+    }
+
+    #[test]
+    fn encrypted_fields_round_trip_through_bson() {
+        let document = to_document(&test_user()).unwrap();
+        let whatsapp = document.get_document("whatsapp_number").unwrap();
+        assert!(whatsapp.get_str("data").is_ok());
+        assert!(!document.to_string().contains("9876543210"));
+
+        let reloaded: UserOut = from_document(document).unwrap();
+        reloaded.initialize_encryption().unwrap();
+
+        assert_eq!(reloaded.whatsapp_number.as_deref(), Some("+919876543210"));
+        assert_eq!(&*reloaded.email, "alice@example.com");
+        assert_eq!(
+            reloaded.whatsapp_number.unwrap().to_string(),
+            "+919876543210"
+        );
+    }
+
+    #[test]
+    fn salt_cannot_be_replaced_once_set() {
+        let reloaded: UserOut = from_document(to_document(&test_user()).unwrap()).unwrap();
+        reloaded.initialize_encryption().unwrap();
+
+        assert!(reloaded.email.set_salt(&reloaded.salt).is_ok());
+        assert!(reloaded.email.set_salt("another-salt").is_err());
+    }
+}
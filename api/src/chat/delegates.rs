@@ -1,20 +1,35 @@
 use axum::http::StatusCode;
 use bytes::Bytes;
-use futures::TryStreamExt;
-use mongodb::{Collection, bson::doc, options::FindOptions};
+use futures::{
+    StreamExt, TryStreamExt,
+    stream::{self, BoxStream},
+};
+use mongodb::{
+    Collection, IndexModel,
+    bson::{Document, doc},
+    change_stream::{
+        ChangeStream,
+        event::{ChangeStreamEvent, OperationType},
+    },
+    options::{FindOptions, FullDocumentType, IndexOptions, ReturnDocument},
+};
 use reqwest::multipart::{Form, Part};
 use std::{
+    collections::{HashMap, VecDeque},
     env::var,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use uuid::Uuid;
 
 use super::schemas::*;
 use crate::{
     DB,
-    apex::utils::VerboseHTTPError,
+    apex::{
+        config::config,
+        utils::{ERROR_CODE_INVALID_PRICE, VerboseHTTPError, now_millis, resize_image},
+    },
     auth::schemas::UserOut,
-    products::schemas::ProductCategory,
+    products::{delegates::schedule_unpins, schemas::ProductCategory},
     recommendations::{auto_log_signal, schemas::SignalType},
 };
 
@@ -94,6 +109,176 @@ pub async fn upload_file_to_filebase(
     ))
 }
 
+fn validate_message_length(
+    message_type: MessageType,
+    content: &str,
+) -> Result<(), VerboseHTTPError> {
+    let max_length = config().max_message_length_for(message_type);
+
+    if content.chars().count() > max_length {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!("Message cannot exceed {} characters", max_length),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn is_blocked(blocker_id: &str, blocked_id: &str) -> Result<bool, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let blocks: Collection<Block> = database.collection(COLLECTIONS_BLOCKS);
+
+    blocks
+        .count_documents(doc! { "blocker_id": blocker_id, "blocked_id": blocked_id })
+        .await
+        .map(|count| count > 0)
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })
+}
+
+async fn ensure_not_blocked(sender_id: &str, recipient_id: &str) -> Result<(), VerboseHTTPError> {
+    if is_blocked(recipient_id, sender_id).await? {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::FORBIDDEN,
+            "This user is not accepting messages from you".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn block_user(user: &UserOut, other_user_id: &str) -> Result<Block, VerboseHTTPError> {
+    if other_user_id == user.uid {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Cannot block yourself".to_string(),
+        ));
+    }
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let users: Collection<UserOut> = database.collection("users");
+    let user_exists = users
+        .count_documents(doc! { "uid": other_user_id })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        > 0;
+
+    if !user_exists {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "User not found".to_string(),
+        ));
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let blocks: Collection<Block> = database.collection(COLLECTIONS_BLOCKS);
+
+    // Upserting keeps repeated blocks idempotent and preserves the original
+    // block time.
+    blocks
+        .update_one(
+            doc! { "blocker_id": &user.uid, "blocked_id": other_user_id },
+            doc! { "$setOnInsert": { "created_at": now as i64 } },
+        )
+        .upsert(true)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to block user".to_string(),
+            )
+        })?;
+
+    blocks
+        .find_one(doc! { "blocker_id": &user.uid, "blocked_id": other_user_id })
+        .await
+        .ok()
+        .flatten()
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to block user".to_string(),
+            )
+        })
+}
+
+pub async fn unblock_user(user: &UserOut, other_user_id: &str) -> Result<bool, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let blocks: Collection<Block> = database.collection(COLLECTIONS_BLOCKS);
+
+    blocks
+        .delete_one(doc! { "blocker_id": &user.uid, "blocked_id": other_user_id })
+        .await
+        .map(|result| result.deleted_count > 0)
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to unblock user".to_string(),
+            )
+        })
+}
+
+pub async fn list_blocked_users(user: &UserOut) -> Result<Vec<Block>, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let blocks: Collection<Block> = database.collection(COLLECTIONS_BLOCKS);
+
+    let cursor = blocks
+        .find(doc! { "blocker_id": &user.uid })
+        .sort(doc! { "created_at": -1 })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to retrieve blocked users".to_string(),
+            )
+        })?;
+
+    cursor.try_collect().await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to retrieve blocked users".to_string(),
+        )
+    })
+}
+
 pub async fn get_or_create_conversation(
     user_id: &str,
     other_user_id: &str,
@@ -119,6 +304,13 @@ pub async fn get_or_create_conversation(
         return Ok(conversation.conversation_id);
     }
 
+    if is_blocked(user_id, other_user_id).await? || is_blocked(other_user_id, user_id).await? {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::FORBIDDEN,
+            "Cannot start a conversation with this user".to_string(),
+        ));
+    }
+
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
@@ -130,6 +322,7 @@ pub async fn get_or_create_conversation(
         created_at: now,
         updated_at: now,
         last_message_at: now,
+        last_read_at: HashMap::new(),
     };
 
     conversations.insert_one(&conversation).await.map_err(|_| {
@@ -142,6 +335,40 @@ pub async fn get_or_create_conversation(
     Ok(conversation.conversation_id)
 }
 
+// Looks up an existing conversation between two users without creating one.
+pub async fn find_conversation(
+    user_id: &str,
+    other_user_id: &str,
+) -> Result<String, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let conversations: Collection<Conversation> = database.collection("conversations");
+
+    let mut participant_ids = vec![user_id.to_string(), other_user_id.to_string()];
+    participant_ids.sort_unstable();
+
+    conversations
+        .find_one(doc! {
+            "participant_ids": { "$all": &participant_ids, "$size": 2 }
+        })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .map(|conversation| conversation.conversation_id)
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "Conversation not found".to_string())
+        })
+}
+
 pub async fn verify_conversation_access(
     conversation_id: &str,
     user_id: &str,
@@ -187,31 +414,47 @@ pub async fn send_text_message(
         ));
     }
 
-    if content.len() > MAX_MESSAGE_LENGTH {
-        return Err(VerboseHTTPError::Standard(
-            StatusCode::BAD_REQUEST,
-            format!("Message cannot exceed {} characters", MAX_MESSAGE_LENGTH),
-        ));
-    }
+    validate_message_length(MessageType::Text, content)?;
+    ensure_not_blocked(&user.uid, other_user_id).await?;
 
-    let conversation_id = get_or_create_conversation(&user.uid, other_user_id).await?;
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    let message = insert_text_message(
+        Uuid::new_v4().to_string(),
+        &user.uid,
+        other_user_id,
+        content,
+    )
+    .await?;
+
+    log_chat_query_signal(user, content).await;
+
+    send_message_notification(&user.username, other_user_id, MessageType::Text).await;
+
+    Ok(message)
+}
+
+async fn insert_text_message(
+    message_id: String,
+    sender_id: &str,
+    other_user_id: &str,
+    content: &str,
+) -> Result<Message, VerboseHTTPError> {
+    let conversation_id = get_or_create_conversation(sender_id, other_user_id).await?;
+    let now_ms = now_millis();
+    let now = now_ms / 1000;
 
     let message = Message {
-        message_id: Uuid::new_v4().to_string(),
+        message_id,
         conversation_id: conversation_id.clone(),
-        sender_id: user.uid.clone(),
+        sender_id: sender_id.to_string(),
         message_type: MessageType::Text,
         content: Some(content.to_string()),
         attachment: None,
         query_data: None,
         quote_data: None,
-        created_at: now,
-        updated_at: now,
+        created_at: now_ms,
+        updated_at: now_ms,
         edit_history: Vec::new(),
+        deleted: false,
     };
 
     let Some(database) = DB.get() else {
@@ -249,63 +492,570 @@ pub async fn send_text_message(
             )
         })?;
 
-    log_chat_query_signal(user, content).await;
+    Ok(message)
+}
 
-    send_message_notification(&user.username, other_user_id, MessageType::Text).await;
+fn scheduled_messages_collection() -> Result<Collection<ScheduledMessage>, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
 
-    Ok(message)
+    Ok(database.collection(COLLECTIONS_SCHEDULED_MESSAGES))
 }
 
-pub async fn send_attachment_message(
-    user: &UserOut,
-    other_user_id: &str,
-    file_name: String,
-    file_data: Bytes,
-    content_type: String,
-) -> Result<Message, VerboseHTTPError> {
-    if !is_allowed_attachment_type(&content_type) {
+// `None` means send right away: no send_at was given or it has already
+// passed. `Some` is a valid future delivery time.
+pub fn scheduled_send_at(
+    send_at: Option<u64>,
+    now_ms: u64,
+) -> Result<Option<u64>, VerboseHTTPError> {
+    let Some(send_at) = send_at.filter(|&send_at| send_at > now_ms) else {
+        return Ok(None);
+    };
+
+    if send_at > now_ms.saturating_add(MAX_SCHEDULE_AHEAD_MS) {
         return Err(VerboseHTTPError::Standard(
             StatusCode::BAD_REQUEST,
-            "File type not allowed".to_string(),
+            format!(
+                "Messages can be scheduled at most {} days ahead",
+                MAX_SCHEDULE_AHEAD_MS / (24 * 60 * 60 * 1000)
+            ),
         ));
     }
 
-    if file_data.len() > MAX_FILE_SIZE {
+    Ok(Some(send_at))
+}
+
+// Scheduled messages nobody is delivering: never claimed, or claimed longer
+// than SCHEDULED_MESSAGE_CLAIM_TIMEOUT_MS ago by a worker that died.
+#[inline]
+fn unclaimed_filter(now_ms: u64) -> Document {
+    let stale_before = now_ms.saturating_sub(SCHEDULED_MESSAGE_CLAIM_TIMEOUT_MS);
+    doc! {
+        "$or": [
+            { "claimed_at": null },
+            { "claimed_at": { "$lte": stale_before as i64 } }
+        ]
+    }
+}
+
+// Messages the worker may deliver now: due and not being delivered by
+// another worker. Anything scheduled later stays invisible until then.
+#[inline]
+fn due_scheduled_filter(now_ms: u64) -> Document {
+    let mut filter = unclaimed_filter(now_ms);
+    filter.insert("send_at", doc! { "$lte": now_ms as i64 });
+    filter
+}
+
+// The sender can cancel any of their messages that no worker has claimed,
+// however far off its send time is.
+#[inline]
+fn cancel_scheduled_filter(message_id: &str, sender_id: &str, now_ms: u64) -> Document {
+    let mut filter = unclaimed_filter(now_ms);
+    filter.insert("message_id", message_id);
+    filter.insert("sender_id", sender_id);
+    filter
+}
+
+// Validates now so the sender hears about problems immediately; the block
+// check is repeated at delivery time.
+pub async fn schedule_text_message(
+    user: &UserOut,
+    other_user_id: &str,
+    content: &str,
+    send_at: u64,
+) -> Result<ScheduledMessage, VerboseHTTPError> {
+    let content = content.trim();
+    if content.is_empty() {
         return Err(VerboseHTTPError::Standard(
             StatusCode::BAD_REQUEST,
-            format!("File size cannot exceed {} bytes", MAX_FILE_SIZE),
+            "Message content cannot be empty".to_string(),
         ));
     }
 
-    let file_url = upload_file_to_filebase(&file_name, file_data.clone(), &content_type).await?;
-    let conversation_id = get_or_create_conversation(&user.uid, other_user_id).await?;
+    let now_ms = now_millis();
+    let Some(send_at) = scheduled_send_at(Some(send_at), now_ms)? else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "send_at must be in the future".to_string(),
+        ));
+    };
 
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    if user.uid == other_user_id {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Cannot schedule a message to yourself".to_string(),
+        ));
+    }
 
-    let attachment = AttachmentData {
-        id: Uuid::new_v4().to_string(),
-        file_name,
-        content_type,
-        url: file_url,
-        size: file_data.len() as u64,
-        upload_timestamp: now,
-    };
+    validate_message_length(MessageType::Text, content)?;
+    ensure_not_blocked(&user.uid, other_user_id).await?;
 
-    let message = Message {
+    let scheduled = ScheduledMessage {
         message_id: Uuid::new_v4().to_string(),
-        conversation_id: conversation_id.clone(),
         sender_id: user.uid.clone(),
-        message_type: MessageType::Attachment,
-        content: None,
-        attachment: Some(attachment),
-        query_data: None,
-        quote_data: None,
-        created_at: now,
-        updated_at: now,
+        sender_username: user.username.clone(),
+        recipient_id: other_user_id.to_string(),
+        content: content.to_string(),
+        send_at,
+        created_at: now_ms,
+        claimed_at: None,
+    };
+
+    scheduled_messages_collection()?
+        .insert_one(&scheduled)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to schedule message".to_string(),
+            )
+        })?;
+
+    log_chat_query_signal(user, content).await;
+
+    Ok(scheduled)
+}
+
+pub async fn list_scheduled_messages(
+    user: &UserOut,
+) -> Result<Vec<ScheduledMessage>, VerboseHTTPError> {
+    scheduled_messages_collection()?
+        .find(doc! { "sender_id": &user.uid })
+        .sort(doc! { "send_at": 1 })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })
+}
+
+// A message the worker has claimed is already being sent and can't be
+// cancelled; an unclaimed one is deleted before the worker can pick it up.
+pub async fn cancel_scheduled_message(
+    user: &UserOut,
+    message_id: &str,
+) -> Result<(), VerboseHTTPError> {
+    let result = scheduled_messages_collection()?
+        .delete_one(cancel_scheduled_filter(message_id, &user.uid, now_millis()))
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to cancel scheduled message".to_string(),
+            )
+        })?;
+
+    if result.deleted_count == 0 {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "Scheduled message not found or already sent".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn remove_scheduled_message(
+    collection: &Collection<ScheduledMessage>,
+    scheduled: &ScheduledMessage,
+) {
+    if let Err(error) = collection
+        .delete_one(doc! { "message_id": &scheduled.message_id })
+        .await
+    {
+        tracing::warn!(
+            "Failed to remove scheduled message {}: {}",
+            scheduled.message_id,
+            error
+        );
+    }
+}
+
+async fn message_exists(message_id: &str) -> Result<bool, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let messages: Collection<Message> = database.collection("messages");
+    messages
+        .count_documents(doc! { "message_id": message_id })
+        .await
+        .map(|count| count > 0)
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })
+}
+
+// Either participant blocking the other stops delivery, matching what
+// get_or_create_conversation enforces for a first conversation.
+async fn ensure_neither_blocked(
+    sender_id: &str,
+    recipient_id: &str,
+) -> Result<(), VerboseHTTPError> {
+    ensure_not_blocked(sender_id, recipient_id).await?;
+    if is_blocked(sender_id, recipient_id).await? {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::FORBIDDEN,
+            "You have blocked this user".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Each due message is claimed first and only removed once it is in the
+// messages collection, so a crash mid-delivery leaves a claim that expires
+// and is retried. A retry after the insert already happened just removes
+// the leftover instead of sending the message twice. A message that fails
+// keeps its claim, which holds it back until the claim expires without
+// stopping delivery of the messages behind it; a message refused because of
+// a block is dropped.
+pub async fn deliver_scheduled_messages() {
+    let Ok(collection) = scheduled_messages_collection() else {
+        return;
+    };
+
+    let mut delivered = 0;
+
+    loop {
+        let now_ms = now_millis();
+        let claimed = collection
+            .find_one_and_update(
+                due_scheduled_filter(now_ms),
+                doc! { "$set": { "claimed_at": now_ms as i64 } },
+            )
+            .sort(doc! { "send_at": 1 })
+            .return_document(ReturnDocument::After)
+            .await;
+
+        let scheduled = match claimed {
+            Ok(Some(scheduled)) => scheduled,
+            Ok(None) => break,
+            Err(error) => {
+                tracing::warn!("Failed to claim scheduled message: {}", error);
+                break;
+            }
+        };
+
+        match message_exists(&scheduled.message_id).await {
+            Ok(true) => {
+                remove_scheduled_message(&collection, &scheduled).await;
+                continue;
+            }
+            Ok(false) => {}
+            Err(error) => {
+                tracing::warn!(
+                    "Failed to check delivery of scheduled message {}: {:?}",
+                    scheduled.message_id,
+                    error
+                );
+                continue;
+            }
+        }
+
+        let result = async {
+            ensure_neither_blocked(&scheduled.sender_id, &scheduled.recipient_id).await?;
+            insert_text_message(
+                scheduled.message_id.clone(),
+                &scheduled.sender_id,
+                &scheduled.recipient_id,
+                &scheduled.content,
+            )
+            .await
+        }
+        .await;
+
+        match result {
+            Ok(_) => {
+                remove_scheduled_message(&collection, &scheduled).await;
+                delivered += 1;
+                send_message_notification(
+                    &scheduled.sender_username,
+                    &scheduled.recipient_id,
+                    MessageType::Text,
+                )
+                .await;
+            }
+            Err(VerboseHTTPError::Standard(StatusCode::FORBIDDEN, message)) => {
+                tracing::info!(
+                    "Dropping scheduled message {}: {}",
+                    scheduled.message_id,
+                    message
+                );
+                remove_scheduled_message(&collection, &scheduled).await;
+            }
+            Err(error) => {
+                tracing::warn!(
+                    "Failed to deliver scheduled message {}: {:?}",
+                    scheduled.message_id,
+                    error
+                );
+            }
+        }
+    }
+
+    if delivered > 0 {
+        tracing::info!("Delivered {} scheduled messages", delivered);
+    }
+}
+
+pub async fn run_scheduled_messages_worker() {
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(SCHEDULED_MESSAGE_POLL_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+        deliver_scheduled_messages().await;
+    }
+}
+
+pub async fn send_quote_message(
+    seller: &UserOut,
+    buyer_id: &str,
+    product_id: &str,
+    quantity: u32,
+    custom_price: &str,
+    note: Option<&str>,
+) -> Result<Message, VerboseHTTPError> {
+    let note = note.map(str::trim).filter(|note| !note.is_empty());
+    if let Some(note) = note {
+        validate_message_length(MessageType::Quote, note)?;
+    }
+
+    if buyer_id == seller.uid {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Cannot send a quote to yourself".to_string(),
+        ));
+    }
+
+    ensure_not_blocked(&seller.uid, buyer_id).await?;
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let products: Collection<crate::products::schemas::Product> = database.collection("products");
+    let product = products
+        .find_one(doc! { "product_id": product_id, "enabled": true })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or_else(VerboseHTTPError::product_not_found)?;
+
+    if product.user_id != seller.uid {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::FORBIDDEN,
+            "Only the seller of this product can send a quote".to_string(),
+        ));
+    }
+
+    crate::products::delegates::validate_order_quantity(&product, quantity)?;
+
+    let price = custom_price.trim().parse::<f64>().map_err(|_| {
+        VerboseHTTPError::Coded(
+            StatusCode::BAD_REQUEST,
+            ERROR_CODE_INVALID_PRICE,
+            "Invalid price format".to_string(),
+        )
+    })?;
+    let price_in_paise = crate::products::delegates::validate_price(price)?;
+
+    let conversation_id = get_or_create_conversation(&seller.uid, buyer_id).await?;
+    let now_ms = now_millis();
+    let now = now_ms / 1000;
+
+    let message = Message {
+        message_id: Uuid::new_v4().to_string(),
+        conversation_id: conversation_id.clone(),
+        sender_id: seller.uid.clone(),
+        message_type: MessageType::Quote,
+        content: note.map(str::to_string),
+        attachment: None,
+        query_data: None,
+        quote_data: Some(QuoteData {
+            product_id: product.product_id,
+            custom_price: format!("{}.{:02}", price_in_paise / 100, price_in_paise % 100),
+            quantity,
+            is_confirmed: false,
+            order_id: None,
+        }),
+        created_at: now_ms,
+        updated_at: now_ms,
         edit_history: Vec::new(),
+        deleted: false,
+    };
+
+    let messages: Collection<Message> = database.collection("messages");
+    let conversations: Collection<Conversation> = database.collection("conversations");
+
+    messages.insert_one(&message).await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to send quote".to_string(),
+        )
+    })?;
+
+    conversations
+        .update_one(
+            doc! { "conversation_id": &conversation_id },
+            doc! {
+                "$set": {
+                    "updated_at": now as i64,
+                    "last_message_at": now as i64
+                }
+            },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update conversation".to_string(),
+            )
+        })?;
+
+    send_message_notification(&seller.username, buyer_id, MessageType::Quote).await;
+
+    Ok(message)
+}
+
+pub async fn migrate_message_timestamps_to_millis() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let messages: Collection<Message> = database.collection("messages");
+
+    let result = messages
+        .update_many(
+            doc! { "created_at": { "$lt": MILLIS_TIMESTAMP_THRESHOLD } },
+            vec![doc! {
+                "$set": {
+                    "created_at": { "$multiply": ["$created_at", 1000] },
+                    "updated_at": { "$multiply": ["$updated_at", 1000] },
+                    "edit_history": {
+                        "$map": {
+                            "input": "$edit_history",
+                            "as": "edit",
+                            "in": {
+                                "$mergeObjects": [
+                                    "$$edit",
+                                    { "edited_at": { "$multiply": ["$$edit.edited_at", 1000] } }
+                                ]
+                            }
+                        }
+                    }
+                }
+            }],
+        )
+        .await;
+
+    match result {
+        Ok(result) if result.modified_count > 0 => {
+            tracing::info!(
+                "Migrated {} messages to millisecond timestamps",
+                result.modified_count
+            );
+        }
+        Ok(_) => {}
+        Err(error) => tracing::warn!("Failed to migrate message timestamps: {}", error),
+    }
+}
+
+async fn generate_attachment_thumbnail(file_name: &str, file_data: &Bytes) -> Option<String> {
+    let thumbnail = resize_image(file_data.to_vec(), ATTACHMENT_THUMBNAIL_SIZE).await?;
+    let thumbnail_name = format!("thumb_{}.jpg", file_name);
+
+    upload_file_to_filebase(&thumbnail_name, Bytes::from(thumbnail), "image/jpeg")
+        .await
+        .ok()
+}
+
+pub async fn send_attachment_message(
+    user: &UserOut,
+    other_user_id: &str,
+    file_name: String,
+    file_data: Bytes,
+    content_type: String,
+) -> Result<Message, VerboseHTTPError> {
+    if !is_allowed_attachment_type(&content_type) {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "File type not allowed".to_string(),
+        ));
+    }
+
+    if file_data.len() > MAX_FILE_SIZE {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!("File size cannot exceed {} bytes", MAX_FILE_SIZE),
+        ));
+    }
+
+    ensure_not_blocked(&user.uid, other_user_id).await?;
+
+    let file_url = upload_file_to_filebase(&file_name, file_data.clone(), &content_type).await?;
+    let thumbnail_url = if content_type.starts_with("image/") {
+        generate_attachment_thumbnail(&file_name, &file_data).await
+    } else {
+        None
+    };
+    let conversation_id = get_or_create_conversation(&user.uid, other_user_id).await?;
+
+    let now_ms = now_millis();
+    let now = now_ms / 1000;
+
+    let attachment = AttachmentData {
+        id: Uuid::new_v4().to_string(),
+        file_name,
+        content_type,
+        url: file_url,
+        thumbnail_url,
+        size: file_data.len() as u64,
+        upload_timestamp: now,
+    };
+
+    let message = Message {
+        message_id: Uuid::new_v4().to_string(),
+        conversation_id: conversation_id.clone(),
+        sender_id: user.uid.clone(),
+        message_type: MessageType::Attachment,
+        content: None,
+        attachment: Some(attachment),
+        query_data: None,
+        quote_data: None,
+        created_at: now_ms,
+        updated_at: now_ms,
+        edit_history: Vec::new(),
+        deleted: false,
     };
 
     let Some(database) = DB.get() else {
@@ -353,7 +1103,15 @@ pub async fn get_messages(
     other_user_id: &str,
     limit: u32,
     before: Option<&str>,
+    after: Option<&str>,
 ) -> Result<Vec<MessageResponse>, VerboseHTTPError> {
+    if before.is_some() && after.is_some() {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Cannot paginate with both before and after".to_string(),
+        ));
+    }
+
     let conversation_id = get_or_create_conversation(&user.uid, other_user_id).await?;
     verify_conversation_access(&conversation_id, &user.uid).await?;
 
@@ -367,19 +1125,34 @@ pub async fn get_messages(
     let messages: Collection<Message> = database.collection("messages");
 
     let mut filter = doc! { "conversation_id": &conversation_id };
+    let ascending = after.is_some();
+    let (cursor_id, comparison) = match (before, after) {
+        (Some(before_id), _) => (Some(before_id), "$lt"),
+        (_, Some(after_id)) => (Some(after_id), "$gt"),
+        _ => (None, "$lt"),
+    };
 
-    if let Some(before_id) = before {
-        if let Ok(Some(before_message)) = messages.find_one(doc! { "message_id": before_id }).await
-        {
-            filter.insert(
-                "created_at",
-                doc! { "$lt": before_message.created_at as i64 },
-            );
-        }
+    if let Some(cursor_id) = cursor_id
+        && let Ok(Some(cursor_message)) = messages
+            .find_one(doc! { "conversation_id": &conversation_id, "message_id": cursor_id })
+            .await
+    {
+        let cursor_created_at = cursor_message.created_at as i64;
+        filter.insert(
+            "$or",
+            vec![
+                doc! { "created_at": { comparison: cursor_created_at } },
+                doc! {
+                    "created_at": cursor_created_at,
+                    "message_id": { comparison: &cursor_message.message_id }
+                },
+            ],
+        );
     }
 
+    let sort_direction = if ascending { 1 } else { -1 };
     let find_options = FindOptions::builder()
-        .sort(doc! { "created_at": -1 })
+        .sort(doc! { "created_at": sort_direction, "message_id": sort_direction })
         .limit(limit as i64)
         .build();
 
@@ -401,44 +1174,338 @@ pub async fn get_messages(
         )
     })?;
 
-    let response_messages = messages_vec
+    let ordered_messages: Vec<Message> = if ascending {
+        messages_vec
+    } else {
+        messages_vec.into_iter().rev().collect()
+    };
+
+    let response_messages = ordered_messages
         .into_iter()
-        .rev()
-        .map(|msg| MessageResponse {
-            message_id: msg.message_id,
-            sender_id: msg.sender_id,
-            message_type: msg.message_type,
-            content: msg.content,
-            attachment: msg.attachment,
-            created_at: msg.created_at,
-            updated_at: msg.updated_at,
-            is_edited: !msg.edit_history.is_empty(),
-        })
+        .map(MessageResponse::from)
         .collect();
 
-    Ok(response_messages)
+    Ok(response_messages)
+}
+
+fn message_event_id(message: &Message) -> String {
+    format!("{}:{}", message.updated_at, message.message_id)
+}
+
+fn parse_message_event_id(event_id: &str) -> Option<(u64, String)> {
+    let (updated_at, message_id) = event_id.trim().split_once(':')?;
+    Some((updated_at.parse().ok()?, message_id.to_string()))
+}
+
+fn message_stream_event(kind: MessageStreamEventKind, message: Message) -> MessageStreamEvent {
+    MessageStreamEvent {
+        kind,
+        id: message_event_id(&message),
+        message: MessageResponse::from(message),
+    }
+}
+
+// Walks a conversation in (updated_at, message_id) order. Sends, edits and
+// deletes all bump updated_at, so one cursor sees every change.
+struct MessagePoll {
+    messages: Collection<Message>,
+    conversation_id: String,
+    last_updated_at: u64,
+    last_message_id: String,
+    pending: VecDeque<Message>,
+}
+
+impl MessagePoll {
+    fn next_pending(&mut self) -> Option<MessageStreamEvent> {
+        let message = self.pending.pop_front()?;
+        self.last_updated_at = message.updated_at;
+        self.last_message_id = message.message_id.clone();
+
+        let kind = if message.updated_at > message.created_at {
+            MessageStreamEventKind::Updated
+        } else {
+            MessageStreamEventKind::Created
+        };
+        Some(message_stream_event(kind, message))
+    }
+
+    async fn fetch(&mut self) -> Result<(), VerboseHTTPError> {
+        let poll_error = |_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to poll messages".to_string(),
+            )
+        };
+
+        let cursor = self
+            .messages
+            .find(doc! {
+                "conversation_id": &self.conversation_id,
+                "$or": [
+                    { "updated_at": { "$gt": self.last_updated_at as i64 } },
+                    {
+                        "updated_at": self.last_updated_at as i64,
+                        "message_id": { "$gt": &self.last_message_id }
+                    }
+                ]
+            })
+            .sort(doc! { "updated_at": 1, "message_id": 1 })
+            .limit(MAX_MESSAGE_LIMIT as i64)
+            .await
+            .map_err(poll_error)?;
+
+        self.pending.extend(
+            cursor
+                .try_collect::<Vec<Message>>()
+                .await
+                .map_err(poll_error)?,
+        );
+        Ok(())
+    }
+}
+
+enum MessageFeed {
+    Changes {
+        stream: ChangeStream<ChangeStreamEvent<Message>>,
+        // Replays what a reconnecting client missed before following the
+        // change stream.
+        catch_up: Option<MessagePoll>,
+    },
+    Poll {
+        poll: MessagePoll,
+        interval: tokio::time::Interval,
+    },
+}
+
+impl MessageFeed {
+    async fn next_event(&mut self) -> Option<Result<MessageStreamEvent, VerboseHTTPError>> {
+        match self {
+            Self::Changes { stream, catch_up } => {
+                if let Some(poll) = catch_up.as_mut() {
+                    if poll.pending.is_empty()
+                        && let Err(error) = poll.fetch().await
+                    {
+                        return Some(Err(error));
+                    }
+                    if let Some(event) = poll.next_pending() {
+                        return Some(Ok(event));
+                    }
+                }
+                *catch_up = None;
+
+                loop {
+                    let event = match stream.next().await? {
+                        Ok(event) => event,
+                        Err(error) => {
+                            tracing::warn!("Message change stream failed: {}", error);
+                            return Some(Err(VerboseHTTPError::Standard(
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                "Message stream interrupted".to_string(),
+                            )));
+                        }
+                    };
+                    let kind = match event.operation_type {
+                        OperationType::Insert => MessageStreamEventKind::Created,
+                        OperationType::Update | OperationType::Replace => {
+                            MessageStreamEventKind::Updated
+                        }
+                        _ => continue,
+                    };
+
+                    if let Some(message) = event.full_document {
+                        return Some(Ok(message_stream_event(kind, message)));
+                    }
+                }
+            }
+            Self::Poll { poll, interval } => loop {
+                if let Some(event) = poll.next_pending() {
+                    return Some(Ok(event));
+                }
+
+                interval.tick().await;
+
+                if let Err(error) = poll.fetch().await {
+                    return Some(Err(error));
+                }
+            },
+        }
+    }
+}
+
+// Resumes after `last_event_id` when the client is reconnecting, otherwise
+// starts from now. A failure is reported as the final item of the stream.
+pub async fn stream_messages(
+    user: &UserOut,
+    other_user_id: &str,
+    last_event_id: Option<&str>,
+) -> Result<BoxStream<'static, Result<MessageStreamEvent, VerboseHTTPError>>, VerboseHTTPError> {
+    let conversation_id = find_conversation(&user.uid, other_user_id).await?;
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let messages: Collection<Message> = database.collection("messages");
+
+    let resume_from = last_event_id.and_then(parse_message_event_id);
+    let (last_updated_at, last_message_id) = resume_from
+        .clone()
+        .unwrap_or_else(|| (now_millis(), String::new()));
+    let poll = MessagePoll {
+        messages: messages.clone(),
+        conversation_id: conversation_id.clone(),
+        last_updated_at,
+        last_message_id,
+        pending: VecDeque::new(),
+    };
+
+    let change_stream = messages
+        .watch()
+        .pipeline([doc! {
+            "$match": {
+                "fullDocument.conversation_id": &conversation_id,
+                "operationType": { "$in": ["insert", "update", "replace"] }
+            }
+        }])
+        .full_document(FullDocumentType::UpdateLookup)
+        .await;
+
+    // Change streams need a replica set; standalone servers fall back to
+    // polling. The stream is opened before catching up, so nothing is lost in
+    // between; a change seen twice is harmless to the client.
+    let feed = match change_stream {
+        Ok(stream) => MessageFeed::Changes {
+            stream,
+            catch_up: resume_from.map(|_| poll),
+        },
+        Err(error) => {
+            tracing::debug!("Change streams unavailable, polling messages: {}", error);
+            MessageFeed::Poll {
+                poll,
+                interval: tokio::time::interval(Duration::from_millis(
+                    MESSAGE_STREAM_POLL_INTERVAL_MS,
+                )),
+            }
+        }
+    };
+
+    Ok(stream::unfold(Some(feed), |feed| async move {
+        let mut feed = feed?;
+        let event = feed.next_event().await?;
+        let feed = event.is_ok().then_some(feed);
+        Some((event, feed))
+    })
+    .boxed())
+}
+
+pub async fn edit_message(
+    user: &UserOut,
+    message_id: &str,
+    new_content: &str,
+) -> Result<Message, VerboseHTTPError> {
+    let new_content = new_content.trim();
+    if new_content.is_empty() {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Message content cannot be empty".to_string(),
+        ));
+    }
+
+    validate_message_length(MessageType::Text, new_content)?;
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let messages: Collection<Message> = database.collection("messages");
+
+    let message = messages
+        .find_one(doc! {
+            "message_id": message_id,
+            "sender_id": &user.uid
+        })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::NOT_FOUND,
+                "Message not found or access denied".to_string(),
+            )
+        })?;
+
+    if message.deleted {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Cannot edit a deleted message".to_string(),
+        ));
+    }
+
+    if message.message_type != MessageType::Text {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Can only edit text messages".to_string(),
+        ));
+    }
+
+    let now = now_millis();
+
+    let edit_entry = MessageEdit {
+        content: message.content.clone(),
+        attachment: message.attachment.clone(),
+        edited_at: now,
+        username: Some(user.username.clone()),
+        deleted: false,
+    };
+
+    let updated_message = messages
+        .find_one_and_update(
+            doc! { "message_id": message_id },
+            doc! {
+                "$set": {
+                    "content": new_content,
+                    "updated_at": now as i64
+                },
+                "$push": {
+                    "edit_history": mongodb::bson::to_bson(&edit_entry).unwrap()
+                }
+            },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to edit message".to_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "Message not found".to_string())
+        })?;
+
+    Ok(updated_message)
 }
 
-pub async fn edit_message(
-    user: &UserOut,
-    message_id: &str,
-    new_content: &str,
-) -> Result<Message, VerboseHTTPError> {
-    let new_content = new_content.trim();
-    if new_content.is_empty() {
-        return Err(VerboseHTTPError::Standard(
-            StatusCode::BAD_REQUEST,
-            "Message content cannot be empty".to_string(),
-        ));
-    }
-
-    if new_content.len() > MAX_MESSAGE_LENGTH {
-        return Err(VerboseHTTPError::Standard(
-            StatusCode::BAD_REQUEST,
-            format!("Message cannot exceed {} characters", MAX_MESSAGE_LENGTH),
-        ));
+// An accepted quote keeps its `quote_data`: cancelling the order it created
+// reopens the quote through `quote_data.order_id`.
+fn deletable_message_filter(message_id: &str) -> Document {
+    doc! {
+        "message_id": message_id,
+        "deleted": { "$ne": true },
+        "quote_data.is_confirmed": { "$ne": true }
     }
+}
 
+pub async fn delete_message(user: &UserOut, message_id: &str) -> Result<(), VerboseHTTPError> {
     let Some(database) = DB.get() else {
         return Err(VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -467,35 +1534,57 @@ pub async fn edit_message(
             )
         })?;
 
-    if message.message_type != MessageType::Text {
+    if message.deleted {
         return Err(VerboseHTTPError::Standard(
             StatusCode::BAD_REQUEST,
-            "Can only edit text messages".to_string(),
+            "Message has already been deleted".to_string(),
         ));
     }
 
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    if message
+        .quote_data
+        .as_ref()
+        .is_some_and(|quote| quote.is_confirmed)
+    {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Accepted quotes cannot be deleted".to_string(),
+        ));
+    }
 
-    let edit_entry = MessageEdit {
-        content: message.content.clone(),
-        attachment: message.attachment.clone(),
+    let now = now_millis();
+
+    // Earlier revisions are scrubbed too, otherwise the history endpoint
+    // would keep serving the content that was just deleted.
+    let mut edit_history: Vec<MessageEdit> = message
+        .edit_history
+        .into_iter()
+        .map(|edit| MessageEdit {
+            content: None,
+            attachment: None,
+            ..edit
+        })
+        .collect();
+    edit_history.push(MessageEdit {
+        content: None,
+        attachment: None,
         edited_at: now,
         username: Some(user.username.clone()),
-    };
+        deleted: true,
+    });
 
-    let updated_message = messages
-        .find_one_and_update(
-            doc! { "message_id": message_id },
+    let updated = messages
+        .update_one(
+            deletable_message_filter(message_id),
             doc! {
                 "$set": {
-                    "content": new_content,
-                    "updated_at": now as i64
-                },
-                "$push": {
-                    "edit_history": mongodb::bson::to_bson(&edit_entry).unwrap()
+                    "deleted": true,
+                    "content": null,
+                    "attachment": null,
+                    "query_data": null,
+                    "quote_data": null,
+                    "updated_at": now as i64,
+                    "edit_history": mongodb::bson::to_bson(&edit_history).unwrap()
                 }
             },
         )
@@ -503,14 +1592,25 @@ pub async fn edit_message(
         .map_err(|_| {
             VerboseHTTPError::Standard(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to edit message".to_string(),
+                "Failed to delete message".to_string(),
             )
-        })?
-        .ok_or_else(|| {
-            VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "Message not found".to_string())
         })?;
 
-    Ok(updated_message)
+    if updated.modified_count == 0 {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Message has already been deleted".to_string(),
+        ));
+    }
+
+    if let Some(attachment) = message.attachment {
+        let file_urls: Vec<String> = std::iter::once(attachment.url)
+            .chain(attachment.thumbnail_url)
+            .collect();
+        schedule_unpins(&file_urls, "delete_message").await;
+    }
+
+    Ok(())
 }
 
 pub async fn get_user_conversations(
@@ -525,8 +1625,78 @@ pub async fn get_user_conversations(
 
     let conversations: Collection<Conversation> = database.collection("conversations");
 
+    // Unread counts come from a $lookup against the caller's read marker,
+    // counting only messages the other participant sent after it. A second
+    // $lookup pulls the newest live message for the preview.
+    let pipeline = vec![
+        doc! { "$match": { "participant_ids": &user.uid } },
+        doc! { "$sort": { "last_message_at": -1 } },
+        doc! {
+            "$lookup": {
+                "from": "messages",
+                "let": {
+                    "conversation_id": "$conversation_id",
+                    "last_read_at": {
+                        "$ifNull": [format!("$last_read_at.{}", user.uid), 0_i64]
+                    }
+                },
+                "pipeline": [
+                    {
+                        "$match": {
+                            "$expr": {
+                                "$and": [
+                                    { "$eq": ["$conversation_id", "$$conversation_id"] },
+                                    { "$ne": ["$sender_id", &user.uid] },
+                                    { "$ne": ["$deleted", true] },
+                                    { "$gt": ["$created_at", "$$last_read_at"] }
+                                ]
+                            }
+                        }
+                    },
+                    { "$count": "count" }
+                ],
+                "as": "unread"
+            }
+        },
+        doc! {
+            "$lookup": {
+                "from": "messages",
+                "let": { "conversation_id": "$conversation_id" },
+                "pipeline": [
+                    {
+                        "$match": {
+                            "$expr": {
+                                "$and": [
+                                    { "$eq": ["$conversation_id", "$$conversation_id"] },
+                                    { "$ne": ["$deleted", true] }
+                                ]
+                            }
+                        }
+                    },
+                    { "$sort": { "created_at": -1 } },
+                    { "$limit": 1 },
+                    { "$project": { "_id": 0, "sender_id": 1, "message_type": 1, "content": 1 } }
+                ],
+                "as": "latest"
+            }
+        },
+        doc! {
+            "$project": {
+                "_id": 0,
+                "conversation_id": 1,
+                "participant_ids": 1,
+                "created_at": 1,
+                "last_message_at": 1,
+                "last_read_at": 1,
+                "unread_count": { "$sum": "$unread.count" },
+                "last_message": { "$first": "$latest" }
+            }
+        },
+    ];
+
     let cursor = conversations
-        .find(doc! { "participant_ids": &user.uid })
+        .aggregate(pipeline)
+        .with_type::<ConversationSummary>()
         .await
         .map_err(|_| {
             VerboseHTTPError::Standard(
@@ -535,7 +1705,7 @@ pub async fn get_user_conversations(
             )
         })?;
 
-    let conversations_vec: Vec<Conversation> = cursor.try_collect().await.map_err(|_| {
+    let conversations_vec: Vec<ConversationSummary> = cursor.try_collect().await.map_err(|_| {
         VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to collect conversations".to_string(),
@@ -551,12 +1721,21 @@ pub async fn get_user_conversations(
                 .find(|&id| id != &user.uid)
                 .unwrap_or(&user.uid)
                 .clone();
+            let other_last_read_at = conv.last_read_at.get(&other_participant_id).copied();
+            let (last_message_preview, last_sender_id) = match conv.last_message {
+                Some(message) => (Some(message_preview(&message)), Some(message.sender_id)),
+                None => (None, None),
+            };
 
             ConversationResponse {
                 conversation_id: conv.conversation_id,
                 other_participant_id,
                 created_at: conv.created_at,
                 last_message_at: conv.last_message_at,
+                unread_count: conv.unread_count,
+                other_last_read_at,
+                last_message_preview,
+                last_sender_id,
             }
         })
         .collect();
@@ -564,6 +1743,268 @@ pub async fn get_user_conversations(
     Ok(response_conversations)
 }
 
+fn message_preview(message: &LastMessage) -> String {
+    match (&message.message_type, message.content.as_deref()) {
+        (MessageType::Attachment, _) => "📎 Attachment".to_string(),
+        (MessageType::Query, _) => "Product query".to_string(),
+        (MessageType::Quote, None) => "Quote".to_string(),
+        (_, content) => {
+            let content = content.unwrap_or_default().trim();
+            if content.chars().count() > MESSAGE_PREVIEW_LENGTH {
+                let truncated: String = content.chars().take(MESSAGE_PREVIEW_LENGTH).collect();
+                format!("{}…", truncated.trim_end())
+            } else {
+                content.to_string()
+            }
+        }
+    }
+}
+
+pub async fn mark_conversation_read(
+    user: &UserOut,
+    other_user_id: &str,
+) -> Result<u64, VerboseHTTPError> {
+    let conversation_id = find_conversation(&user.uid, other_user_id).await?;
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let conversations: Collection<Conversation> = database.collection("conversations");
+    let now_ms = now_millis();
+
+    // $max keeps the marker monotonic if requests arrive out of order.
+    conversations
+        .update_one(
+            doc! { "conversation_id": &conversation_id, "participant_ids": &user.uid },
+            doc! { "$max": { format!("last_read_at.{}", user.uid): now_ms as i64 } },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to mark conversation as read".to_string(),
+            )
+        })?;
+
+    Ok(now_ms)
+}
+
+pub async fn sweep_orphaned_conversations(window_secs: u64) {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let cutoff = now.saturating_sub(window_secs) as i64;
+
+    let conversations: Collection<Conversation> = database.collection("conversations");
+    let messages: Collection<Message> = database.collection("messages");
+
+    // A conversation is orphaned once it has been idle past the window and
+    // either holds no live messages or has lost a participant account.
+    let pipeline = vec![
+        doc! { "$match": { "last_message_at": { "$lt": cutoff } } },
+        doc! {
+            "$lookup": {
+                "from": "messages",
+                "let": { "conversation_id": "$conversation_id" },
+                "pipeline": [
+                    {
+                        "$match": {
+                            "$expr": {
+                                "$and": [
+                                    { "$eq": ["$conversation_id", "$$conversation_id"] },
+                                    { "$ne": ["$deleted", true] }
+                                ]
+                            }
+                        }
+                    },
+                    { "$limit": 1 },
+                    { "$project": { "_id": 1 } }
+                ],
+                "as": "live_messages"
+            }
+        },
+        doc! {
+            "$lookup": {
+                "from": "users",
+                "localField": "participant_ids",
+                "foreignField": "uid",
+                "as": "participants"
+            }
+        },
+        doc! {
+            "$project": {
+                "conversation_id": 1,
+                "has_live_messages": { "$gt": [{ "$size": "$live_messages" }, 0] },
+                "has_all_participants": {
+                    "$eq": [
+                        {
+                            "$size": {
+                                "$filter": {
+                                    "input": "$participants",
+                                    "cond": { "$ne": ["$$this.enabled", false] }
+                                }
+                            }
+                        },
+                        { "$size": { "$setUnion": ["$participant_ids", []] } }
+                    ]
+                }
+            }
+        },
+        doc! {
+            "$match": {
+                "$or": [
+                    { "has_live_messages": false },
+                    { "has_all_participants": false }
+                ]
+            }
+        },
+    ];
+
+    let orphaned: Vec<String> = match conversations.aggregate(pipeline).await {
+        Ok(cursor) => cursor
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|doc| doc.get_str("conversation_id").ok().map(str::to_string))
+            .collect(),
+        Err(error) => {
+            tracing::warn!("Failed to find orphaned conversations: {}", error);
+            return;
+        }
+    };
+
+    let mut removed = 0;
+
+    for conversation_id in orphaned {
+        // Re-checking the cutoff skips conversations that became active
+        // again since the aggregation ran.
+        let deleted = conversations
+            .delete_one(doc! {
+                "conversation_id": &conversation_id,
+                "last_message_at": { "$lt": cutoff }
+            })
+            .await;
+
+        if !matches!(deleted, Ok(ref result) if result.deleted_count == 1) {
+            continue;
+        }
+
+        removed += 1;
+
+        let file_urls: Vec<String> = match messages
+            .find(doc! { "conversation_id": &conversation_id, "attachment": { "$ne": null } })
+            .await
+        {
+            Ok(cursor) => cursor
+                .try_collect::<Vec<Message>>()
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|message| message.attachment)
+                .flat_map(|attachment| {
+                    std::iter::once(attachment.url).chain(attachment.thumbnail_url)
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        if !file_urls.is_empty() {
+            schedule_unpins(&file_urls, "orphaned_conversation").await;
+        }
+
+        if let Err(error) = messages
+            .delete_many(doc! { "conversation_id": &conversation_id })
+            .await
+        {
+            tracing::warn!(
+                "Failed to delete messages of conversation {}: {}",
+                conversation_id,
+                error
+            );
+        }
+    }
+
+    if removed > 0 {
+        tracing::info!("Removed {} orphaned conversations", removed);
+    }
+}
+
+pub async fn run_orphaned_conversations_worker() {
+    let interval_secs = var("CONVERSATION_SWEEP_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(DEFAULT_CONVERSATION_SWEEP_INTERVAL_SECS);
+    let window_secs = var("ORPHANED_CONVERSATION_WINDOW_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_ORPHANED_CONVERSATION_WINDOW_SECS);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+
+    loop {
+        interval.tick().await;
+        sweep_orphaned_conversations(window_secs).await;
+    }
+}
+
+pub async fn ensure_message_indexes() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let messages: Collection<Message> = database.collection("messages");
+    let indexes = [
+        IndexModel::builder()
+            .keys(doc! { "conversation_id": 1, "created_at": -1 })
+            .build(),
+        IndexModel::builder()
+            .keys(doc! { "conversation_id": 1, "updated_at": 1, "message_id": 1 })
+            .build(),
+    ];
+
+    for index in indexes {
+        if let Err(error) = messages.create_index(index).await {
+            tracing::warn!("Failed to create message index: {}", error);
+        }
+    }
+
+    let blocks: Collection<Block> = database.collection(COLLECTIONS_BLOCKS);
+    let index = IndexModel::builder()
+        .keys(doc! { "blocker_id": 1, "blocked_id": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+
+    if let Err(error) = blocks.create_index(index).await {
+        tracing::warn!("Failed to create block index: {}", error);
+    }
+
+    let scheduled: Collection<ScheduledMessage> =
+        database.collection(COLLECTIONS_SCHEDULED_MESSAGES);
+    let indexes = [
+        IndexModel::builder().keys(doc! { "send_at": 1 }).build(),
+        IndexModel::builder()
+            .keys(doc! { "sender_id": 1, "send_at": 1 })
+            .build(),
+    ];
+
+    for index in indexes {
+        if let Err(error) = scheduled.create_index(index).await {
+            tracing::warn!("Failed to create scheduled message index: {}", error);
+        }
+    }
+}
+
 pub async fn get_message_edit_history(
     user: &UserOut,
     message_id: &str,
@@ -592,24 +2033,29 @@ pub async fn get_message_edit_history(
         })?;
 
     verify_conversation_access(&message.conversation_id, &user.uid).await?;
-    
-    // Get the sender's username
-    let sender = users
-        .find_one(doc! { "uid": &message.sender_id })
-        .await
-        .map_err(|_| {
-            VerboseHTTPError::Standard(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Database error".to_string(),
-            )
-        })?;
-    
-    let sender_username = sender.map(|u| u.username);
-    
-    // Add username to all edit history entries
+
     let mut edit_history = message.edit_history;
-    for edit in &mut edit_history {
-        edit.username = sender_username.clone();
+
+    // Entries keep the username recorded at edit time; only older entries
+    // written without one fall back to the sender's current username.
+    if edit_history.iter().any(|edit| edit.username.is_none()) {
+        let sender = users
+            .find_one(doc! { "uid": &message.sender_id })
+            .await
+            .map_err(|_| {
+                VerboseHTTPError::Standard(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Database error".to_string(),
+                )
+            })?;
+
+        let sender_username = sender.map(|u| u.username);
+        for edit in edit_history
+            .iter_mut()
+            .filter(|edit| edit.username.is_none())
+        {
+            edit.username = sender_username.clone();
+        }
     }
 
     Ok(edit_history)
@@ -618,6 +2064,7 @@ pub async fn get_message_edit_history(
 pub async fn create_order_from_quote(
     user: &UserOut,
     message_id: String,
+    address_id: &str,
 ) -> Result<crate::products::schemas::Order, VerboseHTTPError> {
     let Some(database) = DB.get() else {
         return Err(VerboseHTTPError::Standard(
@@ -643,13 +2090,27 @@ pub async fn create_order_from_quote(
 
     verify_conversation_access(&message.conversation_id, &user.uid).await?;
 
-    let Some(quote_data) = message.quote_data else {
+    let (MessageType::Quote, Some(quote_data)) = (message.message_type, message.quote_data) else {
         return Err(VerboseHTTPError::Standard(
             StatusCode::BAD_REQUEST,
             "Message is not a quote".to_string(),
         ));
     };
 
+    if message.sender_id == user.uid {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::FORBIDDEN,
+            "You cannot accept your own quote".to_string(),
+        ));
+    }
+
+    if quote_data.is_confirmed {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::CONFLICT,
+            "Quote has already been accepted".to_string(),
+        ));
+    }
+
     let products: Collection<crate::products::schemas::Product> = database.collection("products");
     let product = products
         .find_one(doc! { "product_id": &quote_data.product_id })
@@ -660,22 +2121,83 @@ pub async fn create_order_from_quote(
                 "Database error".to_string(),
             )
         })?
-        .ok_or_else(|| {
-            VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "Product not found".to_string())
-        })?;
+        .ok_or_else(VerboseHTTPError::product_not_found)?;
+
+    if product.user_id != message.sender_id {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Quote was not sent by the seller of this product".to_string(),
+        ));
+    }
 
-    let price = quote_data.custom_price.parse::<f64>().map_err(|_| {
-        VerboseHTTPError::Standard(StatusCode::BAD_REQUEST, "Invalid price format".to_string())
+    crate::products::delegates::validate_order_quantity(&product, quote_data.quantity)?;
+
+    let price = quote_data.custom_price.trim().parse::<f64>().map_err(|_| {
+        VerboseHTTPError::Coded(
+            StatusCode::BAD_REQUEST,
+            ERROR_CODE_INVALID_PRICE,
+            "Invalid price format".to_string(),
+        )
     })?;
+    let price_in_paise = crate::products::delegates::validate_price(price)?;
+    let shipping_address = crate::auth::shipping_address_snapshot(user, address_id).await?;
+
+    let claimed = messages
+        .update_one(
+            doc! { "message_id": &message_id, "quote_data.is_confirmed": false },
+            doc! { "$set": { "quote_data.is_confirmed": true } },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    if claimed.modified_count == 0 {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::CONFLICT,
+            "Quote has already been accepted".to_string(),
+        ));
+    }
 
-    let order_response = crate::orders::delegates::create_order_internal(
+    let order_response = match crate::orders::delegates::create_order_internal(
         quote_data.product_id,
         product.user_id,
         user.uid.clone(),
         quote_data.quantity,
-        price,
+        price_in_paise,
+        product.region,
+        Some(shipping_address),
     )
-    .await?;
+    .await
+    {
+        Ok(order_response) => order_response,
+        Err(error) => {
+            let _ = messages
+                .update_one(
+                    doc! { "message_id": &message_id },
+                    doc! { "$set": { "quote_data.is_confirmed": false } },
+                )
+                .await;
+            return Err(error);
+        }
+    };
+
+    if let Err(error) = messages
+        .update_one(
+            doc! { "message_id": &message_id },
+            doc! { "$set": { "quote_data.order_id": &order_response.order_id } },
+        )
+        .await
+    {
+        tracing::warn!(
+            "Failed to link quote {} to its order: {}",
+            message_id,
+            error
+        );
+    }
 
     let order = crate::products::schemas::Order {
         order_id: order_response.order_id,
@@ -684,7 +2206,12 @@ pub async fn create_order_from_quote(
         buyer_id: order_response.buyer_id,
         quantity: order_response.quantity,
         price: order_response.price,
+        price_in_paise: order_response.price_in_paise,
         status: order_response.status,
+        seller_note: order_response.seller_note,
+        region: order_response.region,
+        status_history: order_response.status_history,
+        shipping_address: order_response.shipping_address,
         created_at: order_response.created_at,
         updated_at: order_response.updated_at,
     };
@@ -706,7 +2233,7 @@ async fn send_message_notification(
         return;
     };
 
-    if let Err(_) = recipient.initialize_encryption() {
+    if recipient.initialize_encryption().is_err() {
         return;
     }
 
@@ -721,22 +2248,25 @@ async fn send_message_notification(
         notification_message
     );
 
-    let _ = crate::notifications::delegates::send_email_internal(
-        &recipient.email.to_string(),
-        Some(&recipient.username),
-        "New Message - GoodsPoint",
-        &full_message,
-    )
-    .await;
+    if recipient.notification_preferences.email {
+        let _ = crate::notifications::delegates::send_email_internal(
+            &recipient.email.to_string(),
+            Some(&recipient.username),
+            "New Message - GoodsPoint",
+            &full_message,
+        )
+        .await;
+    }
 
-    if recipient.whatsapp_verified {
-        if let Some(ref whatsapp) = recipient.whatsapp_number {
-            let _ = crate::notifications::delegates::send_whatsapp_internal(
-                &whatsapp.to_string(),
-                &full_message,
-            )
-            .await;
-        }
+    if recipient.whatsapp_verified
+        && recipient.notification_preferences.whatsapp
+        && let Some(ref whatsapp) = recipient.whatsapp_number
+    {
+        let _ = crate::notifications::delegates::send_whatsapp_internal(
+            &whatsapp.to_string(),
+            &full_message,
+        )
+        .await;
     }
 }
 
@@ -751,8 +2281,6 @@ async fn log_chat_query_signal(user: &UserOut, content: &str) {
             Some(content.to_string()),
         )
         .await;
-
-
     }
 }
 fn is_product_query_message(content: &str) -> bool {
@@ -874,3 +2402,129 @@ fn infer_category_from_query(query: &str) -> ProductCategory {
         ProductCategory::UnisexClothing
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000_000;
+
+    fn last_message(message_type: MessageType, content: Option<&str>) -> LastMessage {
+        LastMessage {
+            sender_id: "sender".to_string(),
+            message_type,
+            content: content.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn message_preview_describes_non_text_messages() {
+        assert_eq!(
+            message_preview(&last_message(MessageType::Attachment, Some("photo.jpg"))),
+            "📎 Attachment"
+        );
+        assert_eq!(
+            message_preview(&last_message(MessageType::Query, None)),
+            "Product query"
+        );
+        assert_eq!(
+            message_preview(&last_message(MessageType::Quote, None)),
+            "Quote"
+        );
+        assert_eq!(
+            message_preview(&last_message(MessageType::Quote, Some(" Best price "))),
+            "Best price"
+        );
+    }
+
+    #[test]
+    fn message_preview_truncates_long_text_by_characters() {
+        let content = "é".repeat(MESSAGE_PREVIEW_LENGTH + 5);
+        let preview = message_preview(&last_message(MessageType::Text, Some(&content)));
+
+        assert_eq!(preview.chars().count(), MESSAGE_PREVIEW_LENGTH + 1);
+        assert!(preview.ends_with('…'));
+        assert_eq!(
+            message_preview(&last_message(MessageType::Text, Some("hi"))),
+            "hi"
+        );
+    }
+
+    #[test]
+    fn future_send_at_is_scheduled() {
+        let send_at = NOW + 60_000;
+        assert!(matches!(scheduled_send_at(Some(send_at), NOW), Ok(Some(at)) if at == send_at));
+    }
+
+    #[test]
+    fn missing_or_past_send_at_sends_now() {
+        assert!(matches!(scheduled_send_at(None, NOW), Ok(None)));
+        assert!(matches!(scheduled_send_at(Some(NOW), NOW), Ok(None)));
+        assert!(matches!(scheduled_send_at(Some(NOW - 1), NOW), Ok(None)));
+    }
+
+    #[test]
+    fn send_at_beyond_the_limit_is_rejected() {
+        assert!(scheduled_send_at(Some(NOW + MAX_SCHEDULE_AHEAD_MS), NOW).is_ok());
+        assert!(scheduled_send_at(Some(NOW + MAX_SCHEDULE_AHEAD_MS + 1), NOW).is_err());
+    }
+
+    #[test]
+    fn only_unclaimed_or_abandoned_messages_can_be_claimed_or_cancelled() {
+        let stale_before = (NOW - SCHEDULED_MESSAGE_CLAIM_TIMEOUT_MS) as i64;
+        assert_eq!(
+            unclaimed_filter(NOW),
+            doc! {
+                "$or": [
+                    { "claimed_at": null },
+                    { "claimed_at": { "$lte": stale_before } }
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn scheduled_messages_stay_hidden_until_due() {
+        let mut expected = unclaimed_filter(NOW);
+        expected.insert("send_at", doc! { "$lte": NOW as i64 });
+
+        // A message due a minute from now doesn't match `send_at <= now`, so
+        // the worker can't claim and deliver it yet.
+        assert_eq!(due_scheduled_filter(NOW), expected);
+    }
+
+    #[test]
+    fn pending_scheduled_messages_can_be_cancelled_by_their_sender() {
+        let filter = cancel_scheduled_filter("message-1", "sender-1", NOW);
+
+        assert_eq!(filter.get_str("message_id"), Ok("message-1"));
+        assert_eq!(filter.get_str("sender_id"), Ok("sender-1"));
+        assert_eq!(
+            filter.get_array("$or"),
+            unclaimed_filter(NOW).get_array("$or")
+        );
+        assert!(!filter.contains_key("send_at"));
+    }
+
+    #[test]
+    fn message_event_ids_round_trip() {
+        assert_eq!(
+            parse_message_event_id("1700000000000:3f2a-9c"),
+            Some((NOW, "3f2a-9c".to_string()))
+        );
+        assert_eq!(parse_message_event_id("not-an-id"), None);
+        assert_eq!(parse_message_event_id("soon:3f2a"), None);
+    }
+
+    #[test]
+    fn accepted_quote_messages_are_not_deleted() {
+        assert_eq!(
+            deletable_message_filter("message-1"),
+            doc! {
+                "message_id": "message-1",
+                "deleted": { "$ne": true },
+                "quote_data.is_confirmed": { "$ne": true }
+            }
+        );
+    }
+}
@@ -1,23 +1,37 @@
 use axum::{
     Json,
     extract::{Extension, Multipart, Path, Query},
-    http::StatusCode,
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode},
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
 };
 use bytes::Bytes;
+use futures::StreamExt;
 use serde_json::json;
+use std::time::Duration;
 
 use super::{
     delegates::{
-        edit_message, get_message_edit_history, get_messages, get_user_conversations,
-        is_allowed_attachment_type, send_attachment_message, send_text_message,
+        block_user, cancel_scheduled_message, delete_message, edit_message,
+        get_message_edit_history, get_messages, get_user_conversations, is_allowed_attachment_type,
+        list_blocked_users, list_scheduled_messages, mark_conversation_read, schedule_text_message,
+        scheduled_send_at, send_attachment_message, send_quote_message, send_text_message,
+        stream_messages, unblock_user,
     },
     schemas::{
         DEFAULT_MESSAGE_LIMIT, EditMessageRequest, GetMessagesQuery, MAX_FILE_SIZE,
-        MAX_MESSAGE_LIMIT,
+        MESSAGE_STREAM_HEARTBEAT_SECS, SendQuoteRequest,
+    },
+};
+use crate::{
+    apex::{
+        config::config,
+        utils::{VerboseHTTPError, now_millis},
     },
+    auth::schemas::UserOut,
 };
-use crate::{apex::utils::VerboseHTTPError, auth::schemas::UserOut};
 
 pub(crate) async fn send_message_endpoint(
     Extension(user): Extension<UserOut>,
@@ -26,6 +40,7 @@ pub(crate) async fn send_message_endpoint(
 ) -> impl IntoResponse {
     let mut text_content: Option<String> = None;
     let mut attachment_file: Option<(String, Bytes, String)> = None;
+    let mut send_at: Option<u64> = None;
 
     while let Ok(Some(field)) = multipart.next_field().await {
         let Some(field_name) = field.name() else {
@@ -38,6 +53,21 @@ pub(crate) async fn send_message_endpoint(
                     text_content = Some(String::from_utf8_lossy(&bytes).to_string());
                 }
             }
+            "send_at" => {
+                let Ok(bytes) = field.bytes().await else {
+                    continue;
+                };
+                match String::from_utf8_lossy(&bytes).trim().parse::<u64>() {
+                    Ok(value) => send_at = Some(value),
+                    Err(_) => {
+                        return VerboseHTTPError::Standard(
+                            StatusCode::BAD_REQUEST,
+                            "send_at must be a timestamp in milliseconds".to_string(),
+                        )
+                        .into_response();
+                    }
+                }
+            }
             "attachment" => {
                 if let Some(file_name) = field.file_name() {
                     let file_name = file_name.to_string();
@@ -79,6 +109,31 @@ pub(crate) async fn send_message_endpoint(
         .into_response();
     }
 
+    // A send_at that has already passed just sends the message now.
+    let send_at = match scheduled_send_at(send_at, now_millis()) {
+        Ok(send_at) => send_at,
+        Err(err) => return err.into_response(),
+    };
+
+    if let Some(send_at) = send_at {
+        let Some(content) = text_content else {
+            return VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                "Only text messages can be scheduled".to_string(),
+            )
+            .into_response();
+        };
+
+        return match schedule_text_message(&user, &other_user_id, &content, send_at).await {
+            Ok(scheduled) => Json(json!({
+                "status": "scheduled",
+                "scheduled_message": scheduled
+            }))
+            .into_response(),
+            Err(err) => err.into_response(),
+        };
+    }
+
     let message_result = if let Some(content) = text_content {
         send_text_message(&user, &other_user_id, &content).await
     } else if let Some((file_name, file_data, content_type)) = attachment_file {
@@ -110,6 +165,39 @@ pub(crate) async fn send_message_endpoint(
     }
 }
 
+pub(crate) async fn send_quote_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(other_user_id): Path<String>,
+    Json(request): Json<SendQuoteRequest>,
+) -> impl IntoResponse {
+    match send_quote_message(
+        &user,
+        &other_user_id,
+        &request.product_id,
+        request.quantity,
+        &request.custom_price,
+        request.note.as_deref(),
+    )
+    .await
+    {
+        Ok(message) => Json(json!({
+            "status": "ok",
+            "message": {
+                "message_id": message.message_id,
+                "sender_id": message.sender_id,
+                "message_type": message.message_type,
+                "content": message.content,
+                "quote_data": message.quote_data,
+                "created_at": message.created_at,
+                "updated_at": message.updated_at,
+                "is_edited": false
+            }
+        }))
+        .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
 pub(crate) async fn get_messages_endpoint(
     Extension(user): Extension<UserOut>,
     Path(other_user_id): Path<String>,
@@ -118,9 +206,17 @@ pub(crate) async fn get_messages_endpoint(
     let limit = params
         .limit
         .unwrap_or(DEFAULT_MESSAGE_LIMIT)
-        .min(MAX_MESSAGE_LIMIT);
+        .min(config().max_message_limit);
 
-    match get_messages(&user, &other_user_id, limit, params.before.as_deref()).await {
+    match get_messages(
+        &user,
+        &other_user_id,
+        limit,
+        params.before.as_deref(),
+        params.after.as_deref(),
+    )
+    .await
+    {
         Ok(messages) => Json(json!({
             "status": "ok",
             "messages": messages
@@ -130,6 +226,38 @@ pub(crate) async fn get_messages_endpoint(
     }
 }
 
+pub(crate) async fn stream_messages_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(other_user_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok());
+
+    match stream_messages(&user, &other_user_id, last_event_id).await {
+        Ok(events) => {
+            let events = events.map(|event| match event {
+                Ok(event) => Event::default()
+                    .event(event.kind.event_name())
+                    .id(event.id)
+                    .json_data(event.message),
+                Err(VerboseHTTPError::Standard(_, message))
+                | Err(VerboseHTTPError::Coded(_, _, message)) => Event::default()
+                    .event("error")
+                    .json_data(json!({ "message": message })),
+            });
+
+            Sse::new(events)
+                .keep_alive(
+                    KeepAlive::new().interval(Duration::from_secs(MESSAGE_STREAM_HEARTBEAT_SECS)),
+                )
+                .into_response()
+        }
+        Err(err) => err.into_response(),
+    }
+}
+
 pub(crate) async fn edit_message_endpoint(
     Extension(user): Extension<UserOut>,
     Path(message_id): Path<String>,
@@ -165,6 +293,49 @@ pub(crate) async fn edit_message_endpoint(
     }
 }
 
+pub(crate) async fn delete_message_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(message_id): Path<String>,
+) -> impl IntoResponse {
+    match delete_message(&user, &message_id).await {
+        Ok(()) => Json(json!({
+            "status": "ok",
+            "message_id": message_id,
+            "is_deleted": true
+        }))
+        .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+pub(crate) async fn list_scheduled_messages_endpoint(
+    Extension(user): Extension<UserOut>,
+) -> impl IntoResponse {
+    match list_scheduled_messages(&user).await {
+        Ok(scheduled) => Json(json!({
+            "status": "ok",
+            "scheduled_messages": scheduled
+        }))
+        .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+pub(crate) async fn cancel_scheduled_message_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(message_id): Path<String>,
+) -> impl IntoResponse {
+    match cancel_scheduled_message(&user, &message_id).await {
+        Ok(()) => Json(json!({
+            "status": "ok",
+            "message_id": message_id,
+            "is_cancelled": true
+        }))
+        .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
 pub(crate) async fn get_conversations_endpoint(
     Extension(user): Extension<UserOut>,
 ) -> impl IntoResponse {
@@ -178,6 +349,59 @@ pub(crate) async fn get_conversations_endpoint(
     }
 }
 
+pub(crate) async fn mark_conversation_read_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(other_user_id): Path<String>,
+) -> impl IntoResponse {
+    match mark_conversation_read(&user, &other_user_id).await {
+        Ok(last_read_at) => Json(json!({
+            "status": "ok",
+            "last_read_at": last_read_at
+        }))
+        .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+pub(crate) async fn block_user_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(other_user_id): Path<String>,
+) -> impl IntoResponse {
+    match block_user(&user, &other_user_id).await {
+        Ok(block) => Json(json!({
+            "status": "ok",
+            "block": block
+        }))
+        .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+pub(crate) async fn unblock_user_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(other_user_id): Path<String>,
+) -> impl IntoResponse {
+    match unblock_user(&user, &other_user_id).await {
+        Ok(was_blocked) => Json(json!({
+            "status": "ok",
+            "unblocked": was_blocked
+        }))
+        .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+pub(crate) async fn list_blocks_endpoint(Extension(user): Extension<UserOut>) -> impl IntoResponse {
+    match list_blocked_users(&user).await {
+        Ok(blocks) => Json(json!({
+            "status": "ok",
+            "blocks": blocks
+        }))
+        .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
 pub(crate) async fn get_message_history_endpoint(
     Extension(user): Extension<UserOut>,
     Path(message_id): Path<String>,
@@ -196,7 +420,9 @@ pub(crate) async fn create_order_from_quote_endpoint(
     Extension(user): Extension<UserOut>,
     Json(request): Json<crate::products::schemas::CreateOrderFromQuoteRequest>,
 ) -> impl IntoResponse {
-    match super::delegates::create_order_from_quote(&user, request.message_id).await {
+    match super::delegates::create_order_from_quote(&user, request.message_id, &request.address_id)
+        .await
+    {
         Ok(order) => Json(order).into_response(),
         Err(error) => error.into_response(),
     }
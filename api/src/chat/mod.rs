@@ -1,3 +1,8 @@
-pub(self) mod delegates;
+mod delegates;
 pub(crate) mod endpoints;
 pub(crate) mod schemas;
+
+pub(crate) use delegates::{
+    ensure_message_indexes, migrate_message_timestamps_to_millis,
+    run_orphaned_conversations_worker, run_scheduled_messages_worker,
+};
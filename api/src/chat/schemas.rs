@@ -1,11 +1,24 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
+pub const COLLECTIONS_BLOCKS: &str = "blocks";
+pub const COLLECTIONS_SCHEDULED_MESSAGES: &str = "scheduled_messages";
+pub const SCHEDULED_MESSAGE_POLL_INTERVAL_SECS: u64 = 30;
+pub const MAX_SCHEDULE_AHEAD_MS: u64 = 30 * 24 * 60 * 60 * 1000;
+pub const SCHEDULED_MESSAGE_CLAIM_TIMEOUT_MS: u64 = 5 * 60 * 1000;
 pub const MAX_MESSAGE_LENGTH: usize = 4000;
 pub const MAX_FILE_SIZE: usize = 50 * 1024 * 1024;
 pub const DEFAULT_MESSAGE_LIMIT: u32 = 64;
 pub const MAX_MESSAGE_LIMIT: u32 = 100;
+pub const ATTACHMENT_THUMBNAIL_SIZE: u32 = 320;
+pub const MILLIS_TIMESTAMP_THRESHOLD: i64 = 100_000_000_000;
+pub const MESSAGE_STREAM_POLL_INTERVAL_MS: u64 = 2000;
+pub const MESSAGE_STREAM_HEARTBEAT_SECS: u64 = 15;
+pub const MESSAGE_PREVIEW_LENGTH: usize = 80;
+pub const DEFAULT_CONVERSATION_SWEEP_INTERVAL_SECS: u64 = 60 * 60;
+pub const DEFAULT_ORPHANED_CONVERSATION_WINDOW_SECS: u64 = 7 * 24 * 60 * 60;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum MessageType {
     Text,
@@ -33,6 +46,9 @@ pub struct QuoteData {
     pub custom_price: String,
     pub quantity: u32,
     pub is_confirmed: bool,
+    // Set once the quote is accepted so a cancelled order can reopen it.
+    #[serde(default)]
+    pub order_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -41,6 +57,8 @@ pub struct AttachmentData {
     pub file_name: String,
     pub content_type: String,
     pub url: String,
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
     pub size: u64,
     pub upload_timestamp: u64,
 }
@@ -50,7 +68,10 @@ pub struct MessageEdit {
     pub content: Option<String>,
     pub attachment: Option<AttachmentData>,
     pub edited_at: u64,
+    #[serde(default)]
     pub username: Option<String>,
+    #[serde(default)]
+    pub deleted: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -66,6 +87,26 @@ pub struct Message {
     pub created_at: u64,
     pub updated_at: u64,
     pub edit_history: Vec<MessageEdit>,
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+// A text message held back until `send_at` (milliseconds since epoch). It
+// lives outside the messages collection, so the recipient can't see it
+// until it has been delivered.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduledMessage {
+    pub message_id: String,
+    pub sender_id: String,
+    pub sender_username: String,
+    pub recipient_id: String,
+    pub content: String,
+    pub send_at: u64,
+    pub created_at: u64,
+    // Set while the delivery worker is sending it; a claim older than
+    // SCHEDULED_MESSAGE_CLAIM_TIMEOUT_MS is treated as abandoned.
+    #[serde(default)]
+    pub claimed_at: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -75,13 +116,44 @@ pub struct Conversation {
     pub created_at: u64,
     pub updated_at: u64,
     pub last_message_at: u64,
+    #[serde(default)]
+    pub last_read_at: HashMap<String, u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct SendMessageRequest {
+pub struct ConversationSummary {
+    pub conversation_id: String,
+    pub participant_ids: Vec<String>,
+    pub created_at: u64,
+    pub last_message_at: u64,
+    #[serde(default)]
+    pub last_read_at: HashMap<String, u64>,
+    pub unread_count: u64,
+    pub last_message: Option<LastMessage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LastMessage {
+    pub sender_id: String,
+    pub message_type: MessageType,
     pub content: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Block {
+    pub blocker_id: String,
+    pub blocked_id: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendQuoteRequest {
+    pub product_id: String,
+    pub quantity: u32,
+    pub custom_price: String,
+    pub note: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EditMessageRequest {
     pub content: String,
@@ -91,6 +163,7 @@ pub struct EditMessageRequest {
 pub struct GetMessagesQuery {
     pub limit: Option<u32>,
     pub before: Option<String>,
+    pub after: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -100,9 +173,69 @@ pub struct MessageResponse {
     pub message_type: MessageType,
     pub content: Option<String>,
     pub attachment: Option<AttachmentData>,
+    pub query_data: Option<QueryData>,
+    pub quote_data: Option<QuoteData>,
     pub created_at: u64,
     pub updated_at: u64,
     pub is_edited: bool,
+    pub is_deleted: bool,
+}
+
+impl From<Message> for MessageResponse {
+    fn from(msg: Message) -> Self {
+        if msg.deleted {
+            return Self {
+                message_id: msg.message_id,
+                sender_id: msg.sender_id,
+                message_type: msg.message_type,
+                content: None,
+                attachment: None,
+                query_data: None,
+                quote_data: None,
+                created_at: msg.created_at,
+                updated_at: msg.updated_at,
+                is_edited: false,
+                is_deleted: true,
+            };
+        }
+
+        Self {
+            message_id: msg.message_id,
+            sender_id: msg.sender_id,
+            message_type: msg.message_type,
+            content: msg.content,
+            attachment: msg.attachment,
+            query_data: msg.query_data,
+            quote_data: msg.quote_data,
+            created_at: msg.created_at,
+            updated_at: msg.updated_at,
+            is_edited: !msg.edit_history.is_empty(),
+            is_deleted: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageStreamEventKind {
+    Created,
+    Updated,
+}
+
+impl MessageStreamEventKind {
+    pub fn event_name(self) -> &'static str {
+        match self {
+            Self::Created => "message",
+            Self::Updated => "message_updated",
+        }
+    }
+}
+
+pub struct MessageStreamEvent {
+    pub kind: MessageStreamEventKind,
+    // `<updated_at>:<message_id>`; a reconnecting client sends it back as
+    // Last-Event-ID to resume right after this change.
+    pub id: String,
+    pub message: MessageResponse,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -111,4 +244,8 @@ pub struct ConversationResponse {
     pub other_participant_id: String,
     pub created_at: u64,
     pub last_message_at: u64,
+    pub unread_count: u64,
+    pub other_last_read_at: Option<u64>,
+    pub last_message_preview: Option<String>,
+    pub last_sender_id: Option<String>,
 }
@@ -0,0 +1,200 @@
+use axum::http::StatusCode;
+use mongodb::{
+    Collection,
+    bson::{doc, to_bson},
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use super::schemas::*;
+use crate::{
+    DB,
+    apex::{config::config, utils::VerboseHTTPError},
+    auth::schemas::UserOut,
+};
+
+fn jobs_collection() -> Option<Collection<Job>> {
+    Some(DB.get()?.collection(COLLECTIONS_JOBS))
+}
+
+#[inline]
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+pub async fn create_job(
+    owner_id: &str,
+    job_type: JobType,
+    total: u32,
+) -> Result<Job, VerboseHTTPError> {
+    let Some(jobs) = jobs_collection() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let now = now_secs() as u64;
+    let job = Job {
+        job_id: Uuid::new_v4().to_string(),
+        job_type,
+        owner_id: owner_id.to_string(),
+        status: JobStatus::Pending,
+        progress: JobProgress {
+            processed: 0,
+            total,
+        },
+        result: None,
+        error: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    jobs.insert_one(&job).await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to create job".to_string(),
+        )
+    })?;
+
+    Ok(job)
+}
+
+pub async fn has_recent_job(owner_id: &str, job_type: JobType, cooldown_secs: u64) -> bool {
+    let Some(jobs) = jobs_collection() else {
+        return false;
+    };
+
+    let stale_before = (now_secs() as u64).saturating_sub(JOB_STALE_TIMEOUT_SECS) as i64;
+    if let Err(error) = jobs
+        .update_many(
+            doc! {
+                "owner_id": owner_id,
+                "job_type": to_bson(&job_type).unwrap(),
+                "status": { "$in": ["pending", "running"] },
+                "updated_at": { "$lt": stale_before }
+            },
+            doc! {
+                "$set": {
+                    "status": "failed",
+                    "error": "Job timed out",
+                    "updated_at": now_secs()
+                }
+            },
+        )
+        .await
+    {
+        tracing::warn!("Failed to expire stale jobs for {}: {}", owner_id, error);
+    }
+
+    let since = (now_secs() as u64).saturating_sub(cooldown_secs) as i64;
+
+    jobs.find_one(doc! {
+        "owner_id": owner_id,
+        "job_type": to_bson(&job_type).unwrap(),
+        "$or": [
+            { "status": { "$in": ["pending", "running"] } },
+            { "created_at": { "$gt": since } }
+        ]
+    })
+    .await
+    .ok()
+    .flatten()
+    .is_some()
+}
+
+pub async fn mark_job_running(job_id: &str) {
+    let Some(jobs) = jobs_collection() else {
+        return;
+    };
+
+    let _ = jobs
+        .update_one(
+            doc! { "job_id": job_id },
+            doc! { "$set": { "status": "running", "updated_at": now_secs() } },
+        )
+        .await;
+}
+
+pub async fn advance_job(job_id: &str, processed: u32) {
+    let Some(jobs) = jobs_collection() else {
+        return;
+    };
+
+    let _ = jobs
+        .update_one(
+            doc! { "job_id": job_id },
+            doc! {
+                "$inc": { "progress.processed": processed as i64 },
+                "$set": { "updated_at": now_secs() }
+            },
+        )
+        .await;
+}
+
+pub async fn complete_job(job_id: &str, result: serde_json::Value) {
+    let Some(jobs) = jobs_collection() else {
+        return;
+    };
+
+    let _ = jobs
+        .update_one(
+            doc! { "job_id": job_id },
+            doc! {
+                "$set": {
+                    "status": "done",
+                    "result": to_bson(&result).unwrap_or_default(),
+                    "updated_at": now_secs()
+                }
+            },
+        )
+        .await;
+}
+
+pub async fn fail_job(job_id: &str, error: &str) {
+    let Some(jobs) = jobs_collection() else {
+        return;
+    };
+
+    let _ = jobs
+        .update_one(
+            doc! { "job_id": job_id },
+            doc! {
+                "$set": {
+                    "status": "failed",
+                    "error": error,
+                    "updated_at": now_secs()
+                }
+            },
+        )
+        .await;
+}
+
+pub async fn get_job(user: &UserOut, job_id: &str) -> Result<Job, VerboseHTTPError> {
+    let Some(jobs) = jobs_collection() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let mut filter = doc! { "job_id": job_id };
+    if !config().is_admin(&user.uid) {
+        filter.insert("owner_id", &user.uid);
+    }
+
+    jobs.find_one(filter)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "Job not found".to_string())
+        })
+}
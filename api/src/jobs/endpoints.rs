@@ -0,0 +1,18 @@
+use axum::{
+    Json,
+    extract::{Extension, Path},
+    response::IntoResponse,
+};
+
+use super::delegates::get_job;
+use crate::auth::schemas::UserOut;
+
+pub(crate) async fn get_job_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(job_id): Path<String>,
+) -> impl IntoResponse {
+    match get_job(&user, &job_id).await {
+        Ok(job) => Json(job).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+pub const COLLECTIONS_JOBS: &str = "jobs";
+// A pending or running job that has not reported progress for this long is
+// assumed to belong to a crashed worker.
+pub const JOB_STALE_TIMEOUT_SECS: u64 = 15 * 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobType {
+    Reembed,
+    StaleReembed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct JobProgress {
+    pub processed: u32,
+    pub total: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Job {
+    pub job_id: String,
+    pub job_type: JobType,
+    pub owner_id: String,
+    pub status: JobStatus,
+    pub progress: JobProgress,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
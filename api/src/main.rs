@@ -1,34 +1,49 @@
 use axum::{
     Router,
+    extract::DefaultBodyLimit,
     middleware::from_fn as middleware_from_fn,
     routing::{delete, get, post, put},
 };
+use dotenv::dotenv;
 use mongodb::{Client, Database, options::ClientOptions};
 use std::{env::var, net::SocketAddr, sync::OnceLock};
-use dotenv::dotenv;
 
 mod apex;
 mod auth;
 mod chat;
+mod jobs;
 mod notifications;
 mod orders;
 mod products;
 mod recommendations;
+mod reviews;
 mod search;
 
-use apex::endpoints::*;
+use apex::{endpoints::*, i18n::localize_errors, utils::VerboseHTTPError};
 use auth::endpoints::*;
 use chat::endpoints::*;
+use jobs::endpoints::*;
 use orders::endpoints::*;
 use products::endpoints::*;
-use recommendations::endpoints::{get_recommendations, get_knowledge_graph};
+use recommendations::endpoints::{
+    delete_category_relationship_endpoint, get_category_relationship_endpoint, get_knowledge_graph,
+    get_recommendations, list_category_relationships_endpoint, list_featured_products_endpoint,
+    not_interested_endpoint, remove_featured_product_endpoint,
+    seed_category_relationships_endpoint, update_category_relationship_endpoint,
+    upsert_category_relationship_endpoint, upsert_featured_product_endpoint,
+};
+use reviews::endpoints::*;
 use search::endpoints::*;
 
 pub(crate) static DB: OnceLock<Database> = OnceLock::new();
 
+const AUDIO_BODY_LIMIT: usize = search::schemas::MAX_AUDIO_SIZE + 64 * 1024;
+
 #[tokio::main]
 async fn main() {
     dotenv().ok();
+    tracing_subscriber::fmt::init();
+    apex::config::init_config();
 
     let mongodb_uri = var("MONGODB_URI").unwrap();
     let client_options = ClientOptions::parse(mongodb_uri).await.unwrap();
@@ -36,6 +51,38 @@ async fn main() {
 
     DB.set(client.database("goodspoint_main")).unwrap();
 
+    recommendations::delegates::ensure_category_relationship_indexes().await;
+    if let Err(VerboseHTTPError::Standard(_, message) | VerboseHTTPError::Coded(_, _, message)) =
+        recommendations::delegates::reload_category_relationships().await
+    {
+        tracing::warn!(
+            "Failed to load category relationships, using defaults: {}",
+            message
+        );
+    }
+
+    chat::migrate_message_timestamps_to_millis().await;
+    chat::ensure_message_indexes().await;
+    auth::backfill_username_lower().await;
+    auth::backfill_whatsapp_hashes().await;
+    products::delegates::migrate_price_in_paise().await;
+    products::delegates::normalize_stored_embeddings().await;
+    products::delegates::migrate_product_status().await;
+    products::delegates::ensure_region_indexes().await;
+    products::delegates::ensure_product_view_indexes().await;
+    products::delegates::backfill_product_slugs().await;
+    products::delegates::repair_gallery_orders().await;
+    reviews::delegates::ensure_review_indexes().await;
+    reviews::delegates::backfill_product_ratings().await;
+    recommendations::delegates::ensure_category_signal_indexes().await;
+    recommendations::delegates::ensure_recently_viewed_indexes().await;
+    recommendations::delegates::ensure_suppression_indexes().await;
+
+    tokio::spawn(products::delegates::run_pending_unpins_worker());
+    tokio::spawn(chat::run_orphaned_conversations_worker());
+    tokio::spawn(chat::run_scheduled_messages_worker());
+    tokio::spawn(recommendations::delegates::run_time_decay_worker());
+
     let domain = var("DOMAIN").unwrap_or_else(|_| "0.0.0.0".to_string());
     let port = var("PORT")
         .unwrap_or_else(|_| "3000".to_string())
@@ -53,15 +100,39 @@ async fn main() {
     let protected_routes = Router::new()
         .route("/auth/user", get(get_user))
         .route("/auth/logout", post(logout_user))
+        .route("/auth/logout-all", post(logout_all_endpoint))
+        .route("/auth/sessions", get(list_sessions_endpoint))
+        .route(
+            "/auth/sessions/{session_id}",
+            delete(revoke_session_endpoint),
+        )
         .route("/auth/change-password", post(change_password_endpoint))
+        .route("/auth/delete-account", post(delete_account_endpoint))
+        .route("/auth/totp/enable", post(enable_totp_endpoint))
+        .route("/auth/totp/confirm", post(confirm_totp_endpoint))
         .route("/auth/send-whatsapp-otp", post(send_whatsapp_otp_endpoint))
         .route(
             "/auth/verify-whatsapp-otp",
             post(verify_whatsapp_otp_endpoint),
         )
         .route("/auth/whatsapp-status", get(get_whatsapp_status))
+        .route("/auth/whatsapp", delete(remove_whatsapp_endpoint))
+        .route("/auth/profile", put(update_profile_endpoint))
+        .route(
+            "/auth/addresses",
+            get(list_addresses_endpoint).post(create_address_endpoint),
+        )
+        .route(
+            "/auth/addresses/{address_id}",
+            put(update_address_endpoint).delete(delete_address_endpoint),
+        )
         .route("/seller/products/create", post(create_product_endpoint))
         .route("/seller/products/list", get(list_my_products_endpoint))
+        .route(
+            "/seller/products/categories",
+            get(get_category_counts_endpoint),
+        )
+        .route("/seller/products/reembed", post(reembed_catalog_endpoint))
         .route(
             "/seller/products/{product_id}",
             get(get_user_product_endpoint),
@@ -74,6 +145,14 @@ async fn main() {
             "/seller/products/{product_id}",
             delete(delete_product_endpoint),
         )
+        .route(
+            "/seller/products/{product_id}/publish",
+            post(publish_product_endpoint),
+        )
+        .route(
+            "/seller/products/{product_id}/suggest-category",
+            post(suggest_category_endpoint),
+        )
         .route(
             "/seller/products/{product_id}/gallery",
             get(get_gallery_endpoint),
@@ -90,6 +169,10 @@ async fn main() {
             "/seller/products/{product_id}/gallery/reorder",
             post(reorder_gallery_endpoint),
         )
+        .route(
+            "/seller/products/{product_id}/gallery/{item_id}",
+            delete(delete_gallery_item_endpoint),
+        )
         .route(
             "/seller/products/{product_id}/questions",
             get(get_questions_endpoint),
@@ -103,11 +186,34 @@ async fn main() {
             post(generate_questions_endpoint),
         )
         .route("/chat/conversations", get(get_conversations_endpoint))
+        .route("/chat/blocks", get(list_blocks_endpoint))
+        .route("/chat/scheduled", get(list_scheduled_messages_endpoint))
+        .route(
+            "/chat/scheduled/{message_id}",
+            delete(cancel_scheduled_message_endpoint),
+        )
+        .route(
+            "/chat/blocks/{other_user_id}",
+            post(block_user_endpoint).delete(unblock_user_endpoint),
+        )
         .route(
             "/chat/{other_user_id}/messages",
             post(send_message_endpoint),
         )
         .route("/chat/{other_user_id}/messages", get(get_messages_endpoint))
+        .route("/chat/{other_user_id}/quote", post(send_quote_endpoint))
+        .route(
+            "/chat/{other_user_id}/read",
+            post(mark_conversation_read_endpoint),
+        )
+        .route(
+            "/chat/{other_user_id}/stream",
+            get(stream_messages_endpoint),
+        )
+        .route(
+            "/chat/messages/{message_id}",
+            delete(delete_message_endpoint),
+        )
         .route(
             "/chat/messages/{message_id}/edit",
             put(edit_message_endpoint),
@@ -121,11 +227,96 @@ async fn main() {
             post(create_order_from_quote_endpoint),
         )
         .route("/products/buy-now", post(buy_now_endpoint))
+        .route("/products/{product_id}/offers", post(create_offer_endpoint))
+        .route(
+            "/products/{product_id}/can-review",
+            get(can_review_endpoint),
+        )
+        .route(
+            "/products/{product_id}/reviews/create",
+            post(create_review_endpoint),
+        )
+        .route(
+            "/products/{product_id}/reviews/{review_id}/respond",
+            post(respond_to_review_endpoint),
+        )
+        .route("/sellers/offers", get(list_seller_offers_endpoint))
+        .route(
+            "/sellers/offers/{offer_id}/accept",
+            post(accept_offer_endpoint),
+        )
+        .route(
+            "/sellers/offers/{offer_id}/reject",
+            post(reject_offer_endpoint),
+        )
         .route("/orders/list", get(list_orders_endpoint))
         .route("/orders/confirm", post(confirm_order_endpoint))
+        .route("/orders/stats", get(order_stats_endpoint))
+        .route("/orders/{order_id}", get(get_order_endpoint))
+        .route("/orders/{order_id}/cancel", post(cancel_order_endpoint))
         .route("/sellers/orders/list", get(list_seller_orders_endpoint))
+        .route("/jobs/{job_id}", get(get_job_endpoint))
+        .route(
+            "/sellers/orders/{order_id}/note",
+            put(set_seller_note_endpoint),
+        )
+        .route(
+            "/sellers/orders/{order_id}/ship",
+            post(mark_shipped_endpoint),
+        )
+        .route(
+            "/sellers/orders/{order_id}/deliver",
+            post(mark_delivered_endpoint),
+        )
         .route("/homepage/recommendations", get(get_recommendations))
         .route("/homepage/knowledge-graph", get(get_knowledge_graph))
+        .route("/homepage/not-interested", post(not_interested_endpoint))
+        .route(
+            "/search/transcribe",
+            post(transcribe_audio_endpoint).layer(DefaultBodyLimit::max(AUDIO_BODY_LIMIT)),
+        )
+        .route(
+            "/search/translate",
+            post(translate_audio_endpoint).layer(DefaultBodyLimit::max(AUDIO_BODY_LIMIT)),
+        )
+        .route(
+            "/search/history",
+            get(get_search_history_endpoint).delete(clear_search_history_endpoint),
+        )
+        .layer(middleware_from_fn(cookie_auth));
+
+    let admin_routes = Router::new()
+        .route(
+            "/admin/recommendations/relationships",
+            get(list_category_relationships_endpoint).put(upsert_category_relationship_endpoint),
+        )
+        .route(
+            "/admin/recommendations/relationships/seed",
+            post(seed_category_relationships_endpoint),
+        )
+        .route(
+            "/admin/recommendations/relationships/{relationship_id}",
+            get(get_category_relationship_endpoint)
+                .put(update_category_relationship_endpoint)
+                .delete(delete_category_relationship_endpoint),
+        )
+        .route(
+            "/admin/recommendations/featured",
+            get(list_featured_products_endpoint).put(upsert_featured_product_endpoint),
+        )
+        .route(
+            "/admin/recommendations/featured/{product_id}",
+            delete(remove_featured_product_endpoint),
+        )
+        .route(
+            "/admin/products/reembed-stale",
+            post(reembed_stale_products_endpoint),
+        )
+        .route(
+            "/admin/users/{uid}/trusted-seller",
+            put(set_trusted_seller_endpoint),
+        )
+        .layer(middleware_from_fn(admin_auth))
         .layer(middleware_from_fn(cookie_auth));
 
     let unprotected_routes = Router::new()
@@ -133,13 +324,39 @@ async fn main() {
         .route("/auth/login", post(login_user))
         .route("/auth/send-email-otp", post(send_email_otp_endpoint))
         .route("/auth/verify-email-otp", post(verify_email_otp_endpoint))
+        .route(
+            "/auth/send-password-reset",
+            post(send_password_reset_endpoint),
+        )
+        .route("/auth/reset-password", post(reset_password_endpoint))
+        .route("/auth/verify-totp", post(verify_totp_endpoint))
+        .route("/profiles/{username}", get(get_public_profile_endpoint))
         .route("/products/{product_id}", get(get_product_endpoint))
-        .route("/products/search", post(optimized_search_products_endpoint));
+        .route("/products/slug/{slug}", get(get_product_by_slug_endpoint))
+        .route("/products/{product_id}/reviews", get(list_reviews_endpoint))
+        .route(
+            "/products/{product_id}/view",
+            post(record_product_view_endpoint),
+        )
+        .route(
+            "/products/category/{category}",
+            get(browse_category_endpoint),
+        )
+        .route("/products/search", post(optimized_search_products_endpoint))
+        .route("/products/search/feedback", post(search_feedback_endpoint));
 
     let app = Router::new()
         .merge(protected_routes)
+        .merge(admin_routes)
         .merge(unprotected_routes)
-        .route("/", get(root_endpoint));
+        .route("/", get(root_endpoint))
+        .route("/health", get(health_endpoint))
+        .layer(middleware_from_fn(localize_errors));
 
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
@@ -3,12 +3,26 @@ use reqwest::Client;
 use std::env::var;
 
 use super::schemas::*;
-use crate::apex::utils::VerboseHTTPError;
+use crate::apex::utils::{VerboseHTTPError, env_flag};
+
+#[inline]
+fn is_dry_run() -> bool {
+    env_flag("NOTIFICATIONS_DRY_RUN")
+}
 
 pub async fn send_whatsapp_internal(
     phone_number: &str,
     message: &str,
 ) -> Result<(), VerboseHTTPError> {
+    if is_dry_run() {
+        tracing::info!(
+            to = phone_number,
+            body = message,
+            "[dry run] WhatsApp message"
+        );
+        return Ok(());
+    }
+
     let account_sid = var("TWILIO_ACCOUNT_SID").map_err(|_| {
         VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -73,6 +87,16 @@ pub async fn send_email_internal(
     subject: &str,
     html_content: &str,
 ) -> Result<(), VerboseHTTPError> {
+    if is_dry_run() {
+        tracing::info!(
+            to = to_email,
+            subject = subject,
+            body = html_content,
+            "[dry run] email"
+        );
+        return Ok(());
+    }
+
     let api_key = var("SENDGRID_API_KEY").map_err(|_| {
         VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -123,3 +147,64 @@ pub async fn send_email_internal(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn dry_run_logs_the_otp_instead_of_sending() {
+        // SAFETY: only the notification senders read this variable.
+        unsafe { std::env::set_var("NOTIFICATIONS_DRY_RUN", "true") };
+
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        // Without Twilio/SendGrid credentials these would fail before any
+        // request if dry-run mode were ignored.
+        assert!(
+            send_whatsapp_internal("+919876543210", "Your code is 482913")
+                .await
+                .is_ok()
+        );
+        assert!(
+            send_email_internal(
+                "alice@example.com",
+                Some("Alice"),
+                "Verify your email",
+                "<p>Your code is 739104</p>",
+            )
+            .await
+            .is_ok()
+        );
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("[dry run] WhatsApp message"));
+        assert!(logs.contains("482913"));
+        assert!(logs.contains("[dry run] email"));
+        assert!(logs.contains("739104"));
+    }
+}
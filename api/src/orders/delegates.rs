@@ -1,11 +1,28 @@
 use axum::http::StatusCode;
+use chrono::{DateTime, Datelike, TimeZone, Utc};
 use futures::TryStreamExt;
-use mongodb::{Collection, bson::doc};
-use std::time::{SystemTime, UNIX_EPOCH};
+use mongodb::{
+    ClientSession, Collection, Database,
+    bson::{Bson, Document, doc, from_bson, to_bson},
+    options::ReturnDocument,
+};
+use std::{
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use uuid::Uuid;
 
 use super::schemas::*;
-use crate::{DB, apex::utils::VerboseHTTPError, auth::schemas::UserOut};
+use crate::{
+    DB,
+    apex::utils::VerboseHTTPError,
+    auth::schemas::{ShippingAddress, UserOut},
+    products::{
+        delegates::notify_user,
+        schemas::{COLLECTIONS_OFFERS, OfferStatus, ProductCategory},
+    },
+    recommendations::{auto_log_signal, schemas::SignalType},
+};
 
 pub async fn list_orders(
     user: &UserOut,
@@ -40,20 +57,199 @@ pub async fn list_orders(
         )
     })?;
 
-    Ok(orders
-        .into_iter()
-        .map(|order| OrderResponse {
-            order_id: order.order_id,
-            product_id: order.product_id,
-            seller_id: order.seller_id,
-            buyer_id: order.buyer_id,
-            quantity: order.quantity,
-            price: order.price,
-            status: order.status,
-            created_at: order.created_at,
-            updated_at: order.updated_at,
+    Ok(orders.into_iter().map(OrderResponse::from).collect())
+}
+
+// Unpaid orders never charged the buyer and refunded ones gave the money back,
+// so neither counts towards spend alongside cancelled orders.
+const UNSPENT_ORDER_STATUSES: [&str; 3] = ["cancelled", "unpaid", "refunded"];
+
+// Unix timestamp of 00:00 UTC on the first day of the month `months - 1`
+// months ago, so a window of 1 covers just the current month.
+fn stats_window_start(now: DateTime<Utc>, months: u32) -> i64 {
+    let month_index = now.year() * 12 + now.month0() as i32 - (months as i32 - 1);
+
+    Utc.with_ymd_and_hms(
+        month_index.div_euclid(12),
+        month_index.rem_euclid(12) as u32 + 1,
+        1,
+        0,
+        0,
+        0,
+    )
+    .single()
+    .map(|start| start.timestamp())
+    .unwrap_or(0)
+}
+
+#[inline]
+fn bson_count(document: &Document, key: &str) -> u64 {
+    match document.get(key) {
+        Some(Bson::Int32(value)) => (*value).max(0) as u64,
+        Some(Bson::Int64(value)) => (*value).max(0) as u64,
+        Some(Bson::Double(value)) => value.max(0.0).round() as u64,
+        _ => 0,
+    }
+}
+
+fn facet_documents(stats: &Document, facet: &str) -> Vec<Document> {
+    stats
+        .get_array(facet)
+        .map(|documents| {
+            documents
+                .iter()
+                .filter_map(|document| document.as_document().cloned())
+                .collect()
         })
-        .collect())
+        .unwrap_or_default()
+}
+
+pub async fn order_stats(
+    user: &UserOut,
+    months: u32,
+) -> Result<OrderStatsResponse, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let collection: Collection<Order> = database.collection(COLLECTIONS_ORDERS);
+    let window_start = stats_window_start(Utc::now(), months);
+
+    let pipeline = vec![
+        doc! {
+            "$match": {
+                "buyer_id": &user.uid,
+                "status": { "$nin": UNSPENT_ORDER_STATUSES.to_vec() }
+            }
+        },
+        // price_in_paise holds the order total; legacy orders only carry the
+        // rupee price.
+        doc! {
+            "$addFields": {
+                "spend": {
+                    "$ifNull": [
+                        "$price_in_paise",
+                        { "$toLong": { "$round": [{ "$multiply": ["$price", 100] }, 0] } }
+                    ]
+                }
+            }
+        },
+        doc! {
+            "$facet": {
+                "totals": [
+                    {
+                        "$group": {
+                            "_id": null,
+                            "spend": { "$sum": "$spend" },
+                            "orders": { "$sum": 1 }
+                        }
+                    }
+                ],
+                "by_category": [
+                    {
+                        "$lookup": {
+                            "from": "products",
+                            "localField": "product_id",
+                            "foreignField": "product_id",
+                            "as": "product"
+                        }
+                    },
+                    {
+                        "$group": {
+                            "_id": { "$ifNull": [{ "$first": "$product.category" }, "Unknown"] },
+                            "spend": { "$sum": "$spend" },
+                            "orders": { "$sum": 1 }
+                        }
+                    },
+                    { "$sort": { "spend": -1, "_id": 1 } }
+                ],
+                "monthly": [
+                    { "$match": { "created_at": { "$gte": window_start } } },
+                    {
+                        "$group": {
+                            "_id": {
+                                "$dateToString": {
+                                    "format": "%Y-%m",
+                                    "date": { "$toDate": { "$multiply": ["$created_at", 1000] } }
+                                }
+                            },
+                            "spend": { "$sum": "$spend" },
+                            "orders": { "$sum": 1 }
+                        }
+                    },
+                    { "$sort": { "_id": 1 } }
+                ]
+            }
+        },
+    ];
+
+    let stats: Document = collection
+        .aggregate(pipeline)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_next()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .unwrap_or_default();
+
+    let totals = facet_documents(&stats, "totals")
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+
+    Ok(OrderStatsResponse {
+        total_spend_in_paise: bson_count(&totals, "spend"),
+        order_count: bson_count(&totals, "orders"),
+        by_category: facet_documents(&stats, "by_category")
+            .iter()
+            .map(|group| CategorySpend {
+                category: group.get_str("_id").unwrap_or("Unknown").to_string(),
+                spend_in_paise: bson_count(group, "spend"),
+                order_count: bson_count(group, "orders"),
+            })
+            .collect(),
+        window_months: months,
+        monthly: facet_documents(&stats, "monthly")
+            .iter()
+            .filter_map(|group| {
+                Some(MonthlySpend {
+                    month: group.get_str("_id").ok()?.to_string(),
+                    spend_in_paise: bson_count(group, "spend"),
+                    order_count: bson_count(group, "orders"),
+                })
+            })
+            .collect(),
+    })
+}
+
+#[inline]
+pub fn contact_revealed(status: OrderStatus) -> bool {
+    matches!(
+        status,
+        OrderStatus::DeliveryPending | OrderStatus::Shipped | OrderStatus::Delivered
+    )
+}
+
+// The buyer's shipping address, phone included, is withheld from the seller
+// until the order reaches delivery_pending.
+fn seller_view(mut order: OrderResponse) -> OrderResponse {
+    if !contact_revealed(order.status) {
+        order.shipping_address = None;
+    }
+    order
 }
 
 pub async fn list_seller_orders(
@@ -91,24 +287,31 @@ pub async fn list_seller_orders(
 
     Ok(orders
         .into_iter()
-        .map(|order| OrderResponse {
-            order_id: order.order_id,
-            product_id: order.product_id,
-            seller_id: order.seller_id,
-            buyer_id: order.buyer_id,
-            quantity: order.quantity,
-            price: order.price,
-            status: order.status,
-            created_at: order.created_at,
-            updated_at: order.updated_at,
-        })
+        .map(OrderResponse::from)
+        .map(seller_view)
         .collect())
 }
 
-pub async fn confirm_order(
+pub fn can_transition(from: OrderStatus, to: OrderStatus, role: OrderRole) -> bool {
+    use OrderStatus::*;
+
+    match (from, to) {
+        (Unpaid, DeliveryPending) | (Unpaid, Cancelled) => role == OrderRole::Buyer,
+        (DeliveryPending, Shipped) | (Shipped, Delivered) => role == OrderRole::Seller,
+        (DeliveryPending, Refunded) | (Shipped, Refunded) | (Delivered, Refunded) => {
+            role == OrderRole::Seller
+        }
+        _ => false,
+    }
+}
+
+async fn transition_order(
     user: &UserOut,
-    order_id: String,
-) -> Result<OrderResponse, VerboseHTTPError> {
+    order_id: &str,
+    to: OrderStatus,
+    role: OrderRole,
+    mut session: Option<&mut ClientSession>,
+) -> Result<Order, VerboseHTTPError> {
     let Some(database) = DB.get() else {
         return Err(VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -117,49 +320,331 @@ pub async fn confirm_order(
     };
 
     let collection: Collection<Order> = database.collection(COLLECTIONS_ORDERS);
+    let actor_field = match role {
+        OrderRole::Buyer => "buyer_id",
+        OrderRole::Seller => "seller_id",
+    };
+
+    let order = collection.find_one(doc! { "order_id": order_id, actor_field: &user.uid });
+    let order = match session.as_deref_mut() {
+        Some(session) => order.session(session).await,
+        None => order.await,
+    }
+    .map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
+    })?
+    .ok_or_else(|| {
+        VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "Order not found".to_string())
+    })?;
+
+    if !can_transition(order.status, to, role) {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::CONFLICT,
+            format!("Cannot move order from {:?} to {:?}", order.status, to),
+        ));
+    }
+
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
+    let change = OrderStatusChange {
+        from: order.status,
+        to,
+        changed_by: user.uid.clone(),
+        changed_at: now,
+    };
+    let serialize_error = |_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update order".to_string(),
+        )
+    };
 
-    let update_result = collection
+    // Matching on the status we validated makes concurrent transitions lose
+    // cleanly instead of overwriting each other.
+    let updated = collection
         .find_one_and_update(
             doc! {
-                "order_id": &order_id,
-                "buyer_id": &user.uid,
-                "status": "unpaid"
+                "order_id": order_id,
+                actor_field: &user.uid,
+                "status": to_bson(&order.status).map_err(serialize_error)?
             },
             doc! {
                 "$set": {
-                    "status": "delivery_pending",
+                    "status": to_bson(&to).map_err(serialize_error)?,
                     "updated_at": now as i64
-                }
+                },
+                "$push": { "status_history": to_bson(&change).map_err(serialize_error)? }
             },
         )
+        .return_document(ReturnDocument::After);
+    let updated = match session {
+        Some(session) => updated.session(session).await,
+        None => updated.await,
+    }
+    .map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
+    })?;
+
+    updated.ok_or_else(|| {
+        VerboseHTTPError::Standard(
+            StatusCode::CONFLICT,
+            "Order status changed concurrently. Please retry".to_string(),
+        )
+    })
+}
+
+pub async fn confirm_order(
+    user: &UserOut,
+    order_id: String,
+) -> Result<OrderResponse, VerboseHTTPError> {
+    transition_order(
+        user,
+        &order_id,
+        OrderStatus::DeliveryPending,
+        OrderRole::Buyer,
+        None,
+    )
+    .await
+    .map(OrderResponse::from)
+}
+
+pub async fn cancel_order(
+    user: &UserOut,
+    order_id: &str,
+) -> Result<OrderResponse, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let cancel_error = || {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to cancel order".to_string(),
+        )
+    };
+
+    if !transactions_supported(database).await {
+        let order = transition_order(
+            user,
+            order_id,
+            OrderStatus::Cancelled,
+            OrderRole::Buyer,
+            None,
+        )
+        .await?;
+        if let Err(error) = release_order(database, &order, None).await {
+            tracing::warn!(
+                order_id = order.order_id.as_str(),
+                error = %error,
+                "Failed to release cancelled order"
+            );
+        }
+        return Ok(order.into());
+    }
+
+    let mut session = database
+        .client()
+        .start_session()
         .await
-        .map_err(|_| {
-            VerboseHTTPError::Standard(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Database error".to_string(),
-            )
-        })?;
+        .map_err(|_| cancel_error())?;
+    session
+        .start_transaction()
+        .await
+        .map_err(|_| cancel_error())?;
 
-    match update_result {
-        Some(order) => Ok(OrderResponse {
-            order_id: order.order_id,
-            product_id: order.product_id,
-            seller_id: order.seller_id,
-            buyer_id: order.buyer_id,
-            quantity: order.quantity,
-            price: order.price,
-            status: OrderStatus::DeliveryPending,
-            created_at: order.created_at,
-            updated_at: now,
-        }),
-        None => Err(VerboseHTTPError::Standard(
-            StatusCode::NOT_FOUND,
-            "Order not found or not eligible for confirmation".to_string(),
-        )),
+    let result = async {
+        let order = transition_order(
+            user,
+            order_id,
+            OrderStatus::Cancelled,
+            OrderRole::Buyer,
+            Some(&mut session),
+        )
+        .await?;
+        release_order(database, &order, Some(&mut session))
+            .await
+            .map_err(|_| cancel_error())?;
+        Ok(order)
+    }
+    .await;
+
+    let order = match result {
+        Ok(order) => order,
+        Err(error) => {
+            let _ = session.abort_transaction().await;
+            return Err(error);
+        }
+    };
+
+    session
+        .commit_transaction()
+        .await
+        .map_err(|_| cancel_error())?;
+
+    Ok(order.into())
+}
+
+// Hands back the stock reserved when the order was placed (unless the
+// product tracks unlimited quantity) and reopens the offer or quote the order
+// came from so it can be accepted again.
+async fn release_order(
+    database: &Database,
+    order: &Order,
+    mut session: Option<&mut ClientSession>,
+) -> mongodb::error::Result<()> {
+    let products: Collection<Document> = database.collection("products");
+    let restock = products.update_one(
+        doc! {
+            "product_id": &order.product_id,
+            "available_quantity": { "$ne": null }
+        },
+        doc! { "$inc": { "available_quantity": order.quantity as i64 } },
+    );
+    match session.as_deref_mut() {
+        Some(session) => restock.session(session).await?,
+        None => restock.await?,
+    };
+
+    let offers: Collection<Document> = database.collection(COLLECTIONS_OFFERS);
+    let reopen_offer = offers.update_one(
+        doc! { "order_id": &order.order_id },
+        doc! {
+            "$set": {
+                "status": to_bson(&OfferStatus::Pending)?,
+                "order_id": null
+            }
+        },
+    );
+    match session.as_deref_mut() {
+        Some(session) => reopen_offer.session(session).await?,
+        None => reopen_offer.await?,
+    };
+
+    let messages: Collection<Document> = database.collection("messages");
+    let reopen_quote = messages.update_one(
+        doc! { "quote_data.order_id": &order.order_id },
+        doc! {
+            "$set": {
+                "quote_data.is_confirmed": false,
+                "quote_data.order_id": null
+            }
+        },
+    );
+    match session {
+        Some(session) => reopen_quote.session(session).await?,
+        None => reopen_quote.await?,
+    };
+
+    Ok(())
+}
+
+pub async fn mark_shipped(
+    user: &UserOut,
+    order_id: &str,
+) -> Result<OrderResponse, VerboseHTTPError> {
+    transition_order(
+        user,
+        order_id,
+        OrderStatus::Shipped,
+        OrderRole::Seller,
+        None,
+    )
+    .await
+    .map(OrderResponse::from)
+    .map(seller_view)
+}
+
+pub async fn mark_delivered(
+    user: &UserOut,
+    order_id: &str,
+) -> Result<OrderResponse, VerboseHTTPError> {
+    transition_order(
+        user,
+        order_id,
+        OrderStatus::Delivered,
+        OrderRole::Seller,
+        None,
+    )
+    .await
+    .map(OrderResponse::from)
+    .map(seller_view)
+}
+
+static TRANSACTIONS_SUPPORTED: OnceLock<bool> = OnceLock::new();
+
+async fn transactions_supported(database: &Database) -> bool {
+    if let Some(supported) = TRANSACTIONS_SUPPORTED.get() {
+        return *supported;
+    }
+
+    let supported = match database.run_command(doc! { "hello": 1 }).await {
+        Ok(reply) => {
+            reply.contains_key("setName") || reply.get_str("msg").is_ok_and(|msg| msg == "isdbgrid")
+        }
+        Err(_) => false,
+    };
+
+    *TRANSACTIONS_SUPPORTED.get_or_init(|| supported)
+}
+
+async fn reserve_stock(
+    products: &Collection<Document>,
+    product_id: &str,
+    quantity: u32,
+    mut session: Option<&mut ClientSession>,
+) -> Result<bool, VerboseHTTPError> {
+    let database_error = |_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to reserve stock".to_string(),
+        )
+    };
+
+    let decrement = products.update_one(
+        doc! {
+            "product_id": product_id,
+            "available_quantity": { "$gte": quantity as i64 }
+        },
+        doc! { "$inc": { "available_quantity": -(quantity as i64) } },
+    );
+    let result = match session.as_deref_mut() {
+        Some(session) => decrement.session(session).await,
+        None => decrement.await,
+    }
+    .map_err(database_error)?;
+
+    if result.modified_count > 0 {
+        return Ok(true);
+    }
+
+    let unlimited = products.find_one(doc! {
+        "product_id": product_id,
+        "available_quantity": null
+    });
+    let unlimited = match session {
+        Some(session) => unlimited.session(session).await,
+        None => unlimited.await,
+    }
+    .map_err(database_error)?;
+
+    if unlimited.is_some() {
+        Ok(false)
+    } else {
+        Err(VerboseHTTPError::Standard(
+            StatusCode::CONFLICT,
+            "Insufficient stock for the requested quantity".to_string(),
+        ))
     }
 }
 
@@ -168,7 +653,9 @@ pub async fn create_order_internal(
     seller_id: String,
     buyer_id: String,
     quantity: u32,
-    price: f64,
+    price_in_paise: u64,
+    region: Option<String>,
+    shipping_address: Option<ShippingAddress>,
 ) -> Result<OrderResponse, VerboseHTTPError> {
     let Some(database) = DB.get() else {
         return Err(VerboseHTTPError::Standard(
@@ -183,6 +670,7 @@ pub async fn create_order_internal(
         .unwrap()
         .as_secs();
     let order_id = Uuid::new_v4().to_string();
+    let price = price_in_paise as f64 / 100.0;
 
     let order = Order {
         order_id: order_id.clone(),
@@ -191,17 +679,73 @@ pub async fn create_order_internal(
         buyer_id: buyer_id.clone(),
         quantity,
         price,
+        price_in_paise: Some(price_in_paise),
         status: OrderStatus::Unpaid,
+        seller_note: None,
+        region: region.clone(),
+        status_history: Vec::new(),
+        shipping_address: shipping_address.clone(),
         created_at: now,
         updated_at: now,
     };
 
-    collection.insert_one(&order).await.map_err(|_| {
+    let products: Collection<Document> = database.collection("products");
+    let order_error = || {
         VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to create order".to_string(),
         )
-    })?;
+    };
+
+    if transactions_supported(database).await {
+        let mut session = database
+            .client()
+            .start_session()
+            .await
+            .map_err(|_| order_error())?;
+        session
+            .start_transaction()
+            .await
+            .map_err(|_| order_error())?;
+
+        let result = async {
+            reserve_stock(&products, &product_id, quantity, Some(&mut session)).await?;
+            collection
+                .insert_one(&order)
+                .session(&mut session)
+                .await
+                .map_err(|_| order_error())?;
+            Ok(())
+        }
+        .await;
+
+        if let Err(error) = result {
+            let _ = session.abort_transaction().await;
+            return Err(error);
+        }
+
+        session
+            .commit_transaction()
+            .await
+            .map_err(|_| order_error())?;
+    } else {
+        let decremented = reserve_stock(&products, &product_id, quantity, None).await?;
+
+        if collection.insert_one(&order).await.is_err() {
+            if decremented {
+                let _ = products
+                    .update_one(
+                        doc! { "product_id": &product_id },
+                        doc! { "$inc": { "available_quantity": quantity as i64 } },
+                    )
+                    .await;
+            }
+            return Err(order_error());
+        }
+    }
+
+    notify_order_created(&order).await;
+    log_purchase_signal(&order).await;
 
     Ok(OrderResponse {
         order_id,
@@ -210,8 +754,340 @@ pub async fn create_order_internal(
         buyer_id,
         quantity,
         price,
+        price_in_paise: Some(price_in_paise),
         status: OrderStatus::Unpaid,
+        seller_note: None,
+        region,
+        status_history: Vec::new(),
+        shipping_address,
         created_at: now,
         updated_at: now,
     })
 }
+
+// Like other signals this is best effort; a product whose category can't be
+// read is simply not logged.
+async fn log_purchase_signal(order: &Order) {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let products: Collection<Document> = database.collection("products");
+    let category = products
+        .find_one(doc! { "product_id": &order.product_id })
+        .projection(doc! { "category": 1 })
+        .await
+        .ok()
+        .flatten()
+        .and_then(|product| product.get("category").cloned())
+        .and_then(|category| from_bson::<ProductCategory>(category).ok());
+
+    if let Some(category) = category {
+        auto_log_signal(
+            &order.buyer_id,
+            SignalType::Purchase,
+            category,
+            Some(order.product_id.clone()),
+            None,
+        )
+        .await;
+    }
+}
+
+// Delivery failures are swallowed inside notify_user, so a notification
+// problem never fails an order that has already been written.
+async fn notify_order_created(order: &Order) {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let products: Collection<Document> = database.collection("products");
+    let title = products
+        .find_one(doc! { "product_id": &order.product_id })
+        .projection(doc! { "title": 1 })
+        .await
+        .ok()
+        .flatten()
+        .and_then(|product| product.get_str("title").ok().map(str::to_string))
+        .unwrap_or_else(|| "your product".to_string());
+    let order_url = format!("https://goodspoint.tech/orders/{}", order.order_id);
+
+    let seller_message = format!(
+        "New order for {} (qty {}, total {:.2}) - View it on {}",
+        title, order.quantity, order.price, order_url
+    );
+    notify_user(&order.seller_id, "New Order - GoodsPoint", &seller_message).await;
+
+    let buyer_message = format!(
+        "Your order for {} (qty {}, total {:.2}) has been placed - View it on {}",
+        title, order.quantity, order.price, order_url
+    );
+    notify_user(&order.buyer_id, "Order Placed - GoodsPoint", &buyer_message).await;
+}
+
+pub async fn get_order(
+    user: &UserOut,
+    order_id: &str,
+) -> Result<OrderDetailResponse, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let collection: Collection<Order> = database.collection(COLLECTIONS_ORDERS);
+
+    let order = collection
+        .find_one(doc! {
+            "order_id": order_id,
+            "$or": [
+                { "buyer_id": &user.uid },
+                { "seller_id": &user.uid }
+            ]
+        })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    let Some(order) = order else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "Order not found".to_string(),
+        ));
+    };
+
+    // The product may since have been deleted; the order is still returned.
+    let products: Collection<OrderProductInfo> = database.collection("products");
+    let product = products
+        .find_one(doc! { "product_id": &order.product_id })
+        .projection(doc! { "_id": 0, "title": 1, "thumbnail_url": 1, "slug": 1 })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    let role = if order.buyer_id == user.uid {
+        OrderRole::Buyer
+    } else {
+        OrderRole::Seller
+    };
+    let counterparty_contact = counterparty_contact(&order, role).await?;
+
+    let order = match role {
+        OrderRole::Buyer => order.into(),
+        OrderRole::Seller => seller_view(order.into()),
+    };
+
+    Ok(OrderDetailResponse {
+        order,
+        product,
+        counterparty_contact,
+    })
+}
+
+// The seller gets the phone from the buyer's shipping address, falling back
+// to the buyer's WhatsApp number; the buyer gets the seller's WhatsApp
+// number. The stored number is only decrypted here, for the counterparty.
+async fn counterparty_contact(
+    order: &Order,
+    role: OrderRole,
+) -> Result<Option<OrderContact>, VerboseHTTPError> {
+    if !contact_revealed(order.status) {
+        return Ok(None);
+    }
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let counterparty_id = match role {
+        OrderRole::Buyer => &order.seller_id,
+        OrderRole::Seller => &order.buyer_id,
+    };
+
+    let users: Collection<UserOut> = database.collection("users");
+    let counterparty = users
+        .find_one(doc! { "uid": counterparty_id })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    let Some(counterparty) = counterparty else {
+        return Ok(None);
+    };
+
+    let shipping_phone = match role {
+        OrderRole::Seller => order
+            .shipping_address
+            .as_ref()
+            .map(|address| address.phone.clone()),
+        OrderRole::Buyer => None,
+    };
+
+    // Only a number the counterparty has verified is shared.
+    let phone = shipping_phone.or_else(|| {
+        if !counterparty.whatsapp_verified {
+            return None;
+        }
+
+        let whatsapp = counterparty.whatsapp_number.as_ref()?;
+        if let Err(error) = whatsapp.set_salt(&counterparty.salt) {
+            tracing::warn!(
+                "Failed to decrypt WhatsApp number of {}: {}",
+                counterparty.uid,
+                error
+            );
+            return None;
+        }
+        Some(whatsapp.to_string())
+    });
+
+    Ok(Some(OrderContact {
+        username: counterparty.username,
+        phone,
+    }))
+}
+
+pub async fn set_seller_note(
+    user: &UserOut,
+    order_id: &str,
+    note: &str,
+) -> Result<OrderResponse, VerboseHTTPError> {
+    let note = note.trim();
+    if note.chars().count() > MAX_SELLER_NOTE_LENGTH {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Seller note too long. Maximum {} characters allowed",
+                MAX_SELLER_NOTE_LENGTH
+            ),
+        ));
+    }
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let collection: Collection<Order> = database.collection(COLLECTIONS_ORDERS);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let seller_note = if note.is_empty() {
+        None
+    } else {
+        Some(note.to_string())
+    };
+
+    let updated = collection
+        .find_one_and_update(
+            doc! {
+                "order_id": order_id,
+                "seller_id": &user.uid
+            },
+            doc! {
+                "$set": {
+                    "seller_note": seller_note,
+                    "updated_at": now as i64
+                }
+            },
+        )
+        .return_document(ReturnDocument::After)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    match updated {
+        Some(order) => Ok(seller_view(order.into())),
+        None => Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "Order not found".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_window_starts_at_the_first_of_the_oldest_month() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 15, 10, 30, 0).unwrap();
+        let start_of = |year, month| {
+            Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0)
+                .unwrap()
+                .timestamp()
+        };
+
+        assert_eq!(stats_window_start(now, 1), start_of(2026, 3));
+        assert_eq!(stats_window_start(now, 3), start_of(2026, 1));
+        // Crossing into the previous year.
+        assert_eq!(stats_window_start(now, 4), start_of(2025, 12));
+        assert_eq!(stats_window_start(now, 27), start_of(2024, 1));
+    }
+
+    #[test]
+    fn only_the_right_role_can_move_an_order() {
+        use OrderStatus::*;
+
+        assert!(can_transition(Unpaid, Cancelled, OrderRole::Buyer));
+        assert!(!can_transition(Unpaid, Cancelled, OrderRole::Seller));
+        assert!(can_transition(DeliveryPending, Shipped, OrderRole::Seller));
+        assert!(!can_transition(DeliveryPending, Shipped, OrderRole::Buyer));
+        assert!(can_transition(Shipped, Refunded, OrderRole::Seller));
+        assert!(!can_transition(Shipped, Cancelled, OrderRole::Buyer));
+        assert!(!can_transition(Delivered, Shipped, OrderRole::Seller));
+        assert!(!can_transition(Refunded, Refunded, OrderRole::Seller));
+    }
+
+    #[test]
+    fn bson_count_accepts_any_numeric_type() {
+        let document = doc! {
+            "int32": 7,
+            "int64": 9_i64,
+            "double": 2.6,
+            "negative": -4,
+            "text": "12"
+        };
+
+        assert_eq!(bson_count(&document, "int32"), 7);
+        assert_eq!(bson_count(&document, "int64"), 9);
+        assert_eq!(bson_count(&document, "double"), 3);
+        assert_eq!(bson_count(&document, "negative"), 0);
+        assert_eq!(bson_count(&document, "text"), 0);
+        assert_eq!(bson_count(&document, "missing"), 0);
+    }
+
+    #[test]
+    fn facet_documents_skips_non_documents() {
+        let stats = doc! { "totals": [{ "spend": 100 }, 5, { "spend": 200 }] };
+
+        let totals = facet_documents(&stats, "totals");
+        assert_eq!(totals.len(), 2);
+        assert_eq!(bson_count(&totals[1], "spend"), 200);
+        assert!(facet_documents(&stats, "months").is_empty());
+    }
+}
@@ -1,12 +1,18 @@
 use axum::{
     body::Body,
+    extract::{Extension, Path, Query},
     http::{Request, StatusCode},
     response::{IntoResponse, Json},
 };
 
-use super::delegates::*;
+use super::{
+    delegates::*,
+    schemas::{
+        DEFAULT_ORDER_LIMIT, DEFAULT_ORDER_STATS_MONTHS, OrderStatsQuery, SetSellerNoteRequest,
+    },
+};
 use crate::{
-    apex::utils::VerboseHTTPError,
+    apex::{config::config, utils::VerboseHTTPError},
     auth::schemas::UserOut,
     products::schemas::{ConfirmOrderRequest, ListOrdersQuery},
 };
@@ -29,7 +35,10 @@ pub async fn list_orders_endpoint(req: Request<Body>) -> impl IntoResponse {
         }
     };
 
-    let limit = query.limit.unwrap_or(20).min(100);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_ORDER_LIMIT)
+        .min(config().max_order_limit);
     let offset = query.offset.unwrap_or(0);
 
     match list_orders(&user, limit, offset).await {
@@ -56,7 +65,10 @@ pub async fn list_seller_orders_endpoint(req: Request<Body>) -> impl IntoRespons
         }
     };
 
-    let limit = query.limit.unwrap_or(20).min(100);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_ORDER_LIMIT)
+        .min(config().max_order_limit);
     let offset = query.offset.unwrap_or(0);
 
     match list_seller_orders(&user, limit, offset).await {
@@ -95,3 +107,69 @@ pub async fn confirm_order_endpoint(req: Request<Body>) -> impl IntoResponse {
         Err(error) => error.into_response(),
     }
 }
+
+pub async fn order_stats_endpoint(
+    Extension(user): Extension<UserOut>,
+    Query(query): Query<OrderStatsQuery>,
+) -> impl IntoResponse {
+    let months = query
+        .months
+        .unwrap_or(DEFAULT_ORDER_STATS_MONTHS)
+        .clamp(1, config().max_order_stats_months.max(1));
+
+    match order_stats(&user, months).await {
+        Ok(stats) => Json(stats).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn get_order_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(order_id): Path<String>,
+) -> impl IntoResponse {
+    match get_order(&user, &order_id).await {
+        Ok(order) => Json(order).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn cancel_order_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(order_id): Path<String>,
+) -> impl IntoResponse {
+    match cancel_order(&user, &order_id).await {
+        Ok(order) => Json(order).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn mark_shipped_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(order_id): Path<String>,
+) -> impl IntoResponse {
+    match mark_shipped(&user, &order_id).await {
+        Ok(order) => Json(order).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn mark_delivered_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(order_id): Path<String>,
+) -> impl IntoResponse {
+    match mark_delivered(&user, &order_id).await {
+        Ok(order) => Json(order).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn set_seller_note_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(order_id): Path<String>,
+    Json(request): Json<SetSellerNoteRequest>,
+) -> impl IntoResponse {
+    match set_seller_note(&user, &order_id, &request.note).await {
+        Ok(order) => Json(order).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
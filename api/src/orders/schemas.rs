@@ -1,3 +1,76 @@
-pub use crate::products::schemas::{Order, OrderResponse, OrderStatus};
+use serde::{Deserialize, Serialize};
+
+pub use crate::products::schemas::{Order, OrderResponse, OrderStatus, OrderStatusChange};
 
 pub const COLLECTIONS_ORDERS: &str = "orders";
+pub const DEFAULT_ORDER_LIMIT: u32 = 20;
+pub const MAX_ORDER_LIMIT: u32 = 100;
+pub const MAX_SELLER_NOTE_LENGTH: usize = 500;
+pub const DEFAULT_ORDER_STATS_MONTHS: u32 = 12;
+pub const MAX_ORDER_STATS_MONTHS: u32 = 36;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderRole {
+    Buyer,
+    Seller,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderProductInfo {
+    pub title: String,
+    pub thumbnail_url: Option<String>,
+    #[serde(default)]
+    pub slug: Option<String>,
+}
+
+// Contact details of the other party on an order; only filled in once the
+// order has reached delivery_pending.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderContact {
+    pub username: String,
+    pub phone: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderDetailResponse {
+    pub order: OrderResponse,
+    pub product: Option<OrderProductInfo>,
+    #[serde(default)]
+    pub counterparty_contact: Option<OrderContact>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetSellerNoteRequest {
+    pub note: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderStatsQuery {
+    pub months: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategorySpend {
+    pub category: String,
+    pub spend_in_paise: u64,
+    pub order_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MonthlySpend {
+    // Calendar month in UTC, formatted as YYYY-MM.
+    pub month: String,
+    pub spend_in_paise: u64,
+    pub order_count: u64,
+}
+
+// Buyer-side spending summary. Totals and the category split cover every
+// counted order; the monthly breakdown only covers the last `window_months`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderStatsResponse {
+    pub total_spend_in_paise: u64,
+    pub order_count: u64,
+    pub by_category: Vec<CategorySpend>,
+    pub window_months: u32,
+    pub monthly: Vec<MonthlySpend>,
+}
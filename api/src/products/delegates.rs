@@ -1,21 +1,40 @@
 use axum::http::StatusCode;
 use bytes::Bytes;
 use futures::TryStreamExt;
-use mongodb::{Collection, bson::doc, options::FindOptions};
+use mongodb::{
+    Collection, IndexModel,
+    bson::{DateTime as BsonDateTime, Document, doc},
+    options::{FindOptions, IndexOptions},
+};
 use reqwest::multipart::{Form, Part};
-use serde_json;
 use std::{
     env::var,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use uuid::Uuid;
 
 use super::schemas::*;
 use crate::{
     DB,
-    apex::utils::VerboseHTTPError,
+    apex::{
+        config::config,
+        utils::{
+            ERROR_CODE_INVALID_PRICE, ERROR_CODE_RATE_LIMITED, VerboseHTTPError, is_bot_user_agent,
+            mark_migration_done, migration_done, normalize_embedding,
+        },
+    },
     auth::schemas::UserOut,
-    search::{preprocessing::preprocess_text, schemas::FILEBASE_IPFS_ENDPOINT},
+    jobs::{
+        delegates::{
+            advance_job, complete_job, create_job, fail_job, has_recent_job, mark_job_running,
+        },
+        schemas::{Job, JobType},
+    },
+    search::{
+        clip::{clip_request_error, is_clip_unavailable, send_clip_request},
+        preprocessing::{PREPROCESS_VERSION, preprocess_text},
+        schemas::FILEBASE_IPFS_ENDPOINT,
+    },
 };
 
 #[derive(serde::Deserialize)]
@@ -76,6 +95,302 @@ pub async fn upload_file_to_filebase(
     Ok(file_url)
 }
 
+#[inline]
+fn ipfs_hash_from_url(url: &str) -> Option<&str> {
+    url.rsplit_once("/ipfs/")
+        .map(|(_, hash)| hash)
+        .filter(|hash| !hash.is_empty())
+}
+
+pub async fn unpin_file_from_filebase(hash: &str) -> Result<(), VerboseHTTPError> {
+    let access_key = var("FILEBASE_ACCESS_KEY").map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Missing Filebase configuration".to_string(),
+        )
+    })?;
+
+    let response = reqwest::Client::new()
+        .post(format!(
+            "{}/api/v0/pin/rm?arg={}",
+            FILEBASE_IPFS_ENDPOINT, hash
+        ))
+        .header("Authorization", format!("Bearer {}", access_key))
+        .send()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to unpin from Filebase IPFS".to_string(),
+            )
+        })?;
+
+    if !response.status().is_success() {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Filebase unpin failed: {}", response.status()),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn schedule_unpins(file_urls: &[String], reason: &str) {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let pending: Vec<PendingUnpin> = file_urls
+        .iter()
+        .filter_map(|url| ipfs_hash_from_url(url))
+        .map(|hash| PendingUnpin {
+            hash: hash.to_string(),
+            reason: reason.to_string(),
+            attempts: 0,
+            created_at: now,
+        })
+        .collect();
+
+    if pending.is_empty() {
+        return;
+    }
+
+    let collection: Collection<PendingUnpin> = database.collection(COLLECTIONS_PENDING_UNPINS);
+
+    if collection.insert_many(&pending).await.is_err() {
+        tracing::warn!(
+            count = pending.len(),
+            reason,
+            "Failed to schedule orphaned uploads for unpinning"
+        );
+    }
+}
+
+pub async fn process_pending_unpins() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let collection: Collection<PendingUnpin> = database.collection(COLLECTIONS_PENDING_UNPINS);
+
+    let Ok(cursor) = collection
+        .find(doc! { "attempts": { "$lt": MAX_UNPIN_ATTEMPTS as i64 } })
+        .await
+    else {
+        return;
+    };
+
+    let pending: Vec<PendingUnpin> = cursor.try_collect().await.unwrap_or_default();
+
+    for item in pending {
+        match unpin_file_from_filebase(&item.hash).await {
+            Ok(()) => {
+                let _ = collection.delete_one(doc! { "hash": &item.hash }).await;
+            }
+            Err(_) => {
+                let _ = collection
+                    .update_one(
+                        doc! { "hash": &item.hash },
+                        doc! { "$inc": { "attempts": 1 } },
+                    )
+                    .await;
+            }
+        }
+    }
+}
+
+pub async fn run_pending_unpins_worker() {
+    let interval_secs = var("PENDING_UNPINS_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(DEFAULT_UNPIN_INTERVAL_SECS);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+
+    loop {
+        interval.tick().await;
+        process_pending_unpins().await;
+    }
+}
+
+pub fn buyable_products_filter(now: u64) -> Document {
+    doc! {
+        "enabled": true,
+        "status": "published",
+        "$and": [
+            { "$or": [{ "available_quantity": null }, { "available_quantity": { "$gt": 0 } }] },
+            { "$or": [{ "available_from": null }, { "available_from": { "$lte": now as i64 } }] },
+            { "$or": [{ "available_until": null }, { "available_until": { "$gt": now as i64 } }] }
+        ]
+    }
+}
+
+fn validate_availability_window(
+    available_from: Option<u64>,
+    available_until: Option<u64>,
+) -> Result<(), VerboseHTTPError> {
+    if let (Some(from), Some(until)) = (available_from, available_until)
+        && from >= until
+    {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Availability window must end after it starts".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_attributes(attributes: &[ProductAttribute]) -> Result<(), VerboseHTTPError> {
+    if attributes.len() > MAX_ATTRIBUTES_COUNT {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!("Cannot have more than {} attributes", MAX_ATTRIBUTES_COUNT),
+        ));
+    }
+
+    for (index, attribute) in attributes.iter().enumerate() {
+        if attribute.key.trim().is_empty() || attribute.value.trim().is_empty() {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                "Attribute key and value cannot be empty".to_string(),
+            ));
+        }
+        if attribute.key.len() > MAX_ATTRIBUTE_KEY_LENGTH {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Attribute key cannot exceed {} characters",
+                    MAX_ATTRIBUTE_KEY_LENGTH
+                ),
+            ));
+        }
+        if attribute.value.len() > MAX_ATTRIBUTE_VALUE_LENGTH {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Attribute value cannot exceed {} characters",
+                    MAX_ATTRIBUTE_VALUE_LENGTH
+                ),
+            ));
+        }
+        if attributes[..index]
+            .iter()
+            .any(|other| other.key.trim().eq_ignore_ascii_case(attribute.key.trim()))
+        {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                format!("Duplicate attribute key: {}", attribute.key.trim()),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn normalize_attributes(attributes: Vec<ProductAttribute>) -> Vec<ProductAttribute> {
+    attributes
+        .into_iter()
+        .map(|attribute| ProductAttribute {
+            key: attribute.key.trim().to_string(),
+            value: attribute.value.trim().to_string(),
+        })
+        .collect()
+}
+
+fn validate_product_quantity(quantity: &ProductQuantity) -> Result<(), VerboseHTTPError> {
+    if quantity.min_quantity < 1 {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Minimum quantity must be at least 1".to_string(),
+        ));
+    }
+    if quantity.min_quantity > quantity.max_quantity {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Minimum quantity cannot exceed maximum quantity".to_string(),
+        ));
+    }
+    let max_product_quantity = config().max_product_quantity;
+    if quantity.max_quantity > max_product_quantity {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!("Maximum quantity cannot exceed {}", max_product_quantity),
+        ));
+    }
+
+    Ok(())
+}
+
+// Checked before anything is uploaded. Disabled products still count toward
+// the hourly rate so deleting and re-creating doesn't get around it.
+async fn enforce_listing_limits(user: &UserOut, now: u64) -> Result<(), VerboseHTTPError> {
+    if user.trusted_seller {
+        return Ok(());
+    }
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let collection: Collection<Product> = database.collection("products");
+
+    let recent = collection
+        .count_documents(doc! {
+            "user_id": &user.uid,
+            "created_at": { "$gte": now.saturating_sub(PRODUCT_CREATION_WINDOW_SECS) as i64 }
+        })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    if recent >= config().product_creation_rate_limit {
+        return Err(VerboseHTTPError::Coded(
+            StatusCode::TOO_MANY_REQUESTS,
+            ERROR_CODE_RATE_LIMITED,
+            format!(
+                "You can create at most {} products per hour",
+                config().product_creation_rate_limit
+            ),
+        ));
+    }
+
+    let active = collection
+        .count_documents(doc! { "user_id": &user.uid, "enabled": true })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    if active >= config().max_active_listings {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::FORBIDDEN,
+            format!(
+                "You can have at most {} active listings",
+                config().max_active_listings
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
 pub async fn create_product(
     user: &UserOut,
     request: CreateProductRequest,
@@ -116,14 +431,14 @@ pub async fn create_product(
         ));
     }
 
+    let max_questions = config().max_questions_for(request.category);
+    let max_tags = config().max_tags_for(request.category);
+
     if let Some(ref questions) = request.custom_questions {
-        if questions.questions.len() > MAX_QUESTIONS_COUNT {
+        if questions.questions.len() > max_questions {
             return Err(VerboseHTTPError::Standard(
                 StatusCode::BAD_REQUEST,
-                format!(
-                    "Cannot have more than {} custom questions",
-                    MAX_QUESTIONS_COUNT
-                ),
+                format!("Cannot have more than {} custom questions", max_questions),
             ));
         }
 
@@ -148,10 +463,10 @@ pub async fn create_product(
         }
     }
 
-    if request.tags.len() > MAX_TAGS_COUNT {
+    if request.tags.len() > max_tags {
         return Err(VerboseHTTPError::Standard(
             StatusCode::BAD_REQUEST,
-            format!("Cannot have more than {} tags", MAX_TAGS_COUNT).to_string(),
+            format!("Cannot have more than {} tags", max_tags).to_string(),
         ));
     }
 
@@ -170,11 +485,18 @@ pub async fn create_product(
         }
     }
 
+    validate_availability_window(request.available_from, request.available_until)?;
+    validate_product_quantity(&request.quantity)?;
+    validate_attributes(&request.attributes)?;
+    let price_in_paise = validate_price(request.price)?;
+
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
 
+    enforce_listing_limits(user, now).await?;
+
     if gallery_files.len() > MAX_GALLERY_ITEMS {
         return Err(VerboseHTTPError::Standard(
             StatusCode::BAD_REQUEST,
@@ -185,6 +507,8 @@ pub async fn create_product(
         ));
     }
 
+    let mut uploaded_urls: Vec<String> = Vec::with_capacity(gallery_files.len() + 1);
+
     let gallery = if gallery_files.is_empty() {
         Vec::new()
     } else {
@@ -192,6 +516,7 @@ pub async fn create_product(
         for (i, (file_name, file_data, content_type)) in gallery_files.into_iter().enumerate() {
             match upload_file_to_filebase(&file_name, file_data.clone(), &content_type).await {
                 Ok(file_url) => {
+                    uploaded_urls.push(file_url.clone());
                     let item_type = match content_type.as_str() {
                         ct if ct.starts_with("image/") => "picture",
                         ct if ct.starts_with("video/") => "video",
@@ -212,6 +537,7 @@ pub async fn create_product(
                     });
                 }
                 Err(_) => {
+                    schedule_unpins(&uploaded_urls, "create_product").await;
                     return Err(VerboseHTTPError::Standard(
                         StatusCode::INTERNAL_SERVER_ERROR,
                         format!("Failed to upload gallery file: {}", file_name),
@@ -224,8 +550,12 @@ pub async fn create_product(
 
     let thumbnail_url = if let Some((file_name, file_data, content_type)) = thumbnail_file {
         match upload_file_to_filebase(&file_name, file_data, &content_type).await {
-            Ok(url) => Some(url),
+            Ok(url) => {
+                uploaded_urls.push(url.clone());
+                Some(url)
+            }
             Err(_) => {
+                schedule_unpins(&uploaded_urls, "create_product").await;
                 return Err(VerboseHTTPError::Standard(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "Failed to upload thumbnail".to_string(),
@@ -236,21 +566,26 @@ pub async fn create_product(
         None
     };
 
-    let mut combined_text = format!("{} {}", request.title, user.username);
-
-    for tag in &request.tags {
-        combined_text.push_str(" ");
-        combined_text.push_str(tag);
-    }
-
-    let preprocessed_text = preprocess_text(&combined_text);
+    let preprocessed_text = product_embedding_text(
+        &request.title,
+        &request.description,
+        &user.username,
+        &request.tags,
+    );
 
-    let embedding =
+    // While CLIP is unavailable the product is saved without an embedding and
+    // a zero preprocess version, so the stale re-embed job picks it up later.
+    let (embedding, preprocess_version) =
         match generate_combined_embedding(&preprocessed_text, &gallery, thumbnail_url.as_deref())
             .await
         {
-            Ok(embedding) => Some(embedding),
+            Ok(embedding) => (Some(embedding), PREPROCESS_VERSION),
+            Err(error) if is_clip_unavailable(&error) => {
+                tracing::warn!("CLIP unavailable, deferring embedding for new product");
+                (None, 0)
+            }
             Err(_) => {
+                schedule_unpins(&uploaded_urls, "create_product").await;
                 return Err(VerboseHTTPError::Standard(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "Failed to generate required embeddings".to_string(),
@@ -258,8 +593,14 @@ pub async fn create_product(
             }
         };
 
+    let database = DB.get().unwrap();
+    let collection: Collection<Product> = database.collection("products");
+
+    let product_id = Uuid::new_v4().to_string();
+    let slug = unique_product_slug(&collection, &request.title, &product_id).await;
+
     let product = Product {
-        product_id: Uuid::new_v4().to_string(),
+        product_id,
         user_id: user.uid.clone(),
         username: user.username.clone(),
         title: request.title,
@@ -268,26 +609,38 @@ pub async fn create_product(
         purchase_type: request.purchase_type,
         category: request.category,
         tags: request.tags,
+        attributes: normalize_attributes(request.attributes),
         quantity: request.quantity,
+        available_quantity: request.available_quantity,
+        available_from: request.available_from,
+        available_until: request.available_until,
         price: request.price,
+        price_in_paise: Some(price_in_paise),
+        accepts_offers: request.accepts_offers,
         custom_questions: request.custom_questions,
         gallery,
         thumbnail_url,
         embedding,
+        preprocess_version,
         created_at: now,
         updated_at: now,
         enabled: true,
+        status: ProductStatus::Draft,
+        view_count: 0,
+        slug: Some(slug),
+        avg_rating: 0.0,
+        review_count: 0,
+        region: user.region.clone(),
+        city: user.city.clone(),
     };
 
-    let database = DB.get().unwrap();
-    let collection: Collection<Product> = database.collection("products");
-
-    collection.insert_one(&product).await.map_err(|_| {
-        VerboseHTTPError::Standard(
+    if collection.insert_one(&product).await.is_err() {
+        schedule_unpins(&uploaded_urls, "create_product").await;
+        return Err(VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to create product".to_string(),
-        )
-    })?;
+        ));
+    }
 
     Ok(product)
 }
@@ -304,7 +657,7 @@ pub async fn get_product_by_id(product_id: &str) -> Result<Product, VerboseHTTPE
     let collection: Collection<Product> = database.collection("products");
 
     let product = collection
-        .find_one(doc! { "product_id": product_id, "enabled": true })
+        .find_one(doc! { "product_id": product_id, "enabled": true, "status": "published" })
         .await
         .map_err(|_| {
             VerboseHTTPError::Standard(
@@ -312,29 +665,17 @@ pub async fn get_product_by_id(product_id: &str) -> Result<Product, VerboseHTTPE
                 "Database error".to_string(),
             )
         })?
-        .ok_or_else(|| {
-            VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "Product not found".to_string())
-        })?;
+        .ok_or_else(VerboseHTTPError::product_not_found)?;
 
     Ok(product)
 }
 
-pub async fn get_user_product_by_id(
-    user: &UserOut,
-    product_id: &str,
-) -> Result<Product, VerboseHTTPError> {
-    if product_id.trim().is_empty() {
-        return Err(VerboseHTTPError::Standard(
-            StatusCode::BAD_REQUEST,
-            "Product ID cannot be empty".to_string(),
-        ));
-    }
-
+pub async fn get_product_by_slug(slug: &str) -> Result<Product, VerboseHTTPError> {
     let database = DB.get().unwrap();
     let collection: Collection<Product> = database.collection("products");
 
-    let product = collection
-        .find_one(doc! { "product_id": product_id, "user_id": &user.uid })
+    collection
+        .find_one(doc! { "slug": slug.trim(), "enabled": true, "status": "published" })
         .await
         .map_err(|_| {
             VerboseHTTPError::Standard(
@@ -342,52 +683,337 @@ pub async fn get_user_product_by_id(
                 "Database error".to_string(),
             )
         })?
-        .ok_or_else(|| {
-            VerboseHTTPError::Standard(
-                StatusCode::NOT_FOUND,
-                "Product not found or access denied".to_string(),
-            )
-        })?;
-
-    Ok(product)
+        .ok_or_else(VerboseHTTPError::product_not_found)
 }
 
-pub async fn update_product(
-    user: &UserOut,
-    product_id: &str,
-    request: UpdateProductRequest,
-    thumbnail_data: Option<Vec<u8>>,
-) -> Result<Product, VerboseHTTPError> {
-    let existing_product = get_user_product_by_id(user, product_id).await?;
+pub fn slugify_title(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len().min(MAX_SLUG_TITLE_LENGTH));
 
-    if let Some(ref title) = request.title {
-        if title.trim().is_empty() {
-            return Err(VerboseHTTPError::Standard(
-                StatusCode::BAD_REQUEST,
-                "Product title cannot be empty".to_string(),
-            ));
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if slug.len() >= MAX_SLUG_TITLE_LENGTH {
+            break;
         }
-        if title.len() > MAX_TITLE_LENGTH {
-            return Err(VerboseHTTPError::Standard(
-                StatusCode::BAD_REQUEST,
-                format!(
-                    "Product title cannot exceed {} characters",
-                    MAX_TITLE_LENGTH
-                ),
-            ));
+
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
         }
     }
 
-    if let Some(ref description) = request.description {
-        if description.trim().is_empty() {
-            return Err(VerboseHTTPError::Standard(
-                StatusCode::BAD_REQUEST,
-                "Product description cannot be empty".to_string(),
-            ));
-        }
-        if description.len() > MAX_DESCRIPTION_LENGTH {
-            return Err(VerboseHTTPError::Standard(
-                StatusCode::BAD_REQUEST,
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "product".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+// Slugs are fixed at creation so shared links keep working after title
+// edits. The short id normally suffices; longer prefixes of the product id
+// (finally the whole id) resolve the rare collision.
+async fn unique_product_slug(
+    collection: &Collection<Product>,
+    title: &str,
+    product_id: &str,
+) -> String {
+    let base = slugify_title(title);
+    let id: String = product_id.chars().filter(|c| *c != '-').collect();
+
+    for length in [SLUG_SHORT_ID_LENGTH, SLUG_SHORT_ID_LENGTH * 2] {
+        let candidate = format!("{}-{}", base, &id[..length.min(id.len())]);
+        if let Ok(0) = collection
+            .count_documents(doc! { "slug": &candidate })
+            .await
+        {
+            return candidate;
+        }
+    }
+
+    format!("{}-{}", base, id)
+}
+
+pub async fn backfill_product_slugs() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let collection: Collection<Product> = database.collection("products");
+
+    let index = IndexModel::builder()
+        .keys(doc! { "slug": 1 })
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "slug": { "$type": "string" } })
+                .build(),
+        )
+        .build();
+
+    if let Err(error) = collection.create_index(index).await {
+        tracing::warn!("Failed to create product slug index: {}", error);
+    }
+
+    let Ok(cursor) = collection
+        .clone_with_type::<Document>()
+        .find(doc! { "slug": { "$not": { "$type": "string" } } })
+        .projection(doc! { "product_id": 1, "title": 1 })
+        .await
+    else {
+        return;
+    };
+
+    let missing: Vec<Document> = cursor.try_collect().await.unwrap_or_default();
+    let mut backfilled = 0;
+
+    for product in missing {
+        let (Ok(product_id), Ok(title)) = (product.get_str("product_id"), product.get_str("title"))
+        else {
+            continue;
+        };
+
+        let slug = unique_product_slug(&collection, title, product_id).await;
+        if let Ok(result) = collection
+            .update_one(
+                doc! { "product_id": product_id },
+                doc! { "$set": { "slug": slug } },
+            )
+            .await
+        {
+            backfilled += result.modified_count;
+        }
+    }
+
+    if backfilled > 0 {
+        tracing::info!("Backfilled slugs for {} products", backfilled);
+    }
+}
+
+pub async fn ensure_product_view_indexes() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let views: Collection<ProductView> = database.collection(COLLECTIONS_PRODUCT_VIEWS);
+
+    let indexes = [
+        IndexModel::builder()
+            .keys(doc! { "product_id": 1, "viewer": 1, "created_at": -1 })
+            .build(),
+        IndexModel::builder()
+            .keys(doc! { "viewer": 1, "created_at": -1 })
+            .build(),
+        IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .expire_after(Duration::from_secs(0))
+                    .build(),
+            )
+            .build(),
+    ];
+
+    for index in indexes {
+        if let Err(error) = views.create_index(index).await {
+            tracing::warn!("Failed to create product view index: {}", error);
+        }
+    }
+
+    // Views recorded before expires_at existed would otherwise never expire.
+    let expires_at = BsonDateTime::from_millis(
+        BsonDateTime::now().timestamp_millis() + (PRODUCT_VIEW_RETENTION_SECS * 1000) as i64,
+    );
+    if let Err(error) = views
+        .update_many(
+            doc! { "expires_at": { "$exists": false } },
+            doc! { "$set": { "expires_at": expires_at } },
+        )
+        .await
+    {
+        tracing::warn!("Failed to backfill product view expiry: {}", error);
+    }
+}
+
+// Signed-in viewers are deduplicated by account, so only anonymous views
+// fall back to the client address.
+pub async fn record_product_view(
+    product_id: &str,
+    user_agent: Option<&str>,
+    user: Option<&UserOut>,
+    client_ip: Option<String>,
+) -> Result<bool, VerboseHTTPError> {
+    if product_id.trim().is_empty() {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Product ID cannot be empty".to_string(),
+        ));
+    }
+
+    if user_agent.is_none_or(is_bot_user_agent) {
+        return Ok(false);
+    }
+
+    let database = DB.get().unwrap();
+    let collection: Collection<Product> = database.collection("products");
+
+    let product_filter = doc! { "product_id": product_id, "enabled": true, "status": "published" };
+
+    let exists = collection
+        .count_documents(product_filter.clone())
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        > 0;
+
+    if !exists {
+        return Err(VerboseHTTPError::product_not_found());
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let viewer = match user {
+        Some(user) => Some(user.uid.clone()),
+        None => client_ip,
+    };
+
+    if let Some(viewer) = viewer {
+        let views: Collection<ProductView> = database.collection(COLLECTIONS_PRODUCT_VIEWS);
+
+        let recent = views
+            .count_documents(doc! {
+                "product_id": product_id,
+                "viewer": &viewer,
+                "created_at": {
+                    "$gte": now.saturating_sub(PRODUCT_VIEW_DEDUP_WINDOW_SECS) as i64
+                }
+            })
+            .await
+            .map_err(|_| {
+                VerboseHTTPError::Standard(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Database error".to_string(),
+                )
+            })?;
+
+        if recent > 0 {
+            return Ok(false);
+        }
+
+        let entry = ProductView {
+            product_id: product_id.to_string(),
+            viewer,
+            created_at: now,
+            expires_at: Some(BsonDateTime::from_millis(
+                ((now + PRODUCT_VIEW_RETENTION_SECS) * 1000) as i64,
+            )),
+        };
+
+        views.insert_one(&entry).await.map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to record view".to_string(),
+            )
+        })?;
+    }
+
+    collection
+        .update_one(product_filter, doc! { "$inc": { "view_count": 1_i64 } })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to record view".to_string(),
+            )
+        })?;
+
+    Ok(true)
+}
+
+pub async fn get_user_product_by_id(
+    user: &UserOut,
+    product_id: &str,
+) -> Result<Product, VerboseHTTPError> {
+    if product_id.trim().is_empty() {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Product ID cannot be empty".to_string(),
+        ));
+    }
+
+    let database = DB.get().unwrap();
+    let collection: Collection<Product> = database.collection("products");
+
+    let product = collection
+        .find_one(doc! { "product_id": product_id, "user_id": &user.uid })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::NOT_FOUND,
+                "Product not found or access denied".to_string(),
+            )
+        })?;
+
+    Ok(product)
+}
+
+pub async fn update_product(
+    user: &UserOut,
+    product_id: &str,
+    request: UpdateProductRequest,
+    thumbnail_file: Option<(String, Bytes, String)>,
+) -> Result<Product, VerboseHTTPError> {
+    let existing_product = get_user_product_by_id(user, product_id).await?;
+
+    if let Some((_, _, ref content_type)) = thumbnail_file
+        && !is_allowed_image_type(content_type)
+    {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Thumbnail must be a JPEG, PNG, GIF or WebP image".to_string(),
+        ));
+    }
+
+    if let Some(ref title) = request.title {
+        if title.trim().is_empty() {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                "Product title cannot be empty".to_string(),
+            ));
+        }
+        if title.len() > MAX_TITLE_LENGTH {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Product title cannot exceed {} characters",
+                    MAX_TITLE_LENGTH
+                ),
+            ));
+        }
+    }
+
+    if let Some(ref description) = request.description {
+        if description.trim().is_empty() {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                "Product description cannot be empty".to_string(),
+            ));
+        }
+        if description.len() > MAX_DESCRIPTION_LENGTH {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
                 format!(
                     "Product description cannot exceed {} characters",
                     MAX_DESCRIPTION_LENGTH
@@ -396,11 +1022,15 @@ pub async fn update_product(
         }
     }
 
+    let category = request.category.unwrap_or(existing_product.category);
+    let max_questions = config().max_questions_for(category);
+    let max_tags = config().max_tags_for(category);
+
     if let Some(ref questions) = request.custom_questions {
-        if questions.questions.len() > 12 {
+        if questions.questions.len() > max_questions {
             return Err(VerboseHTTPError::Standard(
                 StatusCode::BAD_REQUEST,
-                "Cannot have more than 12 custom questions".to_string(),
+                format!("Cannot have more than {} custom questions", max_questions),
             ));
         }
 
@@ -426,10 +1056,10 @@ pub async fn update_product(
     }
 
     if let Some(ref tags) = request.tags {
-        if tags.len() > 32 {
+        if tags.len() > max_tags {
             return Err(VerboseHTTPError::Standard(
                 StatusCode::BAD_REQUEST,
-                format!("Cannot have more than {} tags", MAX_TAGS_COUNT).to_string(),
+                format!("Cannot have more than {} tags", max_tags).to_string(),
             ));
         }
 
@@ -449,14 +1079,26 @@ pub async fn update_product(
         }
     }
 
+    validate_availability_window(
+        request.available_from.or(existing_product.available_from),
+        request.available_until.or(existing_product.available_until),
+    )?;
+
+    if let Some(ref quantity) = request.quantity {
+        validate_product_quantity(quantity)?;
+    }
+
+    if let Some(ref attributes) = request.attributes {
+        validate_attributes(attributes)?;
+    }
+
+    let price_in_paise = request.price.map(validate_price).transpose()?;
+
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
 
-    let mut update_doc = doc! { "updated_at": now as i64 };
-
-    let mut regenerate_embedding = false;
     let final_title = request
         .title
         .as_ref()
@@ -468,36 +1110,134 @@ pub async fn update_product(
         .unwrap_or(&existing_product.tags)
         .clone();
 
-    if request.title.is_some() || request.tags.is_some() {
-        regenerate_embedding = true;
-    }
+    let final_description = request
+        .description
+        .as_deref()
+        .unwrap_or(&existing_product.description);
 
-    if regenerate_embedding {
-        let mut combined_text = format!("{} {}", final_title, user.username);
-        for tag in &final_tags {
-            combined_text.push_str(" ");
-            combined_text.push_str(tag);
+    let new_thumbnail_url = match thumbnail_file {
+        Some((file_name, file_data, content_type)) => {
+            match upload_file_to_filebase(&file_name, file_data, &content_type).await {
+                Ok(url) => Some(url),
+                Err(_) => {
+                    return Err(VerboseHTTPError::Standard(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to upload thumbnail".to_string(),
+                    ));
+                }
+            }
         }
+        None => None,
+    };
 
-        let preprocessed_text = preprocess_text(&combined_text);
+    let embedding = if embedding_needs_refresh(&request, new_thumbnail_url.is_some()) {
+        let preprocessed_text =
+            product_embedding_text(&final_title, final_description, &user.username, &final_tags);
 
         match generate_combined_embedding(
             &preprocessed_text,
             &existing_product.gallery,
-            existing_product.thumbnail_url.as_deref(),
+            new_thumbnail_url
+                .as_deref()
+                .or(existing_product.thumbnail_url.as_deref()),
         )
         .await
         {
-            Ok(embedding) => {
-                update_doc.insert("embedding", embedding);
+            Ok(embedding) => EmbeddingRefresh::Regenerated(embedding),
+            Err(error) if is_clip_unavailable(&error) => {
+                tracing::warn!(
+                    "CLIP unavailable, deferring embedding for product {}",
+                    existing_product.product_id
+                );
+                EmbeddingRefresh::Deferred
             }
             Err(_) => {
+                if let Some(ref url) = new_thumbnail_url {
+                    schedule_unpins(std::slice::from_ref(url), "update_product").await;
+                }
                 return Err(VerboseHTTPError::Standard(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "Failed to regenerate required embeddings".to_string(),
                 ));
             }
         }
+    } else {
+        EmbeddingRefresh::Unchanged
+    };
+
+    let update = product_update_document(
+        request,
+        price_in_paise,
+        now,
+        new_thumbnail_url.as_deref(),
+        embedding,
+    );
+
+    let database = DB.get().unwrap();
+    let collection: Collection<Product> = database.collection("products");
+
+    if collection
+        .update_one(
+            doc! { "product_id": product_id, "user_id": &user.uid },
+            update,
+        )
+        .await
+        .is_err()
+    {
+        if let Some(ref url) = new_thumbnail_url {
+            schedule_unpins(std::slice::from_ref(url), "update_product").await;
+        }
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update product".to_string(),
+        ));
+    }
+
+    if new_thumbnail_url.is_some()
+        && let Some(old_url) = existing_product.thumbnail_url
+    {
+        schedule_unpins(&[old_url], "update_product").await;
+    }
+
+    get_user_product_by_id(user, product_id).await
+}
+
+enum EmbeddingRefresh {
+    Unchanged,
+    Regenerated(Vec<f32>),
+    // CLIP is down; marked stale so the re-embed job picks it up later.
+    Deferred,
+}
+
+// Everything that feeds `product_embedding_text`, plus the thumbnail.
+fn embedding_needs_refresh(request: &UpdateProductRequest, thumbnail_changed: bool) -> bool {
+    request.title.is_some()
+        || request.description.is_some()
+        || request.tags.is_some()
+        || thumbnail_changed
+}
+
+fn product_update_document(
+    request: UpdateProductRequest,
+    price_in_paise: Option<u64>,
+    now: u64,
+    thumbnail_url: Option<&str>,
+    embedding: EmbeddingRefresh,
+) -> Document {
+    let mut update_doc = doc! { "updated_at": now as i64 };
+
+    if let Some(url) = thumbnail_url {
+        update_doc.insert("thumbnail_url", url);
+    }
+    match embedding {
+        EmbeddingRefresh::Unchanged => {}
+        EmbeddingRefresh::Regenerated(embedding) => {
+            update_doc.insert("embedding", embedding);
+            update_doc.insert("preprocess_version", PREPROCESS_VERSION as i64);
+        }
+        EmbeddingRefresh::Deferred => {
+            update_doc.insert("preprocess_version", 0_i64);
+        }
     }
 
     if let Some(title) = request.title {
@@ -521,8 +1261,29 @@ pub async fn update_product(
     if let Some(quantity) = request.quantity {
         update_doc.insert("quantity", mongodb::bson::to_bson(&quantity).unwrap());
     }
-    if let Some(price) = request.price {
+    if let Some(available_quantity) = request.available_quantity {
+        update_doc.insert("available_quantity", available_quantity as i64);
+    }
+    if let Some(available_from) = request.available_from {
+        update_doc.insert("available_from", available_from as i64);
+    }
+    if let Some(available_until) = request.available_until {
+        update_doc.insert("available_until", available_until as i64);
+    }
+    if let Some(price) = request.price
+        && let Some(price_in_paise) = price_in_paise
+    {
         update_doc.insert("price", price);
+        update_doc.insert("price_in_paise", price_in_paise as i64);
+    }
+    if let Some(accepts_offers) = request.accepts_offers {
+        update_doc.insert("accepts_offers", accepts_offers);
+    }
+    if let Some(attributes) = request.attributes {
+        update_doc.insert(
+            "attributes",
+            mongodb::bson::to_bson(&normalize_attributes(attributes)).unwrap(),
+        );
     }
     if let Some(custom_questions) = request.custom_questions {
         update_doc.insert(
@@ -531,13 +1292,58 @@ pub async fn update_product(
         );
     }
 
-    if let Some(_thumbnail_data) = thumbnail_data {
-        let thumbnail_url = format!("thumbnail_{}.jpg", Uuid::new_v4());
-        update_doc.insert("thumbnail_url", thumbnail_url);
-    }
-
-    let database = DB.get().unwrap();
-    let collection: Collection<Product> = database.collection("products");
+    doc! { "$set": update_doc }
+}
+
+pub async fn publish_product(
+    user: &UserOut,
+    product_id: &str,
+) -> Result<Product, VerboseHTTPError> {
+    let mut product = get_user_product_by_id(user, product_id).await?;
+
+    if product.status == ProductStatus::Published {
+        return Ok(product);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let mut update_doc = doc! { "status": "published", "updated_at": now as i64 };
+
+    if product.embedding.is_none() {
+        let preprocessed_text = product_embedding_text(
+            &product.title,
+            &product.description,
+            &user.username,
+            &product.tags,
+        );
+
+        let embedding = generate_combined_embedding(
+            &preprocessed_text,
+            &product.gallery,
+            product.thumbnail_url.as_deref(),
+        )
+        .await
+        .map_err(|error| {
+            if is_clip_unavailable(&error) {
+                return error;
+            }
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to generate required embeddings".to_string(),
+            )
+        })?;
+
+        update_doc.insert("embedding", embedding.clone());
+        update_doc.insert("preprocess_version", PREPROCESS_VERSION as i64);
+        product.embedding = Some(embedding);
+        product.preprocess_version = PREPROCESS_VERSION;
+    }
+
+    let database = DB.get().unwrap();
+    let collection: Collection<Product> = database.collection("products");
 
     collection
         .update_one(
@@ -548,11 +1354,58 @@ pub async fn update_product(
         .map_err(|_| {
             VerboseHTTPError::Standard(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to update product".to_string(),
+                "Failed to publish product".to_string(),
             )
         })?;
 
-    get_user_product_by_id(user, product_id).await
+    product.status = ProductStatus::Published;
+    product.updated_at = now;
+
+    Ok(product)
+}
+
+pub async fn migrate_product_status() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let products: Collection<Document> = database.collection("products");
+
+    match products
+        .update_many(
+            doc! { "status": { "$exists": false } },
+            doc! { "$set": { "status": "published" } },
+        )
+        .await
+    {
+        Ok(result) if result.modified_count > 0 => {
+            tracing::info!(
+                "Marked {} existing products as published",
+                result.modified_count
+            );
+        }
+        Ok(_) => {}
+        Err(error) => tracing::warn!("Failed to migrate product status: {}", error),
+    }
+}
+
+pub async fn ensure_region_indexes() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    for collection_name in ["products", crate::orders::schemas::COLLECTIONS_ORDERS] {
+        let collection: Collection<Document> = database.collection(collection_name);
+        let index = IndexModel::builder().keys(doc! { "region": 1 }).build();
+
+        if let Err(error) = collection.create_index(index).await {
+            tracing::warn!(
+                "Failed to create region index on {}: {}",
+                collection_name,
+                error
+            );
+        }
+    }
 }
 
 pub async fn delete_product(user: &UserOut, product_id: &str) -> Result<(), VerboseHTTPError> {
@@ -618,6 +1471,10 @@ pub async fn list_user_products(
             quantity: product.quantity,
             created_at: product.created_at,
             enabled: product.enabled,
+            status: product.status,
+            view_count: product.view_count,
+            avg_rating: product.avg_rating,
+            review_count: product.review_count,
             thumbnail_url: product.thumbnail_url,
         });
     }
@@ -625,6 +1482,45 @@ pub async fn list_user_products(
     Ok(products)
 }
 
+pub async fn get_user_category_counts(
+    user: &UserOut,
+) -> Result<Vec<CategoryCount>, VerboseHTTPError> {
+    let database = DB.get().unwrap();
+    let collection: Collection<Product> = database.collection("products");
+
+    let pipeline = vec![
+        // A document without a category would group under a null key that
+        // CategoryCount can't deserialize, cutting the results short.
+        doc! {
+            "$match": {
+                "user_id": &user.uid,
+                "enabled": true,
+                "category": { "$ne": null }
+            }
+        },
+        doc! { "$group": { "_id": "$category", "count": { "$sum": 1 } } },
+        doc! { "$sort": { "count": -1, "_id": 1 } },
+    ];
+
+    let mut cursor = collection
+        .aggregate(pipeline)
+        .with_type::<CategoryCount>()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    let mut counts = Vec::new();
+    while let Ok(Some(count)) = cursor.try_next().await {
+        counts.push(count);
+    }
+
+    Ok(counts)
+}
+
 pub async fn generate_questions_with_groq(
     user: &UserOut,
     request: GenerateQuestionsRequest,
@@ -636,8 +1532,6 @@ pub async fn generate_questions_with_groq(
         )
     })?;
 
-    let groq_model = "compound-beta".to_string();
-
     let product = get_user_product_by_id(user, &request.product_id).await?;
 
     let product_type_str = match product.product_type {
@@ -702,15 +1596,157 @@ pub async fn generate_questions_with_groq(
         },
     };
 
+    let arguments = call_groq_tool(&groq_api_key, prompt, generate_questions_tool, 0.7).await?;
+
+    let questions_array = arguments
+        .get("questions")
+        .and_then(|q| q.as_array())
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Invalid questions format in tool call".to_string(),
+            )
+        })?;
+
+    let questions: Vec<Question> = questions_array
+        .iter()
+        .enumerate()
+        .filter_map(|(i, q)| {
+            let question_text = q.get("question")?.as_str()?;
+            let question_type = q.get("type")?.as_str()?;
+            let mandatory = q.get("mandatory")?.as_bool().unwrap_or(false);
+
+            Some(Question {
+                id: format!("q_{}", i + 1),
+                question: question_text.to_string(),
+                question_type: match question_type {
+                    "yes_no" => QuestionType::YesNo,
+                    _ => QuestionType::FreeResponse,
+                },
+                mandatory,
+            })
+        })
+        .collect();
+
+    if questions.is_empty() {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "No valid questions generated".to_string(),
+        ));
+    }
+
+    Ok(ProductQuestions { questions })
+}
+
+pub async fn suggest_product_category(
+    user: &UserOut,
+    product_id: &str,
+) -> Result<CategorySuggestion, VerboseHTTPError> {
+    let groq_api_key = var("GROQ_API_KEY").map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "GROQ API key not configured".to_string(),
+        )
+    })?;
+
+    let product = get_user_product_by_id(user, product_id).await?;
+
+    let prompt = format!(
+        "Pick the single most appropriate marketplace category for this product listing.
+
+        Title: '{}'
+        Description: '{}'
+        Tags: '{}'
+
+        Choose only from the allowed categories and report how confident you are between 0 and 1.
+
+        Important: Do not attempt to call any scripts, functions, or execute any code. Use only the provided tool to format your response.",
+        product.title,
+        product.description,
+        product.tags.join(", ")
+    );
+
+    let suggest_category_tool = GroqTool {
+        tool_type: "function".to_string(),
+        function: GroqFunction {
+            name: "suggest_product_category".to_string(),
+            description: "Suggest the best category for a product listing".to_string(),
+            parameters: GroqFunctionParameters {
+                param_type: "object".to_string(),
+                properties: serde_json::json!({
+                    "category": {
+                        "type": "string",
+                        "enum": &ProductCategory::ALL[..],
+                        "description": "The most appropriate category for the product"
+                    },
+                    "confidence": {
+                        "type": "number",
+                        "minimum": 0,
+                        "maximum": 1,
+                        "description": "Confidence in the suggestion between 0 and 1"
+                    },
+                    "reason": {
+                        "type": "string",
+                        "description": "One short sentence explaining the choice"
+                    }
+                }),
+                required: vec!["category".to_string(), "confidence".to_string()],
+            },
+        },
+    };
+
+    let arguments = call_groq_tool(&groq_api_key, prompt, suggest_category_tool, 0.2).await?;
+
+    let suggested_category = arguments
+        .get("category")
+        .cloned()
+        .and_then(|category| serde_json::from_value::<ProductCategory>(category).ok())
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Invalid category in tool call".to_string(),
+            )
+        })?;
+
+    let confidence = arguments
+        .get("confidence")
+        .and_then(|confidence| confidence.as_f64())
+        .filter(|confidence| confidence.is_finite())
+        .map(|confidence| confidence.clamp(0.0, 1.0) as f32)
+        .unwrap_or(0.0);
+
+    let reason = arguments
+        .get("reason")
+        .and_then(|reason| reason.as_str())
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty())
+        .map(str::to_string);
+
+    Ok(CategorySuggestion {
+        current_category: product.category,
+        suggested_category,
+        confidence,
+        reason,
+    })
+}
+
+async fn call_groq_tool(
+    groq_api_key: &str,
+    prompt: String,
+    tool: GroqTool,
+    temperature: f32,
+) -> Result<serde_json::Value, VerboseHTTPError> {
+    let tool_name = tool.function.name.clone();
+
     let chat_completion = GroqChatCompletion {
-        model: groq_model,
+        model: "compound-beta".to_string(),
         messages: vec![GroqMessage {
             role: "user".to_string(),
             content: prompt,
         }],
-        temperature: 0.7,
+        temperature,
         max_tokens: AI_MAX_TOKENS,
-        tools: vec![generate_questions_tool],
+        tools: vec![tool],
         tool_choice: "required".to_string(),
     };
 
@@ -767,59 +1803,19 @@ pub async fn generate_questions_with_groq(
     }
 
     let tool_call = &tool_calls[0];
-    if tool_call.function.name != "generate_product_questions" {
+    if tool_call.function.name != tool_name {
         return Err(VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Unexpected tool call function name".to_string(),
         ));
     }
 
-    let arguments: serde_json::Value = serde_json::from_str(&tool_call.function.arguments)
-        .map_err(|_| {
-            VerboseHTTPError::Standard(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to parse tool call arguments".to_string(),
-            )
-        })?;
-
-    let questions_array = arguments
-        .get("questions")
-        .and_then(|q| q.as_array())
-        .ok_or_else(|| {
-            VerboseHTTPError::Standard(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Invalid questions format in tool call".to_string(),
-            )
-        })?;
-
-    let questions: Vec<Question> = questions_array
-        .iter()
-        .enumerate()
-        .filter_map(|(i, q)| {
-            let question_text = q.get("question")?.as_str()?;
-            let question_type = q.get("type")?.as_str()?;
-            let mandatory = q.get("mandatory")?.as_bool().unwrap_or(false);
-
-            Some(Question {
-                id: format!("q_{}", i + 1),
-                question: question_text.to_string(),
-                question_type: match question_type {
-                    "yes_no" => QuestionType::YesNo,
-                    _ => QuestionType::FreeResponse,
-                },
-                mandatory,
-            })
-        })
-        .collect();
-
-    if questions.is_empty() {
-        return Err(VerboseHTTPError::Standard(
+    serde_json::from_str(&tool_call.function.arguments).map_err(|_| {
+        VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
-            "No valid questions generated".to_string(),
-        ));
-    }
-
-    Ok(ProductQuestions { questions })
+            "Failed to parse tool call arguments".to_string(),
+        )
+    })
 }
 
 pub async fn get_gallery(
@@ -831,6 +1827,73 @@ pub async fn get_gallery(
     Ok(product.gallery)
 }
 
+// Clients render galleries by `order`, so every mutation leaves it as a
+// contiguous 0..n sequence that follows the current relative positions.
+fn normalize_gallery_order(gallery: &mut [GalleryItem]) {
+    gallery.sort_by_key(|item| item.order);
+    for (i, item) in gallery.iter_mut().enumerate() {
+        item.order = i as u32;
+    }
+}
+
+fn gallery_order_is_contiguous(gallery: &[GalleryItem]) -> bool {
+    gallery
+        .iter()
+        .enumerate()
+        .all(|(i, item)| item.order == i as u32)
+}
+
+pub async fn repair_gallery_orders() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let collection: Collection<Product> = database.collection("products");
+
+    // Only products whose stored gallery orders aren't already 0..n are
+    // loaded, so a healthy catalog returns nothing.
+    let Ok(cursor) = collection
+        .find(doc! {
+            "gallery.0": { "$exists": true },
+            "$expr": {
+                "$ne": ["$gallery.order", { "$range": [0, { "$size": "$gallery" }] }]
+            }
+        })
+        .projection(doc! { "embedding": 0 })
+        .await
+    else {
+        return;
+    };
+
+    let products: Vec<Product> = cursor.try_collect().await.unwrap_or_default();
+    let mut repaired = 0;
+
+    for mut product in products {
+        if gallery_order_is_contiguous(&product.gallery) {
+            continue;
+        }
+
+        normalize_gallery_order(&mut product.gallery);
+        let Ok(gallery) = mongodb::bson::to_bson(&product.gallery) else {
+            continue;
+        };
+
+        if let Ok(result) = collection
+            .update_one(
+                doc! { "product_id": &product.product_id },
+                doc! { "$set": { "gallery": gallery } },
+            )
+            .await
+        {
+            repaired += result.modified_count;
+        }
+    }
+
+    if repaired > 0 {
+        tracing::info!("Repaired gallery order for {} products", repaired);
+    }
+}
+
 pub async fn replace_gallery(
     user: &UserOut,
     product_id: &str,
@@ -871,13 +1934,12 @@ pub async fn replace_gallery(
 
     let existing_product = get_user_product_by_id(user, product_id).await?;
 
-    let mut combined_text = format!("{} {}", existing_product.title, user.username);
-    for tag in &existing_product.tags {
-        combined_text.push_str(" ");
-        combined_text.push_str(tag);
-    }
-
-    let preprocessed_text = preprocess_text(&combined_text);
+    let preprocessed_text = product_embedding_text(
+        &existing_product.title,
+        &existing_product.description,
+        &user.username,
+        &existing_product.tags,
+    );
 
     let embedding = match generate_combined_embedding(
         &preprocessed_text,
@@ -910,6 +1972,7 @@ pub async fn replace_gallery(
                 "$set": {
                     "gallery": mongodb::bson::to_bson(&gallery_items).unwrap(),
                     "embedding": embedding,
+                    "preprocess_version": PREPROCESS_VERSION as i64,
                     "updated_at": now as i64
                 }
             },
@@ -966,6 +2029,7 @@ pub async fn add_gallery_items(
     let existing_product = get_user_product_by_id(user, product_id).await?;
 
     let mut updated_gallery = existing_product.gallery;
+    normalize_gallery_order(&mut updated_gallery);
     let next_order = updated_gallery.len() as u32;
 
     if updated_gallery.len() + new_items.len() > MAX_GALLERY_ITEMS {
@@ -984,13 +2048,12 @@ pub async fn add_gallery_items(
         updated_gallery.push(item);
     }
 
-    let mut combined_text = format!("{} {}", existing_product.title, user.username);
-    for tag in &existing_product.tags {
-        combined_text.push_str(" ");
-        combined_text.push_str(tag);
-    }
-
-    let preprocessed_text = preprocess_text(&combined_text);
+    let preprocessed_text = product_embedding_text(
+        &existing_product.title,
+        &existing_product.description,
+        &user.username,
+        &existing_product.tags,
+    );
 
     let embedding = match generate_combined_embedding(
         &preprocessed_text,
@@ -1023,6 +2086,7 @@ pub async fn add_gallery_items(
                 "$set": {
                     "gallery": mongodb::bson::to_bson(&updated_gallery).unwrap(),
                     "embedding": embedding,
+                    "preprocess_version": PREPROCESS_VERSION as i64,
                     "updated_at": now as i64
                 }
             },
@@ -1058,6 +2122,7 @@ pub async fn reorder_gallery(
             reordered_gallery.push(item);
         }
     }
+    normalize_gallery_order(&mut reordered_gallery);
 
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -1088,52 +2153,151 @@ pub async fn reorder_gallery(
     Ok(reordered_gallery)
 }
 
-async fn generate_combined_embedding(
-    text: &str,
-    gallery: &[GalleryItem],
-    thumbnail_url: Option<&str>,
-) -> Result<Vec<f32>, VerboseHTTPError> {
-    let clip_api_url =
-        var("CLIP_EMBEDDINGS_API_URL").unwrap_or_else(|_| "http://localhost:8000".to_string());
+pub async fn delete_gallery_item(
+    user: &UserOut,
+    product_id: &str,
+    item_id: &str,
+) -> Result<Vec<GalleryItem>, VerboseHTTPError> {
+    let existing_product = get_user_product_by_id(user, product_id).await?;
 
-    let has_images = gallery.iter().any(|g| g.item_type == "picture") || thumbnail_url.is_some();
-
-    if has_images {
-        let image_url = if let Some(thumb) = thumbnail_url {
-            thumb
-        } else {
-            gallery
-                .iter()
-                .find(|g| g.item_type == "picture")
-                .map(|g| g.url.as_str())
-                .unwrap()
-        };
+    let Some(removed_item) = existing_product
+        .gallery
+        .iter()
+        .find(|g| g.id == item_id)
+        .cloned()
+    else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "Gallery item not found".to_string(),
+        ));
+    };
 
-        let request = serde_json::json!({
-            "text": text,
-            "image_url": image_url
-        });
+    let mut updated_gallery: Vec<GalleryItem> = existing_product
+        .gallery
+        .into_iter()
+        .filter(|g| g.id != item_id)
+        .collect();
+    normalize_gallery_order(&mut updated_gallery);
 
-        let client = reqwest::Client::new();
-        let response = client
-            .post(&format!("{}/embed/combined", clip_api_url))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|_| {
-                VerboseHTTPError::Standard(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to call CLIP embedding API".to_string(),
-                )
-            })?;
+    let preprocessed_text = product_embedding_text(
+        &existing_product.title,
+        &existing_product.description,
+        &user.username,
+        &existing_product.tags,
+    );
 
-        if !response.status().is_success() {
+    let embedding = match generate_combined_embedding(
+        &preprocessed_text,
+        &updated_gallery,
+        existing_product.thumbnail_url.as_deref(),
+    )
+    .await
+    {
+        Ok(embedding) => embedding,
+        Err(_) => {
             return Err(VerboseHTTPError::Standard(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "CLIP embedding API request failed".to_string(),
+                "Failed to regenerate embeddings".to_string(),
             ));
         }
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let database = DB.get().unwrap();
+    let collection: Collection<Product> = database.collection("products");
+
+    collection
+        .update_one(
+            doc! { "product_id": product_id, "user_id": &user.uid },
+            doc! {
+                "$set": {
+                    "gallery": mongodb::bson::to_bson(&updated_gallery).unwrap(),
+                    "embedding": embedding,
+                    "preprocess_version": PREPROCESS_VERSION as i64,
+                    "updated_at": now as i64
+                }
+            },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to delete gallery item".to_string(),
+            )
+        })?;
+
+    schedule_unpins(&[removed_item.url], "delete_gallery_item").await;
+
+    Ok(updated_gallery)
+}
+
+fn product_embedding_text(
+    title: &str,
+    description: &str,
+    username: &str,
+    tags: &[String],
+) -> String {
+    let mut combined_text = format!("{} {} {}", title, description, username);
+    for tag in tags {
+        combined_text.push(' ');
+        combined_text.push_str(tag);
+    }
+
+    preprocess_text(&combined_text)
+}
+
+fn embedding_image_urls(gallery: &[GalleryItem], thumbnail_url: Option<&str>) -> Vec<String> {
+    let mut pictures: Vec<&GalleryItem> = gallery
+        .iter()
+        .filter(|g| g.item_type == "picture")
+        .collect();
+    pictures.sort_by_key(|g| g.order);
+
+    let mut image_urls: Vec<String> = Vec::new();
+    for url in thumbnail_url
+        .into_iter()
+        .chain(pictures.into_iter().map(|g| g.url.as_str()))
+    {
+        if image_urls.len() >= config().embedding_max_images {
+            break;
+        }
+        if !image_urls.iter().any(|existing| existing == url) {
+            image_urls.push(url.to_string());
+        }
+    }
+
+    image_urls
+}
+
+async fn generate_combined_embedding(
+    text: &str,
+    gallery: &[GalleryItem],
+    thumbnail_url: Option<&str>,
+) -> Result<Vec<f32>, VerboseHTTPError> {
+    let clip_api_url =
+        var("CLIP_EMBEDDINGS_API_URL").unwrap_or_else(|_| "http://localhost:8000".to_string());
+
+    let image_urls = embedding_image_urls(gallery, thumbnail_url);
+
+    if !image_urls.is_empty() {
+        let request = ClipMultiImageRequest {
+            text: text.to_string(),
+            image_urls,
+        };
+
+        let client = reqwest::Client::new();
+        let response = send_clip_request(
+            client
+                .post(format!("{}/embed/combined", clip_api_url))
+                .header("Content-Type", "application/json")
+                .json(&request),
+        )
+        .await
+        .map_err(|error| clip_request_error(error, "CLIP embedding API request failed"))?;
 
         let embedding_response: ClipEmbeddingResponse = response.json().await.map_err(|_| {
             VerboseHTTPError::Standard(
@@ -1142,32 +2306,21 @@ async fn generate_combined_embedding(
             )
         })?;
 
-        Ok(embedding_response.embedding)
+        Ok(normalize_embedding(embedding_response.embedding))
     } else {
         let request = ClipCombinedRequest {
             text: text.to_string(),
         };
 
         let client = reqwest::Client::new();
-        let response = client
-            .post(&format!("{}/embed/text", clip_api_url))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|_| {
-                VerboseHTTPError::Standard(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to call CLIP embedding API".to_string(),
-                )
-            })?;
-
-        if !response.status().is_success() {
-            return Err(VerboseHTTPError::Standard(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "CLIP embedding API request failed".to_string(),
-            ));
-        }
+        let response = send_clip_request(
+            client
+                .post(format!("{}/embed/text", clip_api_url))
+                .header("Content-Type", "application/json")
+                .json(&request),
+        )
+        .await
+        .map_err(|error| clip_request_error(error, "CLIP embedding API request failed"))?;
 
         let embedding_response: ClipEmbeddingResponse = response.json().await.map_err(|_| {
             VerboseHTTPError::Standard(
@@ -1176,7 +2329,7 @@ async fn generate_combined_embedding(
             )
         })?;
 
-        Ok(embedding_response.embedding)
+        Ok(normalize_embedding(embedding_response.embedding))
     }
 }
 
@@ -1210,10 +2363,13 @@ pub async fn set_product_questions(
     product_id: &str,
     questions: ProductQuestions,
 ) -> Result<ProductQuestions, VerboseHTTPError> {
-    if questions.questions.len() > 12 {
+    let product = get_user_product_by_id(user, product_id).await?;
+    let max_questions = config().max_questions_for(product.category);
+
+    if questions.questions.len() > max_questions {
         return Err(VerboseHTTPError::Standard(
             StatusCode::BAD_REQUEST,
-            "Cannot have more than 12 custom questions".to_string(),
+            format!("Cannot have more than {} custom questions", max_questions),
         ));
     }
 
@@ -1237,8 +2393,6 @@ pub async fn set_product_questions(
         }
     }
 
-    let _product = get_user_product_by_id(user, product_id).await?;
-
     let database = DB.get().unwrap();
     let collection: Collection<Product> = database.collection("products");
 
@@ -1268,10 +2422,169 @@ pub async fn set_product_questions(
     Ok(questions)
 }
 
+#[inline]
+pub fn price_to_paise(price: f64) -> Option<u64> {
+    (price.is_finite() && (0.0..=MAX_PRICE).contains(&price))
+        .then(|| (price * 100.0).round() as u64)
+}
+
+pub fn validate_price(price: f64) -> Result<u64, VerboseHTTPError> {
+    price_to_paise(price).ok_or_else(|| {
+        VerboseHTTPError::Coded(
+            StatusCode::BAD_REQUEST,
+            ERROR_CODE_INVALID_PRICE,
+            format!("Price must be between 0 and {}", MAX_PRICE),
+        )
+    })
+}
+
+pub async fn migrate_price_in_paise() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let products: Collection<Document> = database.collection("products");
+
+    let result = products
+        .update_many(
+            doc! { "price_in_paise": { "$exists": false } },
+            vec![
+                doc! {
+                    "$set": {
+                        "parsed_price": {
+                            "$convert": {
+                                "input": "$price",
+                                "to": "double",
+                                "onError": null,
+                                "onNull": null
+                            }
+                        }
+                    }
+                },
+                doc! {
+                    "$set": {
+                        "price": { "$ifNull": ["$parsed_price", "$price"] },
+                        "price_in_paise": {
+                            "$cond": [
+                                { "$eq": ["$parsed_price", null] },
+                                null,
+                                { "$toLong": { "$round": [{ "$multiply": ["$parsed_price", 100] }, 0] } }
+                            ]
+                        }
+                    }
+                },
+                doc! { "$unset": "parsed_price" },
+            ],
+        )
+        .await;
+
+    match result {
+        Ok(result) if result.modified_count > 0 => {
+            tracing::info!(
+                "Migrated {} products to integer prices",
+                result.modified_count
+            );
+        }
+        Ok(_) => {}
+        Err(error) => tracing::warn!("Failed to migrate product prices: {}", error),
+    }
+}
+
+// Embeddings have been normalized on write since this ran, so it only has
+// to scan the catalog once.
+pub async fn normalize_stored_embeddings() {
+    const MIGRATION: &str = "normalize_stored_embeddings";
+
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    if migration_done(MIGRATION).await {
+        return;
+    }
+
+    let products: Collection<Document> = database.collection("products");
+
+    let embedding_norm = doc! {
+        "$sqrt": {
+            "$reduce": {
+                "input": "$embedding",
+                "initialValue": 0.0,
+                "in": { "$add": ["$$value", { "$multiply": ["$$this", "$$this"] }] }
+            }
+        }
+    };
+
+    let result = products
+        .update_many(
+            doc! {
+                "embedding.0": { "$exists": true },
+                "$expr": {
+                    "$gt": [{ "$abs": { "$subtract": [embedding_norm.clone(), 1.0] } }, 0.0001]
+                }
+            },
+            vec![
+                doc! { "$set": { "embedding_norm": embedding_norm } },
+                doc! {
+                    "$set": {
+                        "embedding": {
+                            "$cond": [
+                                { "$gt": ["$embedding_norm", 0.0] },
+                                {
+                                    "$map": {
+                                        "input": "$embedding",
+                                        "in": { "$divide": ["$$this", "$embedding_norm"] }
+                                    }
+                                },
+                                "$embedding"
+                            ]
+                        }
+                    }
+                },
+                doc! { "$unset": "embedding_norm" },
+            ],
+        )
+        .await;
+
+    match result {
+        Ok(result) => {
+            if result.modified_count > 0 {
+                tracing::info!(
+                    "Normalized {} stored product embeddings",
+                    result.modified_count
+                );
+            }
+            mark_migration_done(MIGRATION).await;
+        }
+        Err(error) => tracing::warn!("Failed to normalize product embeddings: {}", error),
+    }
+}
+
+pub fn validate_order_quantity(product: &Product, quantity: u32) -> Result<(), VerboseHTTPError> {
+    if quantity < product.quantity.min_quantity || quantity > product.quantity.max_quantity {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Quantity is outside allowed range".to_string(),
+        ));
+    }
+
+    if let Some(available_quantity) = product.available_quantity
+        && quantity > available_quantity
+    {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Insufficient stock for the requested quantity".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
 pub async fn buy_now_product(
     user: &UserOut,
     product_id: String,
     quantity: u32,
+    address_id: &str,
 ) -> Result<crate::orders::schemas::OrderResponse, VerboseHTTPError> {
     let Some(database) = DB.get() else {
         return Err(VerboseHTTPError::Standard(
@@ -1283,7 +2596,7 @@ pub async fn buy_now_product(
     let collection: Collection<Product> = database.collection("products");
 
     let product = collection
-        .find_one(doc! { "product_id": &product_id })
+        .find_one(doc! { "product_id": &product_id, "status": "published" })
         .await
         .map_err(|_| {
             VerboseHTTPError::Standard(
@@ -1291,9 +2604,7 @@ pub async fn buy_now_product(
                 "Database error".to_string(),
             )
         })?
-        .ok_or_else(|| {
-            VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "Product not found".to_string())
-        })?;
+        .ok_or_else(VerboseHTTPError::product_not_found)?;
 
     if product.purchase_type != PurchaseType::BuyNow {
         return Err(VerboseHTTPError::Standard(
@@ -1302,22 +2613,755 @@ pub async fn buy_now_product(
         ));
     }
 
-    if quantity < product.quantity.min_quantity || quantity > product.quantity.max_quantity {
-        return Err(VerboseHTTPError::Standard(
-            StatusCode::BAD_REQUEST,
-            "Quantity is outside allowed range".to_string(),
-        ));
-    }
+    validate_order_quantity(&product, quantity)?;
 
-    let price = product.price;
-    let total_price = price * quantity as f64;
+    let unit_price_in_paise = match product.price_in_paise {
+        Some(price_in_paise) => price_in_paise,
+        None => validate_price(product.price)?,
+    };
+    let shipping_address = crate::auth::shipping_address_snapshot(user, address_id).await?;
 
     crate::orders::delegates::create_order_internal(
         product_id,
         product.user_id,
         user.uid.clone(),
         quantity,
-        total_price,
+        unit_price_in_paise * quantity as u64,
+        product.region,
+        Some(shipping_address),
     )
     .await
 }
+
+pub async fn notify_user(user_id: &str, subject: &str, message: &str) {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let users: Collection<UserOut> = database.collection("users");
+    let Ok(Some(recipient)) = users.find_one(doc! { "uid": user_id }).await else {
+        return;
+    };
+
+    if recipient.initialize_encryption().is_err() {
+        return;
+    }
+
+    if recipient.notification_preferences.email {
+        let _ = crate::notifications::delegates::send_email_internal(
+            &recipient.email.to_string(),
+            Some(&recipient.username),
+            subject,
+            message,
+        )
+        .await;
+    }
+
+    if recipient.whatsapp_verified
+        && recipient.notification_preferences.whatsapp
+        && let Some(ref whatsapp) = recipient.whatsapp_number
+    {
+        let _ =
+            crate::notifications::delegates::send_whatsapp_internal(&whatsapp.to_string(), message)
+                .await;
+    }
+}
+
+pub async fn create_offer(
+    user: &UserOut,
+    product_id: &str,
+    request: CreateOfferRequest,
+) -> Result<Offer, VerboseHTTPError> {
+    if !request.price.is_finite() || request.price <= 0.0 {
+        return Err(VerboseHTTPError::Coded(
+            StatusCode::BAD_REQUEST,
+            ERROR_CODE_INVALID_PRICE,
+            "Offer price must be greater than zero".to_string(),
+        ));
+    }
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let products: Collection<Product> = database.collection("products");
+
+    let product = products
+        .find_one(doc! { "product_id": product_id, "enabled": true, "status": "published" })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or_else(VerboseHTTPError::product_not_found)?;
+
+    if !product.accepts_offers {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Product does not accept offers".to_string(),
+        ));
+    }
+
+    if product.user_id == user.uid {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "You cannot make an offer on your own product".to_string(),
+        ));
+    }
+
+    validate_order_quantity(&product, request.quantity)?;
+    crate::auth::shipping_address_snapshot(user, &request.address_id).await?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let offer = Offer {
+        offer_id: Uuid::new_v4().to_string(),
+        product_id: product.product_id.clone(),
+        seller_id: product.user_id.clone(),
+        buyer_id: user.uid.clone(),
+        quantity: request.quantity,
+        price: request.price,
+        status: OfferStatus::Pending,
+        order_id: None,
+        address_id: Some(request.address_id),
+        created_at: now,
+        updated_at: now,
+    };
+
+    let offers: Collection<Offer> = database.collection(COLLECTIONS_OFFERS);
+    offers.insert_one(&offer).await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to create offer".to_string(),
+        )
+    })?;
+
+    let message = format!(
+        "{} offered {:.2} each for {} x {} - Review it on https://goodspoint.tech",
+        user.username, offer.price, offer.quantity, product.title
+    );
+    notify_user(&product.user_id, "New Offer - GoodsPoint", &message).await;
+
+    Ok(offer)
+}
+
+pub async fn list_seller_offers(
+    user: &UserOut,
+    status: Option<OfferStatus>,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<Offer>, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let offers: Collection<Offer> = database.collection(COLLECTIONS_OFFERS);
+
+    let mut filter = doc! { "seller_id": &user.uid };
+    if let Some(status) = status {
+        filter.insert("status", mongodb::bson::to_bson(&status).unwrap());
+    }
+
+    let cursor = offers
+        .find(filter)
+        .sort(doc! { "created_at": -1 })
+        .skip(offset as u64)
+        .limit(limit as i64)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    cursor.try_collect().await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
+    })
+}
+
+async fn resolve_offer(
+    user: &UserOut,
+    offer_id: &str,
+    status: OfferStatus,
+) -> Result<Offer, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let offers: Collection<Offer> = database.collection(COLLECTIONS_OFFERS);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    offers
+        .find_one_and_update(
+            doc! {
+                "offer_id": offer_id,
+                "seller_id": &user.uid,
+                "status": mongodb::bson::to_bson(&OfferStatus::Pending).unwrap()
+            },
+            doc! {
+                "$set": {
+                    "status": mongodb::bson::to_bson(&status).unwrap(),
+                    "updated_at": now as i64
+                }
+            },
+        )
+        .return_document(mongodb::options::ReturnDocument::After)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::NOT_FOUND,
+                "Offer not found or already resolved".to_string(),
+            )
+        })
+}
+
+pub async fn accept_offer(
+    user: &UserOut,
+    offer_id: &str,
+) -> Result<crate::orders::schemas::OrderResponse, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let offers: Collection<Offer> = database.collection(COLLECTIONS_OFFERS);
+
+    let pending = offers
+        .find_one(doc! {
+            "offer_id": offer_id,
+            "seller_id": &user.uid,
+            "status": mongodb::bson::to_bson(&OfferStatus::Pending).unwrap()
+        })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(
+                StatusCode::NOT_FOUND,
+                "Offer not found or already resolved".to_string(),
+            )
+        })?;
+
+    let product = get_product_by_id(&pending.product_id).await?;
+    validate_order_quantity(&product, pending.quantity)?;
+
+    let Some(address_id) = pending.address_id.as_deref() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::CONFLICT,
+            "Offer has no shipping address; the buyer needs to make a new offer".to_string(),
+        ));
+    };
+
+    let users: Collection<UserOut> = database.collection("users");
+    let buyer = users
+        .find_one(doc! { "uid": &pending.buyer_id })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "Buyer not found".to_string())
+        })?;
+    let shipping_address = crate::auth::shipping_address_snapshot(&buyer, address_id).await?;
+
+    let price_in_paise = validate_price(pending.price)? * pending.quantity as u64;
+
+    let offer = resolve_offer(user, offer_id, OfferStatus::Accepted).await?;
+
+    let order = match crate::orders::delegates::create_order_internal(
+        offer.product_id.clone(),
+        offer.seller_id.clone(),
+        offer.buyer_id.clone(),
+        offer.quantity,
+        price_in_paise,
+        product.region,
+        Some(shipping_address),
+    )
+    .await
+    {
+        Ok(order) => order,
+        Err(error) => {
+            // Put the offer back to pending so the seller can retry once the
+            // cause (stock, database) is fixed.
+            if let Err(revert_error) = offers
+                .update_one(
+                    doc! {
+                        "offer_id": offer_id,
+                        "status": mongodb::bson::to_bson(&OfferStatus::Accepted).unwrap(),
+                        "order_id": null
+                    },
+                    doc! {
+                        "$set": {
+                            "status": mongodb::bson::to_bson(&OfferStatus::Pending).unwrap(),
+                            "updated_at": pending.updated_at as i64
+                        }
+                    },
+                )
+                .await
+            {
+                tracing::warn!("Failed to reopen offer {}: {}", offer_id, revert_error);
+            }
+            return Err(error);
+        }
+    };
+
+    if let Err(error) = offers
+        .update_one(
+            doc! { "offer_id": offer_id },
+            doc! { "$set": { "order_id": &order.order_id } },
+        )
+        .await
+    {
+        tracing::warn!("Failed to link offer {} to its order: {}", offer_id, error);
+    }
+
+    let message = format!(
+        "{} accepted your offer for {} - View your orders on https://goodspoint.tech",
+        user.username, product.title
+    );
+    notify_user(&offer.buyer_id, "Offer Accepted - GoodsPoint", &message).await;
+
+    Ok(order)
+}
+
+pub async fn reject_offer(user: &UserOut, offer_id: &str) -> Result<Offer, VerboseHTTPError> {
+    let offer = resolve_offer(user, offer_id, OfferStatus::Rejected).await?;
+
+    let message = format!(
+        "{} declined your offer - Browse more products: https://goodspoint.tech",
+        user.username
+    );
+    notify_user(&offer.buyer_id, "Offer Declined - GoodsPoint", &message).await;
+
+    Ok(offer)
+}
+
+fn stale_embeddings_filter() -> Document {
+    doc! {
+        "enabled": true,
+        "preprocess_version": { "$not": { "$gte": PREPROCESS_VERSION as i64 } }
+    }
+}
+
+pub async fn start_stale_reembed_job(user: &UserOut) -> Result<Job, VerboseHTTPError> {
+    if has_recent_job(
+        &user.uid,
+        JobType::StaleReembed,
+        STALE_REEMBED_COOLDOWN_SECS,
+    )
+    .await
+    {
+        return Err(VerboseHTTPError::Coded(
+            StatusCode::TOO_MANY_REQUESTS,
+            ERROR_CODE_RATE_LIMITED,
+            "A stale re-embed was requested recently. Please try again later".to_string(),
+        ));
+    }
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let products: Collection<Product> = database.collection("products");
+    let total = products
+        .count_documents(stale_embeddings_filter())
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    let job = create_job(&user.uid, JobType::StaleReembed, total as u32).await?;
+
+    tokio::spawn(run_stale_reembed_job(job.job_id.clone()));
+
+    Ok(job)
+}
+
+async fn run_stale_reembed_job(job_id: String) {
+    let Some(database) = DB.get() else {
+        fail_job(&job_id, "Database unavailable").await;
+        return;
+    };
+
+    mark_job_running(&job_id).await;
+
+    let products: Collection<Product> = database.collection("products");
+
+    // The old embedding is never read, and the cursor streams the stale set in
+    // batches instead of loading it all.
+    let mut cursor = match products
+        .find(stale_embeddings_filter())
+        .projection(doc! { "embedding": 0 })
+        .batch_size(STALE_REEMBED_BATCH_SIZE)
+        .await
+    {
+        Ok(cursor) => cursor,
+        Err(_) => {
+            fail_job(&job_id, "Failed to load products").await;
+            return;
+        }
+    };
+
+    let mut processed = 0u32;
+    let mut failed = 0u32;
+    let mut pending_progress = 0u32;
+
+    loop {
+        let product = match cursor.try_next().await {
+            Ok(Some(product)) => product,
+            Ok(None) => break,
+            Err(_) => {
+                fail_job(&job_id, "Failed to load products").await;
+                return;
+            }
+        };
+
+        if reembed_product(&product, &product.username).await.is_err() {
+            tracing::warn!(
+                job_id = job_id.as_str(),
+                product_id = product.product_id.as_str(),
+                "Failed to re-embed stale product"
+            );
+            failed += 1;
+        }
+
+        processed += 1;
+        pending_progress += 1;
+        if pending_progress == STALE_REEMBED_BATCH_SIZE {
+            advance_job(&job_id, pending_progress).await;
+            pending_progress = 0;
+        }
+    }
+
+    if pending_progress > 0 {
+        advance_job(&job_id, pending_progress).await;
+    }
+
+    complete_job(
+        &job_id,
+        serde_json::json!({
+            "reembedded": processed - failed,
+            "failed": failed,
+            "preprocess_version": PREPROCESS_VERSION
+        }),
+    )
+    .await;
+}
+
+pub async fn reembed_product(product: &Product, username: &str) -> Result<(), VerboseHTTPError> {
+    let preprocessed_text = product_embedding_text(
+        &product.title,
+        &product.description,
+        username,
+        &product.tags,
+    );
+
+    let embedding = generate_combined_embedding(
+        &preprocessed_text,
+        &product.gallery,
+        product.thumbnail_url.as_deref(),
+    )
+    .await?;
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let collection: Collection<Product> = database.collection("products");
+
+    collection
+        .update_one(
+            doc! { "product_id": &product.product_id },
+            doc! {
+                "$set": {
+                    "embedding": embedding,
+                    "preprocess_version": PREPROCESS_VERSION as i64
+                }
+            },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update embedding".to_string(),
+            )
+        })?;
+
+    Ok(())
+}
+
+pub async fn start_reembed_job(user: &UserOut) -> Result<Job, VerboseHTTPError> {
+    if has_recent_job(&user.uid, JobType::Reembed, REEMBED_COOLDOWN_SECS).await {
+        return Err(VerboseHTTPError::Coded(
+            StatusCode::TOO_MANY_REQUESTS,
+            ERROR_CODE_RATE_LIMITED,
+            "A catalog re-embed was requested recently. Please try again later".to_string(),
+        ));
+    }
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let products: Collection<Product> = database.collection("products");
+    let total = products
+        .count_documents(doc! { "user_id": &user.uid, "enabled": true })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    let job = create_job(&user.uid, JobType::Reembed, total as u32).await?;
+
+    tokio::spawn(run_reembed_job(
+        job.job_id.clone(),
+        user.uid.clone(),
+        user.username.clone(),
+    ));
+
+    Ok(job)
+}
+
+async fn run_reembed_job(job_id: String, user_id: String, username: String) {
+    let Some(database) = DB.get() else {
+        fail_job(&job_id, "Database unavailable").await;
+        return;
+    };
+
+    mark_job_running(&job_id).await;
+
+    let products: Collection<Product> = database.collection("products");
+
+    let catalog: Vec<Product> = match products
+        .find(doc! { "user_id": &user_id, "enabled": true })
+        .await
+    {
+        Ok(cursor) => cursor.try_collect().await.unwrap_or_default(),
+        Err(_) => {
+            fail_job(&job_id, "Failed to load products").await;
+            return;
+        }
+    };
+
+    let mut failed = 0u32;
+
+    for product in &catalog {
+        if reembed_product(product, &username).await.is_err() {
+            tracing::warn!(
+                job_id = job_id.as_str(),
+                product_id = product.product_id.as_str(),
+                "Failed to re-embed product"
+            );
+            failed += 1;
+        }
+
+        advance_job(&job_id, 1).await;
+    }
+
+    complete_job(
+        &job_id,
+        serde_json::json!({
+            "reembedded": catalog.len() as u32 - failed,
+            "failed": failed
+        }),
+    )
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gallery_item(id: &str, order: u32) -> GalleryItem {
+        GalleryItem {
+            id: id.to_string(),
+            item_type: "image".to_string(),
+            url: format!("https://example.com/{id}"),
+            size: 1,
+            order,
+            upload_timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn slugify_title_collapses_separators() {
+        assert_eq!(
+            slugify_title("Apple iPhone 15 Pro (256 GB)"),
+            "apple-iphone-15-pro-256-gb"
+        );
+        assert_eq!(slugify_title("  --Hello,   World!--  "), "hello-world");
+        assert_eq!(slugify_title("नमस्ते !!"), "product");
+        assert_eq!(slugify_title(""), "product");
+    }
+
+    #[test]
+    fn slugify_title_caps_length_without_trailing_dash() {
+        let slug = slugify_title(&"ab ".repeat(MAX_SLUG_TITLE_LENGTH));
+
+        assert!(slug.len() <= MAX_SLUG_TITLE_LENGTH);
+        assert!(!slug.ends_with('-'));
+    }
+
+    #[test]
+    fn normalize_gallery_order_keeps_relative_positions() {
+        let mut gallery = vec![
+            gallery_item("c", 7),
+            gallery_item("a", 0),
+            gallery_item("b", 3),
+        ];
+
+        normalize_gallery_order(&mut gallery);
+
+        let ids: Vec<&str> = gallery.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+        assert!(gallery_order_is_contiguous(&gallery));
+    }
+
+    #[test]
+    fn price_to_paise_rounds_and_rejects_out_of_range() {
+        assert_eq!(price_to_paise(0.0), Some(0));
+        assert_eq!(price_to_paise(19.99), Some(1999));
+        assert_eq!(price_to_paise(0.105), Some(11));
+        assert_eq!(price_to_paise(MAX_PRICE), Some((MAX_PRICE * 100.0) as u64));
+        assert_eq!(price_to_paise(-1.0), None);
+        assert_eq!(price_to_paise(MAX_PRICE + 1.0), None);
+        assert_eq!(price_to_paise(f64::NAN), None);
+        assert_eq!(price_to_paise(f64::INFINITY), None);
+    }
+
+    fn quantity(min_quantity: u32, max_quantity: u32) -> ProductQuantity {
+        ProductQuantity {
+            min_quantity,
+            max_quantity,
+        }
+    }
+
+    fn quantity_error(quantity: &ProductQuantity) -> String {
+        match validate_product_quantity(quantity) {
+            Err(VerboseHTTPError::Standard(StatusCode::BAD_REQUEST, message)) => message,
+            other => panic!("expected a bad request, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn product_quantity_rejects_inverted_range() {
+        assert_eq!(
+            quantity_error(&quantity(5, 2)),
+            "Minimum quantity cannot exceed maximum quantity"
+        );
+        assert!(validate_product_quantity(&quantity(2, 2)).is_ok());
+    }
+
+    #[test]
+    fn product_quantity_rejects_zero_minimum() {
+        assert_eq!(
+            quantity_error(&quantity(0, 3)),
+            "Minimum quantity must be at least 1"
+        );
+    }
+
+    #[test]
+    fn product_quantity_respects_configured_maximum() {
+        let max_product_quantity = config().max_product_quantity;
+        assert!(validate_product_quantity(&quantity(1, max_product_quantity)).is_ok());
+        assert_eq!(
+            quantity_error(&quantity(1, max_product_quantity + 1)),
+            format!("Maximum quantity cannot exceed {}", max_product_quantity)
+        );
+    }
+
+    fn update_request(fields: serde_json::Value) -> UpdateProductRequest {
+        serde_json::from_value(fields).unwrap()
+    }
+
+    #[test]
+    fn description_change_refreshes_the_embedding() {
+        let request = update_request(serde_json::json!({ "description": "Barely used, with box" }));
+        assert!(embedding_needs_refresh(&request, false));
+
+        let update = product_update_document(
+            request,
+            None,
+            1_700_000_000,
+            None,
+            EmbeddingRefresh::Regenerated(vec![0.6, 0.8]),
+        );
+        let set = update.get_document("$set").unwrap();
+        assert_eq!(set.get_str("description"), Ok("Barely used, with box"));
+        assert!(set.get_array("embedding").is_ok());
+        assert_eq!(
+            set.get_i64("preprocess_version"),
+            Ok(PREPROCESS_VERSION as i64)
+        );
+    }
+
+    #[test]
+    fn unrelated_changes_keep_the_embedding() {
+        let request = update_request(serde_json::json!({ "accepts_offers": true }));
+        assert!(!embedding_needs_refresh(&request, false));
+        assert!(embedding_needs_refresh(&request, true));
+
+        let update = product_update_document(
+            request,
+            None,
+            1_700_000_000,
+            None,
+            EmbeddingRefresh::Unchanged,
+        );
+        let set = update.get_document("$set").unwrap();
+        assert!(!set.contains_key("embedding"));
+        assert!(!set.contains_key("preprocess_version"));
+        assert_eq!(set.get_bool("accepts_offers"), Ok(true));
+    }
+}
@@ -1,32 +1,42 @@
 use axum::{
     Json,
-    extract::{Extension, Multipart, Path, Query},
-    http::StatusCode,
+    body::{Body, to_bytes},
+    extract::{ConnectInfo, Extension, FromRequest, Multipart, Path, Query},
+    http::{
+        HeaderMap, Request, StatusCode,
+        header::{CONTENT_TYPE, USER_AGENT},
+    },
     response::IntoResponse,
 };
 use bytes::Bytes;
 use serde_json::{Value, json};
+use std::net::SocketAddr;
 
 use super::{
     delegates::{
-        add_gallery_items, buy_now_product, create_product, delete_product,
-        generate_questions_with_groq, get_gallery, get_product_by_id, get_user_product_by_id,
-        is_allowed_content_type, is_allowed_image_type, list_user_products, reorder_gallery,
-        replace_gallery, set_product_questions, update_product,
+        accept_offer, add_gallery_items, buy_now_product, create_offer, create_product,
+        delete_gallery_item, delete_product, generate_questions_with_groq, get_gallery,
+        get_product_by_id, get_product_by_slug, get_user_category_counts, get_user_product_by_id,
+        is_allowed_content_type, is_allowed_image_type, list_seller_offers, list_user_products,
+        publish_product, record_product_view, reject_offer, reorder_gallery, replace_gallery,
+        set_product_questions, start_reembed_job, start_stale_reembed_job,
+        suggest_product_category, update_product,
     },
     schemas::{
-        BuyNowRequest, CreateProductRequest, DEFAULT_PAGE_LIMIT, GenerateQuestionsPayload,
-        GenerateQuestionsRequest, ListMyProductsQuery, MAX_FILE_SIZE, MAX_GALLERY_ITEMS,
-        MAX_PAGE_LIMIT, ProductQuestions, ReorderGalleryRequest, UpdateProductRequest,
+        BuyNowRequest, CreateOfferRequest, CreateProductRequest, DEFAULT_PAGE_LIMIT,
+        GenerateQuestionsPayload, GenerateQuestionsRequest, ListMyProductsQuery, ListOffersQuery,
+        MAX_FILE_SIZE, MAX_GALLERY_ITEMS, Product, ProductQuestions, ReorderGalleryRequest,
+        UpdateProductRequest,
     },
 };
 use crate::{
-    DB,
-    apex::utils::VerboseHTTPError,
-    auth::schemas::UserOut,
-    recommendations::{auto_log_signal, schemas::SignalType},
+    apex::{
+        config::config,
+        utils::{VerboseHTTPError, client_ip},
+    },
+    auth::{endpoints::optional_user, schemas::UserOut},
+    recommendations::{auto_log_signal, delegates::record_recently_viewed, schemas::SignalType},
 };
-use mongodb::{Collection, bson::doc};
 
 #[inline]
 fn strip_embedding_from_product(mut product_value: Value) -> Value {
@@ -57,10 +67,11 @@ pub(crate) async fn create_product_endpoint(
                 if let Some(file_name) = field.file_name() {
                     let file_name = file_name.to_string();
                     let content_type = field.content_type().unwrap_or("image/jpeg").to_string();
-                    if let Ok(bytes) = field.bytes().await {
-                        if is_allowed_image_type(&content_type) && bytes.len() <= MAX_FILE_SIZE {
-                            thumbnail_file = Some((file_name, bytes, content_type));
-                        }
+                    if let Ok(bytes) = field.bytes().await
+                        && is_allowed_image_type(&content_type)
+                        && bytes.len() <= MAX_FILE_SIZE
+                    {
+                        thumbnail_file = Some((file_name, bytes, content_type));
                     }
                 }
             }
@@ -71,10 +82,11 @@ pub(crate) async fn create_product_endpoint(
                         .content_type()
                         .unwrap_or("application/octet-stream")
                         .to_string();
-                    if let Ok(bytes) = field.bytes().await {
-                        if is_allowed_content_type(&content_type) && bytes.len() <= MAX_FILE_SIZE {
-                            gallery_files.push((file_name, bytes, content_type));
-                        }
+                    if let Ok(bytes) = field.bytes().await
+                        && is_allowed_content_type(&content_type)
+                        && bytes.len() <= MAX_FILE_SIZE
+                    {
+                        gallery_files.push((file_name, bytes, content_type));
                     }
                 }
             }
@@ -121,48 +133,48 @@ pub(crate) async fn get_product_endpoint(
     headers: axum::http::HeaderMap,
     user: Option<Extension<UserOut>>,
 ) -> impl IntoResponse {
-    match get_product_by_id(&product_id).await {
+    product_detail_response(get_product_by_id(&product_id).await, &headers, user).await
+}
+
+pub(crate) async fn get_product_by_slug_endpoint(
+    Path(slug): Path<String>,
+    headers: axum::http::HeaderMap,
+    user: Option<Extension<UserOut>>,
+) -> impl IntoResponse {
+    product_detail_response(get_product_by_slug(&slug).await, &headers, user).await
+}
+
+async fn product_detail_response(
+    product: Result<Product, VerboseHTTPError>,
+    headers: &axum::http::HeaderMap,
+    user: Option<Extension<UserOut>>,
+) -> axum::response::Response {
+    match product {
         Ok(product) => {
-            if let Some(Extension(user)) = user {
+            let viewer = match user {
+                Some(Extension(user)) => Some(user),
+                None => optional_user(headers).await,
+            };
+
+            if let Some(user) = viewer {
                 auto_log_signal(
                     &user.uid,
                     SignalType::ProductView,
-                    product.category.clone(),
-                    Some(product_id.clone()),
+                    product.category,
+                    Some(product.product_id.clone()),
                     None,
                 )
                 .await;
-            } 
-            else if let Some(cookie_header) = headers.get(axum::http::header::COOKIE) {
-                if let Ok(cookie_str) = cookie_header.to_str() {
-                    let mut auth_cookie = None;
-                    for cookie_part in cookie_str.split(';') {
-                        let cookie_part = cookie_part.trim();
-                        if cookie_part.starts_with("GOODSPOINT_AUTHENTICATION=") {
-                            auth_cookie = Some(cookie_part.split('=').nth(1).unwrap_or(""));
-                            break;
-                        }
-                    }
-                    
-                    if let Some(cookie_value) = auth_cookie {
-                        if let Some(database) = DB.get() {
-                            let collection: Collection<UserOut> = database.collection("users");
-                            let user_result = collection
-                                .find_one(doc! {"auth.cookie": cookie_value})
-                                .await;
-                                
-                            if let Ok(Some(user)) = user_result {
-                                auto_log_signal(
-                                    &user.uid,
-                                    SignalType::ProductView,
-                                    product.category.clone(),
-                                    Some(product_id.clone()),
-                                    None,
-                                )
-                                .await;
-                            }
-                        }
-                    }
+
+                if product.user_id != user.uid
+                    && let Err(error) =
+                        record_recently_viewed(&user.uid, &product.product_id, &product.title).await
+                {
+                    tracing::warn!(
+                        "Failed to record recently viewed product {}: {:?}",
+                        product.product_id,
+                        error
+                    );
                 }
             }
 
@@ -175,10 +187,29 @@ pub(crate) async fn get_product_endpoint(
             }))
             .into_response()
         }
-        Err(_) => {
-            VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "Product not found".to_string())
-                .into_response()
-        }
+        Err(_) => VerboseHTTPError::product_not_found().into_response(),
+    }
+}
+
+pub(crate) async fn record_product_view_endpoint(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Path(product_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let user_agent = headers
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok());
+
+    let user = optional_user(&headers).await;
+    let client_ip = Some(client_ip(&headers, peer));
+
+    match record_product_view(&product_id, user_agent, user.as_ref(), client_ip).await {
+        Ok(counted) => Json(json!({
+            "status": "ok",
+            "counted": counted
+        }))
+        .into_response(),
+        Err(err) => err.into_response(),
     }
 }
 
@@ -191,7 +222,7 @@ pub(crate) async fn get_user_product_endpoint(
             auto_log_signal(
                 &user.uid,
                 SignalType::ProductView,
-                product.category.clone(),
+                product.category,
                 Some(product_id.clone()),
                 None,
             )
@@ -213,9 +244,70 @@ pub(crate) async fn get_user_product_endpoint(
 pub(crate) async fn update_product_endpoint(
     Extension(user): Extension<UserOut>,
     Path(product_id): Path<String>,
-    body: String,
+    request: Request<Body>,
 ) -> impl IntoResponse {
-    let payload: UpdateProductRequest = match serde_json::from_str(&body) {
+    let is_multipart = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("multipart/form-data"));
+
+    let mut product_data = String::new();
+    let mut thumbnail_file: Option<(String, Bytes, String)> = None;
+
+    if is_multipart {
+        let Ok(mut multipart) = Multipart::from_request(request, &()).await else {
+            return VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                "Invalid multipart body".to_string(),
+            )
+            .into_response();
+        };
+
+        while let Ok(Some(field)) = multipart.next_field().await {
+            match field.name().unwrap_or("") {
+                "product" => {
+                    if let Ok(bytes) = field.bytes().await {
+                        product_data = String::from_utf8_lossy(&bytes).to_string();
+                    }
+                }
+                "thumbnail" => {
+                    if let Some(file_name) = field.file_name() {
+                        let file_name = file_name.to_string();
+                        let content_type = field.content_type().unwrap_or("image/jpeg").to_string();
+                        if let Ok(bytes) = field.bytes().await {
+                            if bytes.len() > MAX_FILE_SIZE {
+                                return VerboseHTTPError::Standard(
+                                    StatusCode::BAD_REQUEST,
+                                    "Thumbnail file is too large".to_string(),
+                                )
+                                .into_response();
+                            }
+                            thumbnail_file = Some((file_name, bytes, content_type));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    } else {
+        match to_bytes(request.into_body(), MAX_FILE_SIZE).await {
+            Ok(bytes) => product_data = String::from_utf8_lossy(&bytes).to_string(),
+            Err(_) => {
+                return VerboseHTTPError::Standard(
+                    StatusCode::BAD_REQUEST,
+                    "Invalid request body".to_string(),
+                )
+                .into_response();
+            }
+        }
+    }
+
+    if product_data.trim().is_empty() {
+        product_data = "{}".to_string();
+    }
+
+    let payload: UpdateProductRequest = match serde_json::from_str(&product_data) {
         Ok(data) => data,
         Err(e) => {
             return VerboseHTTPError::Standard(
@@ -226,7 +318,26 @@ pub(crate) async fn update_product_endpoint(
         }
     };
 
-    match update_product(&user, &product_id, payload, None).await {
+    match update_product(&user, &product_id, payload, thumbnail_file).await {
+        Ok(product) => {
+            let product_json = serde_json::to_value(&product).unwrap();
+            let clean_product = strip_embedding_from_product(product_json);
+
+            Json(json!({
+                "status": "ok",
+                "product": clean_product
+            }))
+            .into_response()
+        }
+        Err(err) => err.into_response(),
+    }
+}
+
+pub(crate) async fn publish_product_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(product_id): Path<String>,
+) -> impl IntoResponse {
+    match publish_product(&user, &product_id).await {
         Ok(product) => {
             let product_json = serde_json::to_value(&product).unwrap();
             let clean_product = strip_embedding_from_product(product_json);
@@ -266,7 +377,7 @@ pub(crate) async fn list_my_products_endpoint(
     let limit = params
         .limit
         .unwrap_or(DEFAULT_PAGE_LIMIT)
-        .min(MAX_PAGE_LIMIT);
+        .min(config().max_page_limit);
     let offset = params.offset.unwrap_or(0);
 
     match list_user_products(&user, limit, offset).await {
@@ -283,6 +394,19 @@ pub(crate) async fn list_my_products_endpoint(
     }
 }
 
+pub(crate) async fn get_category_counts_endpoint(
+    Extension(user): Extension<UserOut>,
+) -> impl IntoResponse {
+    match get_user_category_counts(&user).await {
+        Ok(categories) => Json(json!({
+            "status": "ok",
+            "categories": categories
+        }))
+        .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
 pub(crate) async fn generate_questions_endpoint(
     Extension(user): Extension<UserOut>,
     Path(product_id): Path<String>,
@@ -314,6 +438,20 @@ pub(crate) async fn generate_questions_endpoint(
     }
 }
 
+pub(crate) async fn suggest_category_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(product_id): Path<String>,
+) -> impl IntoResponse {
+    match suggest_product_category(&user, &product_id).await {
+        Ok(suggestion) => Json(json!({
+            "status": "ok",
+            "suggestion": suggestion
+        }))
+        .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
 pub(crate) async fn get_gallery_endpoint(
     Extension(user): Extension<UserOut>,
     Path(product_id): Path<String>,
@@ -342,18 +480,19 @@ pub(crate) async fn replace_gallery_endpoint(
     while let Ok(Some(field)) = multipart.next_field().await {
         let field_name = field.name().unwrap_or("").to_string();
 
-        if field_name == "gallery" {
-            if let Some(file_name) = field.file_name() {
-                let file_name = file_name.to_string();
-                let content_type = field
-                    .content_type()
-                    .unwrap_or("application/octet-stream")
-                    .to_string();
-                if let Ok(bytes) = field.bytes().await {
-                    if is_allowed_content_type(&content_type) && bytes.len() <= MAX_FILE_SIZE {
-                        gallery_files.push((file_name, bytes, content_type));
-                    }
-                }
+        if field_name == "gallery"
+            && let Some(file_name) = field.file_name()
+        {
+            let file_name = file_name.to_string();
+            let content_type = field
+                .content_type()
+                .unwrap_or("application/octet-stream")
+                .to_string();
+            if let Ok(bytes) = field.bytes().await
+                && is_allowed_content_type(&content_type)
+                && bytes.len() <= MAX_FILE_SIZE
+            {
+                gallery_files.push((file_name, bytes, content_type));
             }
         }
     }
@@ -393,18 +532,19 @@ pub(crate) async fn add_gallery_items_endpoint(
     while let Ok(Some(field)) = multipart.next_field().await {
         let field_name = field.name().unwrap_or("").to_string();
 
-        if field_name == "gallery" {
-            if let Some(file_name) = field.file_name() {
-                let file_name = file_name.to_string();
-                let content_type = field
-                    .content_type()
-                    .unwrap_or("application/octet-stream")
-                    .to_string();
-                if let Ok(bytes) = field.bytes().await {
-                    if is_allowed_content_type(&content_type) && bytes.len() <= MAX_FILE_SIZE {
-                        gallery_files.push((file_name, bytes, content_type));
-                    }
-                }
+        if field_name == "gallery"
+            && let Some(file_name) = field.file_name()
+        {
+            let file_name = file_name.to_string();
+            let content_type = field
+                .content_type()
+                .unwrap_or("application/octet-stream")
+                .to_string();
+            if let Ok(bytes) = field.bytes().await
+                && is_allowed_content_type(&content_type)
+                && bytes.len() <= MAX_FILE_SIZE
+            {
+                gallery_files.push((file_name, bytes, content_type));
             }
         }
     }
@@ -460,6 +600,20 @@ pub(crate) async fn reorder_gallery_endpoint(
     }
 }
 
+pub(crate) async fn delete_gallery_item_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path((product_id, item_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    match delete_gallery_item(&user, &product_id, &item_id).await {
+        Ok(gallery) => Json(json!({
+            "status": "ok",
+            "gallery": gallery
+        }))
+        .into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
 pub(crate) async fn get_questions_endpoint(
     Extension(user): Extension<UserOut>,
     Path(product_id): Path<String>,
@@ -509,8 +663,82 @@ pub async fn buy_now_endpoint(
     Extension(user): Extension<UserOut>,
     Json(request): Json<BuyNowRequest>,
 ) -> impl IntoResponse {
-    match buy_now_product(&user, request.product_id, request.quantity).await {
+    match buy_now_product(
+        &user,
+        request.product_id,
+        request.quantity,
+        &request.address_id,
+    )
+    .await
+    {
         Ok(order) => Json(order).into_response(),
         Err(error) => error.into_response(),
     }
 }
+
+pub async fn create_offer_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(product_id): Path<String>,
+    Json(request): Json<CreateOfferRequest>,
+) -> impl IntoResponse {
+    match create_offer(&user, &product_id, request).await {
+        Ok(offer) => Json(offer).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn list_seller_offers_endpoint(
+    Extension(user): Extension<UserOut>,
+    Query(query): Query<ListOffersQuery>,
+) -> impl IntoResponse {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .min(config().max_page_limit);
+    let offset = query.offset.unwrap_or(0);
+
+    match list_seller_offers(&user, query.status, limit, offset).await {
+        Ok(offers) => Json(json!({
+            "status": "ok",
+            "offers": offers
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn accept_offer_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(offer_id): Path<String>,
+) -> impl IntoResponse {
+    match accept_offer(&user, &offer_id).await {
+        Ok(order) => Json(order).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn reject_offer_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(offer_id): Path<String>,
+) -> impl IntoResponse {
+    match reject_offer(&user, &offer_id).await {
+        Ok(offer) => Json(offer).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn reembed_catalog_endpoint(Extension(user): Extension<UserOut>) -> impl IntoResponse {
+    match start_reembed_job(&user).await {
+        Ok(job) => (StatusCode::ACCEPTED, Json(job)).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn reembed_stale_products_endpoint(
+    Extension(user): Extension<UserOut>,
+) -> impl IntoResponse {
+    match start_stale_reembed_job(&user).await {
+        Ok(job) => (StatusCode::ACCEPTED, Json(job)).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
@@ -1,16 +1,40 @@
 use serde::{Deserialize, Serialize};
 
+use crate::auth::schemas::ShippingAddress;
+
 pub const MAX_TITLE_LENGTH: usize = 200;
 pub const MAX_DESCRIPTION_LENGTH: usize = 2000;
 pub const MAX_QUESTIONS_COUNT: usize = 12;
 pub const MAX_QUESTION_LENGTH: usize = 1300;
 pub const MAX_TAGS_COUNT: usize = 32;
 pub const MAX_TAG_LENGTH: usize = 50;
+pub const MAX_ATTRIBUTES_COUNT: usize = 30;
+pub const MAX_ATTRIBUTE_KEY_LENGTH: usize = 50;
+pub const MAX_ATTRIBUTE_VALUE_LENGTH: usize = 200;
 pub const MAX_GALLERY_ITEMS: usize = 6;
 pub const MAX_FILE_SIZE: usize = 50 * 1024 * 1024;
 pub const DEFAULT_PAGE_LIMIT: u32 = 20;
 pub const MAX_PAGE_LIMIT: u32 = 100;
 pub const AI_MAX_TOKENS: u32 = 2048;
+pub const COLLECTIONS_PENDING_UNPINS: &str = "pending_unpins";
+pub const MAX_UNPIN_ATTEMPTS: u32 = 10;
+pub const DEFAULT_UNPIN_INTERVAL_SECS: u64 = 300;
+pub const MAX_SLUG_TITLE_LENGTH: usize = 60;
+pub const SLUG_SHORT_ID_LENGTH: usize = 8;
+pub const MAX_PRICE: f64 = 1_000_000_000.0;
+pub const COLLECTIONS_OFFERS: &str = "offers";
+pub const REEMBED_COOLDOWN_SECS: u64 = 3600;
+pub const STALE_REEMBED_COOLDOWN_SECS: u64 = 600;
+pub const STALE_REEMBED_BATCH_SIZE: u32 = 50;
+pub const DEFAULT_EMBEDDING_MAX_IMAGES: usize = 4;
+pub const DEFAULT_MAX_PRODUCT_QUANTITY: u32 = 10_000;
+pub const COLLECTIONS_PRODUCT_VIEWS: &str = "product_views";
+pub const PRODUCT_VIEW_DEDUP_WINDOW_SECS: u64 = 1800;
+// Must outlast the dedup and trending windows.
+pub const PRODUCT_VIEW_RETENTION_SECS: u64 = 30 * 24 * 60 * 60;
+pub const PRODUCT_CREATION_WINDOW_SECS: u64 = 3600;
+pub const DEFAULT_PRODUCT_CREATION_RATE_LIMIT: u64 = 20;
+pub const DEFAULT_MAX_ACTIVE_LISTINGS: u64 = 500;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -26,6 +50,15 @@ pub enum PurchaseType {
     Inquire,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductStatus {
+    Draft,
+    #[default]
+    Published,
+    Archived,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "PascalCase")]
 pub enum ProductCategory {
@@ -82,6 +115,62 @@ pub enum ProductCategory {
     Other,
 }
 
+impl ProductCategory {
+    pub const ALL: [ProductCategory; 51] = [
+        ProductCategory::Smartphones,
+        ProductCategory::Computers,
+        ProductCategory::Audio,
+        ProductCategory::Cameras,
+        ProductCategory::Gaming,
+        ProductCategory::Wearables,
+        ProductCategory::HomeElectronics,
+        ProductCategory::MensClothing,
+        ProductCategory::WomensClothing,
+        ProductCategory::UnisexClothing,
+        ProductCategory::Shoes,
+        ProductCategory::Accessories,
+        ProductCategory::Jewelry,
+        ProductCategory::Bags,
+        ProductCategory::Beauty,
+        ProductCategory::Furniture,
+        ProductCategory::HomeDecor,
+        ProductCategory::Kitchen,
+        ProductCategory::Garden,
+        ProductCategory::HomeTools,
+        ProductCategory::HomeImprovement,
+        ProductCategory::FitnessEquipment,
+        ProductCategory::OutdoorGear,
+        ProductCategory::SportsEquipment,
+        ProductCategory::Bicycles,
+        ProductCategory::WaterSports,
+        ProductCategory::WinterSports,
+        ProductCategory::CarParts,
+        ProductCategory::Motorcycles,
+        ProductCategory::AutoTools,
+        ProductCategory::CarAccessories,
+        ProductCategory::Books,
+        ProductCategory::Music,
+        ProductCategory::Movies,
+        ProductCategory::VideoGames,
+        ProductCategory::HealthEquipment,
+        ProductCategory::PersonalCare,
+        ProductCategory::Supplements,
+        ProductCategory::MedicalDevices,
+        ProductCategory::BabyClothing,
+        ProductCategory::Toys,
+        ProductCategory::BabyGear,
+        ProductCategory::KidsElectronics,
+        ProductCategory::Collectibles,
+        ProductCategory::Antiques,
+        ProductCategory::Art,
+        ProductCategory::Crafts,
+        ProductCategory::OfficeSupplies,
+        ProductCategory::IndustrialEquipment,
+        ProductCategory::BusinessEquipment,
+        ProductCategory::Other,
+    ];
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum QuestionType {
@@ -112,12 +201,26 @@ pub struct GalleryItem {
     pub upload_timestamp: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PendingUnpin {
+    pub hash: String,
+    pub reason: String,
+    pub attempts: u32,
+    pub created_at: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ProductQuantity {
     pub min_quantity: u32,
     pub max_quantity: u32,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProductAttribute {
+    pub key: String,
+    pub value: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Product {
     pub product_id: String,
@@ -129,16 +232,44 @@ pub struct Product {
     pub purchase_type: PurchaseType,
     pub category: ProductCategory,
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub attributes: Vec<ProductAttribute>,
     pub quantity: ProductQuantity,
+    #[serde(default)]
+    pub available_quantity: Option<u32>,
+    #[serde(default)]
+    pub available_from: Option<u64>,
+    #[serde(default)]
+    pub available_until: Option<u64>,
     pub price: f64,
+    #[serde(default)]
+    pub price_in_paise: Option<u64>,
+    #[serde(default)]
+    pub accepts_offers: bool,
     pub custom_questions: Option<ProductQuestions>,
     #[serde(default)]
     pub gallery: Vec<GalleryItem>,
     pub thumbnail_url: Option<String>,
     pub embedding: Option<Vec<f32>>,
+    #[serde(default)]
+    pub preprocess_version: u32,
     pub created_at: u64,
     pub updated_at: u64,
     pub enabled: bool,
+    #[serde(default)]
+    pub status: ProductStatus,
+    #[serde(default)]
+    pub view_count: u64,
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub avg_rating: f64,
+    #[serde(default)]
+    pub review_count: u64,
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub city: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -150,8 +281,15 @@ pub struct CreateProductRequest {
     pub category: ProductCategory,
     #[serde(default)]
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub attributes: Vec<ProductAttribute>,
     pub quantity: ProductQuantity,
+    pub available_quantity: Option<u32>,
+    pub available_from: Option<u64>,
+    pub available_until: Option<u64>,
     pub price: f64,
+    #[serde(default)]
+    pub accepts_offers: bool,
     pub custom_questions: Option<ProductQuestions>,
 }
 
@@ -163,8 +301,13 @@ pub struct UpdateProductRequest {
     pub purchase_type: Option<PurchaseType>,
     pub category: Option<ProductCategory>,
     pub tags: Option<Vec<String>>,
+    pub attributes: Option<Vec<ProductAttribute>>,
     pub quantity: Option<ProductQuantity>,
+    pub available_quantity: Option<u32>,
+    pub available_from: Option<u64>,
+    pub available_until: Option<u64>,
     pub price: Option<f64>,
+    pub accepts_offers: Option<bool>,
     pub custom_questions: Option<ProductQuestions>,
 }
 
@@ -176,9 +319,29 @@ pub struct ProductListItem {
     pub quantity: ProductQuantity,
     pub created_at: u64,
     pub enabled: bool,
+    pub status: ProductStatus,
+    pub view_count: u64,
+    pub avg_rating: f64,
+    pub review_count: u64,
     pub thumbnail_url: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductView {
+    pub product_id: String,
+    pub viewer: String,
+    pub created_at: u64,
+    #[serde(default)]
+    pub expires_at: Option<mongodb::bson::DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryCount {
+    #[serde(alias = "_id")]
+    pub category: ProductCategory,
+    pub count: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GenerateQuestionsRequest {
     pub product_id: String,
@@ -259,11 +422,25 @@ pub struct GroqToolCallFunction {
     pub arguments: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategorySuggestion {
+    pub current_category: ProductCategory,
+    pub suggested_category: ProductCategory,
+    pub confidence: f32,
+    pub reason: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClipCombinedRequest {
     pub text: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClipMultiImageRequest {
+    pub text: String,
+    pub image_urls: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClipEmbeddingResponse {
     pub embedding: Vec<f32>,
@@ -284,7 +461,19 @@ pub struct ListMyProductsQuery {
 #[serde(rename_all = "snake_case")]
 pub enum OrderStatus {
     Unpaid,
+    Cancelled,
     DeliveryPending,
+    Shipped,
+    Delivered,
+    Refunded,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrderStatusChange {
+    pub from: OrderStatus,
+    pub to: OrderStatus,
+    pub changed_by: String,
+    pub changed_at: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -295,7 +484,16 @@ pub struct Order {
     pub buyer_id: String,
     pub quantity: u32,
     pub price: f64,
+    #[serde(default)]
+    pub price_in_paise: Option<u64>,
     pub status: OrderStatus,
+    pub seller_note: Option<String>,
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub status_history: Vec<OrderStatusChange>,
+    #[serde(default)]
+    pub shipping_address: Option<ShippingAddress>,
     pub created_at: u64,
     pub updated_at: u64,
 }
@@ -304,6 +502,7 @@ pub struct Order {
 pub struct BuyNowRequest {
     pub product_id: String,
     pub quantity: u32,
+    pub address_id: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -314,6 +513,7 @@ pub struct ConfirmOrderRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateOrderFromQuoteRequest {
     pub message_id: String,
+    pub address_id: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -324,13 +524,82 @@ pub struct OrderResponse {
     pub buyer_id: String,
     pub quantity: u32,
     pub price: f64,
+    #[serde(default)]
+    pub price_in_paise: Option<u64>,
     pub status: OrderStatus,
+    pub seller_note: Option<String>,
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub status_history: Vec<OrderStatusChange>,
+    #[serde(default)]
+    pub shipping_address: Option<ShippingAddress>,
     pub created_at: u64,
     pub updated_at: u64,
 }
 
+impl From<Order> for OrderResponse {
+    fn from(order: Order) -> Self {
+        Self {
+            order_id: order.order_id,
+            product_id: order.product_id,
+            seller_id: order.seller_id,
+            buyer_id: order.buyer_id,
+            quantity: order.quantity,
+            price: order.price,
+            price_in_paise: order.price_in_paise,
+            status: order.status,
+            seller_note: order.seller_note,
+            region: order.region,
+            status_history: order.status_history,
+            shipping_address: order.shipping_address,
+            created_at: order.created_at,
+            updated_at: order.updated_at,
+        }
+    }
+}
+
 #[derive(serde::Deserialize, Default)]
 pub struct ListOrdersQuery {
     pub limit: Option<u32>,
     pub offset: Option<u32>,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OfferStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Offer {
+    pub offer_id: String,
+    pub product_id: String,
+    pub seller_id: String,
+    pub buyer_id: String,
+    pub quantity: u32,
+    pub price: f64,
+    pub status: OfferStatus,
+    pub order_id: Option<String>,
+    // The buyer's saved address; it is snapshotted onto the order on accept.
+    #[serde(default)]
+    pub address_id: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateOfferRequest {
+    pub price: f64,
+    pub quantity: u32,
+    pub address_id: String,
+}
+
+#[derive(serde::Deserialize, Default)]
+pub struct ListOffersQuery {
+    pub status: Option<OfferStatus>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
@@ -1,27 +1,42 @@
 use axum::http::StatusCode;
 use futures::TryStreamExt;
 use mongodb::{
-    Collection,
-    bson::{DateTime as BsonDateTime, doc},
+    Collection, IndexModel,
+    bson::{Bson, DateTime as BsonDateTime, Document, doc, from_document, oid::ObjectId},
+    error::{ErrorKind, WriteFailure},
+    options::IndexOptions,
 };
-use rand::seq::SliceRandom;
-use std::collections::HashMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use super::schemas::*;
 use crate::{
     DB,
-    apex::utils::VerboseHTTPError,
+    apex::{
+        config::config,
+        utils::{VerboseHTTPError, mark_migration_done, migration_done, normalize_embedding},
+    },
     auth::schemas::UserOut,
-    products::schemas::{Product, ProductCategory},
+    orders::schemas::COLLECTIONS_ORDERS,
+    products::{
+        delegates::{buyable_products_filter, get_product_by_id},
+        schemas::{COLLECTIONS_PRODUCT_VIEWS, Product, ProductCategory, ProductView},
+    },
+    search::vector_search_stage,
 };
 
+static CATEGORY_RELATIONSHIPS: LazyLock<RwLock<(bool, Arc<Vec<CategoryRelationship>>)>> =
+    LazyLock::new(|| RwLock::new((false, Arc::new(default_category_relationships()))));
+static CATEGORY_RELATIONSHIPS_LOADED_AT: RwLock<Option<Instant>> = RwLock::new(None);
+
 impl SignalType {
     pub fn boost_value(&self) -> f64 {
         match self {
             SignalType::Query => TIER_1_BOOST,
             SignalType::ProductView => TIER_2_BOOST,
             SignalType::Search => TIER_3_BOOST,
+            SignalType::Purchase => PURCHASE_BOOST,
         }
     }
 
@@ -30,13 +45,460 @@ impl SignalType {
             SignalType::Query => TIER_1_DECAY,
             SignalType::ProductView => TIER_2_DECAY,
             SignalType::Search => TIER_3_DECAY,
+            SignalType::Purchase => PURCHASE_DECAY,
+        }
+    }
+}
+
+pub fn category_relationships() -> Arc<Vec<CategoryRelationship>> {
+    CATEGORY_RELATIONSHIPS.read().unwrap().1.clone()
+}
+
+// Reloads the cache once it is older than the TTL so edits made by another API
+// instance are picked up. A failed reload keeps serving the cached list and
+// waits a full TTL before retrying rather than hitting Mongo on every signal.
+pub async fn fresh_category_relationships() -> Arc<Vec<CategoryRelationship>> {
+    let ttl = Duration::from_secs(CATEGORY_RELATIONSHIPS_CACHE_TTL_SECS);
+    let stale = CATEGORY_RELATIONSHIPS_LOADED_AT
+        .read()
+        .unwrap()
+        .is_none_or(|loaded_at| loaded_at.elapsed() >= ttl);
+
+    if stale
+        && let Err(VerboseHTTPError::Standard(_, message) | VerboseHTTPError::Coded(_, _, message)) =
+            reload_category_relationships().await
+    {
+        tracing::warn!("Failed to refresh category relationships: {}", message);
+        *CATEGORY_RELATIONSHIPS_LOADED_AT.write().unwrap() = Some(Instant::now());
+    }
+
+    category_relationships()
+}
+
+pub async fn ensure_category_relationship_indexes() {
+    let Ok(collection) = relationships_collection() else {
+        return;
+    };
+
+    let index = IndexModel::builder()
+        .keys(doc! { "category_a": 1, "category_b": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+
+    if let Err(error) = collection.create_index(index).await {
+        tracing::warn!("Failed to create category relationship index: {}", error);
+    }
+}
+
+async fn load_category_relationships(
+    collection: &Collection<CategoryRelationship>,
+) -> Result<Vec<CategoryRelationship>, VerboseHTTPError> {
+    collection
+        .find(doc! {})
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })
+}
+
+pub async fn reload_category_relationships() -> Result<usize, VerboseHTTPError> {
+    const MIGRATION: &str = "seed_category_relationships";
+
+    let collection = relationships_collection()?;
+
+    let mut stored = load_category_relationships(&collection).await?;
+
+    // The built-in list is persisted once so admins edit real documents from
+    // then on. After that an empty collection means they deleted every
+    // relationship, so it is not seeded again.
+    let marked = migration_done(MIGRATION).await;
+    let mut seeded = marked || !stored.is_empty();
+
+    if !seeded {
+        match seed_default_relationships(&collection).await {
+            Ok(_) => {
+                stored = load_category_relationships(&collection).await?;
+                seeded = true;
+            }
+            Err(
+                VerboseHTTPError::Standard(_, message) | VerboseHTTPError::Coded(_, _, message),
+            ) => {
+                tracing::warn!("Failed to seed category relationships: {}", message);
+            }
+        }
+    }
+
+    if seeded && !marked {
+        mark_migration_done(MIGRATION).await;
+    }
+
+    let (from_database, relationships) = if seeded {
+        (true, stored)
+    } else {
+        (false, default_category_relationships())
+    };
+    let count = relationships.len();
+
+    *CATEGORY_RELATIONSHIPS.write().unwrap() = (from_database, Arc::new(relationships));
+    *CATEGORY_RELATIONSHIPS_LOADED_AT.write().unwrap() = Some(Instant::now());
+
+    Ok(count)
+}
+
+pub fn list_category_relationships() -> CategoryRelationshipsResponse {
+    let (from_database, relationships) = CATEGORY_RELATIONSHIPS.read().unwrap().clone();
+
+    CategoryRelationshipsResponse {
+        from_database,
+        relationships: relationships
+            .iter()
+            .map(CategoryRelationshipOut::from)
+            .collect(),
+    }
+}
+
+fn validate_relationship_request(
+    request: &CategoryRelationshipRequest,
+) -> Result<(), VerboseHTTPError> {
+    if request.category_a == request.category_b {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "A category cannot be related to itself".to_string(),
+        ));
+    }
+
+    if !request.relationship_strength.is_finite()
+        || request.relationship_strength <= 0.0
+        || request.relationship_strength > MAX_RELATIONSHIP_STRENGTH
+    {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Relationship strength must be greater than 0 and at most {}",
+                MAX_RELATIONSHIP_STRENGTH
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn relationship_pair_filter(category_a: &str, category_b: &str) -> Document {
+    doc! {
+        "$or": [
+            { "category_a": category_a, "category_b": category_b },
+            { "category_a": category_b, "category_b": category_a }
+        ]
+    }
+}
+
+fn parse_relationship_id(relationship_id: &str) -> Result<ObjectId, VerboseHTTPError> {
+    ObjectId::parse_str(relationship_id).map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Invalid relationship ID".to_string(),
+        )
+    })
+}
+
+fn relationships_collection() -> Result<Collection<CategoryRelationship>, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    Ok(database.collection(COLLECTIONS_CATEGORY_RELATIONSHIPS))
+}
+
+// Inserts each default pair that doesn't exist yet, in either direction.
+// Instances seeding at the same time race on the unique index, and the loser
+// counts the pair as skipped. Returns (inserted, skipped).
+async fn seed_default_relationships(
+    collection: &Collection<CategoryRelationship>,
+) -> Result<(usize, usize), VerboseHTTPError> {
+    let mut inserted = 0;
+    let mut skipped = 0;
+
+    for relationship in default_category_relationships() {
+        let category_a = format!("{:?}", relationship.category_a);
+        let category_b = format!("{:?}", relationship.category_b);
+
+        let result = collection
+            .update_one(
+                relationship_pair_filter(&category_a, &category_b),
+                doc! {
+                    "$setOnInsert": {
+                        "category_a": &category_a,
+                        "category_b": &category_b,
+                        "relationship_strength": relationship.relationship_strength,
+                        "bidirectional": relationship.bidirectional
+                    }
+                },
+            )
+            .upsert(true)
+            .await;
+
+        match result {
+            Ok(result) if result.upserted_id.is_some() => inserted += 1,
+            Ok(_) => skipped += 1,
+            Err(error)
+                if matches!(
+                    *error.kind,
+                    ErrorKind::Write(WriteFailure::WriteError(ref write_error))
+                        if write_error.code == 11000
+                ) =>
+            {
+                skipped += 1
+            }
+            Err(_) => {
+                return Err(VerboseHTTPError::Standard(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to seed relationship".to_string(),
+                ));
+            }
+        }
+    }
+
+    Ok((inserted, skipped))
+}
+
+pub async fn seed_category_relationships() -> Result<SeedRelationshipsResponse, VerboseHTTPError> {
+    let collection = relationships_collection()?;
+
+    let (inserted, skipped) = seed_default_relationships(&collection).await?;
+
+    reload_category_relationships().await?;
+
+    Ok(SeedRelationshipsResponse { inserted, skipped })
+}
+
+pub async fn get_category_relationship(
+    relationship_id: &str,
+) -> Result<CategoryRelationshipOut, VerboseHTTPError> {
+    let id = parse_relationship_id(relationship_id)?;
+    let collection = relationships_collection()?;
+
+    let relationship = collection.find_one(doc! { "_id": id }).await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
+    })?;
+
+    match relationship {
+        Some(relationship) => Ok(CategoryRelationshipOut::from(&relationship)),
+        None => Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "Relationship not found".to_string(),
+        )),
+    }
+}
+
+pub async fn update_category_relationship(
+    relationship_id: &str,
+    request: CategoryRelationshipRequest,
+) -> Result<CategoryRelationshipOut, VerboseHTTPError> {
+    validate_relationship_request(&request)?;
+
+    let id = parse_relationship_id(relationship_id)?;
+    let collection = relationships_collection()?;
+
+    let category_a = format!("{:?}", request.category_a);
+    let category_b = format!("{:?}", request.category_b);
+
+    let mut conflict_filter = relationship_pair_filter(&category_a, &category_b);
+    conflict_filter.insert("_id", doc! { "$ne": id });
+
+    let conflicting = collection
+        .count_documents(conflict_filter)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    if conflicting > 0 {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::CONFLICT,
+            "A relationship between these categories already exists".to_string(),
+        ));
+    }
+
+    let result = collection
+        .update_one(
+            doc! { "_id": id },
+            doc! {
+                "$set": {
+                    "category_a": &category_a,
+                    "category_b": &category_b,
+                    "relationship_strength": request.relationship_strength,
+                    "bidirectional": request.bidirectional
+                }
+            },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update relationship".to_string(),
+            )
+        })?;
+
+    if result.matched_count == 0 {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "Relationship not found".to_string(),
+        ));
+    }
+
+    reload_category_relationships().await?;
+
+    Ok(CategoryRelationshipOut {
+        relationship_id: Some(id.to_hex()),
+        category_a: request.category_a,
+        category_b: request.category_b,
+        relationship_strength: request.relationship_strength,
+        bidirectional: request.bidirectional,
+    })
+}
+
+pub async fn delete_category_relationship(relationship_id: &str) -> Result<(), VerboseHTTPError> {
+    let id = parse_relationship_id(relationship_id)?;
+    let collection = relationships_collection()?;
+
+    let result = collection
+        .delete_one(doc! { "_id": id })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to delete relationship".to_string(),
+            )
+        })?;
+
+    if result.deleted_count == 0 {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "Relationship not found".to_string(),
+        ));
+    }
+
+    reload_category_relationships().await?;
+
+    Ok(())
+}
+
+pub async fn upsert_category_relationship(
+    request: CategoryRelationshipRequest,
+) -> Result<CategoryRelationshipsResponse, VerboseHTTPError> {
+    validate_relationship_request(&request)?;
+
+    let collection = relationships_collection()?;
+
+    let category_a = format!("{:?}", request.category_a);
+    let category_b = format!("{:?}", request.category_b);
+
+    collection
+        .update_one(
+            relationship_pair_filter(&category_a, &category_b),
+            doc! {
+                "$set": {
+                    "category_a": &category_a,
+                    "category_b": &category_b,
+                    "relationship_strength": request.relationship_strength,
+                    "bidirectional": request.bidirectional
+                }
+            },
+        )
+        .upsert(true)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save relationship".to_string(),
+            )
+        })?;
+
+    reload_category_relationships().await?;
+
+    Ok(list_category_relationships())
+}
+
+// Signals whose decay clock is at least one full period old.
+#[inline]
+fn decay_due_filter(now_millis: i64) -> Document {
+    doc! {
+        "last_decay_check": {
+            "$lte": BsonDateTime::from_millis(now_millis - TIME_DECAY_PERIOD_SECS * 1000)
         }
     }
 }
 
+// Decays one signal by the whole periods elapsed and moves `last_decay_check`
+// forward by exactly those periods, keeping any partial period for next time.
+// The update only applies if `last_decay_check` is unchanged, so the request
+// path and the background job can race on a signal without decaying it twice.
+async fn decay_signal(
+    collection: &Collection<UserCategorySignal>,
+    signal: &UserCategorySignal,
+    now_millis: i64,
+) -> Result<(), VerboseHTTPError> {
+    let last_decay_millis = signal.last_decay_check.timestamp_millis();
+    let periods = (now_millis - last_decay_millis) / (TIME_DECAY_PERIOD_SECS * 1000);
+
+    if periods <= 0 {
+        return Ok(());
+    }
+
+    let decay_factor = TIME_DECAY_FACTOR.powi(periods.min(i32::MAX as i64) as i32);
+    let new_strength = (signal.signal_strength * decay_factor).max(MIN_EDGE_WEIGHT);
+    let decayed_until =
+        BsonDateTime::from_millis(last_decay_millis + periods * TIME_DECAY_PERIOD_SECS * 1000);
+
+    collection
+        .update_one(
+            doc! { "_id": signal.id, "last_decay_check": signal.last_decay_check },
+            doc! {
+                "$set": {
+                    "signal_strength": new_strength,
+                    "last_decay_check": decayed_until
+                }
+            },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update signal".to_string(),
+            )
+        })?;
 
+    Ok(())
+}
 
+// Request-path catch-up for a single user. Only signals that are actually due
+// are fetched, and REQUEST_TIME_DECAY=false leaves decay to the background job.
 pub async fn apply_time_decay(user_id: &str) -> Result<(), VerboseHTTPError> {
+    if !config().request_time_decay {
+        return Ok(());
+    }
+
     let Some(database) = DB.get() else {
         return Err(VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -46,61 +508,1015 @@ pub async fn apply_time_decay(user_id: &str) -> Result<(), VerboseHTTPError> {
 
     let collection: Collection<UserCategorySignal> =
         database.collection(COLLECTIONS_USER_CATEGORY_SIGNALS);
+    let now_millis = BsonDateTime::now().timestamp_millis();
+
+    let mut filter = decay_due_filter(now_millis);
+    filter.insert("user_id", user_id);
+
+    let signals: Vec<UserCategorySignal> = collection
+        .find(filter)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    for signal in &signals {
+        decay_signal(&collection, signal, now_millis).await?;
+    }
+
+    Ok(())
+}
+
+// One pass over every due signal, paged by _id so inactive users decay too.
+async fn decay_stale_signals() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let collection: Collection<UserCategorySignal> =
+        database.collection(COLLECTIONS_USER_CATEGORY_SIGNALS);
+    let now_millis = BsonDateTime::now().timestamp_millis();
+    let batch_size = config().time_decay_batch_size;
+
+    let mut last_id: Option<ObjectId> = None;
+    let mut decayed = 0;
+
+    loop {
+        let mut filter = decay_due_filter(now_millis);
+        if let Some(last_id) = last_id {
+            filter.insert("_id", doc! { "$gt": last_id });
+        }
+
+        let batch: Vec<UserCategorySignal> = match collection
+            .find(filter)
+            .sort(doc! { "_id": 1 })
+            .limit(batch_size)
+            .await
+        {
+            Ok(cursor) => match cursor.try_collect().await {
+                Ok(batch) => batch,
+                Err(error) => {
+                    tracing::warn!("Failed to read signals for time decay: {}", error);
+                    return;
+                }
+            },
+            Err(error) => {
+                tracing::warn!("Failed to query signals for time decay: {}", error);
+                return;
+            }
+        };
+
+        for signal in &batch {
+            if let Err(VerboseHTTPError::Standard(_, message)) =
+                decay_signal(&collection, signal, now_millis).await
+            {
+                tracing::warn!("Failed to decay signal {:?}: {}", signal.id, message);
+                continue;
+            }
+            decayed += 1;
+        }
+
+        if (batch.len() as i64) < batch_size {
+            break;
+        }
+        last_id = batch.last().and_then(|signal| signal.id);
+        if last_id.is_none() {
+            break;
+        }
+    }
+
+    if decayed > 0 {
+        tracing::info!("Applied time decay to {} category signals", decayed);
+    }
+}
+
+pub async fn run_time_decay_worker() {
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(config().time_decay_interval_secs));
+
+    loop {
+        interval.tick().await;
+        decay_stale_signals().await;
+    }
+}
+
+pub async fn process_signal(signal_log: SignalLog) -> Result<(), VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    apply_time_decay(&signal_log.user_id).await?;
+
+    let signals_collection: Collection<UserCategorySignal> =
+        database.collection(COLLECTIONS_USER_CATEGORY_SIGNALS);
     let now = BsonDateTime::now();
-    let now_timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
 
-    let cursor = collection
-        .find(doc! { "user_id": user_id })
+    let relationships = fresh_category_relationships().await;
+
+    let existing_signal = signals_collection
+        .find_one(doc! {
+            "user_id": &signal_log.user_id,
+            "category": format!("{:?}", signal_log.category)
+        })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    let boost = signal_log.signal_type.boost_value();
+    let decay = signal_log.signal_type.decay_value();
+
+    // Strength changes are applied in place rather than by replacing the
+    // document, so a concurrent decay pass is never overwritten.
+    if let Some(signal) = existing_signal {
+        signals_collection
+            .update_one(
+                doc! { "_id": signal.id },
+                doc! {
+                    "$inc": { "signal_strength": boost },
+                    "$set": { "last_updated": now }
+                },
+            )
+            .await
+            .map_err(|_| {
+                VerboseHTTPError::Standard(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to update signal".to_string(),
+                )
+            })?;
+    } else {
+        let initial_strength = MIN_EDGE_WEIGHT + boost;
+
+        let new_signal = UserCategorySignal {
+            id: None,
+            user_id: signal_log.user_id.clone(),
+            category: signal_log.category,
+            signal_strength: initial_strength,
+            last_updated: now,
+            last_decay_check: now,
+        };
+
+        signals_collection
+            .insert_one(&new_signal)
+            .await
+            .map_err(|_| {
+                VerboseHTTPError::Standard(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to create signal".to_string(),
+                )
+            })?;
+    }
+
+    let all_user_signals = signals_collection
+        .find(doc! { "user_id": &signal_log.user_id })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect::<Vec<UserCategorySignal>>()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    let mut related_categories: HashMap<ProductCategory, f64> = HashMap::new();
+    for rel in relationships.iter() {
+        if rel.category_a == signal_log.category {
+            related_categories.insert(rel.category_b, rel.relationship_strength);
+        }
+        if rel.bidirectional && rel.category_b == signal_log.category {
+            related_categories.insert(rel.category_a, rel.relationship_strength);
+        }
+    }
+
+    for user_signal in all_user_signals {
+        if user_signal.category == signal_log.category {
+            continue;
+        }
+
+        let strength =
+            if let Some(relationship_strength) = related_categories.get(&user_signal.category) {
+                doc! { "$add": ["$signal_strength", boost * relationship_strength] }
+            } else {
+                doc! { "$max": [{ "$subtract": ["$signal_strength", decay] }, MIN_EDGE_WEIGHT] }
+            };
+
+        signals_collection
+            .update_one(
+                doc! { "_id": user_signal.id },
+                vec![doc! { "$set": { "signal_strength": strength, "last_updated": now } }],
+            )
+            .await
+            .map_err(|_| {
+                VerboseHTTPError::Standard(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to update related signal".to_string(),
+                )
+            })?;
+    }
+
+    Ok(())
+}
+
+pub async fn ensure_category_signal_indexes() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let collection: Collection<UserCategorySignal> =
+        database.collection(COLLECTIONS_USER_CATEGORY_SIGNALS);
+
+    let indexes = [
+        IndexModel::builder()
+            .keys(doc! { "user_id": 1, "category": 1 })
+            .build(),
+        IndexModel::builder()
+            .keys(doc! { "last_decay_check": 1 })
+            .build(),
+    ];
+
+    for index in indexes {
+        if let Err(error) = collection.create_index(index).await {
+            tracing::warn!("Failed to create category signal index: {}", error);
+        }
+    }
+}
+
+pub async fn ensure_recently_viewed_indexes() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let collection: Collection<UserLastProduct> =
+        database.collection(COLLECTIONS_USER_LAST_PRODUCTS);
+
+    let indexes = [
+        IndexModel::builder()
+            .keys(doc! { "user_id": 1, "product_id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build(),
+        IndexModel::builder()
+            .keys(doc! { "user_id": 1, "visited_at": -1 })
+            .build(),
+    ];
+
+    for index in indexes {
+        if let Err(error) = collection.create_index(index).await {
+            tracing::warn!("Failed to create recently viewed index: {}", error);
+        }
+    }
+}
+
+// One entry per (user, product); a repeat view only moves it to the front.
+// Anything beyond the newest MAX_RECENTLY_VIEWED entries is trimmed.
+pub async fn record_recently_viewed(
+    user_id: &str,
+    product_id: &str,
+    product_title: &str,
+) -> Result<(), VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let collection: Collection<UserLastProduct> =
+        database.collection(COLLECTIONS_USER_LAST_PRODUCTS);
+
+    collection
+        .update_one(
+            doc! { "user_id": user_id, "product_id": product_id },
+            doc! {
+                "$set": {
+                    "product_title": product_title,
+                    "visited_at": BsonDateTime::now()
+                }
+            },
+        )
+        .upsert(true)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to record recently viewed product".to_string(),
+            )
+        })?;
+
+    let stale_ids: Vec<ObjectId> = collection
+        .find(doc! { "user_id": user_id })
+        .sort(doc! { "visited_at": -1 })
+        .skip(MAX_RECENTLY_VIEWED)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect::<Vec<UserLastProduct>>()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .into_iter()
+        .filter_map(|entry| entry.id)
+        .collect();
+
+    if !stale_ids.is_empty() {
+        collection
+            .delete_many(doc! { "_id": { "$in": stale_ids } })
+            .await
+            .map_err(|_| {
+                VerboseHTTPError::Standard(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to trim recently viewed products".to_string(),
+                )
+            })?;
+    }
+
+    Ok(())
+}
+
+// Viewed products that no longer match `base_filter` (disabled, out of
+// stock, outside their availability window, the user's own or already
+// ordered) or that an earlier row has shown are skipped, so the row can come
+// out shorter than the stored history.
+async fn recently_viewed_row(
+    user_id: &str,
+    base_filter: &Document,
+    shown_ids: &HashSet<String>,
+) -> Result<Option<RecommendationRow>, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let history_collection: Collection<UserLastProduct> =
+        database.collection(COLLECTIONS_USER_LAST_PRODUCTS);
+
+    let history: Vec<UserLastProduct> = history_collection
+        .find(doc! { "user_id": user_id })
+        .sort(doc! { "visited_at": -1 })
+        .limit(MAX_RECENTLY_VIEWED as i64)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    if history.is_empty() {
+        return Ok(None);
+    }
+
+    let product_ids: Vec<&str> = history
+        .iter()
+        .map(|entry| entry.product_id.as_str())
+        .collect();
+
+    let shown_ids: Vec<&String> = shown_ids.iter().collect();
+
+    let products_collection: Collection<Product> = database.collection("products");
+    let mut products: HashMap<String, Product> = products_collection
+        .find(doc! {
+            "$and": [
+                base_filter,
+                { "product_id": { "$in": &product_ids, "$nin": shown_ids } }
+            ]
+        })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect::<Vec<Product>>()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .into_iter()
+        .map(|product| (product.product_id.clone(), product))
+        .collect();
+
+    let recent_products: Vec<ProductSummary> = product_ids
+        .into_iter()
+        .filter_map(|product_id| products.remove(product_id))
+        .take(RECOMMENDATION_ROW_SIZE)
+        .map(|product| ProductSummary {
+            product_id: product.product_id,
+            title: product.title,
+            price_in_inr: Some(product.price),
+            thumbnail_url: product.thumbnail_url,
+            category: format!("{:?}", product.category),
+            relevance_score: 1.0,
+        })
+        .collect();
+
+    if recent_products.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(RecommendationRow {
+        title: "Recently viewed".to_string(),
+        products: recent_products,
+    }))
+}
+
+fn featured_collection() -> Result<Collection<FeaturedProduct>, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    Ok(database.collection(COLLECTIONS_FEATURED_PRODUCTS))
+}
+
+pub async fn list_featured_products() -> Result<FeaturedProductsResponse, VerboseHTTPError> {
+    let featured = featured_collection()?
+        .find(doc! {})
+        .sort(doc! { "position": 1 })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    Ok(FeaturedProductsResponse { featured })
+}
+
+pub async fn upsert_featured_product(
+    request: FeaturedProductRequest,
+) -> Result<FeaturedProductsResponse, VerboseHTTPError> {
+    if let (Some(starts_at), Some(ends_at)) = (request.starts_at, request.ends_at)
+        && ends_at <= starts_at
+    {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "ends_at must be after starts_at".to_string(),
+        ));
+    }
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let products_collection: Collection<Product> = database.collection("products");
+    let product_exists = products_collection
+        .count_documents(doc! { "product_id": &request.product_id })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        > 0;
+
+    if !product_exists {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "Product not found".to_string(),
+        ));
+    }
+
+    let collection = featured_collection()?;
+    let current = list_featured_products().await?.featured;
+    let already_featured = current.iter().any(|f| f.product_id == request.product_id);
+
+    if !already_featured && current.len() >= MAX_FEATURED_PRODUCTS {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!("At most {} products can be featured", MAX_FEATURED_PRODUCTS),
+        ));
+    }
+
+    let position = request.position.unwrap_or_else(|| {
+        current
+            .iter()
+            .filter(|f| f.product_id != request.product_id)
+            .map(|f| f.position + 1)
+            .max()
+            .unwrap_or(0)
+    });
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    collection
+        .update_one(
+            doc! { "product_id": &request.product_id },
+            doc! {
+                "$set": {
+                    "position": position as i64,
+                    "starts_at": request.starts_at.map(|t| t as i64),
+                    "ends_at": request.ends_at.map(|t| t as i64)
+                },
+                "$setOnInsert": { "created_at": now as i64 }
+            },
+        )
+        .upsert(true)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save featured product".to_string(),
+            )
+        })?;
+
+    list_featured_products().await
+}
+
+pub async fn remove_featured_product(product_id: &str) -> Result<(), VerboseHTTPError> {
+    let result = featured_collection()?
+        .delete_one(doc! { "product_id": product_id })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to remove featured product".to_string(),
+            )
+        })?;
+
+    if result.deleted_count == 0 {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::NOT_FOUND,
+            "Product is not featured".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Featured entries are admin-ordered; products that don't match
+// `base_filter` are dropped from the row along with entries outside the
+// featured window.
+async fn featured_row(
+    base_filter: &Document,
+    now: u64,
+) -> Result<Option<RecommendationRow>, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let featured: Vec<FeaturedProduct> = featured_collection()?
+        .find(doc! {
+            "$and": [
+                { "$or": [{ "starts_at": null }, { "starts_at": { "$lte": now as i64 } }] },
+                { "$or": [{ "ends_at": null }, { "ends_at": { "$gt": now as i64 } }] }
+            ]
+        })
+        .sort(doc! { "position": 1 })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    if featured.is_empty() {
+        return Ok(None);
+    }
+
+    let product_ids: Vec<&str> = featured.iter().map(|f| f.product_id.as_str()).collect();
+    let filter = doc! { "$and": [base_filter, { "product_id": { "$in": &product_ids } }] };
+
+    let products_collection: Collection<Product> = database.collection("products");
+    let mut products: HashMap<String, Product> = products_collection
+        .find(filter)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect::<Vec<Product>>()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .into_iter()
+        .map(|product| (product.product_id.clone(), product))
+        .collect();
+
+    let featured_products: Vec<ProductSummary> = product_ids
+        .into_iter()
+        .filter_map(|product_id| products.remove(product_id))
+        .map(|product| ProductSummary {
+            product_id: product.product_id,
+            title: product.title,
+            price_in_inr: Some(product.price),
+            thumbnail_url: product.thumbnail_url,
+            category: format!("{:?}", product.category),
+            relevance_score: 1.0,
+        })
+        .collect();
+
+    if featured_products.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(RecommendationRow {
+        title: "Featured".to_string(),
+        products: featured_products,
+    }))
+}
+
+fn average_embedding(products: &[Product]) -> Option<Vec<f32>> {
+    let mut embeddings = products
+        .iter()
+        .filter_map(|product| product.embedding.as_deref())
+        .filter(|embedding| !embedding.is_empty());
+
+    let mut sum = embeddings.next()?.to_vec();
+    let dimension = sum.len();
+    for embedding in embeddings.filter(|embedding| embedding.len() == dimension) {
+        sum.iter_mut()
+            .zip(embedding)
+            .for_each(|(total, value)| *total += value);
+    }
+
+    Some(normalize_embedding(sum))
+}
+
+// Seeds from the user's recently viewed history, falling back to the most
+// viewed buyable products in their strongest category. The viewed seeds
+// themselves and products already shown in earlier rows are left out on top
+// of `base_filter`.
+async fn similar_products_row(
+    user_id: &str,
+    strongest_category: Option<ProductCategory>,
+    base_filter: &Document,
+    shown_ids: &HashSet<String>,
+    now: u64,
+) -> Result<Option<RecommendationRow>, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let database_error = |_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
+    };
+
+    let history_collection: Collection<UserLastProduct> =
+        database.collection(COLLECTIONS_USER_LAST_PRODUCTS);
+    let products_collection: Collection<Product> = database.collection("products");
+
+    // Recently viewed history holds one entry per product, newest first.
+    let history: Vec<UserLastProduct> = history_collection
+        .find(doc! { "user_id": user_id })
+        .sort(doc! { "visited_at": -1 })
+        .limit(SIMILAR_PRODUCTS_SEED_COUNT)
+        .await
+        .map_err(database_error)?
+        .try_collect()
+        .await
+        .map_err(database_error)?;
+
+    let latest_title = history.first().map(|entry| entry.product_title.clone());
+    let viewed_ids: Vec<String> = history.into_iter().map(|entry| entry.product_id).collect();
+
+    let (seed_filter, title) = if let Some(latest_title) = latest_title {
+        (
+            doc! { "product_id": { "$in": &viewed_ids }, "embedding": { "$ne": null } },
+            format!("Because you viewed {}", latest_title),
+        )
+    } else if let Some(category) = strongest_category {
+        let category_str = format!("{:?}", category);
+        let mut filter = buyable_products_filter(now);
+        filter.insert("category", &category_str);
+        filter.insert("user_id", doc! { "$ne": user_id });
+        filter.insert("embedding", doc! { "$ne": null });
+        (
+            filter,
+            format!("Because you viewed {} products", category_str),
+        )
+    } else {
+        return Ok(None);
+    };
+
+    let seeds: Vec<Product> = products_collection
+        .find(seed_filter)
+        .sort(doc! { "view_count": -1 })
+        .limit(SIMILAR_PRODUCTS_SEED_COUNT)
+        .await
+        .map_err(database_error)?
+        .try_collect()
+        .await
+        .map_err(database_error)?;
+
+    let Some(query_vector) = average_embedding(&seeds) else {
+        return Ok(None);
+    };
+
+    let mut excluded_ids = viewed_ids;
+    excluded_ids.extend(shown_ids.iter().cloned());
+
+    let filter = doc! { "$and": [base_filter, { "product_id": { "$nin": excluded_ids } }] };
+
+    let pipeline = vec![
+        vector_search_stage(&query_vector, RECOMMENDATION_ROW_SIZE as u32, 0),
+        doc! { "$addFields": { "similarity": { "$meta": "vectorSearchScore" } } },
+        doc! { "$match": filter },
+        doc! { "$limit": RECOMMENDATION_ROW_SIZE as i64 },
+        doc! { "$project": { "embedding": 0 } },
+    ];
+
+    let results: Vec<Document> = products_collection
+        .aggregate(pipeline)
+        .await
+        .map_err(database_error)?
+        .try_collect()
+        .await
+        .map_err(database_error)?;
+
+    let similar_products: Vec<ProductSummary> = results
+        .into_iter()
+        .filter_map(|document| {
+            let similarity = document.get_f64("similarity").unwrap_or(0.0);
+            let product: Product = from_document(document).ok()?;
+            Some(ProductSummary {
+                product_id: product.product_id,
+                title: product.title,
+                price_in_inr: Some(product.price),
+                thumbnail_url: product.thumbnail_url,
+                category: format!("{:?}", product.category),
+                relevance_score: similarity,
+            })
+        })
+        .collect();
+
+    if similar_products.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(RecommendationRow {
+        title,
+        products: similar_products,
+    }))
+}
+
+// Takes up to RECOMMENDATION_ROW_SIZE products that no earlier row has
+// shown; the row is dropped when nothing is left.
+fn push_row(
+    rows: &mut Vec<RecommendationRow>,
+    shown_ids: &mut HashSet<String>,
+    title: String,
+    products: Vec<Product>,
+) {
+    let products: Vec<ProductSummary> = products
+        .into_iter()
+        .filter(|product| shown_ids.insert(product.product_id.clone()))
+        .take(RECOMMENDATION_ROW_SIZE)
+        .map(|product| ProductSummary {
+            product_id: product.product_id,
+            title: product.title,
+            price_in_inr: Some(product.price),
+            thumbnail_url: product.thumbnail_url,
+            category: format!("{:?}", product.category),
+            relevance_score: 1.0,
+        })
+        .collect();
+
+    if !products.is_empty() {
+        rows.push(RecommendationRow { title, products });
+    }
+}
+
+// A random row's worth of products matching `filter` that no earlier row
+// has shown.
+async fn shuffled_products(
+    collection: &Collection<Product>,
+    filter: Document,
+    shown_ids: &HashSet<String>,
+) -> Result<Vec<Product>, VerboseHTTPError> {
+    let shown_ids: Vec<&String> = shown_ids.iter().collect();
+
+    let pipeline = vec![
+        doc! { "$match": { "$and": [filter, { "product_id": { "$nin": shown_ids } }] } },
+        doc! { "$sample": { "size": RECOMMENDATION_ROW_SIZE as i64 } },
+        doc! { "$project": { "embedding": 0 } },
+    ];
+
+    collection
+        .aggregate(pipeline)
+        .with_type::<Product>()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })
+}
+
+// Categories related to the user's top categories, weighted by both the
+// user's signal and the relationship strength. Categories that already
+// have their own row are left out.
+fn related_categories(
+    signals: &[UserCategorySignal],
+    relationships: &[CategoryRelationship],
+) -> Vec<ProductCategory> {
+    let mut scores: HashMap<ProductCategory, f64> = HashMap::new();
+
+    for signal in signals {
+        for rel in relationships.iter() {
+            let related = if rel.category_a == signal.category {
+                rel.category_b
+            } else if rel.bidirectional && rel.category_b == signal.category {
+                rel.category_a
+            } else {
+                continue;
+            };
+
+            if signals.iter().any(|other| other.category == related) {
+                continue;
+            }
+
+            *scores.entry(related).or_default() +=
+                signal.signal_strength * rel.relationship_strength;
+        }
+    }
+
+    let mut related: Vec<(ProductCategory, f64)> = scores.into_iter().collect();
+    related.sort_by(|a, b| b.1.total_cmp(&a.1));
+    related
+        .into_iter()
+        .take(MAX_RELATED_CATEGORIES)
+        .map(|(category, _)| category)
+        .collect()
+}
+
+// Products matching `base_filter` that were viewed most over the last
+// TRENDING_WINDOW_SECS, most viewed first.
+async fn trending_products(
+    base_filter: &Document,
+    now: u64,
+) -> Result<Vec<Product>, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let views_collection: Collection<ProductView> = database.collection(COLLECTIONS_PRODUCT_VIEWS);
+
+    let pipeline = vec![
+        doc! {
+            "$match": {
+                "created_at": { "$gte": now.saturating_sub(TRENDING_WINDOW_SECS) as i64 }
+            }
+        },
+        doc! { "$group": { "_id": "$product_id", "views": { "$sum": 1 } } },
+        doc! { "$sort": { "views": -1, "_id": 1 } },
+        doc! { "$limit": TRENDING_CANDIDATES },
+    ];
+
+    let product_ids: Vec<String> = views_collection
+        .aggregate(pipeline)
         .await
         .map_err(|_| {
             VerboseHTTPError::Standard(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Database error".to_string(),
             )
-        })?;
-
-    let signals: Vec<UserCategorySignal> = cursor.try_collect().await.map_err(|_| {
-        VerboseHTTPError::Standard(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Database error".to_string(),
-        )
-    })?;
+        })?
+        .try_collect::<Vec<Document>>()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .into_iter()
+        .filter_map(|document| document.get_str("_id").ok().map(str::to_string))
+        .collect();
 
-    for signal in signals {
-        let last_decay_timestamp = signal.last_decay_check.timestamp_millis() / 1000;
-        let days_since_decay = (now_timestamp as i64 - last_decay_timestamp) / 86400;
-
-        if days_since_decay > 0 {
-            let decay_factor = TIME_DECAY_FACTOR.powi(days_since_decay as i32);
-            let new_strength = (signal.signal_strength * decay_factor).max(MIN_EDGE_WEIGHT);
-
-            collection
-                .update_one(
-                    doc! { "_id": signal.id },
-                    doc! {
-                        "$set": {
-                            "signal_strength": new_strength,
-                            "last_decay_check": now
-                        }
-                    },
-                )
-                .await
-                .map_err(|_| {
-                    VerboseHTTPError::Standard(
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "Failed to update signal".to_string(),
-                    )
-                })?;
-        }
+    if product_ids.is_empty() {
+        return Ok(Vec::new());
     }
 
-    Ok(())
+    let filter = doc! { "$and": [base_filter, { "product_id": { "$in": &product_ids } }] };
+
+    let products_collection: Collection<Product> = database.collection("products");
+    let mut products: HashMap<String, Product> = products_collection
+        .find(filter)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .try_collect::<Vec<Product>>()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .into_iter()
+        .map(|product| (product.product_id.clone(), product))
+        .collect();
+
+    Ok(product_ids
+        .iter()
+        .filter_map(|product_id| products.remove(product_id))
+        .collect())
 }
 
-pub async fn process_signal(signal_log: SignalLog) -> Result<(), VerboseHTTPError> {
+fn suppressions_collection() -> Result<Collection<RecommendationSuppression>, VerboseHTTPError> {
     let Some(database) = DB.get() else {
         return Err(VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -108,69 +1524,105 @@ pub async fn process_signal(signal_log: SignalLog) -> Result<(), VerboseHTTPErro
         ));
     };
 
-    apply_time_decay(&signal_log.user_id).await?;
+    Ok(database.collection(COLLECTIONS_RECOMMENDATION_SUPPRESSIONS))
+}
+
+pub async fn ensure_suppression_indexes() {
+    let Ok(collection) = suppressions_collection() else {
+        return;
+    };
+
+    let indexes = [
+        IndexModel::builder()
+            .keys(doc! { "user_id": 1, "product_id": 1, "category": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build(),
+        IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .expire_after(Duration::from_secs(0))
+                    .build(),
+            )
+            .build(),
+    ];
+
+    for index in indexes {
+        if let Err(error) = collection.create_index(index).await {
+            tracing::warn!("Failed to create suppression index: {}", error);
+        }
+    }
+}
+
+// Marking the same product or category again restarts its window.
+pub async fn mark_not_interested(
+    user: &UserOut,
+    request: NotInterestedRequest,
+) -> Result<NotInterestedResponse, VerboseHTTPError> {
+    let (product_id, product_title, category) = match (request.product_id, request.category) {
+        (Some(product_id), None) => {
+            let product = get_product_by_id(&product_id).await?;
+            (Some(product.product_id), Some(product.title), None)
+        }
+        (None, Some(category)) => (None, None, Some(category)),
+        _ => {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                "Provide either a product_id or a category".to_string(),
+            ));
+        }
+    };
 
-    let signals_collection: Collection<UserCategorySignal> =
-        database.collection(COLLECTIONS_USER_CATEGORY_SIGNALS);
     let now = BsonDateTime::now();
-    
-    let relationships = super::schemas::get_category_relationships();
+    let window_millis = config().not_interested_window_secs.saturating_mul(1000);
+    let expires_at = BsonDateTime::from_millis(
+        now.timestamp_millis()
+            .saturating_add(i64::try_from(window_millis).unwrap_or(i64::MAX)),
+    );
 
-    let existing_signal = signals_collection
-        .find_one(doc! {
-            "user_id": &signal_log.user_id,
-            "category": format!("{:?}", signal_log.category)
-        })
+    let category_bson = match category {
+        Some(category) => Bson::String(format!("{:?}", category)),
+        None => Bson::Null,
+    };
+
+    suppressions_collection()?
+        .update_one(
+            doc! {
+                "user_id": &user.uid,
+                "product_id": &product_id,
+                "category": category_bson,
+            },
+            doc! {
+                "$set": {
+                    "product_title": &product_title,
+                    "created_at": now,
+                    "expires_at": expires_at
+                }
+            },
+        )
+        .upsert(true)
         .await
         .map_err(|_| {
             VerboseHTTPError::Standard(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "Database error".to_string(),
+                "Failed to save preference".to_string(),
             )
         })?;
 
-    let boost = signal_log.signal_type.boost_value();
-    let decay = signal_log.signal_type.decay_value();
-
-    if let Some(mut signal) = existing_signal {
-        signal.signal_strength += boost;
-        signal.last_updated = now;
-        signal.last_decay_check = now;
-
-        signals_collection
-            .replace_one(doc! { "_id": signal.id }, &signal)
-            .await
-            .map_err(|_| {
-                VerboseHTTPError::Standard(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to update signal".to_string(),
-                )
-            })?;
-    } else {
-        let initial_strength = MIN_EDGE_WEIGHT + boost;
-        
-        let new_signal = UserCategorySignal {
-            id: None,
-            user_id: signal_log.user_id.clone(),
-            category: signal_log.category,
-            signal_strength: initial_strength,
-            last_updated: now,
-            last_decay_check: now,
-        };
-
-        signals_collection
-            .insert_one(&new_signal)
-            .await
-            .map_err(|_| {
-                VerboseHTTPError::Standard(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to create signal".to_string(),
-                )
-            })?;
-    }
+    Ok(NotInterestedResponse {
+        product_id,
+        category,
+        expires_at,
+    })
+}
 
-    let all_user_signals = signals_collection
-        .find(doc! { "user_id": &signal_log.user_id })
+async fn active_suppressions(
+    user_id: &str,
+) -> Result<Vec<RecommendationSuppression>, VerboseHTTPError> {
+    // The TTL monitor only runs about once a minute, so expiry is also
+    // checked here.
+    suppressions_collection()?
+        .find(doc! { "user_id": user_id, "expires_at": { "$gt": BsonDateTime::now() } })
         .await
         .map_err(|_| {
             VerboseHTTPError::Standard(
@@ -178,52 +1630,83 @@ pub async fn process_signal(signal_log: SignalLog) -> Result<(), VerboseHTTPErro
                 "Database error".to_string(),
             )
         })?
-        .try_collect::<Vec<UserCategorySignal>>()
+        .try_collect()
         .await
         .map_err(|_| {
             VerboseHTTPError::Standard(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Database error".to_string(),
             )
-        })?;
+        })
+}
 
-    let mut related_categories: HashMap<ProductCategory, f64> = HashMap::new();
-    for rel in relationships {
-        if rel.category_a == signal_log.category {
-            related_categories.insert(rel.category_b, rel.relationship_strength);
-        }
-        if rel.bidirectional && rel.category_b == signal_log.category {
-            related_categories.insert(rel.category_a, rel.relationship_strength);
-        }
+// Products in suppressed categories keep their place in mixed rows but drop
+// to the end with a reduced score, and rows made up entirely of suppressed
+// categories move below every other row.
+fn down_weight_suppressed_categories(
+    rows: &mut [RecommendationRow],
+    suppressed_categories: &HashSet<String>,
+) {
+    if suppressed_categories.is_empty() {
+        return;
     }
 
-    for mut user_signal in all_user_signals {
-        if user_signal.category == signal_log.category {
-            continue;
-        }
+    let is_suppressed =
+        |product: &ProductSummary| suppressed_categories.contains(&product.category);
 
-        if let Some(relationship_strength) = related_categories.get(&user_signal.category) {
-            let related_boost = boost * relationship_strength;
-            user_signal.signal_strength += related_boost;
-        } else {
-            user_signal.signal_strength =
-                (user_signal.signal_strength - decay).max(MIN_EDGE_WEIGHT);
+    for row in rows.iter_mut() {
+        for product in row
+            .products
+            .iter_mut()
+            .filter(|product| is_suppressed(product))
+        {
+            product.relevance_score *= SUPPRESSED_CATEGORY_WEIGHT;
         }
+        row.products.sort_by_key(&is_suppressed);
+    }
 
-        user_signal.last_updated = now;
+    rows.sort_by_key(|row| row.products.iter().all(&is_suppressed));
+}
 
-        signals_collection
-            .replace_one(doc! { "_id": user_signal.id }, &user_signal)
-            .await
-            .map_err(|_| {
-                VerboseHTTPError::Standard(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to update related signal".to_string(),
-                )
-            })?;
-    }
+// Cancelled orders don't count; the user may still want those products.
+async fn ordered_product_ids(user_id: &str) -> Result<Vec<String>, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
 
-    Ok(())
+    let orders_collection: Collection<Document> = database.collection(COLLECTIONS_ORDERS);
+    let product_ids = orders_collection
+        .distinct(
+            "product_id",
+            doc! { "buyer_id": user_id, "status": { "$ne": "cancelled" } },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    Ok(product_ids
+        .into_iter()
+        .filter_map(|product_id| match product_id {
+            Bson::String(product_id) => Some(product_id),
+            _ => None,
+        })
+        .collect())
+}
+
+// Buyable products that aren't the user's own listings and aren't in
+// `excluded_ids` (already ordered or marked not interested).
+fn recommendable_products_filter(user_id: &str, excluded_ids: &[String], now: u64) -> Document {
+    let mut filter = buyable_products_filter(now);
+    filter.insert("user_id", doc! { "$ne": user_id });
+    filter.insert("product_id", doc! { "$nin": excluded_ids });
+    filter
 }
 
 pub async fn get_recommendations(
@@ -241,13 +1724,49 @@ pub async fn get_recommendations(
     let signals_collection: Collection<UserCategorySignal> =
         database.collection(COLLECTIONS_USER_CATEGORY_SIGNALS);
     let products_collection: Collection<Product> = database.collection("products");
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let suppressions = active_suppressions(&user.uid).await?;
+    let suppressed_categories: HashSet<String> = suppressions
+        .iter()
+        .filter_map(|suppression| suppression.category)
+        .map(|category| format!("{:?}", category))
+        .collect();
+
+    let mut excluded_ids = ordered_product_ids(&user.uid).await?;
+    excluded_ids.extend(
+        suppressions
+            .into_iter()
+            .filter_map(|suppression| suppression.product_id),
+    );
+    let base_filter = recommendable_products_filter(&user.uid, &excluded_ids, now);
 
     let mut rows = Vec::new();
+    let mut shown_ids = HashSet::new();
+
+    if let Some(row) = featured_row(&base_filter, now).await? {
+        shown_ids.extend(
+            row.products
+                .iter()
+                .map(|product| product.product_id.clone()),
+        );
+        rows.push(row);
+    }
+
+    if let Some(row) = recently_viewed_row(&user.uid, &base_filter, &shown_ids).await? {
+        shown_ids.extend(
+            row.products
+                .iter()
+                .map(|product| product.product_id.clone()),
+        );
+        rows.push(row);
+    }
 
-    let strongest_signal = signals_collection
+    let mut top_signals: Vec<UserCategorySignal> = signals_collection
         .find(doc! { "user_id": &user.uid })
-        .sort(doc! { "signal_strength": -1 })
-        .limit(1)
         .await
         .map_err(|_| {
             VerboseHTTPError::Standard(
@@ -255,25 +1774,72 @@ pub async fn get_recommendations(
                 "Database error".to_string(),
             )
         })?
-        .try_collect::<Vec<UserCategorySignal>>()
+        .try_collect()
         .await
         .map_err(|_| {
             VerboseHTTPError::Standard(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Database error".to_string(),
             )
-        })?
-        .into_iter()
-        .next();
+        })?;
+
+    for signal in top_signals.iter_mut() {
+        if suppressed_categories.contains(&format!("{:?}", signal.category)) {
+            signal.signal_strength *= SUPPRESSED_CATEGORY_WEIGHT;
+        }
+    }
+    top_signals.sort_by(|a, b| b.signal_strength.total_cmp(&a.signal_strength));
+    top_signals.truncate(TOP_CATEGORY_ROWS as usize);
 
-    if let Some(signal) = strongest_signal {
+    // A suppressed category may still rank first after down-weighting, but it
+    // shouldn't seed the similar products fallback.
+    let strongest_category = top_signals
+        .iter()
+        .find(|signal| !suppressed_categories.contains(&format!("{:?}", signal.category)))
+        .map(|signal| signal.category);
+
+    for signal in &top_signals {
         let category_str = format!("{:?}", signal.category);
 
+        let mut filter = base_filter.clone();
+        filter.insert("category", &category_str);
+
+        let products = shuffled_products(&products_collection, filter, &shown_ids).await?;
+        push_row(
+            &mut rows,
+            &mut shown_ids,
+            format!("Products in the {} category", category_str),
+            products,
+        );
+    }
+
+    let related = related_categories(&top_signals, &category_relationships());
+    if !related.is_empty() {
+        let category_strs: Vec<String> = related
+            .iter()
+            .map(|category| format!("{:?}", category))
+            .collect();
+
+        let mut filter = base_filter.clone();
+        filter.insert("category", doc! { "$in": category_strs });
+
+        let products = shuffled_products(&products_collection, filter, &shown_ids).await?;
+        push_row(
+            &mut rows,
+            &mut shown_ids,
+            "You might also like".to_string(),
+            products,
+        );
+    }
+
+    let trending = trending_products(&base_filter, now).await?;
+    push_row(&mut rows, &mut shown_ids, "Trending".to_string(), trending);
+
+    if top_signals.is_empty() {
         let cursor = products_collection
-            .find(doc! {
-                "category": &category_str,
-                "enabled": true
-            })
+            .find(base_filter.clone())
+            .sort(doc! { "created_at": -1 })
+            .limit(RECOMMENDATION_ROW_SIZE as i64 * 2)
             .await
             .map_err(|_| {
                 VerboseHTTPError::Standard(
@@ -282,79 +1848,31 @@ pub async fn get_recommendations(
                 )
             })?;
 
-        let mut products: Vec<Product> = cursor.try_collect().await.map_err(|_| {
+        let latest_products: Vec<Product> = cursor.try_collect().await.map_err(|_| {
             VerboseHTTPError::Standard(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Database error".to_string(),
             )
         })?;
 
-        let mut rng = rand::thread_rng();
-        products.shuffle(&mut rng);
-
-        let category_products: Vec<ProductSummary> = products
-            .into_iter()
-            .take(6)
-            .map(|product| ProductSummary {
-                product_id: product.product_id,
-                title: product.title,
-                price_in_inr: Some(product.price),
-                thumbnail_url: product.thumbnail_url,
-                category: category_str.clone(),
-                relevance_score: 1.0,
-            })
-            .collect();
-
-        if !category_products.is_empty() {
-            rows.push(RecommendationRow {
-                title: format!(
-                    "Products in the {} category",
-                    category_str.replace("Category::", "")
-                ),
-                products: category_products,
-            });
-        }
-    } else {
-        let cursor = products_collection
-            .find(doc! { "enabled": true })
-            .sort(doc! { "created_at": -1 })
-            .limit(6)
-            .await
-            .map_err(|_| {
-                VerboseHTTPError::Standard(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Database error".to_string(),
-                )
-            })?;
-
-        let latest_products: Vec<ProductSummary> = cursor
-            .try_collect::<Vec<Product>>()
-            .await
-            .map_err(|_| {
-                VerboseHTTPError::Standard(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Database error".to_string(),
-                )
-            })?
-            .into_iter()
-            .map(|product| ProductSummary {
-                product_id: product.product_id,
-                title: product.title,
-                price_in_inr: Some(product.price),
-                thumbnail_url: product.thumbnail_url,
-                category: format!("{:?}", product.category),
-                relevance_score: 1.0,
-            })
-            .collect();
+        push_row(
+            &mut rows,
+            &mut shown_ids,
+            "Latest Products".to_string(),
+            latest_products,
+        );
+    }
 
-        if !latest_products.is_empty() {
-            rows.push(RecommendationRow {
-                title: "Latest Products".to_string(),
-                products: latest_products,
-            });
-        }
+    // Vector search needs the Atlas index; without it the row is skipped
+    // rather than failing the whole response.
+    match similar_products_row(&user.uid, strongest_category, &base_filter, &shown_ids, now).await {
+        Ok(Some(row)) => rows.push(row),
+        Ok(None) => {}
+        Err(error) => tracing::warn!("Failed to build similar products row: {:?}", error),
     }
 
+    down_weight_suppressed_categories(&mut rows, &suppressed_categories);
+
     Ok(RecommendationResponse {
         user_id: user.uid.clone(),
         rows,
@@ -362,8 +1880,6 @@ pub async fn get_recommendations(
     })
 }
 
-
-
 pub async fn get_knowledge_graph_data(
     user_id: &str,
 ) -> Result<KnowledgeGraphData, VerboseHTTPError> {
@@ -375,8 +1891,8 @@ pub async fn get_knowledge_graph_data(
     };
 
     apply_time_decay(user_id).await?;
-    
-    let relationships = super::schemas::get_category_relationships();
+
+    let relationships = fresh_category_relationships().await;
 
     let signals_collection: Collection<UserCategorySignal> =
         database.collection(COLLECTIONS_USER_CATEGORY_SIGNALS);
@@ -403,18 +1919,18 @@ pub async fn get_knowledge_graph_data(
     let mut total_signal_strength = 0.0;
     let mut strongest_category = None;
     let mut max_strength = 0.0;
-    
+
     let mut all_categories = std::collections::HashSet::new();
-    
-    for rel in &relationships {
+
+    for rel in relationships.iter() {
         all_categories.insert(rel.category_a);
         all_categories.insert(rel.category_b);
     }
-    
+
     for category in all_categories {
         let category_str = format!("{:?}", category);
         let node_id = format!("category:{}", category_str);
-        
+
         if !nodes.iter().any(|n: &GraphNode| n.id == node_id) {
             nodes.push(GraphNode {
                 id: node_id,
@@ -424,22 +1940,24 @@ pub async fn get_knowledge_graph_data(
             });
         }
     }
-    
-    for rel in &relationships {
+
+    for rel in relationships.iter() {
         let cat_a_str = format!("{:?}", rel.category_a);
         let cat_b_str = format!("{:?}", rel.category_b);
-        
+
         edges.push(GraphEdge {
             source: format!("category:{}", cat_a_str),
             target: format!("category:{}", cat_b_str),
+            edge_type: "relationship".to_string(),
             weight: rel.relationship_strength,
             last_updated: BsonDateTime::now(),
         });
-        
+
         if rel.bidirectional {
             edges.push(GraphEdge {
                 source: format!("category:{}", cat_b_str),
                 target: format!("category:{}", cat_a_str),
+                edge_type: "relationship".to_string(),
                 weight: rel.relationship_strength,
                 last_updated: BsonDateTime::now(),
             });
@@ -477,14 +1995,44 @@ pub async fn get_knowledge_graph_data(
         edges.push(GraphEdge {
             source: format!("user:{}", user_id),
             target: node_id,
+            edge_type: "signal".to_string(),
             weight: signal.signal_strength,
             last_updated: signal.last_updated,
         });
     }
 
+    // Not-interested marks point away from the user with their own edge type;
+    // suppressed products get a node of their own.
+    for suppression in active_suppressions(user_id).await? {
+        let target = match (&suppression.product_id, suppression.category) {
+            (Some(product_id), _) => {
+                let node_id = format!("product:{}", product_id);
+                nodes.push(GraphNode {
+                    id: node_id.clone(),
+                    label: suppression
+                        .product_title
+                        .clone()
+                        .unwrap_or(product_id.clone()),
+                    node_type: "suppressed_product".to_string(),
+                    weight: SUPPRESSED_CATEGORY_WEIGHT,
+                });
+                node_id
+            }
+            (None, Some(category)) => format!("category:{:?}", category),
+            (None, None) => continue,
+        };
+
+        edges.push(GraphEdge {
+            source: format!("user:{}", user_id),
+            target,
+            edge_type: "not_interested".to_string(),
+            weight: SUPPRESSED_CATEGORY_WEIGHT,
+            last_updated: suppression.created_at,
+        });
+    }
+
     let category_count = nodes.iter().filter(|n| n.node_type == "category").count();
 
-    
     Ok(KnowledgeGraphData {
         user_id: user_id.to_string(),
         nodes,
@@ -496,3 +2044,91 @@ pub async fn get_knowledge_graph_data(
         },
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal(category: ProductCategory, signal_strength: f64) -> UserCategorySignal {
+        UserCategorySignal {
+            id: None,
+            user_id: "user".to_string(),
+            category,
+            signal_strength,
+            last_updated: BsonDateTime::now(),
+            last_decay_check: BsonDateTime::now(),
+        }
+    }
+
+    fn relationship(
+        category_a: ProductCategory,
+        category_b: ProductCategory,
+        relationship_strength: f64,
+        bidirectional: bool,
+    ) -> CategoryRelationship {
+        CategoryRelationship {
+            id: None,
+            category_a,
+            category_b,
+            relationship_strength,
+            bidirectional,
+        }
+    }
+
+    #[test]
+    fn related_categories_rank_by_signal_and_relationship_strength() {
+        let signals = [
+            signal(ProductCategory::Smartphones, 10.0),
+            signal(ProductCategory::Shoes, 2.0),
+        ];
+        let relationships = [
+            relationship(
+                ProductCategory::Smartphones,
+                ProductCategory::Audio,
+                0.5,
+                true,
+            ),
+            relationship(
+                ProductCategory::Smartphones,
+                ProductCategory::Wearables,
+                0.8,
+                true,
+            ),
+            relationship(ProductCategory::Shoes, ProductCategory::Bags, 0.9, true),
+            // Both already have their own row.
+            relationship(
+                ProductCategory::Smartphones,
+                ProductCategory::Shoes,
+                1.0,
+                true,
+            ),
+        ];
+
+        assert_eq!(
+            related_categories(&signals, &relationships),
+            vec![
+                ProductCategory::Wearables,
+                ProductCategory::Audio,
+                ProductCategory::Bags
+            ]
+        );
+    }
+
+    #[test]
+    fn related_categories_follow_one_way_relationships_forward_only() {
+        let relationships = [relationship(
+            ProductCategory::Cameras,
+            ProductCategory::Bags,
+            0.7,
+            false,
+        )];
+
+        assert_eq!(
+            related_categories(&[signal(ProductCategory::Cameras, 1.0)], &relationships),
+            vec![ProductCategory::Bags]
+        );
+        assert!(
+            related_categories(&[signal(ProductCategory::Bags, 1.0)], &relationships).is_empty()
+        );
+    }
+}
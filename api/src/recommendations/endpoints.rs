@@ -1,4 +1,5 @@
-use axum::{Extension, response::Json};
+use axum::{Extension, extract::Path, response::Json};
+use serde_json::{Value, json};
 
 use super::{delegates, schemas::*};
 use crate::{
@@ -19,7 +20,72 @@ pub async fn get_knowledge_graph(
     Ok(Json(kg_data))
 }
 
+pub async fn not_interested_endpoint(
+    Extension(user): Extension<UserOut>,
+    Json(request): Json<NotInterestedRequest>,
+) -> Result<Json<NotInterestedResponse>, VerboseHTTPError> {
+    let suppression = delegates::mark_not_interested(&user, request).await?;
+    Ok(Json(suppression))
+}
+
+pub async fn list_category_relationships_endpoint() -> Json<CategoryRelationshipsResponse> {
+    Json(delegates::list_category_relationships())
+}
+
+pub async fn upsert_category_relationship_endpoint(
+    Json(request): Json<CategoryRelationshipRequest>,
+) -> Result<Json<CategoryRelationshipsResponse>, VerboseHTTPError> {
+    let relationships = delegates::upsert_category_relationship(request).await?;
+    Ok(Json(relationships))
+}
+
+pub async fn seed_category_relationships_endpoint()
+-> Result<Json<SeedRelationshipsResponse>, VerboseHTTPError> {
+    let seeded = delegates::seed_category_relationships().await?;
+    Ok(Json(seeded))
+}
+
+pub async fn get_category_relationship_endpoint(
+    Path(relationship_id): Path<String>,
+) -> Result<Json<CategoryRelationshipOut>, VerboseHTTPError> {
+    let relationship = delegates::get_category_relationship(&relationship_id).await?;
+    Ok(Json(relationship))
+}
 
+pub async fn update_category_relationship_endpoint(
+    Path(relationship_id): Path<String>,
+    Json(request): Json<CategoryRelationshipRequest>,
+) -> Result<Json<CategoryRelationshipOut>, VerboseHTTPError> {
+    let relationship = delegates::update_category_relationship(&relationship_id, request).await?;
+    Ok(Json(relationship))
+}
+
+pub async fn delete_category_relationship_endpoint(
+    Path(relationship_id): Path<String>,
+) -> Result<Json<Value>, VerboseHTTPError> {
+    delegates::delete_category_relationship(&relationship_id).await?;
+    Ok(Json(json!({ "status": "ok" })))
+}
+
+pub async fn list_featured_products_endpoint()
+-> Result<Json<FeaturedProductsResponse>, VerboseHTTPError> {
+    let featured = delegates::list_featured_products().await?;
+    Ok(Json(featured))
+}
+
+pub async fn upsert_featured_product_endpoint(
+    Json(request): Json<FeaturedProductRequest>,
+) -> Result<Json<FeaturedProductsResponse>, VerboseHTTPError> {
+    let featured = delegates::upsert_featured_product(request).await?;
+    Ok(Json(featured))
+}
+
+pub async fn remove_featured_product_endpoint(
+    Path(product_id): Path<String>,
+) -> Result<Json<Value>, VerboseHTTPError> {
+    delegates::remove_featured_product(&product_id).await?;
+    Ok(Json(json!({ "status": "ok" })))
+}
 
 pub async fn auto_log_signal(
     user_id: &str,
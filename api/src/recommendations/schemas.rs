@@ -8,6 +8,7 @@ pub enum SignalType {
     Query,
     ProductView,
     Search,
+    Purchase,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -31,6 +32,53 @@ pub struct CategoryRelationship {
     pub bidirectional: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryRelationshipRequest {
+    pub category_a: ProductCategory,
+    pub category_b: ProductCategory,
+    pub relationship_strength: f64,
+    #[serde(default = "default_bidirectional")]
+    pub bidirectional: bool,
+}
+
+#[inline]
+fn default_bidirectional() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryRelationshipOut {
+    pub relationship_id: Option<String>,
+    pub category_a: ProductCategory,
+    pub category_b: ProductCategory,
+    pub relationship_strength: f64,
+    pub bidirectional: bool,
+}
+
+impl From<&CategoryRelationship> for CategoryRelationshipOut {
+    fn from(relationship: &CategoryRelationship) -> Self {
+        Self {
+            relationship_id: relationship.id.map(|id| id.to_hex()),
+            category_a: relationship.category_a,
+            category_b: relationship.category_b,
+            relationship_strength: relationship.relationship_strength,
+            bidirectional: relationship.bidirectional,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryRelationshipsResponse {
+    pub from_database: bool,
+    pub relationships: Vec<CategoryRelationshipOut>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeedRelationshipsResponse {
+    pub inserted: usize,
+    pub skipped: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UserLastProduct {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
@@ -41,6 +89,34 @@ pub struct UserLastProduct {
     pub visited_at: DateTime,
 }
 
+// A "not interested" mark. Exactly one of `product_id` / `category` is set;
+// the document is ignored (and TTL-deleted) once `expires_at` passes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecommendationSuppression {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: String,
+    pub product_id: Option<String>,
+    #[serde(default)]
+    pub product_title: Option<String>,
+    pub category: Option<ProductCategory>,
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotInterestedRequest {
+    pub product_id: Option<String>,
+    pub category: Option<ProductCategory>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotInterestedResponse {
+    pub product_id: Option<String>,
+    pub category: Option<ProductCategory>,
+    pub expires_at: DateTime,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SignalLog {
     pub user_id: String,
@@ -73,11 +149,26 @@ pub struct RecommendationResponse {
     pub generated_at: DateTime,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FeaturedProduct {
+    pub product_id: String,
+    pub position: u32,
+    pub starts_at: Option<u64>,
+    pub ends_at: Option<u64>,
+    pub created_at: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ProductViewLog {
+pub struct FeaturedProductRequest {
     pub product_id: String,
-    pub duration_seconds: Option<u32>,
-    pub source: Option<String>,
+    pub position: Option<u32>,
+    pub starts_at: Option<u64>,
+    pub ends_at: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeaturedProductsResponse {
+    pub featured: Vec<FeaturedProduct>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -92,6 +183,8 @@ pub struct GraphNode {
 pub struct GraphEdge {
     pub source: String,
     pub target: String,
+    // "relationship", "signal" or "not_interested".
+    pub edge_type: String,
     pub weight: f64,
     pub last_updated: DateTime,
 }
@@ -113,7 +206,7 @@ pub struct KgStats {
 
 pub const MIN_EDGE_WEIGHT: f64 = 1.0;
 
-pub fn get_category_relationships() -> Vec<CategoryRelationship> {
+pub fn default_category_relationships() -> Vec<CategoryRelationship> {
     vec![
         CategoryRelationship {
             id: None,
@@ -264,12 +357,38 @@ pub fn get_category_relationships() -> Vec<CategoryRelationship> {
         },
     ]
 }
+// Boost is added to the signal's own category; decay is taken from
+// unrelated categories. Purchases sit above tier 1 (queries), then product
+// views (tier 2) and searches (tier 3).
+pub const PURCHASE_BOOST: f64 = 5.0;
 pub const TIER_1_BOOST: f64 = 3.0;
 pub const TIER_2_BOOST: f64 = 2.0;
 pub const TIER_3_BOOST: f64 = 1.0;
+pub const PURCHASE_DECAY: f64 = 0.4;
 pub const TIER_1_DECAY: f64 = 0.3;
 pub const TIER_2_DECAY: f64 = 0.2;
 pub const TIER_3_DECAY: f64 = 0.1;
 pub const TIME_DECAY_FACTOR: f64 = 0.95;
+pub const TIME_DECAY_PERIOD_SECS: i64 = 24 * 60 * 60;
+pub const DEFAULT_TIME_DECAY_INTERVAL_SECS: u64 = 60 * 60;
+pub const DEFAULT_TIME_DECAY_BATCH_SIZE: i64 = 500;
 
 pub const COLLECTIONS_USER_CATEGORY_SIGNALS: &str = "user_category_signals";
+pub const COLLECTIONS_CATEGORY_RELATIONSHIPS: &str = "category_relationships";
+pub const COLLECTIONS_FEATURED_PRODUCTS: &str = "featured_products";
+pub const COLLECTIONS_USER_LAST_PRODUCTS: &str = "user_last_products";
+pub const COLLECTIONS_RECOMMENDATION_SUPPRESSIONS: &str = "recommendation_suppressions";
+pub const DEFAULT_NOT_INTERESTED_WINDOW_SECS: u64 = 30 * 24 * 60 * 60;
+// Signal and relevance multiplier for categories the user marked as not
+// interesting; low enough to sink them below unsuppressed categories.
+pub const SUPPRESSED_CATEGORY_WEIGHT: f64 = 0.2;
+pub const MAX_RECENTLY_VIEWED: u64 = 20;
+pub const MAX_FEATURED_PRODUCTS: usize = 12;
+pub const SIMILAR_PRODUCTS_SEED_COUNT: i64 = 10;
+pub const RECOMMENDATION_ROW_SIZE: usize = 6;
+pub const TOP_CATEGORY_ROWS: i64 = 3;
+pub const MAX_RELATED_CATEGORIES: usize = 3;
+pub const TRENDING_WINDOW_SECS: u64 = 7 * 24 * 60 * 60;
+pub const TRENDING_CANDIDATES: i64 = 50;
+pub const MAX_RELATIONSHIP_STRENGTH: f64 = 1.0;
+pub const CATEGORY_RELATIONSHIPS_CACHE_TTL_SECS: u64 = 5 * 60;
@@ -0,0 +1,415 @@
+use axum::http::StatusCode;
+use futures::TryStreamExt;
+use mongodb::{
+    Collection, Database, IndexModel,
+    bson::doc,
+    error::{ErrorKind, WriteFailure},
+    options::IndexOptions,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use super::schemas::*;
+use crate::{
+    DB,
+    apex::utils::VerboseHTTPError,
+    auth::schemas::UserOut,
+    orders::schemas::{COLLECTIONS_ORDERS, Order},
+    products::schemas::Product,
+};
+
+pub async fn ensure_review_indexes() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let reviews: Collection<Review> = database.collection(COLLECTIONS_REVIEWS);
+
+    let indexes = [
+        IndexModel::builder()
+            .keys(doc! { "product_id": 1, "buyer_id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build(),
+        IndexModel::builder()
+            .keys(doc! { "product_id": 1, "created_at": -1 })
+            .build(),
+    ];
+
+    for index in indexes {
+        if let Err(error) = reviews.create_index(index).await {
+            tracing::warn!("Failed to create review index: {}", error);
+        }
+    }
+}
+
+pub async fn backfill_product_ratings() {
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let reviews: Collection<Review> = database.collection(COLLECTIONS_REVIEWS);
+    let product_ids = match reviews.distinct("product_id", doc! {}).await {
+        Ok(product_ids) => product_ids,
+        Err(error) => {
+            tracing::warn!("Failed to list reviewed products: {}", error);
+            return;
+        }
+    };
+
+    for product_id in product_ids.iter().filter_map(|id| id.as_str()) {
+        refresh_product_rating(database, product_id).await;
+    }
+}
+
+async fn refresh_product_rating(database: &Database, product_id: &str) {
+    let reviews: Collection<Review> = database.collection(COLLECTIONS_REVIEWS);
+
+    let pipeline = vec![
+        doc! { "$match": { "product_id": product_id } },
+        doc! {
+            "$group": {
+                "_id": null,
+                "avg_rating": { "$avg": "$rating" },
+                "review_count": { "$sum": 1 }
+            }
+        },
+    ];
+
+    let summary = match reviews.aggregate(pipeline).await {
+        Ok(mut cursor) => cursor.try_next().await.ok().flatten(),
+        Err(error) => {
+            tracing::warn!("Failed to aggregate ratings for {}: {}", product_id, error);
+            return;
+        }
+    };
+
+    let (avg_rating, review_count) = summary
+        .map(|summary| {
+            (
+                summary.get_f64("avg_rating").unwrap_or(0.0),
+                summary.get_i32("review_count").unwrap_or(0) as i64,
+            )
+        })
+        .unwrap_or((0.0, 0));
+
+    let products: Collection<Product> = database.collection("products");
+    if let Err(error) = products
+        .update_one(
+            doc! { "product_id": product_id },
+            doc! {
+                "$set": {
+                    "avg_rating": (avg_rating * 100.0).round() / 100.0,
+                    "review_count": review_count
+                }
+            },
+        )
+        .await
+    {
+        tracing::warn!("Failed to update rating for {}: {}", product_id, error);
+    }
+}
+
+async fn review_eligibility(
+    database: &Database,
+    user: &UserOut,
+    product_id: &str,
+) -> Result<Result<(Product, String), ReviewIneligibility>, VerboseHTTPError> {
+    let database_error = |_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
+    };
+
+    let products: Collection<Product> = database.collection("products");
+    let product = products
+        .find_one(doc! { "product_id": product_id })
+        .await
+        .map_err(database_error)?
+        .ok_or_else(VerboseHTTPError::product_not_found)?;
+
+    if product.user_id == user.uid {
+        return Ok(Err(ReviewIneligibility::OwnProduct));
+    }
+
+    let reviews: Collection<Review> = database.collection(COLLECTIONS_REVIEWS);
+    let already_reviewed = reviews
+        .count_documents(doc! { "product_id": product_id, "buyer_id": &user.uid })
+        .await
+        .map_err(database_error)?
+        > 0;
+
+    if already_reviewed {
+        return Ok(Err(ReviewIneligibility::AlreadyReviewed));
+    }
+
+    let orders: Collection<Order> = database.collection(COLLECTIONS_ORDERS);
+    let delivered_order = orders
+        .find_one(doc! {
+            "product_id": product_id,
+            "buyer_id": &user.uid,
+            "status": "delivered"
+        })
+        .await
+        .map_err(database_error)?;
+
+    Ok(match delivered_order {
+        Some(order) => Ok((product, order.order_id)),
+        None => Err(ReviewIneligibility::NoDeliveredOrder),
+    })
+}
+
+pub async fn can_review(
+    user: &UserOut,
+    product_id: &str,
+) -> Result<CanReviewResponse, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    Ok(
+        match review_eligibility(database, user, product_id).await? {
+            Ok(_) => CanReviewResponse {
+                can_review: true,
+                reason: None,
+            },
+            Err(reason) => CanReviewResponse {
+                can_review: false,
+                reason: Some(reason),
+            },
+        },
+    )
+}
+
+fn sanitize_text(text: &str, max_length: usize, label: &str) -> Result<String, VerboseHTTPError> {
+    let text = text.trim();
+
+    if text.chars().count() > max_length {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!("{} cannot exceed {} characters", label, max_length),
+        ));
+    }
+
+    if text.chars().any(|c| c.is_control() && c != '\n') {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!("{} contains invalid characters", label),
+        ));
+    }
+
+    Ok(text.to_string())
+}
+
+fn normalize_review_text(text: Option<String>) -> Result<Option<String>, VerboseHTTPError> {
+    let Some(text) = text else {
+        return Ok(None);
+    };
+
+    let text = sanitize_text(&text, MAX_REVIEW_TEXT_LENGTH, "Review text")?;
+    Ok((!text.is_empty()).then_some(text))
+}
+
+pub async fn create_review(
+    user: &UserOut,
+    product_id: &str,
+    request: CreateReviewRequest,
+) -> Result<Review, VerboseHTTPError> {
+    if !(MIN_RATING..=MAX_RATING).contains(&request.rating) {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!("Rating must be between {} and {}", MIN_RATING, MAX_RATING),
+        ));
+    }
+
+    let text = normalize_review_text(request.text)?;
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let (product, order_id) = match review_eligibility(database, user, product_id).await? {
+        Ok(eligible) => eligible,
+        Err(ReviewIneligibility::AlreadyReviewed) => {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::CONFLICT,
+                "You have already reviewed this product".to_string(),
+            ));
+        }
+        Err(ReviewIneligibility::OwnProduct) => {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::FORBIDDEN,
+                "You cannot review your own product".to_string(),
+            ));
+        }
+        Err(ReviewIneligibility::NoDeliveredOrder) => {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::FORBIDDEN,
+                "Only buyers with a delivered order can review this product".to_string(),
+            ));
+        }
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let review = Review {
+        review_id: Uuid::new_v4().to_string(),
+        product_id: product.product_id,
+        seller_id: product.user_id,
+        buyer_id: user.uid.clone(),
+        username: user.username.clone(),
+        order_id,
+        rating: request.rating,
+        text,
+        seller_response: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let reviews: Collection<Review> = database.collection(COLLECTIONS_REVIEWS);
+
+    match reviews.insert_one(&review).await {
+        Ok(_) => {
+            refresh_product_rating(database, &review.product_id).await;
+            Ok(review)
+        }
+        Err(error) => match *error.kind {
+            ErrorKind::Write(WriteFailure::WriteError(ref write_error))
+                if write_error.code == 11000 =>
+            {
+                Err(VerboseHTTPError::Standard(
+                    StatusCode::CONFLICT,
+                    "You have already reviewed this product".to_string(),
+                ))
+            }
+            _ => Err(VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save review".to_string(),
+            )),
+        },
+    }
+}
+
+pub async fn respond_to_review(
+    user: &UserOut,
+    product_id: &str,
+    review_id: &str,
+    request: RespondToReviewRequest,
+) -> Result<Review, VerboseHTTPError> {
+    let text = sanitize_text(&request.text, MAX_SELLER_RESPONSE_LENGTH, "Response")?;
+    if text.is_empty() {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Response cannot be empty".to_string(),
+        ));
+    }
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let reviews: Collection<Review> = database.collection(COLLECTIONS_REVIEWS);
+    let filter = doc! { "review_id": review_id, "product_id": product_id };
+
+    let review = reviews
+        .find_one(filter.clone())
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or_else(|| {
+            VerboseHTTPError::Standard(StatusCode::NOT_FOUND, "Review not found".to_string())
+        })?;
+
+    if review.seller_id != user.uid {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::FORBIDDEN,
+            "Only the seller of this product can respond to its reviews".to_string(),
+        ));
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let seller_response = SellerResponse {
+        text,
+        created_at: now,
+    };
+
+    reviews
+        .update_one(
+            filter,
+            doc! {
+                "$set": {
+                    "seller_response": {
+                        "text": &seller_response.text,
+                        "created_at": now as i64
+                    }
+                }
+            },
+        )
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save response".to_string(),
+            )
+        })?;
+
+    Ok(Review {
+        seller_response: Some(seller_response),
+        ..review
+    })
+}
+
+pub async fn list_product_reviews(
+    product_id: &str,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<Review>, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let reviews: Collection<Review> = database.collection(COLLECTIONS_REVIEWS);
+
+    let cursor = reviews
+        .find(doc! { "product_id": product_id })
+        .sort(doc! { "created_at": -1 })
+        .skip(offset as u64)
+        .limit(limit as i64)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    cursor.try_collect().await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
+    })
+}
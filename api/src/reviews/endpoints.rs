@@ -0,0 +1,74 @@
+use axum::{
+    Json,
+    extract::{Extension, Path, Query},
+    response::IntoResponse,
+};
+use serde_json::json;
+
+use super::{
+    delegates::{can_review, create_review, list_product_reviews, respond_to_review},
+    schemas::{
+        CreateReviewRequest, DEFAULT_REVIEW_LIMIT, ListReviewsQuery, RespondToReviewRequest,
+    },
+};
+use crate::{apex::config::config, auth::schemas::UserOut};
+
+pub(crate) async fn can_review_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(product_id): Path<String>,
+) -> impl IntoResponse {
+    match can_review(&user, &product_id).await {
+        Ok(response) => Json(response).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn create_review_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path(product_id): Path<String>,
+    Json(request): Json<CreateReviewRequest>,
+) -> impl IntoResponse {
+    match create_review(&user, &product_id, request).await {
+        Ok(review) => Json(json!({
+            "status": "ok",
+            "review": review
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn respond_to_review_endpoint(
+    Extension(user): Extension<UserOut>,
+    Path((product_id, review_id)): Path<(String, String)>,
+    Json(request): Json<RespondToReviewRequest>,
+) -> impl IntoResponse {
+    match respond_to_review(&user, &product_id, &review_id, request).await {
+        Ok(review) => Json(json!({
+            "status": "ok",
+            "review": review
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub(crate) async fn list_reviews_endpoint(
+    Path(product_id): Path<String>,
+    Query(query): Query<ListReviewsQuery>,
+) -> impl IntoResponse {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_REVIEW_LIMIT)
+        .min(config().max_page_limit);
+    let offset = query.offset.unwrap_or(0);
+
+    match list_product_reviews(&product_id, limit, offset).await {
+        Ok(reviews) => Json(json!({
+            "status": "ok",
+            "reviews": reviews
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
@@ -0,0 +1,3 @@
+pub(crate) mod delegates;
+pub(crate) mod endpoints;
+pub(crate) mod schemas;
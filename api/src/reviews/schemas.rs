@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+pub const COLLECTIONS_REVIEWS: &str = "reviews";
+pub const MIN_RATING: u8 = 1;
+pub const MAX_RATING: u8 = 5;
+pub const MAX_REVIEW_TEXT_LENGTH: usize = 2000;
+pub const MAX_SELLER_RESPONSE_LENGTH: usize = 1000;
+pub const DEFAULT_REVIEW_LIMIT: u32 = 20;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Review {
+    pub review_id: String,
+    pub product_id: String,
+    pub seller_id: String,
+    pub buyer_id: String,
+    pub username: String,
+    pub order_id: String,
+    pub rating: u8,
+    pub text: Option<String>,
+    #[serde(default)]
+    pub seller_response: Option<SellerResponse>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SellerResponse {
+    pub text: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateReviewRequest {
+    pub rating: u8,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RespondToReviewRequest {
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ListReviewsQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewIneligibility {
+    OwnProduct,
+    AlreadyReviewed,
+    NoDeliveredOrder,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CanReviewResponse {
+    pub can_review: bool,
+    pub reason: Option<ReviewIneligibility>,
+}
@@ -0,0 +1,79 @@
+use axum::http::StatusCode;
+use reqwest::{RequestBuilder, Response};
+use std::time::Duration;
+
+use crate::apex::{
+    circuit::{CircuitBreaker, CircuitRequestError, CircuitStatus},
+    config::config,
+    utils::{ERROR_CODE_CLIP_UNAVAILABLE, VerboseHTTPError},
+};
+
+// Shared by every CLIP caller so a degraded service is detected once and
+// searches/product writes stop paying the full request timeout each time.
+static CLIP_CIRCUIT: CircuitBreaker = CircuitBreaker::new("CLIP");
+
+pub fn clip_request_error(error: CircuitRequestError, message: &str) -> VerboseHTTPError {
+    match error {
+        CircuitRequestError::Open => VerboseHTTPError::Coded(
+            StatusCode::SERVICE_UNAVAILABLE,
+            ERROR_CODE_CLIP_UNAVAILABLE,
+            "Embedding service is temporarily unavailable".to_string(),
+        ),
+        CircuitRequestError::Failed => {
+            VerboseHTTPError::Standard(StatusCode::INTERNAL_SERVER_ERROR, message.to_string())
+        }
+    }
+}
+
+#[inline]
+pub fn is_clip_unavailable(error: &VerboseHTTPError) -> bool {
+    matches!(
+        error,
+        VerboseHTTPError::Coded(_, ERROR_CODE_CLIP_UNAVAILABLE, _)
+    )
+}
+
+#[inline]
+pub fn clip_circuit_status() -> CircuitStatus {
+    CLIP_CIRCUIT.status(Duration::from_secs(config().clip_cooldown_secs))
+}
+
+#[inline]
+pub async fn send_clip_request(request: RequestBuilder) -> Result<Response, CircuitRequestError> {
+    CLIP_CIRCUIT
+        .send(
+            request,
+            config().clip_failure_threshold,
+            Duration::from_secs(config().clip_cooldown_secs),
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{delegates::vector_search, schemas::SearchFilters};
+
+    #[tokio::test]
+    async fn searches_skip_clip_while_the_circuit_is_open() {
+        let threshold = config().clip_failure_threshold;
+        for _ in 0..threshold {
+            CLIP_CIRCUIT.record_failure(threshold);
+        }
+
+        // Rejected before any request or database access.
+        let result = vector_search(
+            &Some("red running shoes".to_string()),
+            &[],
+            &SearchFilters::default(),
+            0.5,
+            None,
+            10,
+            0,
+        )
+        .await;
+        CLIP_CIRCUIT.record_success();
+
+        assert!(matches!(result, Err(ref error) if is_clip_unavailable(error)));
+    }
+}
@@ -3,18 +3,30 @@ use bytes::Bytes;
 use futures::TryStreamExt;
 use mongodb::{
     Collection,
-    bson::{Document, doc},
+    bson::{Bson, Document, doc, from_document},
 };
-use std::{collections::HashMap, env::var, time::SystemTime};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env::var,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use uuid::Uuid;
 
 use super::{
-    preprocessing::{create_search_variants, has_stopwords, preprocess_text},
+    clip::{clip_request_error, send_clip_request},
+    groq::{groq_circuit_open, send_groq_request},
+    preprocessing::{create_search_variants, escape_regex, has_stopwords, preprocess_text},
     schemas::*,
 };
 use crate::{
     DB,
-    apex::utils::VerboseHTTPError,
+    apex::{
+        config::config,
+        utils::{ERROR_CODE_RATE_LIMITED, VerboseHTTPError, normalize_embedding},
+    },
+    auth::schemas::{UserOut, normalize_region},
     products::schemas::{Product, ProductCategory, ProductQuantity, ProductType},
+    reviews::schemas::MAX_RATING,
 };
 
 pub async fn optimized_search_products(
@@ -26,9 +38,69 @@ pub async fn optimized_search_products(
     let limit = request
         .limit
         .unwrap_or(DEFAULT_SEARCH_LIMIT)
-        .min(MAX_SEARCH_RESULTS);
+        .min(config().max_search_results);
+    let offset = request.offset.unwrap_or(0);
+
+    if offset > MAX_SEARCH_OFFSET {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!("Offset cannot exceed {}", MAX_SEARCH_OFFSET),
+        ));
+    }
+
+    for price in [request.price_min, request.price_max].into_iter().flatten() {
+        if !price.is_finite() || price < 0.0 {
+            return Err(VerboseHTTPError::Standard(
+                StatusCode::BAD_REQUEST,
+                "Price filters must be non-negative numbers".to_string(),
+            ));
+        }
+    }
+
+    if let (Some(price_min), Some(price_max)) = (request.price_min, request.price_max)
+        && price_min > price_max
+    {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "price_min cannot be greater than price_max".to_string(),
+        ));
+    }
+
+    let region = match request.region.as_deref().map(str::trim) {
+        Some("") | None => None,
+        Some(region) => Some(normalize_region(region).ok_or_else(|| {
+            VerboseHTTPError::Standard(StatusCode::BAD_REQUEST, "Invalid region".to_string())
+        })?),
+    };
+
+    if let Some(min_rating) = request.min_rating
+        && (!min_rating.is_finite() || !(0.0..=MAX_RATING as f64).contains(&min_rating))
+    {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!("min_rating must be between 0 and {}", MAX_RATING),
+        ));
+    }
+
+    let sort = request.sort.unwrap_or(SearchSort::Relevance);
+    let order = request.order.unwrap_or(SortOrder::Desc);
+    let debug = request.debug;
+
+    let min_similarity = request
+        .min_similarity
+        .filter(|threshold| threshold.is_finite())
+        .map(|threshold| threshold.clamp(0.0, 1.0))
+        .unwrap_or(SEARCH_SIMILARITY_THRESHOLD);
 
     let filters = SearchFilters {
+        category: request.category,
+        product_type: request.product_type,
+        price_min: request.price_min,
+        price_max: request.price_max,
+        has_images: request.has_images,
+        attributes: request.attributes.clone(),
+        region,
+        min_rating: request.min_rating.filter(|min_rating| *min_rating > 0.0),
         enabled_only: true,
         ..Default::default()
     };
@@ -36,6 +108,7 @@ pub async fn optimized_search_products(
     let mut enhanced_query = None;
     let mut ai_enhancement_triggered = false;
     let mut inferred_category = None;
+    let mut query_too_short = false;
 
     let final_query = match request.query {
         Some(ref query) => {
@@ -51,9 +124,10 @@ pub async fn optimized_search_products(
 
             if query.trim().is_empty() {
                 None
-            } else if (query.len() > 10 || has_stopwords(query))
-                && !request.force_original.unwrap_or(false)
-            {
+            } else if query.trim().chars().count() < config().min_query_length {
+                query_too_short = true;
+                None
+            } else if wants_query_enhancement(query, request.force_original.unwrap_or(false)) {
                 ai_enhancement_triggered = true;
                 match enhance_query_with_ai(query).await {
                     Ok((enhanced, category)) => {
@@ -75,47 +149,107 @@ pub async fn optimized_search_products(
         None => None,
     };
 
-    let results = match final_query {
+    let window = offset + limit;
+    // An explicit sort is applied in the database to every match above the
+    // similarity cutoff, before paging.
+    let explicit_sort = sort_document(sort, order);
+
+    let SearchPage {
+        mut results,
+        total: total_count,
+        attribute_facets,
+    } = match final_query {
         Some(ref query_text) => {
             match vector_search(
                 &Some(query_text.clone()),
                 &image_files,
                 &filters,
-                limit * 2,
+                min_similarity,
+                explicit_sort.as_ref(),
+                window * 2,
                 0,
             )
             .await
             {
-                Ok(vector_results) if !vector_results.is_empty() => {
-                    match text_search(query_text, &filters, limit, 0).await {
-                        Ok(text_results) => {
-                            hybrid_combine_results(vector_results, text_results, limit, 0)
+                Ok(vector_page) if !vector_page.results.is_empty() => {
+                    match text_search(query_text, &filters, explicit_sort.as_ref(), window, 0).await
+                    {
+                        Ok(text_page) => {
+                            let (page, combined_total) = hybrid_combine_results(
+                                vector_page.results,
+                                text_page.results,
+                                sort,
+                                order,
+                                limit,
+                                offset,
+                            );
+                            SearchPage {
+                                results: page,
+                                total: combined_total.max(vector_page.total).max(text_page.total),
+                                attribute_facets: merge_attribute_facets(
+                                    vector_page.attribute_facets,
+                                    text_page.attribute_facets,
+                                ),
+                            }
                         }
-                        Err(_) => vector_results.into_iter().take(limit as usize).collect(),
+                        Err(_) => with_debug_source(
+                            SearchPage {
+                                results: vector_page
+                                    .results
+                                    .into_iter()
+                                    .skip(offset as usize)
+                                    .take(limit as usize)
+                                    .collect(),
+                                ..vector_page
+                            },
+                            SearchResultSource::Vector,
+                        ),
                     }
                 }
-                Ok(_) => text_search(query_text, &filters, limit, 0)
-                    .await
-                    .unwrap_or_default(),
-                Err(_) => text_search(query_text, &filters, limit, 0)
-                    .await
-                    .unwrap_or_default(),
+                Ok(_) | Err(_) => with_debug_source(
+                    text_search(query_text, &filters, explicit_sort.as_ref(), limit, offset)
+                        .await
+                        .unwrap_or_default(),
+                    SearchResultSource::Text,
+                ),
             }
         }
         None if !image_files.is_empty() => {
-            match vector_search(&None, &image_files, &filters, limit, 0).await {
-                Ok(results) => results,
-                Err(_) => browse_products(&filters, limit, 0)
+            match vector_search(
+                &None,
+                &image_files,
+                &filters,
+                min_similarity,
+                explicit_sort.as_ref(),
+                limit,
+                offset,
+            )
+            .await
+            {
+                Ok(results) => with_debug_source(results, SearchResultSource::Vector),
+                Err(_) => browse_products(&filters, sort, order, limit, offset)
                     .await
                     .unwrap_or_default(),
             }
         }
-        None => browse_products(&filters, limit, 0)
+        None => browse_products(&filters, sort, order, limit, offset)
             .await
             .unwrap_or_default(),
     };
 
-    let total_count = results.len() as u64;
+    if !debug {
+        for result in &mut results {
+            result.debug_info = None;
+        }
+    }
+
+    let did_you_mean = match request.query {
+        Some(ref query) if results.is_empty() && !query.trim().is_empty() && !query_too_short => {
+            suggest_query_correction(query).await.unwrap_or(None)
+        }
+        _ => None,
+    };
+
     let processing_time = start_time.elapsed().unwrap_or_default().as_millis() as u64;
 
     Ok(SimpleSearchResponse {
@@ -125,9 +259,229 @@ pub async fn optimized_search_products(
         ai_enhancement_triggered,
         processing_time_ms: processing_time,
         inferred_category,
+        did_you_mean,
+        query_too_short,
+        attribute_facets,
+        similarity_threshold: min_similarity,
+    })
+}
+
+pub async fn record_search_feedback(
+    user: Option<&UserOut>,
+    client_ip: Option<String>,
+    request: SearchFeedbackRequest,
+) -> Result<(), VerboseHTTPError> {
+    let query = request.query.trim();
+    if query.is_empty() {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Query cannot be empty".to_string(),
+        ));
+    }
+
+    if query.len() > MAX_SEARCH_QUERY_LENGTH {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Query too long. Maximum {} characters allowed",
+                MAX_SEARCH_QUERY_LENGTH
+            ),
+        ));
+    }
+
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let products: Collection<Product> = database.collection("products");
+    let product_exists = products
+        .count_documents(doc! { "product_id": &request.product_id })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        > 0;
+
+    if !product_exists {
+        return Err(VerboseHTTPError::product_not_found());
+    }
+
+    let user_id = user.map(|u| u.uid.clone());
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let mut rate_filter = match (&user_id, &client_ip) {
+        (Some(uid), _) => doc! { "user_id": uid },
+        (None, Some(ip)) => doc! { "user_id": null, "client_ip": ip },
+        (None, None) => doc! { "user_id": null, "client_ip": null },
+    };
+    rate_filter.insert(
+        "created_at",
+        doc! { "$gte": now.saturating_sub(SEARCH_FEEDBACK_RATE_WINDOW_SECS) as i64 },
+    );
+
+    let feedback: Collection<SearchFeedback> = database.collection(COLLECTIONS_SEARCH_FEEDBACK);
+
+    let recent_count = feedback.count_documents(rate_filter).await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
+    })?;
+
+    if recent_count >= SEARCH_FEEDBACK_RATE_LIMIT {
+        return Err(VerboseHTTPError::Coded(
+            StatusCode::TOO_MANY_REQUESTS,
+            ERROR_CODE_RATE_LIMITED,
+            "Too much feedback submitted. Please try again later".to_string(),
+        ));
+    }
+
+    let entry = SearchFeedback {
+        query: query.to_string(),
+        product_id: request.product_id,
+        signal: request.signal,
+        client_ip: if user_id.is_some() { None } else { client_ip },
+        user_id,
+        created_at: now,
+    };
+
+    feedback.insert_one(&entry).await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to record feedback".to_string(),
+        )
+    })?;
+
+    Ok(())
+}
+
+pub async fn record_search_history(user: &UserOut, query: &str) {
+    let query = query.trim();
+    if query.is_empty() {
+        return;
+    }
+
+    let Some(database) = DB.get() else {
+        return;
+    };
+
+    let history: Collection<SearchHistoryEntry> = database.collection(COLLECTIONS_SEARCH_HISTORY);
+
+    if let Ok(Some(latest)) = history
+        .find_one(doc! { "user_id": &user.uid })
+        .sort(doc! { "created_at": -1 })
+        .await
+        && latest.query == query
+    {
+        return;
+    }
+
+    let entry = SearchHistoryEntry {
+        entry_id: Uuid::new_v4().to_string(),
+        user_id: user.uid.clone(),
+        query: query.to_string(),
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+
+    if history.insert_one(&entry).await.is_err() {
+        return;
+    }
+
+    let Ok(cursor) = history
+        .find(doc! { "user_id": &user.uid })
+        .sort(doc! { "created_at": -1 })
+        .skip(SEARCH_HISTORY_LIMIT)
+        .await
+    else {
+        return;
+    };
+
+    let stale: Vec<SearchHistoryEntry> = cursor.try_collect().await.unwrap_or_default();
+    if stale.is_empty() {
+        return;
+    }
+
+    let stale_ids: Vec<String> = stale.into_iter().map(|entry| entry.entry_id).collect();
+    let _ = history
+        .delete_many(doc! { "entry_id": { "$in": stale_ids } })
+        .await;
+}
+
+pub async fn get_search_history(user: &UserOut) -> Result<SearchHistoryResponse, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let history: Collection<SearchHistoryEntry> = database.collection(COLLECTIONS_SEARCH_HISTORY);
+
+    let cursor = history
+        .find(doc! { "user_id": &user.uid })
+        .sort(doc! { "created_at": -1 })
+        .limit(SEARCH_HISTORY_LIMIT as i64)
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    let entries: Vec<SearchHistoryEntry> = cursor.try_collect().await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
+    })?;
+
+    Ok(SearchHistoryResponse {
+        history: entries
+            .into_iter()
+            .map(|entry| SearchHistoryItem {
+                query: entry.query,
+                created_at: entry.created_at,
+            })
+            .collect(),
     })
 }
 
+pub async fn clear_search_history(user: &UserOut) -> Result<u64, VerboseHTTPError> {
+    let Some(database) = DB.get() else {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database unavailable".to_string(),
+        ));
+    };
+
+    let history: Collection<SearchHistoryEntry> = database.collection(COLLECTIONS_SEARCH_HISTORY);
+
+    let result = history
+        .delete_many(doc! { "user_id": &user.uid })
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to clear search history".to_string(),
+            )
+        })?;
+
+    Ok(result.deleted_count)
+}
+
 async fn enhance_query_with_ai(
     query: &str,
 ) -> Result<(String, Option<crate::products::schemas::ProductCategory>), VerboseHTTPError> {
@@ -188,31 +542,20 @@ Important: Do not include any other text, explanations, or formatting like markd
 
     let client = reqwest::Client::new();
 
-    let response = client
-        .post(GROQ_API_ENDPOINT)
-        .header("Authorization", format!("Bearer {}", groq_api_key))
-        .header("Content-Type", "application/json")
-        .json(&enhancement_request)
-        .send()
-        .await
-        .map_err(|_| {
-            VerboseHTTPError::Standard(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to call Groq API for query enhancement".to_string(),
-            )
-        })?;
-
-    let status_code = response.status();
-
-    if !status_code.is_success() {
-        return Err(VerboseHTTPError::Standard(
+    let response = send_groq_request(
+        client
+            .post(GROQ_API_ENDPOINT)
+            .header("Authorization", format!("Bearer {}", groq_api_key))
+            .header("Content-Type", "application/json")
+            .json(&enhancement_request),
+    )
+    .await
+    .map_err(|_| {
+        VerboseHTTPError::Standard(
             StatusCode::INTERNAL_SERVER_ERROR,
-            format!(
-                "Groq API request failed for query enhancement: {}",
-                status_code
-            ),
-        ));
-    }
+            "Groq API request failed for query enhancement".to_string(),
+        )
+    })?;
 
     let response_text = response.text().await.map_err(|_| {
         VerboseHTTPError::Standard(
@@ -240,7 +583,10 @@ Important: Do not include any other text, explanations, or formatting like markd
     if let Some(content) = &choice.message.content {
         if let Ok(parsed_json) = serde_json::from_str::<GroqEnhancementResponse>(content) {
             let enhanced_query = parsed_json.enhanced_query.trim().to_string();
-            return Ok((enhanced_query, parsed_json.category));
+            return Ok((
+                enhanced_query,
+                parse_inferred_category(parsed_json.category),
+            ));
         }
 
         let cleaned_content = content
@@ -252,7 +598,10 @@ Important: Do not include any other text, explanations, or formatting like markd
 
         if let Ok(parsed_json) = serde_json::from_str::<GroqEnhancementResponse>(cleaned_content) {
             let enhanced_query = parsed_json.enhanced_query.trim().to_string();
-            return Ok((enhanced_query, parsed_json.category));
+            return Ok((
+                enhanced_query,
+                parse_inferred_category(parsed_json.category),
+            ));
         }
 
         let fallback_query = cleaned_content.to_string();
@@ -262,13 +611,284 @@ Important: Do not include any other text, explanations, or formatting like markd
     Ok((query.to_string(), None))
 }
 
+fn parse_inferred_category(category: Option<serde_json::Value>) -> Option<ProductCategory> {
+    category?.as_str().and_then(parse_category_name)
+}
+
+fn parse_category_name(name: &str) -> Option<ProductCategory> {
+    let normalized = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    ProductCategory::ALL.into_iter().find(|candidate| {
+        serde_json::to_value(candidate)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_ascii_lowercase))
+            .is_some_and(|candidate_name| candidate_name == normalized)
+    })
+}
+
+pub fn is_allowed_audio_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+
+    ALLOWED_AUDIO_TYPES.contains(&mime.as_str())
+}
+
+// Normalizes an optional ISO-639-1 code and checks it against `allowed`.
+// No language lets Whisper detect it.
+pub fn validate_transcription_language(
+    language: Option<&str>,
+    allowed: &[String],
+) -> Result<Option<String>, VerboseHTTPError> {
+    let Some(language) = language
+        .map(|language| language.trim().to_ascii_lowercase())
+        .filter(|language| !language.is_empty())
+    else {
+        return Ok(None);
+    };
+
+    if !allowed.contains(&language) {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Unsupported transcription language '{}'; supported: {}",
+                language,
+                allowed.join(", ")
+            ),
+        ));
+    }
+
+    Ok(Some(language))
+}
+
+pub async fn process_audio(
+    task: AudioTask,
+    file_name: String,
+    data: Bytes,
+    content_type: &str,
+    language: Option<&str>,
+) -> Result<String, VerboseHTTPError> {
+    let groq_api_key = var("GROQ_API_KEY").map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "GROQ API key not configured".to_string(),
+        )
+    })?;
+
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or(content_type)
+        .trim();
+    let file_part = reqwest::multipart::Part::bytes(data.to_vec())
+        .file_name(file_name)
+        .mime_str(mime)
+        .map_err(|_| {
+            VerboseHTTPError::Standard(StatusCode::BAD_REQUEST, "Invalid audio type".to_string())
+        })?;
+
+    let mut form = reqwest::multipart::Form::new()
+        .text("model", GROQ_WHISPER_MODEL)
+        .text("response_format", "json")
+        .part("file", file_part);
+
+    if let Some(language) = language {
+        form = form.text("language", language.to_string());
+    }
+
+    let endpoint = match task {
+        AudioTask::Transcribe => GROQ_TRANSCRIPTION_ENDPOINT,
+        AudioTask::Translate => GROQ_TRANSLATION_ENDPOINT,
+    };
+
+    let response = reqwest::Client::new()
+        .post(endpoint)
+        .header("Authorization", format!("Bearer {}", groq_api_key))
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::BAD_GATEWAY,
+                "Failed to call Groq API for audio processing".to_string(),
+            )
+        })?;
+
+    let status_code = response.status();
+    let response_text = response.text().await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::BAD_GATEWAY,
+            "Failed to read Groq response".to_string(),
+        )
+    })?;
+
+    if !status_code.is_success() {
+        let detail = serde_json::from_str::<GroqErrorResponse>(&response_text)
+            .map(|error| error.error.message)
+            .unwrap_or_else(|_| response_text.chars().take(300).collect());
+
+        let status = match status_code.as_u16() {
+            400 | 413 | 415 | 422 => StatusCode::BAD_REQUEST,
+            429 => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::BAD_GATEWAY,
+        };
+
+        return Err(VerboseHTTPError::Standard(
+            status,
+            format!(
+                "Audio processing failed ({}): {}",
+                status_code.as_u16(),
+                detail
+            ),
+        ));
+    }
+
+    let parsed: GroqAudioResponse = serde_json::from_str(&response_text).map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::BAD_GATEWAY,
+            "Failed to parse Groq audio response".to_string(),
+        )
+    })?;
+
+    Ok(parsed.text.trim().to_string())
+}
+
+async fn suggest_query_correction(query: &str) -> Result<Option<String>, VerboseHTTPError> {
+    let groq_api_key = var("GROQ_API_KEY").map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "GROQ API key not configured".to_string(),
+        )
+    })?;
+
+    let prompt = format!(
+        "A shopper searched an e-commerce platform for the following query and got no results.
+
+Query: \"{}\"
+
+If the query looks misspelled or uses an unusual phrasing, suggest the most likely intended product search.
+If the query already looks correct, return null.
+
+Return only a JSON object with this exact format:
+{{
+  \"did_you_mean\": \"corrected search query\" or null
+}}
+
+Important: Do not include any other text, explanations, or formatting like markdown code blocks. Do not call any scripts, functions or attempt to execute any code.",
+        query
+    );
+
+    let suggestion_request = GroqQueryEnhancementRequest {
+        model: GROQ_AI_MODEL.to_string(),
+        messages: vec![
+            GroqMessage {
+                role: "system".to_string(),
+                content: "You are a product search spelling corrector. Respond only with a JSON object. No markdown formatting, script execution, function calls or extra text.".to_string(),
+            },
+            GroqMessage {
+                role: "user".to_string(),
+                content: prompt,
+            },
+        ],
+        temperature: 0.1,
+        max_tokens: 60,
+        response_format: None,
+        tools: None,
+    };
+
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(GROQ_API_ENDPOINT)
+        .header("Authorization", format!("Bearer {}", groq_api_key))
+        .header("Content-Type", "application/json")
+        .json(&suggestion_request)
+        .send()
+        .await
+        .map_err(|_| {
+            VerboseHTTPError::Standard(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to call Groq API for query suggestion".to_string(),
+            )
+        })?;
+
+    if !response.status().is_success() {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Groq API request failed for query suggestion: {}",
+                response.status()
+            ),
+        ));
+    }
+
+    let groq_response: GroqResponse = response.json().await.map_err(|_| {
+        VerboseHTTPError::Standard(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to parse Groq response".to_string(),
+        )
+    })?;
+
+    let Some(content) = groq_response
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+    else {
+        return Ok(None);
+    };
+
+    let cleaned_content = content
+        .trim()
+        .trim_matches('`')
+        .trim_start_matches("json")
+        .trim();
+
+    let suggestion = serde_json::from_str::<GroqSuggestionResponse>(cleaned_content)
+        .ok()
+        .and_then(|parsed| parsed.did_you_mean)
+        .map(|suggestion| suggestion.trim().to_string())
+        .filter(|suggestion| {
+            !suggestion.is_empty() && !suggestion.eq_ignore_ascii_case(query.trim())
+        });
+
+    Ok(suggestion)
+}
+
+// Results that came from a single backend carry their raw similarity (if
+// any) as both the component and the final score.
+fn with_debug_source(mut page: SearchPage, source: SearchResultSource) -> SearchPage {
+    for result in &mut page.results {
+        result.debug_info = Some(SearchDebugInfo {
+            source,
+            vector_score: match source {
+                SearchResultSource::Text => None,
+                _ => result.similarity_score,
+            },
+            text_score: None,
+            final_score: result.similarity_score,
+        });
+    }
+
+    page
+}
+
 #[inline]
 fn hybrid_combine_results(
     vector_results: Vec<SearchResult>,
     text_results: Vec<SearchResult>,
+    sort: SearchSort,
+    order: SortOrder,
     limit: u32,
     offset: u32,
-) -> Vec<SearchResult> {
+) -> (Vec<SearchResult>, u64) {
     let mut result_map: HashMap<String, SearchResult> =
         HashMap::with_capacity(vector_results.len() + text_results.len());
     let mut scores: HashMap<String, f32> =
@@ -280,6 +900,12 @@ fn hybrid_combine_results(
         let weighted_score = (vector_score * HYBRID_VECTOR_WEIGHT) - position_penalty;
 
         result.similarity_score = Some(weighted_score);
+        result.debug_info = Some(SearchDebugInfo {
+            source: SearchResultSource::Vector,
+            vector_score: Some(vector_score),
+            text_score: None,
+            final_score: Some(weighted_score),
+        });
         scores.insert(result.product_id.clone(), weighted_score);
         result_map.insert(result.product_id.clone(), result);
     }
@@ -298,11 +924,22 @@ fn hybrid_combine_results(
 
                 if let Some(existing_result) = result_map.get_mut(&product_id) {
                     existing_result.similarity_score = Some(combined_score);
+                    if let Some(ref mut debug_info) = existing_result.debug_info {
+                        debug_info.source = SearchResultSource::Both;
+                        debug_info.text_score = Some(text_score);
+                        debug_info.final_score = Some(combined_score);
+                    }
                 }
             }
             None => {
                 let mut new_result = result;
                 new_result.similarity_score = Some(weighted_score);
+                new_result.debug_info = Some(SearchDebugInfo {
+                    source: SearchResultSource::Text,
+                    vector_score: None,
+                    text_score: Some(text_score),
+                    final_score: Some(weighted_score),
+                });
                 scores.insert(product_id.clone(), weighted_score);
                 result_map.insert(product_id, new_result);
             }
@@ -317,60 +954,160 @@ fn hybrid_combine_results(
             .partial_cmp(&score_a)
             .unwrap_or(std::cmp::Ordering::Equal)
     });
+    sort_results(&mut final_results, sort, order);
 
+    let total = final_results.len() as u64;
     let start = offset as usize;
     let end = start + (limit as usize);
 
     if start >= final_results.len() {
-        Vec::new()
+        (Vec::new(), total)
     } else {
-        final_results[start..end.min(final_results.len())].to_vec()
+        (
+            final_results[start..end.min(final_results.len())].to_vec(),
+            total,
+        )
+    }
+}
+
+// Counts attribute key/value pairs across every match, keeping the most
+// common values per key.
+fn attribute_facet_stages() -> Vec<Document> {
+    vec![
+        doc! { "$unwind": "$attributes" },
+        doc! {
+            "$group": {
+                "_id": { "key": "$attributes.key", "value": "$attributes.value" },
+                "count": { "$sum": 1 }
+            }
+        },
+        doc! { "$sort": { "count": -1, "_id.value": 1 } },
+        doc! {
+            "$group": {
+                "_id": "$_id.key",
+                "values": { "$push": { "value": "$_id.value", "count": "$count" } }
+            }
+        },
+        doc! { "$project": { "values": { "$slice": ["$values", MAX_FACET_VALUES] } } },
+        doc! { "$sort": { "_id": 1 } },
+    ]
+}
+
+// Hybrid search matches the union of the vector and text sets; like its
+// total, each facet count is the larger of the two.
+fn merge_attribute_facets(
+    left: Vec<AttributeFacet>,
+    right: Vec<AttributeFacet>,
+) -> Vec<AttributeFacet> {
+    let mut merged: BTreeMap<String, HashMap<String, u64>> = BTreeMap::new();
+
+    for facet in left.into_iter().chain(right) {
+        let values = merged.entry(facet.key).or_default();
+        for value in facet.values {
+            let count = values.entry(value.value).or_default();
+            *count = (*count).max(value.count);
+        }
     }
+
+    merged
+        .into_iter()
+        .map(|(key, values)| {
+            let mut values: Vec<AttributeFacetValue> = values
+                .into_iter()
+                .map(|(value, count)| AttributeFacetValue { value, count })
+                .collect();
+            values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+            values.truncate(MAX_FACET_VALUES as usize);
+            AttributeFacet { key, values }
+        })
+        .collect()
+}
+
+// Longer or conversational queries are rewritten by Groq unless the caller
+// asked for the original or the Groq circuit is open, in which case the query
+// is searched as typed.
+pub(super) fn wants_query_enhancement(query: &str, force_original: bool) -> bool {
+    (query.len() > 10 || has_stopwords(query)) && !force_original && !groq_circuit_open()
 }
 
-async fn vector_search(
+pub(super) async fn vector_search(
     query: &Option<String>,
     image_files: &[(String, Bytes, String)],
     filters: &SearchFilters,
+    min_similarity: f32,
+    sort: Option<&Document>,
     limit: u32,
     offset: u32,
-) -> Result<Vec<SearchResult>, VerboseHTTPError> {
+) -> Result<SearchPage, VerboseHTTPError> {
     let embedding = generate_search_embedding(query, image_files).await?;
 
     let database = DB.get().unwrap();
     let collection: Collection<Product> = database.collection("products");
 
-    match ann_vector_search(&collection, &embedding, filters, limit, offset).await {
-        Ok(results) if !results.is_empty() => Ok(results),
-        Ok(_) => linear_vector_search(&collection, &embedding, filters, limit, offset).await,
-        Err(_) => linear_vector_search(&collection, &embedding, filters, limit, offset).await,
+    match ann_vector_search(
+        &collection,
+        &embedding,
+        filters,
+        min_similarity,
+        sort,
+        limit,
+        offset,
+    )
+    .await
+    {
+        Ok(page) if !page.results.is_empty() => Ok(page),
+        _ => {
+            linear_vector_search(
+                &collection,
+                &embedding,
+                filters,
+                min_similarity,
+                sort,
+                limit,
+                offset,
+            )
+            .await
+        }
     }
 }
 
-async fn ann_vector_search(
-    collection: &Collection<Product>,
-    embedding: &[f32],
-    filters: &SearchFilters,
-    limit: u32,
-    offset: u32,
-) -> Result<Vec<SearchResult>, VerboseHTTPError> {
-    let mut pipeline = vec![];
+/// Builds the `$vectorSearch` stage against the product embeddings index,
+/// sized so that `offset + limit` results survive later filtering stages.
+pub fn vector_search_stage(embedding: &[f32], limit: u32, offset: u32) -> Document {
+    let candidates = vector_search_candidates(
+        limit,
+        offset,
+        config().vector_search_candidates_multiplier,
+        config().max_vector_search_candidates,
+    );
 
-    let candidates = std::cmp::max(
-        MIN_SEARCH_CANDIDATES,
-        limit * VECTOR_SEARCH_CANDIDATES_MULTIPLIER,
-    )
-    .min(1000);
-    let vector_search_stage = doc! {
+    doc! {
         "$vectorSearch": {
             "index": "product_embeddings_index",
             "path": "embedding",
             "queryVector": embedding,
             "numCandidates": candidates,
-            "limit": limit + offset,
+            "limit": candidates,
         }
-    };
-    pipeline.push(vector_search_stage);
+    }
+}
+
+fn vector_search_candidates(limit: u32, offset: u32, multiplier: u32, ceiling: u32) -> u32 {
+    std::cmp::max(MIN_SEARCH_CANDIDATES, limit.saturating_mul(multiplier))
+        .max(offset + limit)
+        .min(ceiling)
+}
+
+async fn ann_vector_search(
+    collection: &Collection<Product>,
+    embedding: &[f32],
+    filters: &SearchFilters,
+    min_similarity: f32,
+    sort: Option<&Document>,
+    limit: u32,
+    offset: u32,
+) -> Result<SearchPage, VerboseHTTPError> {
+    let mut pipeline = vec![vector_search_stage(embedding, limit, offset)];
 
     pipeline.push(doc! {
         "$addFields": {
@@ -385,48 +1122,33 @@ async fn ann_vector_search(
 
     pipeline.push(doc! {
         "$match": {
-            "similarity": { "$gte": SEARCH_SIMILARITY_THRESHOLD }
-        }
-    });
-
-    pipeline.push(doc! {
-        "$lookup": {
-            "from": "users",
-            "localField": "user_id",
-            "foreignField": "uid",
-            "as": "user_info"
+            "similarity": { "$gte": min_similarity }
         }
     });
 
-    if offset > 0 {
-        pipeline.push(doc! { "$skip": offset as i64 });
-    }
-    pipeline.push(doc! { "$limit": limit as i64 });
-
-    let mut cursor = collection.aggregate(pipeline).await.map_err(|_| {
-        VerboseHTTPError::Standard(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "ANN vector search failed".to_string(),
-        )
-    })?;
-
-    let mut results = Vec::new();
-    while let Ok(Some(doc)) = cursor.try_next().await {
-        if let Ok(search_result) = convert_doc_to_search_result(doc) {
-            results.push(search_result);
-        }
+    if let Some(sort) = sort {
+        pipeline.push(doc! { "$sort": sort });
     }
 
-    Ok(results)
+    run_paged_search(
+        collection,
+        pipeline,
+        limit,
+        offset,
+        "ANN vector search failed",
+    )
+    .await
 }
 
 async fn linear_vector_search(
     collection: &Collection<Product>,
     embedding: &[f32],
     filters: &SearchFilters,
+    min_similarity: f32,
+    sort: Option<&Document>,
     limit: u32,
     offset: u32,
-) -> Result<Vec<SearchResult>, VerboseHTTPError> {
+) -> Result<SearchPage, VerboseHTTPError> {
     let mut pipeline = vec![];
 
     let match_stage = build_filter_stage(filters);
@@ -435,72 +1157,65 @@ async fn linear_vector_search(
     }
 
     pipeline.push(doc! {
-        "$addFields": {
-            "similarity": {
-                "$let": {
-                    "vars": {
-                        "dotProduct": {
-                            "$reduce": {
-                                "input": { "$zip": { "inputs": ["$embedding", embedding.to_vec()] } },
-                                "initialValue": 0.0,
-                                "in": { "$add": ["$$value", { "$multiply": [{ "$arrayElemAt": ["$$this", 0] }, { "$arrayElemAt": ["$$this", 1] }] }] }
-                            }
-                        }
-                    },
-                    "in": "$$dotProduct"
-                }
-            }
-        }
+        "$addFields": { "similarity": linear_similarity_expression(embedding) }
     });
 
     pipeline.push(doc! {
         "$match": {
-            "similarity": { "$gte": SEARCH_SIMILARITY_THRESHOLD }
+            "similarity": { "$gte": min_similarity }
         }
     });
 
     pipeline.push(doc! {
-        "$sort": { "similarity": -1 }
-    });
-
-    pipeline.push(doc! { "$skip": offset as i64 });
-    pipeline.push(doc! { "$limit": limit as i64 });
-
-    pipeline.push(doc! {
-        "$lookup": {
-            "from": "users",
-            "localField": "user_id",
-            "foreignField": "uid",
-            "as": "user_info"
-        }
+        "$sort": sort.cloned().unwrap_or_else(|| doc! { "similarity": -1 })
     });
 
-    let mut cursor = collection.aggregate(pipeline).await.map_err(|_| {
-        VerboseHTTPError::Standard(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Linear vector search failed".to_string(),
-        )
-    })?;
+    run_paged_search(
+        collection,
+        pipeline,
+        limit,
+        offset,
+        "Linear vector search failed",
+    )
+    .await
+}
 
-    let mut results = Vec::new();
-    while let Ok(Some(doc)) = cursor.try_next().await {
-        if let Ok(search_result) = convert_doc_to_search_result(doc) {
-            results.push(search_result);
+// (1 + cos) / 2 against the stored embedding, the same scale as the cosine
+// `vectorSearchScore` ANN returns. The query embedding is already unit length.
+fn linear_similarity_expression(embedding: &[f32]) -> Document {
+    doc! {
+        "$let": {
+            "vars": {
+                "dotProduct": {
+                    "$reduce": {
+                        "input": { "$zip": { "inputs": ["$embedding", embedding.to_vec()] } },
+                        "initialValue": 0.0,
+                        "in": { "$add": ["$$value", { "$multiply": [{ "$arrayElemAt": ["$$this", 0] }, { "$arrayElemAt": ["$$this", 1] }] }] }
+                    }
+                },
+                "norm": {
+                    "$sqrt": {
+                        "$reduce": {
+                            "input": "$embedding",
+                            "initialValue": 0.0,
+                            "in": { "$add": ["$$value", { "$multiply": ["$$this", "$$this"] }] }
+                        }
+                    }
+                }
+            },
+            "in": {
+                "$cond": [
+                    { "$gt": ["$$norm", 0.0] },
+                    { "$divide": [{ "$add": [1.0, { "$divide": ["$$dotProduct", "$$norm"] }] }, 2.0] },
+                    0.0
+                ]
+            }
         }
     }
-
-    Ok(results)
 }
 
-async fn text_search(
-    query: &str,
-    filters: &SearchFilters,
-    limit: u32,
-    offset: u32,
-) -> Result<Vec<SearchResult>, VerboseHTTPError> {
-    let database = DB.get().unwrap();
-    let collection: Collection<Product> = database.collection("products");
-
+// The filters plus a title/tag match on any query variant or keyword.
+fn text_match_stage(query: &str, filters: &SearchFilters) -> Document {
     let search_variants = create_search_variants(query);
     let processed_query = preprocess_text(query);
 
@@ -508,10 +1223,11 @@ async fn text_search(
 
     for variant in &search_variants {
         if !variant.is_empty() {
+            let pattern = escape_regex(variant);
             text_conditions.push(doc! {
                 "$or": [
-                    { "title": { "$regex": variant, "$options": "i" } },
-                    { "tags": { "$regex": variant, "$options": "i" } }
+                    { "title": { "$regex": &pattern, "$options": "i" } },
+                    { "tags": { "$regex": &pattern, "$options": "i" } }
                 ]
             });
         }
@@ -521,10 +1237,11 @@ async fn text_search(
         let keywords: Vec<&str> = processed_query.split_whitespace().collect();
         for keyword in keywords {
             if keyword.len() >= 2 {
+                let pattern = escape_regex(keyword);
                 text_conditions.push(doc! {
                     "$or": [
-                        { "title": { "$regex": keyword, "$options": "i" } },
-                        { "tags": { "$regex": keyword, "$options": "i" } }
+                        { "title": { "$regex": &pattern, "$options": "i" } },
+                        { "tags": { "$regex": &pattern, "$options": "i" } }
                     ]
                 });
             }
@@ -533,10 +1250,33 @@ async fn text_search(
 
     let mut match_stage = build_filter_stage(filters);
 
+    // The filters may use `$or` or `$and` themselves (has_images), so the
+    // text match is added as one more `$and` condition rather than
+    // replacing either key.
     if !text_conditions.is_empty() {
-        match_stage.insert("$or", text_conditions);
+        let text_match = doc! { "$or": text_conditions };
+        match match_stage.get_array_mut("$and") {
+            Ok(conditions) => conditions.push(text_match.into()),
+            Err(_) => {
+                match_stage.insert("$and", vec![text_match]);
+            }
+        }
     }
 
+    match_stage
+}
+
+async fn text_search(
+    query: &str,
+    filters: &SearchFilters,
+    sort: Option<&Document>,
+    limit: u32,
+    offset: u32,
+) -> Result<SearchPage, VerboseHTTPError> {
+    let database = DB.get().unwrap();
+    let collection: Collection<Product> = database.collection("products");
+
+    let match_stage = text_match_stage(query, filters);
     let mut pipeline = vec![];
 
     if !match_stage.is_empty() {
@@ -544,41 +1284,144 @@ async fn text_search(
     }
 
     pipeline.push(doc! {
-        "$lookup": {
-            "from": "users",
-            "localField": "user_id",
-            "foreignField": "uid",
-            "as": "user_info"
-        }
+        "$sort": sort.cloned().unwrap_or_else(|| doc! { "created_at": -1 })
     });
 
-    pipeline.push(doc! { "$sort": { "created_at": -1 } });
-    pipeline.push(doc! { "$skip": offset as i64 });
-    pipeline.push(doc! { "$limit": limit as i64 });
+    run_paged_search(&collection, pipeline, limit, offset, "Text search failed").await
+}
 
-    let mut cursor = collection.aggregate(pipeline).await.map_err(|_| {
+pub async fn browse_category(
+    category: &str,
+    query: BrowseCategoryQuery,
+) -> Result<(ProductCategory, Vec<SearchResult>, u64), VerboseHTTPError> {
+    let category = parse_category_name(category).ok_or_else(|| {
         VerboseHTTPError::Standard(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Text search failed".to_string(),
+            StatusCode::BAD_REQUEST,
+            format!("Unknown category '{}'", category),
         )
     })?;
 
-    let mut results = Vec::new();
-    while let Ok(Some(doc)) = cursor.try_next().await {
-        if let Ok(search_result) = convert_doc_to_search_result(doc) {
-            results.push(search_result);
-        } else {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, config().max_search_results);
+    let offset = query.offset.unwrap_or(0);
+
+    if offset > MAX_SEARCH_OFFSET {
+        return Err(VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            format!("Offset cannot exceed {}", MAX_SEARCH_OFFSET),
+        ));
+    }
+
+    let sort_stage = sort_document(
+        query.sort.unwrap_or(SearchSort::CreatedAt),
+        query.order.unwrap_or(SortOrder::Desc),
+    )
+    .ok_or_else(|| {
+        VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Relevance sorting requires a search query".to_string(),
+        )
+    })?;
+
+    let filters = SearchFilters {
+        category: Some(category),
+        enabled_only: true,
+        ..Default::default()
+    };
+
+    let database = DB.get().unwrap();
+    let collection: Collection<Product> = database.collection("products");
+
+    let pipeline = vec![
+        doc! { "$match": build_filter_stage(&filters) },
+        doc! { "$sort": sort_stage },
+    ];
+
+    let page = run_paged_search(
+        &collection,
+        pipeline,
+        limit,
+        offset,
+        "Browse category failed",
+    )
+    .await?;
+
+    Ok((category, page.results, page.total))
+}
+
+fn sort_document(sort: SearchSort, order: SortOrder) -> Option<Document> {
+    let direction = match order {
+        SortOrder::Asc => 1,
+        SortOrder::Desc => -1,
+    };
+
+    match sort {
+        SearchSort::CreatedAt => Some(doc! { "created_at": direction, "product_id": 1 }),
+        SearchSort::Price => {
+            Some(doc! { "price_in_paise": direction, "created_at": -1, "product_id": 1 })
         }
+        SearchSort::Popularity => {
+            Some(doc! { "view_count": direction, "created_at": -1, "product_id": 1 })
+        }
+        SearchSort::Rating => Some(doc! {
+            "avg_rating": direction,
+            "review_count": direction,
+            "created_at": -1,
+            "product_id": 1
+        }),
+        SearchSort::Relevance => None,
+    }
+}
+
+// Mirrors `sort_document` so hybrid results merged from two database-sorted
+// lists keep the same order. Relevance leaves the order untouched.
+fn sort_results(results: &mut [SearchResult], sort: SearchSort, order: SortOrder) {
+    if sort == SearchSort::Relevance {
+        return;
     }
 
-    Ok(results)
+    let price = |result: &SearchResult| {
+        result
+            .price
+            .as_deref()
+            .and_then(|price| price.parse::<f64>().ok())
+            .unwrap_or(0.0)
+    };
+
+    let primary = |a: &SearchResult, b: &SearchResult| {
+        let ordering = match sort {
+            SearchSort::Relevance => std::cmp::Ordering::Equal,
+            SearchSort::Price => price(a).total_cmp(&price(b)),
+            SearchSort::CreatedAt => a.created_at.cmp(&b.created_at),
+            SearchSort::Popularity => a.view_count.cmp(&b.view_count),
+            SearchSort::Rating => a
+                .avg_rating
+                .total_cmp(&b.avg_rating)
+                .then(a.review_count.cmp(&b.review_count)),
+        };
+
+        match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    };
+
+    results.sort_by(|a, b| {
+        primary(a, b)
+            .then(b.created_at.cmp(&a.created_at))
+            .then_with(|| a.product_id.cmp(&b.product_id))
+    });
 }
 
 async fn browse_products(
     filters: &SearchFilters,
+    sort: SearchSort,
+    order: SortOrder,
     limit: u32,
     offset: u32,
-) -> Result<Vec<SearchResult>, VerboseHTTPError> {
+) -> Result<SearchPage, VerboseHTTPError> {
     let database = DB.get().unwrap();
     let collection: Collection<Product> = database.collection("products");
 
@@ -590,35 +1433,92 @@ async fn browse_products(
         pipeline.push(doc! { "$match": match_stage });
     }
 
+    let sort_stage =
+        sort_document(sort, order).unwrap_or_else(|| doc! { "created_at": -1, "product_id": 1 });
+    pipeline.push(doc! { "$sort": sort_stage });
+
+    run_paged_search(
+        &collection,
+        pipeline,
+        limit,
+        offset,
+        "Browse products failed",
+    )
+    .await
+}
+
+async fn run_paged_search(
+    collection: &Collection<Product>,
+    mut pipeline: Vec<Document>,
+    limit: u32,
+    offset: u32,
+    error_message: &str,
+) -> Result<SearchPage, VerboseHTTPError> {
     pipeline.push(doc! {
-        "$lookup": {
-            "from": "users",
-            "localField": "user_id",
-            "foreignField": "uid",
-            "as": "user_info"
+        "$facet": {
+            "results": [
+                { "$skip": offset as i64 },
+                { "$limit": limit as i64 },
+                {
+                    "$lookup": {
+                        "from": "users",
+                        "localField": "user_id",
+                        "foreignField": "uid",
+                        "as": "user_info"
+                    }
+                }
+            ],
+            "total": [{ "$count": "count" }],
+            "attributes": attribute_facet_stages()
         }
     });
 
-    pipeline.push(doc! { "$sort": { "created_at": -1 } });
-    pipeline.push(doc! { "$skip": offset as i64 });
-    pipeline.push(doc! { "$limit": limit as i64 });
-
     let mut cursor = collection.aggregate(pipeline).await.map_err(|_| {
-        VerboseHTTPError::Standard(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Browse products failed".to_string(),
-        )
+        VerboseHTTPError::Standard(StatusCode::INTERNAL_SERVER_ERROR, error_message.to_string())
     })?;
 
-    let mut results = Vec::new();
-    while let Ok(Some(doc)) = cursor.try_next().await {
-        if let Ok(search_result) = convert_doc_to_search_result(doc) {
-            results.push(search_result);
-        } else {
-        }
-    }
+    let Ok(Some(page)) = cursor.try_next().await else {
+        return Ok(SearchPage::default());
+    };
 
-    Ok(results)
+    let results = page
+        .get_array("results")
+        .map(|docs| {
+            docs.iter()
+                .filter_map(|doc| doc.as_document().cloned())
+                .filter_map(|doc| convert_doc_to_search_result(doc).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let total = page
+        .get_array("total")
+        .ok()
+        .and_then(|counts| counts.first())
+        .and_then(|count| count.as_document())
+        .and_then(|count| match count.get("count") {
+            Some(Bson::Int32(value)) => Some(*value as u64),
+            Some(Bson::Int64(value)) => Some(*value as u64),
+            _ => None,
+        })
+        .unwrap_or(0);
+
+    let attribute_facets = page
+        .get_array("attributes")
+        .map(|facets| {
+            facets
+                .iter()
+                .filter_map(|facet| facet.as_document().cloned())
+                .filter_map(|facet| from_document(facet).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(SearchPage {
+        results,
+        total,
+        attribute_facets,
+    })
 }
 
 async fn generate_search_embedding(
@@ -639,25 +1539,14 @@ async fn generate_search_embedding(
                 image_urls,
             };
 
-            let response = client
-                .post(&format!("{}/embed/combined", clip_api_url))
-                .header("Content-Type", "application/json")
-                .json(&request)
-                .send()
-                .await
-                .map_err(|_| {
-                    VerboseHTTPError::Standard(
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "Failed to call CLIP embedding API".to_string(),
-                    )
-                })?;
-
-            if !response.status().is_success() {
-                return Err(VerboseHTTPError::Standard(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "CLIP embedding API request failed".to_string(),
-                ));
-            }
+            let response = send_clip_request(
+                client
+                    .post(format!("{}/embed/combined", clip_api_url))
+                    .header("Content-Type", "application/json")
+                    .json(&request),
+            )
+            .await
+            .map_err(|error| clip_request_error(error, "CLIP embedding API request failed"))?;
 
             let embedding_response: ClipEmbeddingResponse =
                 response.json().await.map_err(|_| {
@@ -667,31 +1556,20 @@ async fn generate_search_embedding(
                     )
                 })?;
 
-            Ok(embedding_response.embedding)
+            Ok(normalize_embedding(embedding_response.embedding))
         } else {
             let request = ClipTextRequest {
                 text: preprocess_text(query_text),
             };
 
-            let response = client
-                .post(&format!("{}/embed/text", clip_api_url))
-                .header("Content-Type", "application/json")
-                .json(&request)
-                .send()
-                .await
-                .map_err(|_| {
-                    VerboseHTTPError::Standard(
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "Failed to call CLIP text embedding API".to_string(),
-                    )
-                })?;
-
-            if !response.status().is_success() {
-                return Err(VerboseHTTPError::Standard(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "CLIP text embedding API request failed".to_string(),
-                ));
-            }
+            let response = send_clip_request(
+                client
+                    .post(format!("{}/embed/text", clip_api_url))
+                    .header("Content-Type", "application/json")
+                    .json(&request),
+            )
+            .await
+            .map_err(|error| clip_request_error(error, "CLIP text embedding API request failed"))?;
 
             let embedding_response: ClipEmbeddingResponse =
                 response.json().await.map_err(|_| {
@@ -701,7 +1579,7 @@ async fn generate_search_embedding(
                     )
                 })?;
 
-            Ok(embedding_response.embedding)
+            Ok(normalize_embedding(embedding_response.embedding))
         }
     } else if !image_files.is_empty() {
         let image_urls = upload_temp_images_for_search(image_files).await?;
@@ -711,29 +1589,14 @@ async fn generate_search_embedding(
             image_urls,
         };
 
-        let response = client
-            .post(&format!("{}/embed/image", clip_api_url))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|_| {
-                VerboseHTTPError::Standard(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to call CLIP image embedding API".to_string(),
-                )
-            })?;
-
-        let status_code = response.status();
-        if !status_code.is_success() {
-            return Err(VerboseHTTPError::Standard(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!(
-                    "CLIP image embedding API request failed with status: {}",
-                    status_code
-                ),
-            ));
-        }
+        let response = send_clip_request(
+            client
+                .post(format!("{}/embed/image", clip_api_url))
+                .header("Content-Type", "application/json")
+                .json(&request),
+        )
+        .await
+        .map_err(|error| clip_request_error(error, "CLIP image embedding API request failed"))?;
 
         let embedding_response: ClipEmbeddingResponse = response.json().await.map_err(|_| {
             VerboseHTTPError::Standard(
@@ -742,7 +1605,7 @@ async fn generate_search_embedding(
             )
         })?;
 
-        Ok(embedding_response.embedding)
+        Ok(normalize_embedding(embedding_response.embedding))
     } else {
         Err(VerboseHTTPError::Standard(
             StatusCode::BAD_REQUEST,
@@ -788,6 +1651,7 @@ fn build_filter_stage(filters: &SearchFilters) -> Document {
 
     if filters.enabled_only {
         match_doc.insert("enabled", true);
+        match_doc.insert("status", "published");
     }
 
     if let Some(ref category) = filters.category {
@@ -805,20 +1669,44 @@ fn build_filter_stage(filters: &SearchFilters) -> Document {
         let mut price_filter = Document::new();
 
         if let Some(min_price) = filters.price_min {
-            price_filter.insert("$gte", min_price);
+            price_filter.insert("$gte", (min_price * 100.0).ceil() as i64);
         }
 
         if let Some(max_price) = filters.price_max {
-            price_filter.insert("$lte", max_price);
+            price_filter.insert("$lte", (max_price * 100.0).floor() as i64);
         }
 
-        match_doc.insert("price", price_filter);
+        match_doc.insert("price_in_paise", price_filter);
     }
 
     if let Some(ref user_id) = filters.user_id {
         match_doc.insert("user_id", user_id);
     }
 
+    if let Some(ref region) = filters.region {
+        match_doc.insert("region", region);
+    }
+
+    if let Some(min_rating) = filters.min_rating {
+        match_doc.insert("avg_rating", doc! { "$gte": min_rating });
+    }
+
+    if !filters.attributes.is_empty() {
+        let conditions: Vec<Document> = filters
+            .attributes
+            .iter()
+            .map(|(key, value)| {
+                doc! {
+                    "$elemMatch": {
+                        "key": key.trim(),
+                        "value": value.trim()
+                    }
+                }
+            })
+            .collect();
+        match_doc.insert("attributes", doc! { "$all": conditions });
+    }
+
     if filters.created_after.is_some() || filters.created_before.is_some() {
         let mut date_filter = Document::new();
 
@@ -868,8 +1756,25 @@ fn convert_doc_to_search_result(doc: Document) -> Result<SearchResult, Box<dyn s
         _ => ProductType::New,
     };
 
-    let category_str = doc.get_str("category")?;
-    let category = serde_json::from_str::<ProductCategory>(&format!("\"{}\"", category_str))?;
+    // Older documents may carry a missing or drifted category; keep the
+    // result visible as Other instead of dropping it.
+    let category = doc
+        .get_str("category")
+        .ok()
+        .and_then(|category| {
+            serde_json::from_value::<ProductCategory>(serde_json::Value::String(
+                category.to_string(),
+            ))
+            .ok()
+        })
+        .unwrap_or_else(|| {
+            tracing::warn!(
+                product_id = product_id.as_str(),
+                category = ?doc.get("category"),
+                "Product has a missing or invalid category, treating it as Other"
+            );
+            ProductCategory::Other
+        });
 
     let tags = doc
         .get_array("tags")?
@@ -884,8 +1789,9 @@ fn convert_doc_to_search_result(doc: Document) -> Result<SearchResult, Box<dyn s
     };
 
     let price = doc
-        .get_str("price")
-        .map(str::to_string)
+        .get_i64("price_in_paise")
+        .map(|paise| format!("{:.2}", paise as f64 / 100.0))
+        .or_else(|_| doc.get_str("price").map(str::to_string))
         .or_else(|_| doc.get_f64("price").map(|p| p.to_string()))
         .or_else(|_| doc.get_i32("price").map(|p| p.to_string()))
         .or_else(|_| doc.get_i64("price").map(|p| p.to_string()))
@@ -893,6 +1799,15 @@ fn convert_doc_to_search_result(doc: Document) -> Result<SearchResult, Box<dyn s
 
     let thumbnail_url = doc.get_str("thumbnail_url").ok().map(str::to_string);
     let created_at = doc.get_i64("created_at")? as u64;
+    let view_count = doc
+        .get_i64("view_count")
+        .or_else(|_| doc.get_i32("view_count").map(i64::from))
+        .unwrap_or(0) as u64;
+    let avg_rating = doc.get_f64("avg_rating").unwrap_or(0.0);
+    let review_count = doc
+        .get_i64("review_count")
+        .or_else(|_| doc.get_i32("review_count").map(i64::from))
+        .unwrap_or(0) as u64;
     let similarity_score = doc.get_f64("similarity").ok().map(|s| s as f32);
 
     let user_info = doc.get_array("user_info")?;
@@ -914,7 +1829,338 @@ fn convert_doc_to_search_result(doc: Document) -> Result<SearchResult, Box<dyn s
         price,
         thumbnail_url,
         created_at,
+        view_count,
+        avg_rating,
+        review_count,
         similarity_score,
         username,
+        debug_info: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_search_keeps_the_image_filter() {
+        let filters = SearchFilters {
+            has_images: Some(true),
+            ..SearchFilters::default()
+        };
+        let image_filter = build_filter_stage(&filters);
+        let match_stage = text_match_stage("red phone", &filters);
+
+        assert_eq!(match_stage.get("$or"), image_filter.get("$or"));
+        let conditions = match_stage.get_array("$and").unwrap();
+        assert_eq!(conditions.len(), 1);
+        assert!(conditions[0].as_document().unwrap().contains_key("$or"));
+    }
+
+    #[test]
+    fn text_search_adds_to_the_no_images_filter() {
+        let filters = SearchFilters {
+            has_images: Some(false),
+            ..SearchFilters::default()
+        };
+        let match_stage = text_match_stage("red phone", &filters);
+
+        // Both "no images" conditions plus the text match.
+        assert_eq!(match_stage.get_array("$and").unwrap().len(), 3);
+    }
+
+    fn allowed(languages: &[&str]) -> Vec<String> {
+        languages
+            .iter()
+            .map(|language| language.to_string())
+            .collect()
+    }
+
+    fn result(product_id: &str, price: &str, created_at: u64, views: u64) -> SearchResult {
+        SearchResult {
+            product_id: product_id.to_string(),
+            title: product_id.to_string(),
+            description: String::new(),
+            product_type: ProductType::New,
+            category: ProductCategory::Smartphones,
+            tags: Vec::new(),
+            quantity: ProductQuantity {
+                min_quantity: 1,
+                max_quantity: 1,
+            },
+            price: Some(price.to_string()),
+            thumbnail_url: None,
+            created_at,
+            view_count: views,
+            avg_rating: 0.0,
+            review_count: 0,
+            similarity_score: None,
+            username: String::new(),
+            debug_info: None,
+        }
+    }
+
+    fn ids(results: &[SearchResult]) -> Vec<&str> {
+        results
+            .iter()
+            .map(|result| result.product_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn sort_results_orders_by_each_key() {
+        let mut results = vec![
+            result("a", "300.00", 1, 5),
+            result("b", "100.00", 3, 9),
+            result("c", "200.00", 2, 1),
+        ];
+
+        sort_results(&mut results, SearchSort::Price, SortOrder::Asc);
+        assert_eq!(ids(&results), ["b", "c", "a"]);
+
+        sort_results(&mut results, SearchSort::Price, SortOrder::Desc);
+        assert_eq!(ids(&results), ["a", "c", "b"]);
+
+        sort_results(&mut results, SearchSort::CreatedAt, SortOrder::Desc);
+        assert_eq!(ids(&results), ["b", "c", "a"]);
+
+        sort_results(&mut results, SearchSort::Popularity, SortOrder::Desc);
+        assert_eq!(ids(&results), ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn sort_results_breaks_ties_like_the_database() {
+        let mut results = vec![
+            result("b", "100.00", 1, 0),
+            result("c", "100.00", 2, 0),
+            result("a", "100.00", 1, 0),
+        ];
+
+        sort_results(&mut results, SearchSort::Price, SortOrder::Asc);
+        assert_eq!(ids(&results), ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn relevance_keeps_the_relevance_order() {
+        let mut results = vec![result("b", "1", 1, 0), result("a", "2", 2, 0)];
+
+        sort_results(&mut results, SearchSort::Relevance, SortOrder::Asc);
+        assert_eq!(ids(&results), ["b", "a"]);
+        assert!(sort_document(SearchSort::Relevance, SortOrder::Asc).is_none());
+        assert_eq!(
+            sort_document(SearchSort::Price, SortOrder::Asc),
+            Some(doc! { "price_in_paise": 1, "created_at": -1, "product_id": 1 })
+        );
+    }
+
+    fn facet(key: &str, values: &[(&str, u64)]) -> AttributeFacet {
+        AttributeFacet {
+            key: key.to_string(),
+            values: values
+                .iter()
+                .map(|(value, count)| AttributeFacetValue {
+                    value: value.to_string(),
+                    count: *count,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn merged_facets_keep_the_larger_count_per_value() {
+        let merged = merge_attribute_facets(
+            vec![facet("color", &[("red", 4), ("blue", 1)])],
+            vec![
+                facet("color", &[("blue", 3)]),
+                facet("brand", &[("acme", 2)]),
+            ],
+        );
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].key, "brand");
+        assert_eq!(merged[1].key, "color");
+        let colors: Vec<(&str, u64)> = merged[1]
+            .values
+            .iter()
+            .map(|value| (value.value.as_str(), value.count))
+            .collect();
+        assert_eq!(colors, vec![("red", 4), ("blue", 3)]);
+    }
+
+    #[test]
+    fn transcription_language_accepts_configured_codes() {
+        let allowed = allowed(&["en", "hi", "ta"]);
+        assert_eq!(
+            validate_transcription_language(Some("ta"), &allowed).unwrap(),
+            Some("ta".to_string())
+        );
+        assert_eq!(
+            validate_transcription_language(Some(" HI "), &allowed).unwrap(),
+            Some("hi".to_string())
+        );
+    }
+
+    #[test]
+    fn transcription_language_rejects_unconfigured_codes() {
+        let allowed = allowed(&["en", "hi"]);
+        assert!(matches!(
+            validate_transcription_language(Some("fr"), &allowed),
+            Err(VerboseHTTPError::Standard(StatusCode::BAD_REQUEST, _))
+        ));
+    }
+
+    #[test]
+    fn transcription_language_is_optional() {
+        let allowed = allowed(&["en"]);
+        assert_eq!(
+            validate_transcription_language(None, &allowed).unwrap(),
+            None
+        );
+        assert_eq!(
+            validate_transcription_language(Some("  "), &allowed).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn candidate_multiplier_scales_num_candidates() {
+        assert_eq!(vector_search_candidates(20, 0, 10, 10_000), 200);
+        assert_eq!(vector_search_candidates(20, 0, 25, 10_000), 500);
+        assert_eq!(
+            vector_search_candidates(1, 0, 2, 10_000),
+            MIN_SEARCH_CANDIDATES
+        );
+    }
+
+    #[test]
+    fn candidate_ceiling_caps_num_candidates() {
+        assert_eq!(vector_search_candidates(80, 0, 50, 1_000), 1_000);
+        assert_eq!(vector_search_candidates(20, 5_000, 10, 1_000), 1_000);
+
+        let stage = vector_search_stage(&[0.0; 4], 80, 0);
+        let search = stage.get_document("$vectorSearch").unwrap();
+        let expected = vector_search_candidates(
+            80,
+            0,
+            config().vector_search_candidates_multiplier,
+            config().max_vector_search_candidates,
+        );
+        assert_eq!(search.get("numCandidates"), Some(&Bson::from(expected)));
+    }
+
+    // What `linear_similarity_expression` computes for one stored embedding.
+    fn linear_score(stored: &[f32], query: &[f32]) -> f64 {
+        let dot_product: f64 = stored
+            .iter()
+            .zip(query)
+            .map(|(a, b)| f64::from(*a) * f64::from(*b))
+            .sum();
+        let norm = stored
+            .iter()
+            .map(|value| f64::from(*value).powi(2))
+            .sum::<f64>()
+            .sqrt();
+        if norm > 0.0 {
+            (1.0 + dot_product / norm) / 2.0
+        } else {
+            0.0
+        }
+    }
+
+    // Atlas' cosine `vectorSearchScore`.
+    fn ann_score(stored: &[f32], query: &[f32]) -> f64 {
+        let norm = |vector: &[f32]| {
+            vector
+                .iter()
+                .map(|value| f64::from(*value).powi(2))
+                .sum::<f64>()
+                .sqrt()
+        };
+        let dot_product: f64 = stored
+            .iter()
+            .zip(query)
+            .map(|(a, b)| f64::from(*a) * f64::from(*b))
+            .sum();
+        (1.0 + dot_product / (norm(stored) * norm(query))) / 2.0
+    }
+
+    #[test]
+    fn linear_similarity_is_on_the_ann_score_scale() {
+        let expression = linear_similarity_expression(&[1.0, 0.0]);
+        let score = expression
+            .get_document("$let")
+            .unwrap()
+            .get_document("in")
+            .unwrap()
+            .get_array("$cond")
+            .unwrap()[1]
+            .clone();
+        assert_eq!(
+            score,
+            Bson::Document(doc! {
+                "$divide": [{ "$add": [1.0, { "$divide": ["$$dotProduct", "$$norm"] }] }, 2.0]
+            })
+        );
+
+        let query = normalize_embedding(vec![0.3, -0.4, 1.2]);
+        for stored in [
+            vec![0.3, -0.4, 1.2],
+            vec![-0.3, 0.4, -1.2],
+            vec![2.0, 5.0, 0.1],
+            vec![0.4, 0.3, 0.0],
+        ] {
+            let linear = linear_score(&stored, &query);
+            assert!((0.0..=1.0).contains(&linear), "{linear}");
+            assert!((linear - ann_score(&stored, &query)).abs() < 1e-6);
+        }
+        assert!((linear_score(&[0.3, -0.4, 1.2], &query) - 1.0).abs() < 1e-6);
+        assert!(linear_score(&[-0.3, 0.4, -1.2], &query).abs() < 1e-6);
+    }
+
+    fn text_patterns(match_stage: &Document) -> Vec<String> {
+        let Ok(conditions) = match_stage.get_array("$and") else {
+            return Vec::new();
+        };
+        conditions
+            .iter()
+            .filter_map(|condition| condition.as_document()?.get_array("$or").ok())
+            .flatten()
+            .filter_map(|text_condition| text_condition.as_document()?.get_array("$or").ok())
+            .flatten()
+            .filter_map(|field| {
+                let (_, regex) = field.as_document()?.iter().next()?;
+                Some(regex.as_document()?.get_str("$regex").ok()?.to_string())
+            })
+            .collect()
+    }
+
+    fn is_literal_pattern(pattern: &str) -> bool {
+        let mut escaped = false;
+        for c in pattern.chars() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c.is_ascii_punctuation() {
+                return false;
+            }
+        }
+        !escaped
+    }
+
+    #[test]
+    fn text_search_escapes_regex_characters_in_queries() {
+        let patterns = text_patterns(&text_match_stage("C++", &SearchFilters::default()));
+        assert!(!patterns.is_empty());
+        assert!(patterns.iter().all(|pattern| is_literal_pattern(pattern)));
+        assert!(
+            patterns
+                .iter()
+                .any(|pattern| pattern.eq_ignore_ascii_case("c\\+\\+"))
+        );
+
+        let patterns = text_patterns(&text_match_stage(".*", &SearchFilters::default()));
+        assert!(patterns.iter().all(|pattern| is_literal_pattern(pattern)));
+        assert!(!patterns.iter().any(|pattern| pattern == ".*"));
+    }
+}
@@ -1,21 +1,31 @@
 use axum::{
     Json,
-    extract::{Extension, Multipart, Query},
-    http::StatusCode,
+    extract::{ConnectInfo, Extension, Multipart, Path, Query},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
+use bytes::Bytes;
 use serde::Deserialize;
+use serde_json::json;
+use std::net::SocketAddr;
 
 use super::{
-    delegates::{optimized_search_products},
+    delegates::{
+        browse_category, clear_search_history, get_search_history, is_allowed_audio_type,
+        optimized_search_products, process_audio, record_search_feedback, record_search_history,
+        validate_transcription_language,
+    },
     schemas::{
-        MAX_IMAGE_SIZE, MAX_IMAGES_PER_REQUEST,
-        SimpleSearchRequest,
+        AudioTask, BrowseCategoryQuery, MAX_AUDIO_SIZE, MAX_IMAGE_SIZE, MAX_IMAGES_PER_REQUEST,
+        SearchFeedbackRequest, SimpleSearchRequest,
     },
 };
 use crate::{
-    apex::utils::VerboseHTTPError,
-    auth::schemas::UserOut,
+    apex::{
+        config::config,
+        utils::{VerboseHTTPError, client_ip},
+    },
+    auth::{endpoints::optional_user, schemas::UserOut},
     recommendations::{auto_log_signal, schemas::SignalType},
 };
 
@@ -26,13 +36,27 @@ pub struct SearchQueryParams {
 
 pub async fn optimized_search_products_endpoint(
     Query(params): Query<SearchQueryParams>,
-    user: Option<Extension<UserOut>>,
+    headers: HeaderMap,
     mut multipart: Multipart,
 ) -> impl IntoResponse {
+    let user = optional_user(&headers).await;
     let mut request = SimpleSearchRequest {
         query: None,
         limit: None,
+        offset: None,
         force_original: params.force_original,
+        attributes: Default::default(),
+        category: None,
+        product_type: None,
+        price_min: None,
+        price_max: None,
+        has_images: None,
+        region: None,
+        min_similarity: None,
+        min_rating: None,
+        sort: None,
+        order: None,
+        debug: false,
     };
     let mut image_files = Vec::with_capacity(MAX_IMAGES_PER_REQUEST);
     let mut image_count = 0;
@@ -54,7 +78,8 @@ pub async fn optimized_search_products_endpoint(
                         return VerboseHTTPError::Standard(
                             StatusCode::BAD_REQUEST,
                             "Invalid JSON in body field".to_string(),
-                        ).into_response();
+                        )
+                        .into_response();
                     }
                 }
             }
@@ -63,7 +88,8 @@ pub async fn optimized_search_products_endpoint(
                     return VerboseHTTPError::Standard(
                         StatusCode::BAD_REQUEST,
                         "Maximum 2 images allowed per search request".to_string(),
-                    ).into_response();
+                    )
+                    .into_response();
                 }
 
                 let filename = field.file_name().unwrap_or("image").to_string();
@@ -76,7 +102,8 @@ pub async fn optimized_search_products_endpoint(
                     return VerboseHTTPError::Standard(
                         StatusCode::BAD_REQUEST,
                         format!("File '{}' is not a valid image", filename),
-                    ).into_response();
+                    )
+                    .into_response();
                 }
 
                 if let Ok(data) = field.bytes().await {
@@ -96,17 +123,35 @@ pub async fn optimized_search_products_endpoint(
                     return VerboseHTTPError::Standard(
                         StatusCode::BAD_REQUEST,
                         format!("Failed to read image data for '{}'", filename),
-                    ).into_response();
+                    )
+                    .into_response();
                 }
             }
             _ => {}
         }
     }
 
+    if request.debug
+        && !user
+            .as_ref()
+            .is_some_and(|user| config().is_admin(&user.uid))
+    {
+        return VerboseHTTPError::Standard(
+            StatusCode::FORBIDDEN,
+            "Search debug mode requires admin access".to_string(),
+        )
+        .into_response();
+    }
+
     let original_query = request.query.clone();
 
     match optimized_search_products(request, image_files).await {
-        Ok(response) => {                if let Some(Extension(user)) = user {
+        Ok(response) => {
+            if let Some(user) = user {
+                if let Some(ref orig_query) = original_query {
+                    record_search_history(&user, orig_query).await;
+                }
+
                 if let Some(ref query) = response.enhanced_query {
                     auto_log_signal(
                         &user.uid,
@@ -135,3 +180,161 @@ pub async fn optimized_search_products_endpoint(
         Err(error) => error.into_response(),
     }
 }
+
+pub async fn search_feedback_endpoint(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<SearchFeedbackRequest>,
+) -> impl IntoResponse {
+    let user = optional_user(&headers).await;
+    let client_ip = Some(client_ip(&headers, peer));
+
+    match record_search_feedback(user.as_ref(), client_ip, request).await {
+        Ok(()) => Json(json!({
+            "status": "ok",
+            "message": "Feedback recorded"
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn browse_category_endpoint(
+    Path(category): Path<String>,
+    Query(query): Query<BrowseCategoryQuery>,
+) -> impl IntoResponse {
+    match browse_category(&category, query).await {
+        Ok((category, results, total_count)) => Json(json!({
+            "status": "ok",
+            "category": category,
+            "results": results,
+            "total_count": total_count
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn transcribe_audio_endpoint(
+    Extension(_user): Extension<UserOut>,
+    multipart: Multipart,
+) -> impl IntoResponse {
+    handle_audio_upload(AudioTask::Transcribe, multipart).await
+}
+
+pub async fn translate_audio_endpoint(
+    Extension(_user): Extension<UserOut>,
+    multipart: Multipart,
+) -> impl IntoResponse {
+    handle_audio_upload(AudioTask::Translate, multipart).await
+}
+
+async fn handle_audio_upload(
+    task: AudioTask,
+    mut multipart: Multipart,
+) -> axum::response::Response {
+    let mut audio: Option<(String, Bytes, String)> = None;
+    let mut language: Option<String> = None;
+
+    while let Ok(Some(field)) = multipart.next_field().await {
+        match field.name() {
+            Some("language") => {
+                if let Ok(value) = field.text().await {
+                    language = Some(value);
+                }
+            }
+            Some("audio") => {
+                let filename = field.file_name().unwrap_or("audio").to_string();
+                let content_type = field
+                    .content_type()
+                    .unwrap_or("application/octet-stream")
+                    .to_string();
+
+                if !is_allowed_audio_type(&content_type) {
+                    return VerboseHTTPError::Standard(
+                        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                        format!("Unsupported audio type '{}'", content_type),
+                    )
+                    .into_response();
+                }
+
+                let Ok(data) = field.bytes().await else {
+                    return VerboseHTTPError::Standard(
+                        StatusCode::BAD_REQUEST,
+                        "Failed to read audio data".to_string(),
+                    )
+                    .into_response();
+                };
+
+                if data.is_empty() {
+                    return VerboseHTTPError::Standard(
+                        StatusCode::BAD_REQUEST,
+                        "Audio file is empty".to_string(),
+                    )
+                    .into_response();
+                }
+
+                if data.len() > MAX_AUDIO_SIZE {
+                    return VerboseHTTPError::Standard(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        "Audio exceeds 25MB size limit".to_string(),
+                    )
+                    .into_response();
+                }
+
+                audio = Some((filename, data, content_type));
+            }
+            _ => {}
+        }
+    }
+
+    let Some((filename, data, content_type)) = audio else {
+        return VerboseHTTPError::Standard(
+            StatusCode::BAD_REQUEST,
+            "Missing 'audio' file field".to_string(),
+        )
+        .into_response();
+    };
+
+    // Translation always produces English, so the spoken language only
+    // matters for transcription.
+    let language = match task {
+        AudioTask::Transcribe => match validate_transcription_language(
+            language.as_deref(),
+            &config().transcription_languages,
+        ) {
+            Ok(language) => language,
+            Err(error) => return error.into_response(),
+        },
+        AudioTask::Translate => None,
+    };
+
+    match process_audio(task, filename, data, &content_type, language.as_deref()).await {
+        Ok(text) => Json(json!({
+            "status": "ok",
+            "text": text
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn get_search_history_endpoint(Extension(user): Extension<UserOut>) -> impl IntoResponse {
+    match get_search_history(&user).await {
+        Ok(response) => Json(response).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+pub async fn clear_search_history_endpoint(
+    Extension(user): Extension<UserOut>,
+) -> impl IntoResponse {
+    match clear_search_history(&user).await {
+        Ok(deleted_count) => Json(json!({
+            "status": "ok",
+            "deleted_count": deleted_count
+        }))
+        .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
@@ -0,0 +1,56 @@
+use reqwest::{RequestBuilder, Response};
+use std::time::Duration;
+
+use crate::apex::{
+    circuit::{CircuitBreaker, CircuitRequestError, CircuitStatus},
+    config::config,
+};
+
+// Query enhancement is optional, so while Groq is failing searches skip it
+// and use the original query instead of waiting out the request timeout.
+static GROQ_CIRCUIT: CircuitBreaker = CircuitBreaker::new("Groq");
+
+#[inline]
+fn groq_cooldown() -> Duration {
+    Duration::from_secs(config().groq_cooldown_secs)
+}
+
+#[inline]
+pub fn groq_circuit_open() -> bool {
+    GROQ_CIRCUIT.is_open(groq_cooldown())
+}
+
+#[inline]
+pub fn groq_circuit_status() -> CircuitStatus {
+    GROQ_CIRCUIT.status(groq_cooldown())
+}
+
+#[inline]
+pub async fn send_groq_request(request: RequestBuilder) -> Result<Response, CircuitRequestError> {
+    GROQ_CIRCUIT
+        .send(request, config().groq_failure_threshold, groq_cooldown())
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::delegates::wants_query_enhancement;
+
+    #[test]
+    fn open_circuit_skips_query_enhancement() {
+        let query = "cheap phones for students";
+        assert!(!wants_query_enhancement(query, true));
+
+        let threshold = config().groq_failure_threshold;
+        for _ in 0..threshold {
+            GROQ_CIRCUIT.record_failure(threshold);
+        }
+        assert!(groq_circuit_open());
+        assert!(!wants_query_enhancement(query, false));
+
+        GROQ_CIRCUIT.record_success();
+        assert!(!groq_circuit_open());
+        assert!(wants_query_enhancement(query, false));
+    }
+}
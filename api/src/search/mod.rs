@@ -1,4 +1,8 @@
-pub(self) mod delegates;
+pub(crate) mod clip;
+mod delegates;
 pub(crate) mod endpoints;
+pub(crate) mod groq;
 pub(crate) mod preprocessing;
 pub(crate) mod schemas;
+
+pub(crate) use delegates::vector_search_stage;
@@ -1,3 +1,7 @@
+// Bump whenever preprocess_text output changes, so product embeddings built
+// with the old output are picked up by the stale re-embed job.
+pub const PREPROCESS_VERSION: u32 = 2;
+
 const STOPWORDS: &[&str] = &[
     "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "has", "he", "in", "is", "it",
     "its", "of", "on", "that", "the", "to", "was", "will", "with", "the", "this", "but", "they",
@@ -7,27 +11,114 @@ const STOPWORDS: &[&str] = &[
     "who", "oil", "sit", "now", "find", "down", "day", "did", "get", "come", "made", "may", "part",
 ];
 
+const HINDI_STOPWORDS: &[&str] = &[
+    "का",
+    "की",
+    "के",
+    "को",
+    "से",
+    "में",
+    "मे",
+    "है",
+    "हैं",
+    "और",
+    "या",
+    "एक",
+    "यह",
+    "ये",
+    "वह",
+    "वो",
+    "पर",
+    "भी",
+    "तो",
+    "ही",
+    "था",
+    "थी",
+    "थे",
+    "हो",
+    "कि",
+    "जो",
+    "कुछ",
+    "लिए",
+    "मुझे",
+    "चाहिए",
+    "वाला",
+    "वाली",
+    "वाले",
+    "क्या",
+    "कोई",
+    "नहीं",
+    "बहुत",
+    "अपना",
+    "अपनी",
+    "अपने",
+    "करना",
+    "दो",
+    "आप",
+    "आपका",
+    "आपकी",
+    "आपके",
+    "पास",
+    "अंदर",
+];
+
+const ROMAN_HINDI_STOPWORDS: &[&str] = &[
+    "ka", "ki", "ke", "ko", "se", "mein", "hai", "hain", "aur", "ya", "yeh", "woh", "bhi", "hi",
+    "tha", "thi", "jo", "kuch", "liye", "mujhe", "chahiye", "wala", "wali", "wale", "kya", "koi",
+    "nahi", "nahin", "bahut", "apna", "apni", "apne", "karna", "dikhao", "ek", "aap", "aapka",
+    "aapki", "aapke", "paas", "andar",
+];
+
+#[inline]
+fn is_devanagari(word: &str) -> bool {
+    word.chars().any(|c| ('\u{0900}'..='\u{097F}').contains(&c))
+}
+
 #[inline]
-fn is_stopword(word: &str) -> bool {
-    STOPWORDS.contains(&word)
+fn is_devanagari_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0900}'..='\u{0903}'
+            | '\u{093A}'..='\u{094F}'
+            | '\u{0951}'..='\u{0957}'
+            | '\u{0962}'..='\u{0963}'
+    )
+}
+
+// Two-letter Roman Hindi stopwords ("se", "hi", "ki") are also English words
+// and model names, so only a longer one marks the query as Hinglish.
+fn is_hinglish<'a>(mut words: impl Iterator<Item = &'a str>) -> bool {
+    words.any(|word| word.len() > 2 && ROMAN_HINDI_STOPWORDS.contains(&word))
+}
+
+#[inline]
+fn is_stopword(word: &str, hinglish: bool) -> bool {
+    if is_devanagari(word) {
+        HINDI_STOPWORDS.contains(&word)
+    } else {
+        STOPWORDS.contains(&word) || (hinglish && ROMAN_HINDI_STOPWORDS.contains(&word))
+    }
 }
 
 pub fn preprocess_text(text: &str) -> String {
-    text.to_lowercase()
+    let cleaned = text
+        .to_lowercase()
         .chars()
         .map(|c| {
-            if c.is_alphanumeric() || c.is_whitespace() {
+            if c.is_alphanumeric() || c.is_whitespace() || is_devanagari_mark(c) {
                 c
             } else {
                 ' '
             }
         })
-        .collect::<String>()
+        .collect::<String>();
+    let hinglish = is_hinglish(cleaned.split_whitespace());
+
+    cleaned
         .split_whitespace()
         .filter(|word| {
-            !word.is_empty()
-                && word.len() > 1
-                && !is_stopword(word)
+            word.chars().count() > 1
+                && !is_stopword(word, hinglish)
                 && word.chars().any(|c| c.is_alphabetic())
         })
         .collect::<Vec<&str>>()
@@ -35,9 +126,15 @@ pub fn preprocess_text(text: &str) -> String {
 }
 
 pub fn has_stopwords(text: &str) -> bool {
-    text.to_lowercase()
-        .split_whitespace()
-        .any(|word| is_stopword(word))
+    let lowercase = text.to_lowercase();
+    let words = || {
+        lowercase
+            .split_whitespace()
+            .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric() && !is_devanagari_mark(c)))
+    };
+    let hinglish = is_hinglish(words());
+
+    words().any(|word| is_stopword(word, hinglish))
 }
 
 #[inline]
@@ -56,11 +153,22 @@ pub fn normalize_punctuation(text: &str) -> String {
         .join(" ")
 }
 
+pub fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_punctuation() {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 #[inline]
 pub fn extract_keywords(text: &str) -> Vec<String> {
     preprocess_text(text)
         .split_whitespace()
-        .filter(|word| word.len() >= 3)
+        .filter(|word| word.chars().count() >= 3)
         .map(str::to_string)
         .collect()
 }
@@ -83,8 +191,34 @@ pub fn create_search_variants(query: &str) -> Vec<String> {
         variants.push(keywords.join(" "));
     }
 
-    variants.sort_unstable_by(|a, b| b.len().cmp(&a.len()));
+    variants.sort_unstable_by_key(|variant| std::cmp::Reverse(variant.len()));
     variants.dedup();
 
     variants
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn english_queries_keep_short_hindi_stopwords() {
+        assert_eq!(preprocess_text("iPhone SE"), "iphone se");
+        assert_eq!(preprocess_text("Hi-Fi speakers"), "hi fi speakers");
+        assert!(!has_stopwords("iPhone SE"));
+    }
+
+    #[test]
+    fn hinglish_queries_drop_roman_hindi_stopwords() {
+        assert_eq!(preprocess_text("mujhe iPhone SE chahiye"), "iphone");
+        assert_eq!(preprocess_text("red wala phone dikhao"), "red phone");
+        assert!(has_stopwords("sasta phone chahiye"));
+    }
+
+    #[test]
+    fn escape_regex_escapes_punctuation() {
+        assert_eq!(escape_regex("c++ (2024)"), "c\\+\\+ \\(2024\\)");
+        assert_eq!(escape_regex(".*"), "\\.\\*");
+        assert_eq!(escape_regex("plain text"), "plain text");
+    }
+}
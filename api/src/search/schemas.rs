@@ -1,69 +1,116 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 pub const MAX_SEARCH_QUERY_LENGTH: usize = 1000;
+pub const DEFAULT_MIN_QUERY_LENGTH: usize = 2;
 pub const MAX_SEARCH_RESULTS: u32 = 80;
 pub const DEFAULT_SEARCH_LIMIT: u32 = 20;
+pub const MAX_SEARCH_OFFSET: u32 = 1000;
 pub const MIN_SEARCH_CANDIDATES: u32 = 20;
-pub const SEARCH_SIMILARITY_THRESHOLD: f32 = 0.3;
+// On the (1 + cos) / 2 scale, where 0.5 means unrelated.
+pub const SEARCH_SIMILARITY_THRESHOLD: f32 = 0.65;
+pub const MAX_FACET_VALUES: i32 = 20;
 
 pub const GROQ_AI_MODEL: &str = "compound-beta";
 pub const GROQ_API_ENDPOINT: &str = "https://api.groq.com/openai/v1/chat/completions";
 pub const FILEBASE_IPFS_ENDPOINT: &str = "https://rpc.filebase.io";
+pub const GROQ_WHISPER_MODEL: &str = "whisper-large-v3";
+pub const GROQ_TRANSCRIPTION_ENDPOINT: &str = "https://api.groq.com/openai/v1/audio/transcriptions";
+pub const GROQ_TRANSLATION_ENDPOINT: &str = "https://api.groq.com/openai/v1/audio/translations";
 
 pub const HYBRID_VECTOR_WEIGHT: f32 = 0.7;
 pub const HYBRID_TEXT_WEIGHT: f32 = 0.3;
-pub const VECTOR_SEARCH_CANDIDATES_MULTIPLIER: u32 = 10;
+pub const DEFAULT_VECTOR_SEARCH_CANDIDATES_MULTIPLIER: u32 = 10;
+pub const DEFAULT_MAX_VECTOR_SEARCH_CANDIDATES: u32 = 1000;
+// Atlas rejects $vectorSearch stages with numCandidates above this.
+pub const VECTOR_SEARCH_CANDIDATES_HARD_LIMIT: u32 = 10_000;
+pub const DEFAULT_CLIP_FAILURE_THRESHOLD: u32 = 5;
+pub const DEFAULT_CLIP_COOLDOWN_SECS: u64 = 30;
+pub const DEFAULT_GROQ_FAILURE_THRESHOLD: u32 = 5;
+pub const DEFAULT_GROQ_COOLDOWN_SECS: u64 = 30;
 
 pub const MAX_IMAGE_SIZE: usize = 5 * 1024 * 1024;
 pub const MAX_IMAGES_PER_REQUEST: usize = 2;
-
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "snake_case")]
-pub enum SearchMode {
-    Vector,
-    Text,
-    Combined,
-    Hybrid,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub const MAX_AUDIO_SIZE: usize = 25 * 1024 * 1024;
+pub const DEFAULT_TRANSCRIPTION_LANGUAGES: &[&str] = &["en", "hi"];
+pub const ALLOWED_AUDIO_TYPES: [&str; 13] = [
+    "audio/flac",
+    "audio/x-flac",
+    "audio/mpeg",
+    "audio/mp3",
+    "audio/mp4",
+    "audio/m4a",
+    "audio/x-m4a",
+    "audio/ogg",
+    "audio/opus",
+    "audio/wav",
+    "audio/x-wav",
+    "audio/wave",
+    "audio/webm",
+];
+
+pub const COLLECTIONS_SEARCH_FEEDBACK: &str = "search_feedback";
+pub const SEARCH_FEEDBACK_RATE_LIMIT: u64 = 30;
+pub const SEARCH_FEEDBACK_RATE_WINDOW_SECS: u64 = 3600;
+
+pub const COLLECTIONS_SEARCH_HISTORY: &str = "search_history";
+pub const SEARCH_HISTORY_LIMIT: u64 = 50;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum SearchSort {
+    #[default]
     Relevance,
     Price,
     CreatedAt,
     Popularity,
+    Rating,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum SortOrder {
     Asc,
+    #[default]
     Desc,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct SearchRequest {
+pub struct SimpleSearchRequest {
     pub query: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub force_original: Option<bool>,
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
     pub category: Option<crate::products::schemas::ProductCategory>,
     pub product_type: Option<crate::products::schemas::ProductType>,
     pub price_min: Option<f64>,
     pub price_max: Option<f64>,
-    pub mode: Option<SearchMode>,
+    pub has_images: Option<bool>,
+    pub region: Option<String>,
+    pub min_similarity: Option<f32>,
+    pub min_rating: Option<f64>,
     pub sort: Option<SearchSort>,
-    pub sort_order: Option<SortOrder>,
-    pub limit: Option<u32>,
-    pub offset: Option<u32>,
-    pub use_ai_enhancement: Option<bool>,
-    pub conversation_id: Option<String>,
-    pub should_refine: Option<bool>,
+    pub order: Option<SortOrder>,
+    #[serde(default)]
+    pub debug: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SimpleSearchRequest {
-    pub query: Option<String>,
-    pub limit: Option<u32>,
-    pub force_original: Option<bool>,
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchResultSource {
+    Vector,
+    Text,
+    Both,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchDebugInfo {
+    pub source: SearchResultSource,
+    pub vector_score: Option<f32>,
+    pub text_score: Option<f32>,
+    pub final_score: Option<f32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -78,33 +125,92 @@ pub struct SearchResult {
     pub price: Option<String>,
     pub thumbnail_url: Option<String>,
     pub created_at: u64,
+    pub view_count: u64,
+    pub avg_rating: f64,
+    pub review_count: u64,
     pub similarity_score: Option<f32>,
     pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug_info: Option<SearchDebugInfo>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct SearchResponse {
-    pub status: String,
+pub struct SimpleSearchResponse {
     pub results: Vec<SearchResult>,
     pub total_count: u64,
     pub enhanced_query: Option<String>,
     pub ai_enhancement_triggered: bool,
-    pub search_mode: SearchMode,
     pub processing_time_ms: u64,
-    pub conversation_id: Option<String>,
-    pub ai_suggestions: Option<Vec<String>>,
-    pub needs_refinement: Option<bool>,
-    pub refinement_questions: Option<Vec<String>>,
+    pub inferred_category: Option<crate::products::schemas::ProductCategory>,
+    pub did_you_mean: Option<String>,
+    pub query_too_short: bool,
+    pub attribute_facets: Vec<AttributeFacet>,
+    pub similarity_threshold: f32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct SimpleSearchResponse {
+pub struct AttributeFacetValue {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttributeFacet {
+    #[serde(alias = "_id")]
+    pub key: String,
+    pub values: Vec<AttributeFacetValue>,
+}
+
+/// One page of matches plus the total and attribute facets of the whole
+/// match set.
+#[derive(Debug, Default)]
+pub struct SearchPage {
     pub results: Vec<SearchResult>,
-    pub total_count: u64,
-    pub enhanced_query: Option<String>,
-    pub ai_enhancement_triggered: bool,
-    pub processing_time_ms: u64,
-    pub inferred_category: Option<crate::products::schemas::ProductCategory>,
+    pub total: u64,
+    pub attribute_facets: Vec<AttributeFacet>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchFeedbackSignal {
+    Irrelevant,
+    Relevant,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchFeedbackRequest {
+    pub query: String,
+    pub product_id: String,
+    pub signal: SearchFeedbackSignal,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchFeedback {
+    pub query: String,
+    pub product_id: String,
+    pub signal: SearchFeedbackSignal,
+    pub user_id: Option<String>,
+    pub client_ip: Option<String>,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchHistoryEntry {
+    pub entry_id: String,
+    pub user_id: String,
+    pub query: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchHistoryItem {
+    pub query: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchHistoryResponse {
+    pub history: Vec<SearchHistoryItem>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -120,83 +226,64 @@ pub struct GroqQueryEnhancementRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GroqEnhancementResponse {
     pub enhanced_query: String,
-    pub category: Option<crate::products::schemas::ProductCategory>,
+    #[serde(default)]
+    pub category: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct GroqMessage {
-    pub role: String,
-    pub content: String,
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BrowseCategoryQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub sort: Option<SearchSort>,
+    pub order: Option<SortOrder>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct GroqResponse {
-    pub choices: Vec<GroqChoice>,
+pub struct GroqAudioResponse {
+    pub text: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct GroqChoice {
-    pub message: GroqResponseMessage,
+pub struct GroqErrorResponse {
+    pub error: GroqErrorDetail,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct GroqResponseMessage {
-    pub role: String,
-    pub content: Option<String>,
+pub struct GroqErrorDetail {
+    pub message: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SearchConversation {
-    pub conversation_id: String,
-    pub turns: Vec<ConversationTurn>,
-    pub created_at: u64,
-    pub updated_at: u64,
-    pub user_session: Option<String>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioTask {
+    Transcribe,
+    Translate,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ConversationTurn {
-    pub user_query: String,
-    pub enhanced_query: Option<String>,
-    pub ai_response: Option<String>,
-    pub search_results_count: Option<u32>,
-    pub suggestions: Option<Vec<String>>,
-    pub timestamp: u64,
+pub struct GroqSuggestionResponse {
+    pub did_you_mean: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct QueryRefinementRequest {
-    pub conversation_id: String,
-    pub user_input: String,
-    pub previous_query: Option<String>,
-    pub search_results_count: Option<u32>,
+pub struct GroqMessage {
+    pub role: String,
+    pub content: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct QueryRefinementResponse {
-    pub refined_query: Option<String>,
-    pub suggestions: Vec<String>,
-    pub should_search_immediately: bool,
-    pub clarification_questions: Option<Vec<String>>,
-    pub conversation_id: String,
+pub struct GroqResponse {
+    pub choices: Vec<GroqChoice>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct VectorSearchParams {
-    pub vector: Vec<f32>,
-    pub path: String,
-    pub num_candidates: u32,
-    pub limit: u32,
-    pub index: String,
+pub struct GroqChoice {
+    pub message: GroqResponseMessage,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct SearchRefinementTool {
-    pub action: String,
-    pub enhanced_query: Option<String>,
-    pub suggestions: Vec<String>,
-    pub clarification_questions: Option<Vec<String>>,
-    pub should_search_immediately: bool,
+pub struct GroqResponseMessage {
+    pub role: String,
+    pub content: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -215,20 +302,6 @@ pub struct ClipEmbeddingResponse {
     pub embedding: Vec<f32>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TextSearchQuery {
-    pub query: String,
-    pub fields: Vec<String>,
-    pub boost_factors: Option<std::collections::HashMap<String, f32>>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct VectorSearchQuery {
-    pub embedding: Vec<f32>,
-    pub k: u32,
-    pub threshold: Option<f32>,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchFilters {
     pub category: Option<crate::products::schemas::ProductCategory>,
@@ -239,11 +312,12 @@ pub struct SearchFilters {
     pub created_after: Option<u64>,
     pub created_before: Option<u64>,
     pub has_images: Option<bool>,
+    pub attributes: BTreeMap<String, String>,
+    pub region: Option<String>,
+    pub min_rating: Option<f64>,
     pub enabled_only: bool,
 }
 
-
-
 impl Default for SearchFilters {
     fn default() -> Self {
         Self {
@@ -255,25 +329,10 @@ impl Default for SearchFilters {
             created_after: None,
             created_before: None,
             has_images: None,
+            attributes: BTreeMap::new(),
+            region: None,
+            min_rating: None,
             enabled_only: true,
         }
     }
 }
-
-impl Default for SearchMode {
-    fn default() -> Self {
-        Self::Hybrid
-    }
-}
-
-impl Default for SearchSort {
-    fn default() -> Self {
-        Self::Relevance
-    }
-}
-
-impl Default for SortOrder {
-    fn default() -> Self {
-        Self::Desc
-    }
-}
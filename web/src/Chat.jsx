@@ -219,7 +219,7 @@ function ChatWindow({ conversation, messages, onSendMessage, lang, currentUserId
   };
 
   const formatMessageTime = (timestamp) => {
-    return new Date(timestamp * 1000).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' });
+    return new Date(timestamp).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' });
   };
 
   if (!conversation) {
@@ -416,7 +416,7 @@ export default function Chat() {
         // Update conversations list to reflect the new message
         setConversations(prev => prev.map(conv => 
           conv.other_participant_id === otherUserId 
-            ? { ...conv, last_message_at: newMessage.created_at }
+            ? { ...conv, last_message_at: Math.floor(newMessage.created_at / 1000) }
             : conv
         ));
       }
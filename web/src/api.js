@@ -103,12 +103,19 @@ class GoodsPointAPI {
     // ============================
 
     /**
-     * Get API status
+     * Get API status and version info, useful for checking what is deployed
      * @returns {Promise<Object>} Status message
      * 
      * Response structure:
      * {
-     *   message: "ok"
+     *   message: "ok",
+     *   name: string,
+     *   version: string, // crate version of the running API
+     *   build: {
+     *     commit: string | null,
+     *     built_at: string | null,
+     *     profile: "debug" | "release"
+     *   }
      * }
      */
     async getStatus() {
@@ -184,6 +191,57 @@ class GoodsPointAPI {
         });
     }
 
+    /**
+     * Logout every session of the current user
+     * @returns {Promise<Object>} Logout response
+     *
+     * Response structure:
+     * {
+     *   status: "ok"
+     * }
+     */
+    async logoutAll() {
+        return this.request('/auth/logout-all', {
+            method: 'POST',
+        });
+    }
+
+    /**
+     * List active sessions of the current user
+     * @returns {Promise<Object>} Sessions response
+     *
+     * Response structure:
+     * {
+     *   status: "ok",
+     *   sessions: [{
+     *     session_id: string,
+     *     device: string | null,
+     *     created_at: number,
+     *     expires_at: number,
+     *     current: boolean
+     *   }]
+     * }
+     */
+    async getSessions() {
+        return this.request('/auth/sessions');
+    }
+
+    /**
+     * Revoke a single session
+     * @param {string} sessionId - Session ID to revoke
+     * @returns {Promise<Object>} Revoke response
+     *
+     * Response structure:
+     * {
+     *   status: "ok"
+     * }
+     */
+    async revokeSession(sessionId) {
+        return this.request(`/auth/sessions/${sessionId}`, {
+            method: 'DELETE',
+        });
+    }
+
     /**
      * Get current user information
      * @returns {Promise<Object>} User information
@@ -311,6 +369,95 @@ class GoodsPointAPI {
         return this.request('/auth/whatsapp-status');
     }
 
+    /**
+     * Remove the WhatsApp number of the current user
+     * @param {string} password - Current password
+     * @returns {Promise<Object>} Removal response
+     *
+     * Response structure:
+     * {
+     *   status: "ok"
+     * }
+     */
+    async removeWhatsApp(password) {
+        return this.request('/auth/whatsapp', {
+            method: 'DELETE',
+            body: JSON.stringify({ password }),
+        });
+    }
+
+    /**
+     * Get a seller's public profile (public endpoint)
+     * @param {string} username - Seller username (case-insensitive)
+     * @returns {Promise<Object>} { status, profile: { username, bio, region, city, product_count } }
+     *   bio is plain text; render it as text, never as HTML
+     */
+    async getPublicProfile(username) {
+        return this.request(`/profiles/${encodeURIComponent(username)}`);
+    }
+
+    /**
+     * Update non-credential profile fields. Omitted fields are left unchanged; an empty string clears a text field
+     * @param {Object} profile - Profile fields
+     * @param {string} profile.region - Region code such as "in-south" (letters, digits, - and _, max 32), copied onto your products
+     * @param {string} profile.city - City name for display (max 64 characters), copied onto your products
+     * @param {string} profile.bio - Short public bio (max 500 characters, no control characters or < >)
+     * @param {Object} profile.notification_preferences - { email: boolean, whatsapp: boolean } for message, offer and order alerts
+     * @returns {Promise<Object>} { status, profile: { region, city, bio, notification_preferences } }
+     */
+    async updateProfile(profile) {
+        return this.request('/auth/profile', {
+            method: 'PUT',
+            body: JSON.stringify(profile),
+        });
+    }
+
+    /**
+     * List your saved shipping addresses
+     * @returns {Promise<Object>} { status, addresses: [{ address_id, label, address, created_at, updated_at }] }
+     */
+    async getAddresses() {
+        return this.request('/auth/addresses');
+    }
+
+    /**
+     * Save a shipping address (max 10 per user)
+     * @param {Object} address - { full_name, phone, line1, line2?, city, state, postal_code, country }
+     * @param {string} label - Optional label such as "Home" (max 40 characters)
+     * @returns {Promise<Object>} { status, address }
+     */
+    async createAddress(address, label) {
+        return this.request('/auth/addresses', {
+            method: 'POST',
+            body: JSON.stringify({ label, address }),
+        });
+    }
+
+    /**
+     * Replace a saved shipping address. Orders already placed keep their original snapshot
+     * @param {string} addressId - ID of the address
+     * @param {Object} address - Same shape as createAddress
+     * @param {string} label - Optional label
+     * @returns {Promise<Object>} { status, address }
+     */
+    async updateAddress(addressId, address, label) {
+        return this.request(`/auth/addresses/${addressId}`, {
+            method: 'PUT',
+            body: JSON.stringify({ label, address }),
+        });
+    }
+
+    /**
+     * Delete a saved shipping address
+     * @param {string} addressId - ID of the address
+     * @returns {Promise<Object>} { status: "ok" }
+     */
+    async deleteAddress(addressId) {
+        return this.request(`/auth/addresses/${addressId}`, {
+            method: 'DELETE',
+        });
+    }
+
     // ============================
     // PRODUCT API ENDPOINTS
     // ============================
@@ -324,6 +471,7 @@ class GoodsPointAPI {
      * @param {string} productData.purchase_type - "buy_now" or "inquire"
      * @param {string} productData.category - Product category (see ProductCategory enum)
      * @param {string[]} productData.tags - Product tags (max 32 tags, 50 chars each)
+     * @param {Object[]} productData.attributes - Spec sheet as {key, value} pairs (max 30, optional)
      * @param {Object} productData.quantity - Quantity constraints
      * @param {number} productData.quantity.min_quantity - Minimum quantity
      * @param {number} productData.quantity.max_quantity - Maximum quantity
@@ -393,13 +541,37 @@ class GoodsPointAPI {
      *   thumbnail_url: string | null,
      *   created_at: number,
      *   updated_at: number,
-     *   enabled: boolean
+     *   enabled: boolean,
+     *   view_count: number,
+     *   slug: string, // e.g. "iphone-13-pro-1a2b3c4d", usable with getProductBySlug
+     *   avg_rating: number, // 0 until the product has reviews
+     *   review_count: number
      * }
      */
     async getProduct(productId) {
         return this.request(`/products/${productId}`);
     }
 
+    /**
+     * Get a published product by its URL slug (public endpoint)
+     * @param {string} slug - Product slug, e.g. "iphone-13-pro-1a2b3c4d"
+     * @returns {Promise<Object>} Same response as getProduct
+     */
+    async getProductBySlug(slug) {
+        return this.request(`/products/slug/${encodeURIComponent(slug)}`);
+    }
+
+    /**
+     * Record a view of a product page. Bots and repeat views within 30 minutes are not counted
+     * @param {string} productId - ID of the product
+     * @returns {Promise<Object>} { status, counted }
+     */
+    async recordProductView(productId) {
+        return this.request(`/products/${productId}/view`, {
+            method: 'POST',
+        });
+    }
+
     /**
      * Get current user's product details
      * @param {string} productId - ID of the product
@@ -433,10 +605,86 @@ class GoodsPointAPI {
         });
     }
 
+    /**
+     * Publish a draft product so it appears in search and recommendations
+     * @param {string} productId - ID of the product
+     * @returns {Promise<Object>} Published product
+     */
+    async publishProduct(productId) {
+        return this.request(`/seller/products/${productId}/publish`, {
+            method: 'POST',
+        });
+    }
+
+    /**
+     * Ask the AI for a category suggestion without changing the product
+     * @param {string} productId - ID of the product
+     * @returns {Promise<Object>} Suggestion with current_category, suggested_category,
+     *   confidence (0-1) and an optional reason
+     */
+    async suggestProductCategory(productId) {
+        return this.request(`/seller/products/${productId}/suggest-category`, {
+            method: 'POST',
+        });
+    }
+
+    /**
+     * Check whether the current user may review a product
+     * @param {string} productId - ID of the product
+     * @returns {Promise<Object>} { can_review: boolean, reason: null | "own_product" | "already_reviewed" | "no_delivered_order" }
+     */
+    async canReview(productId) {
+        return this.request(`/products/${productId}/can-review`);
+    }
+
+    /**
+     * Review a product after one of your orders for it has been delivered
+     * @param {string} productId - ID of the product
+     * @param {number} rating - Whole-star rating from 1 to 5
+     * @param {string} text - Optional review text (max 2000 characters)
+     * @returns {Promise<Object>} { status, review }
+     */
+    async createReview(productId, rating, text = null) {
+        return this.request(`/products/${productId}/reviews/create`, {
+            method: 'POST',
+            body: JSON.stringify({ rating, text }),
+        });
+    }
+
+    /**
+     * Publicly respond to a review of one of your products (replaces any earlier response)
+     * @param {string} productId - ID of the product
+     * @param {string} reviewId - ID of the review
+     * @param {string} text - Response text (max 1000 characters)
+     * @returns {Promise<Object>} { status, review }
+     */
+    async respondToReview(productId, reviewId, text) {
+        return this.request(`/products/${productId}/reviews/${reviewId}/respond`, {
+            method: 'POST',
+            body: JSON.stringify({ text }),
+        });
+    }
+
+    /**
+     * List reviews for a product, newest first (public endpoint)
+     * @param {string} productId - ID of the product
+     * @param {Object} options - Paging options { limit, offset }
+     * @returns {Promise<Object>} { status, reviews: [{ review_id, username, rating, text, seller_response: { text, created_at } | null, created_at, ... }] }
+     */
+    async getProductReviews(productId, options = {}) {
+        const params = new URLSearchParams();
+        if (options.limit) params.append('limit', options.limit.toString());
+        if (options.offset) params.append('offset', options.offset.toString());
+
+        const query = params.toString() ? `?${params.toString()}` : '';
+        return this.request(`/products/${productId}/reviews${query}`);
+    }
+
     /**
      * Buy a product immediately (create order with buy-now functionality)
      * @param {string} productId - ID of the product to buy
      * @param {number} quantity - Quantity to purchase
+     * @param {string} addressId - ID of one of your saved addresses to ship to
      * @returns {Promise<Object>} Order object
      * 
      * Response structure:
@@ -448,16 +696,18 @@ class GoodsPointAPI {
      *   quantity: number,
      *   price: number,
      *   status: "unpaid" | "delivery_pending",
+     *   shipping_address: Object | null, // snapshot taken when the order was placed
      *   created_at: number,
      *   updated_at: number
      * }
      */
-    async buyNowProduct(productId, quantity) {
+    async buyNowProduct(productId, quantity, addressId) {
         return this.request('/products/buy-now', {
             method: 'POST',
             body: JSON.stringify({ 
                 product_id: productId, 
-                quantity: quantity 
+                quantity: quantity,
+                address_id: addressId
             }),
         });
     }
@@ -610,6 +860,9 @@ class GoodsPointAPI {
      * Send a text message to another user
      * @param {string} otherUserId - ID of the recipient user
      * @param {string} content - Message content (max 4000 characters)
+     * @param {number} [sendAt] - Deliver later, in milliseconds since epoch (at most 30 days ahead).
+     *   A future sendAt returns { status: "scheduled", scheduled_message } instead; the recipient
+     *   sees nothing until it is delivered.
      * @returns {Promise<Object>} Message object
      * 
      * Response structure:
@@ -621,19 +874,50 @@ class GoodsPointAPI {
      *     message_type: "text" | "attachment" | "query" | "quote",
      *     content: string,
      *     attachment: null,
-     *     created_at: number,
-     *     updated_at: number,
+     *     created_at: number, // milliseconds since epoch
+     *     updated_at: number, // milliseconds since epoch
      *     is_edited: boolean
      *   }
      * }
      */
-    async sendTextMessage(otherUserId, content) {
+    async sendTextMessage(otherUserId, content, sendAt = null) {
         const formData = new FormData();
         formData.append('content', content);
+        if (sendAt) formData.append('send_at', sendAt.toString());
         
         return this.multipartRequest(`/chat/${otherUserId}/messages`, formData);
     }
 
+    /**
+     * List your scheduled messages that haven't been delivered yet
+     * @returns {Promise<Object>} { status: "ok", scheduled_messages: [...] }, soonest first
+     *
+     * Each scheduled message:
+     * {
+     *   message_id: string, // kept as the message ID once delivered
+     *   sender_id: string,
+     *   sender_username: string,
+     *   recipient_id: string,
+     *   content: string,
+     *   send_at: number, // milliseconds since epoch
+     *   created_at: number // milliseconds since epoch
+     * }
+     */
+    async getScheduledMessages() {
+        return this.request('/chat/scheduled');
+    }
+
+    /**
+     * Cancel a scheduled message before it is delivered
+     * @param {string} messageId - ID of the scheduled message
+     * @returns {Promise<Object>} { status: "ok", message_id, is_cancelled: true }; 404 once sent
+     */
+    async cancelScheduledMessage(messageId) {
+        return this.request(`/chat/scheduled/${messageId}`, {
+            method: 'DELETE',
+        });
+    }
+
     /**
      * Send an attachment message to another user
      * @param {string} otherUserId - ID of the recipient user
@@ -656,6 +940,7 @@ class GoodsPointAPI {
      * @param {Object} options - Query options
      * @param {number} options.limit - Number of messages to fetch (default: 64, max: 100)
      * @param {string} options.before - Message ID to fetch messages before
+     * @param {string} options.after - Message ID to fetch messages after
      * @returns {Promise<Object>} Messages array
      * 
      * Response structure:
@@ -672,12 +957,20 @@ class GoodsPointAPI {
      *         file_name: string,
      *         content_type: string,
      *         url: string,
+     *         thumbnail_url: string | null,
      *         size: number,
      *         upload_timestamp: number
      *       } | null,
-     *       created_at: number,
-     *       updated_at: number,
-     *       is_edited: boolean
+     *       quote_data: {
+     *         product_id: string,
+     *         custom_price: string,
+     *         quantity: number,
+     *         is_confirmed: boolean
+     *       } | null,
+     *       created_at: number, // milliseconds since epoch
+     *       updated_at: number, // milliseconds since epoch
+     *       is_edited: boolean,
+     *       is_deleted: boolean // content, attachment and quote are null when true
      *     }
      *   ]
      * }
@@ -686,11 +979,43 @@ class GoodsPointAPI {
         const params = new URLSearchParams();
         if (options.limit) params.append('limit', options.limit.toString());
         if (options.before) params.append('before', options.before);
+        if (options.after) params.append('after', options.after);
         
         const query = params.toString() ? `?${params.toString()}` : '';
         return this.request(`/chat/${otherUserId}/messages${query}`);
     }
 
+    /**
+     * Subscribe to live messages in a conversation via Server-Sent Events
+     * @param {string} otherUserId - ID of the other user
+     * @param {Object} handlers - Event callbacks
+     * @param {Function} handlers.onMessage - Called with each new message (same shape as getMessages entries)
+     * @param {Function} handlers.onMessageUpdated - Called when a message is edited or deleted (optional)
+     * @param {Function} handlers.onError - Called on connection errors; EventSource retries automatically
+     *   and resumes after the last event it received (optional)
+     * @param {Function} handlers.onStreamError - Called with { message } when the server ends the stream
+     *   because of an error; the reconnect resumes where it left off (optional)
+     * @returns {EventSource} Call close() on it to unsubscribe
+     */
+    streamMessages(otherUserId, handlers = {}) {
+        const source = new EventSource(`${this.baseURL}/chat/${otherUserId}/stream`, {
+            withCredentials: true,
+        });
+
+        source.addEventListener('message', (event) => {
+            handlers.onMessage?.(JSON.parse(event.data));
+        });
+        source.addEventListener('message_updated', (event) => {
+            handlers.onMessageUpdated?.(JSON.parse(event.data));
+        });
+        source.addEventListener('error', (event) => {
+            if (event.data) handlers.onStreamError?.(JSON.parse(event.data));
+        });
+        if (handlers.onError) source.onerror = handlers.onError;
+
+        return source;
+    }
+
     /**
      * Edit a text message
      * @param {string} messageId - ID of the message to edit
@@ -704,6 +1029,18 @@ class GoodsPointAPI {
         });
     }
 
+    /**
+     * Delete one of your own messages. It stays in the conversation as a
+     * tombstone ({ is_deleted: true }) with its content and attachment removed.
+     * @param {string} messageId - ID of the message to delete
+     * @returns {Promise<Object>} { status, message_id, is_deleted }
+     */
+    async deleteMessage(messageId) {
+        return this.request(`/chat/messages/${messageId}`, {
+            method: 'DELETE',
+        });
+    }
+
     /**
      * Get all conversations for the current user
      * @returns {Promise<Object>} Conversations array
@@ -716,7 +1053,11 @@ class GoodsPointAPI {
      *       conversation_id: string,
      *       other_participant_id: string,
      *       created_at: number,
-     *       last_message_at: number
+     *       last_message_at: number,
+     *       unread_count: number, // messages from the other participant since you last marked it read
+     *       other_last_read_at: number | null, // milliseconds since epoch, for read receipts
+     *       last_message_preview: string | null, // truncated text, or "📎 Attachment"
+     *       last_sender_id: string | null
      *     }
      *   ]
      * }
@@ -725,6 +1066,48 @@ class GoodsPointAPI {
         return this.request('/chat/conversations');
     }
 
+    /**
+     * Block a user so they can no longer message you. Blocks are one-directional:
+     * you can still message them, and order notifications are unaffected
+     * @param {string} otherUserId - ID of the user to block
+     * @returns {Promise<Object>} { status, block: { blocker_id, blocked_id, created_at } }
+     */
+    async blockUser(otherUserId) {
+        return this.request(`/chat/blocks/${otherUserId}`, {
+            method: 'POST',
+        });
+    }
+
+    /**
+     * Unblock a previously blocked user
+     * @param {string} otherUserId - ID of the user to unblock
+     * @returns {Promise<Object>} { status, unblocked } where unblocked is false if no block existed
+     */
+    async unblockUser(otherUserId) {
+        return this.request(`/chat/blocks/${otherUserId}`, {
+            method: 'DELETE',
+        });
+    }
+
+    /**
+     * List users you have blocked, most recent first
+     * @returns {Promise<Object>} { status, blocks: [{ blocker_id, blocked_id, created_at }] }
+     */
+    async getBlockedUsers() {
+        return this.request('/chat/blocks');
+    }
+
+    /**
+     * Mark a conversation as read up to now
+     * @param {string} otherUserId - ID of the other user
+     * @returns {Promise<Object>} { status, last_read_at } with last_read_at in milliseconds since epoch
+     */
+    async markConversationRead(otherUserId) {
+        return this.request(`/chat/${otherUserId}/read`, {
+            method: 'POST',
+        });
+    }
+
     /**
      * Get edit history for a message
      * @param {string} messageId - ID of the message
@@ -737,7 +1120,7 @@ class GoodsPointAPI {
      *     {
      *       content: string | null,
      *       attachment: object | null,
-     *       edited_at: number,
+     *       edited_at: number, // milliseconds since epoch
      *       username: string | null
      *     }
      *   ]
@@ -747,15 +1130,37 @@ class GoodsPointAPI {
         return this.request(`/chat/messages/${messageId}/history`);
     }
 
+    /**
+     * Send a price quote for one of your products to another user
+     * @param {string} otherUserId - ID of the buyer receiving the quote
+     * @param {string} productId - ID of the product being quoted (must be yours)
+     * @param {number} quantity - Quantity covered by the quote
+     * @param {string} customPrice - Total price for the quoted quantity, in rupees
+     * @param {string} note - Optional note shown with the quote
+     * @returns {Promise<Object>} Quote message object
+     */
+    async sendQuote(otherUserId, productId, quantity, customPrice, note = null) {
+        return this.request(`/chat/${otherUserId}/quote`, {
+            method: 'POST',
+            body: JSON.stringify({
+                product_id: productId,
+                quantity,
+                custom_price: customPrice,
+                note,
+            }),
+        });
+    }
+
     /**
      * Create an order from a quote message
      * @param {string} messageId - ID of the quote message
+     * @param {string} addressId - ID of one of your saved addresses to ship to
      * @returns {Promise<Object>} Order object
      */
-    async createOrderFromQuote(messageId) {
+    async createOrderFromQuote(messageId, addressId) {
         return this.request('/chat/quotes/create-order', {
             method: 'POST',
-            body: JSON.stringify({ message_id: messageId }),
+            body: JSON.stringify({ message_id: messageId, address_id: addressId }),
         });
     }
 
@@ -810,6 +1215,47 @@ class GoodsPointAPI {
         return this.request(`/sellers/orders/list${query}`);
     }
 
+    /**
+     * Get the current user's spending summary as a buyer
+     * @param {Object} options - Query options
+     * @param {number} options.months - Months covered by the monthly breakdown (default: 12, max: 36)
+     * @returns {Promise<Object>} Spending stats; cancelled, unpaid and refunded orders are excluded
+     *
+     * Response structure:
+     * {
+     *   total_spend_in_paise: number,
+     *   order_count: number,
+     *   by_category: [{ category: string, spend_in_paise: number, order_count: number }],
+     *   window_months: number,
+     *   monthly: [{ month: string, spend_in_paise: number, order_count: number }] // month is YYYY-MM
+     * }
+     */
+    async getOrderStats(options = {}) {
+        const params = new URLSearchParams();
+        if (options.months) params.append('months', options.months.toString());
+
+        const query = params.toString() ? `?${params.toString()}` : '';
+        return this.request(`/orders/stats${query}`);
+    }
+
+    /**
+     * Get a single order you bought or sold, with basic product info
+     * @param {string} orderId - ID of the order
+     * @returns {Promise<Object>} Order detail; 404 if you are not the buyer or seller
+     *
+     * Response structure:
+     * {
+     *   order: Object, // same shape as listOrders entries
+     *   product: { title: string, thumbnail_url: string | null, slug: string | null } | null,
+     *   // The other party's contact, revealed once the order reaches delivery_pending.
+     *   // Sellers don't see the buyer's shipping_address before then either.
+     *   counterparty_contact: { username: string, phone: string | null } | null
+     * }
+     */
+    async getOrder(orderId) {
+        return this.request(`/orders/${orderId}`);
+    }
+
     /**
      * Confirm an order (change status from unpaid to delivery_pending)
      * @param {string} orderId - ID of the order to confirm
@@ -822,6 +1268,39 @@ class GoodsPointAPI {
         });
     }
 
+    /**
+     * Cancel an unpaid order (buyer only). Reserved stock is returned to the product.
+     * @param {string} orderId - ID of the order to cancel
+     * @returns {Promise<Object>} Updated order object; 409 if the order is no longer unpaid
+     */
+    async cancelOrder(orderId) {
+        return this.request(`/orders/${orderId}/cancel`, {
+            method: 'POST',
+        });
+    }
+
+    /**
+     * Mark an order as shipped (seller only, from delivery_pending)
+     * @param {string} orderId - ID of the order
+     * @returns {Promise<Object>} Updated order object; 409 on an illegal transition
+     */
+    async markOrderShipped(orderId) {
+        return this.request(`/sellers/orders/${orderId}/ship`, {
+            method: 'POST',
+        });
+    }
+
+    /**
+     * Mark an order as delivered (seller only, from shipped)
+     * @param {string} orderId - ID of the order
+     * @returns {Promise<Object>} Updated order object; 409 on an illegal transition
+     */
+    async markOrderDelivered(orderId) {
+        return this.request(`/sellers/orders/${orderId}/deliver`, {
+            method: 'POST',
+        });
+    }
+
     // ============================
     // SEARCH API ENDPOINTS
     // ============================
@@ -831,7 +1310,21 @@ class GoodsPointAPI {
      * @param {Object} searchData - Search parameters
      * @param {string} searchData.query - Search query text (optional)
      * @param {number} searchData.limit - Number of results to return (optional)
+     * @param {number} searchData.offset - Number of results to skip for pagination (optional, max 1000)
      * @param {boolean} searchData.force_original - Force original query without AI enhancement
+     * @param {Object} searchData.attributes - Attribute filters as {key: value}, e.g. {brand: "Apple"} (optional)
+     * @param {string} searchData.category - Restrict results to a category, e.g. "Shoes" (optional)
+     * @param {string} searchData.product_type - "new" or "used" (optional)
+     * @param {number} searchData.price_min - Minimum price in rupees (optional)
+     * @param {number} searchData.price_max - Maximum price in rupees (optional)
+     * @param {boolean} searchData.has_images - Only products with (true) or without (false) images (optional)
+     * @param {string} searchData.region - Only products from sellers in this region (optional)
+     * @param {number} searchData.min_similarity - Minimum vector similarity 0-1, default 0.65 (optional)
+     * @param {number} searchData.min_rating - Minimum average rating 0-5 (optional)
+     * @param {string} searchData.sort - "relevance" (default), "rating", "price", "created_at" or "popularity" (optional).
+     *   With a query or images, results are still picked by relevance and the sort reorders each page
+     * @param {string} searchData.order - "desc" (default) or "asc" (optional)
+     * @param {boolean} searchData.debug - Include per-result debug_info (admins only, 403 otherwise) (optional)
      * @param {File[]} images - Array of image files to search with (max 2 images, 5MB each)
      * @returns {Promise<Object>} Search results
      * 
@@ -849,21 +1342,37 @@ class GoodsPointAPI {
      *       price: string | null,
      *       thumbnail_url: string | null,
      *       created_at: number,
+     *       view_count: number,
+     *       avg_rating: number,
+     *       review_count: number,
      *       similarity_score: number | null,
-     *       username: string
+     *       username: string,
+     *       debug_info?: { // only present with debug: true
+     *         source: "vector" | "text" | "both",
+     *         vector_score: number | null,
+     *         text_score: number | null,
+     *         final_score: number | null
+     *       }
      *     }
      *   ],
      *   total_count: number,
      *   enhanced_query: string | null,
      *   ai_enhancement_triggered: boolean,
      *   processing_time_ms: number,
-     *   inferred_category: string | null
+     *   inferred_category: string | null,
+     *   did_you_mean: string | null,
+     *   query_too_short: boolean,
+     *   attribute_facets: [{ key: string, values: [{ value: string, count: number }] }],
+     *   similarity_threshold: number
      * }
      */
     async searchProducts(searchData, images = []) {
         const formData = new FormData();
         
-        if (searchData.query || searchData.limit !== undefined || searchData.force_original !== undefined) {
+        const hasFilters = ['category', 'product_type', 'price_min', 'price_max', 'has_images', 'region', 'min_similarity', 'min_rating', 'sort', 'order', 'debug']
+            .some(key => searchData[key] !== undefined);
+
+        if (searchData.query || searchData.limit !== undefined || searchData.offset !== undefined || searchData.force_original !== undefined || searchData.attributes || hasFilters) {
             formData.append('body', JSON.stringify(searchData));
         }
         
@@ -874,6 +1383,56 @@ class GoodsPointAPI {
         return this.multipartRequest('/products/search', formData);
     }
 
+    /**
+     * List published products in a category for category landing pages
+     * @param {string} category - Category name, e.g. "Smartphones" (case-insensitive)
+     * @param {Object} options - Paging and sorting
+     * @param {number} options.limit - Number of results (optional)
+     * @param {number} options.offset - Number of results to skip (optional, max 1000)
+     * @param {string} options.sort - "created_at" (default), "price", "popularity" or "rating" (optional)
+     * @param {string} options.order - "desc" (default) or "asc" (optional)
+     * @returns {Promise<Object>} { status, category, results, total_count } with results shaped like search results
+     */
+    async getCategoryProducts(category, options = {}) {
+        const params = new URLSearchParams();
+        if (options.limit) params.append('limit', options.limit.toString());
+        if (options.offset) params.append('offset', options.offset.toString());
+        if (options.sort) params.append('sort', options.sort);
+        if (options.order) params.append('order', options.order);
+
+        const query = params.toString() ? `?${params.toString()}` : '';
+        return this.request(`/products/category/${encodeURIComponent(category)}${query}`);
+    }
+
+    /**
+     * Transcribe a recorded voice clip so it can be used as a search query
+     * @param {Blob|File} audio - Audio clip sent as the multipart 'audio' field
+     *   (flac, mp3, mp4/m4a, ogg, opus, wav or webm; max 25MB)
+     * @param {string} [language] - ISO-639-1 code of the spoken language; must be one of
+     *   the server's TRANSCRIPTION_LANGUAGES (omit to let the model detect it)
+     * @returns {Promise<Object>} { status, text } with the transcript in the spoken language
+     */
+    async transcribeAudio(audio, language) {
+        const formData = new FormData();
+        formData.append('audio', audio);
+        if (language) {
+            formData.append('language', language);
+        }
+        return this.multipartRequest('/search/transcribe', formData);
+    }
+
+    /**
+     * Transcribe a recorded voice clip and translate it to English
+     * @param {Blob|File} audio - Audio clip sent as the multipart 'audio' field
+     *   (flac, mp3, mp4/m4a, ogg, opus, wav or webm; max 25MB)
+     * @returns {Promise<Object>} { status, text } with the English translation
+     */
+    async translateAudio(audio) {
+        const formData = new FormData();
+        formData.append('audio', audio);
+        return this.multipartRequest('/search/translate', formData);
+    }
+
 
 
     // ============================
@@ -889,7 +1448,7 @@ class GoodsPointAPI {
      *   user_id: string,
      *   rows: [
      *     {
-     *       title: string,
+     *       title: string, // "Featured" first when operators have promoted products
      *       products: [
      *         {
      *           product_id: string,
@@ -909,6 +1468,20 @@ class GoodsPointAPI {
         return this.request('/homepage/recommendations');
     }
 
+    /**
+     * Tell recommendations the user is not interested in a product or a category
+     * @param {Object} target - Exactly one of product_id or category
+     * @param {string} target.product_id - Hide this product from recommendations
+     * @param {string} target.category - Push this category's products and rows to the bottom
+     * @returns {Promise<Object>} { product_id, category, expires_at }; the mark expires after a while
+     */
+    async markNotInterested(target) {
+        return this.request('/homepage/not-interested', {
+            method: 'POST',
+            body: JSON.stringify(target),
+        });
+    }
+
     // ============================
     // UTILITY METHODS
     // ============================